@@ -0,0 +1,914 @@
+use std::fmt::Display;
+
+/// Functions that are built into the language. Rather than being called, they are expanded by the compiler.
+/// A user-defined function or variable with the same name takes precedence over a builtin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Builtin {
+    // snapshot("name", a, b, ...) copies the given variables into data storage
+    Snapshot,
+    // rollback("name", a, b, ...) restores the given variables from a snapshot of the same name
+    Rollback,
+    // print("@a", parts...) sends a chat message built from text components. Without a target, e.g print("x is ", x),
+    // it's sent to every player
+    Print,
+    // title("@a", parts...), subtitle(...) and actionbar(...) show text on the screen of the targets
+    Title,
+    Subtitle,
+    Actionbar,
+    // bossbar("id", parts...) sets the name of a bossbar owned by the pack, creating it when the pack loads
+    Bossbar,
+    // the text component builders, which may only be used as parts of the builtins above
+    Color,
+    Bold,
+    Italic,
+    Underlined,
+    Hover,
+    ClickRun,
+    ClickSuggest,
+    ClickUrl,
+    // click_trigger(func, part) runs a function without arguments as the player that clicked, through a trigger objective
+    // so that players without operator permissions can use it. click_function(func, part) runs it through /function instead
+    ClickTrigger,
+    ClickFunction,
+    Selector,
+    // score(x) shows an int through a score component, which is what an int part is shown as anyway
+    Score,
+    // forceload(x, z) keeps the chunk with the given block loaded, forceload(x, z, radius) all chunks within radius blocks of it.
+    // unforceload(...) takes the same arguments and stops keeping them loaded
+    Forceload,
+    Unforceload,
+    // unforceload_all() stops keeping every chunk of the dimension loaded
+    UnforceloadAll,
+    // is_loaded(x, y, z) checks if the chunk with the given block is loaded
+    IsLoaded,
+    // place_structure("id", x, y, z) places a structure template with its lowest corner at the given block
+    PlaceStructure,
+    // fill(x1, y1, z1, x2, y2, z2, "block") fills the area between two corners with a block
+    Fill,
+    // clone(x1, y1, z1, x2, y2, z2, x, y, z) copies the area between two corners so that its lowest corner is at the given block
+    Clone,
+    // set_time(Day) sets the time of day
+    SetTime,
+    // set_weather(Rain) changes the weather, set_weather(Rain, ticks) changes it for the given number of ticks
+    SetWeather,
+    // get_attribute("@s", MaxHealth) returns the value of an attribute of an entity, get_attribute("@s", MovementSpeed, 1000)
+    // multiplies it by the given scale first since the value is rounded down to an int
+    GetAttribute,
+    // set_attribute("@s", MaxHealth, value) sets the base value of an attribute. The value is an int or a decimal string like "0.15"
+    SetAttribute,
+    // add_modifier("@s", MovementSpeed, "name", amount, Add) adds a modifier to an attribute, or replaces the modifier
+    // with the same name. The modifier's UUID is derived from its name, which remove_modifier("@s", MovementSpeed, "name") uses to remove it
+    AddModifier,
+    RemoveModifier,
+    // give_effect("@a", Speed, seconds, amplifier, hide_particles) gives an effect, all but the target and effect are optional
+    GiveEffect,
+    // clear_effect("@a") removes every effect, clear_effect("@a", Speed) just the given one
+    ClearEffect,
+    // damage(@e, amount, Fall) deals damage of a type, which is Generic if it's left out. Versions without the damage
+    // command, see DAMAGE_PACK_FORMAT, give instant damage of the level closest to the amount instead, whatever the type.
+    // heal(@a, amount) gives instant health, which heals 4 health per level, as many times as it takes to heal the amount
+    // rounded to the nearest 4. Both work the other way around on undead mobs
+    Damage,
+    Heal,
+    // xp_levels(@s) and xp_points(@s) return the experience of a player
+    XpLevels,
+    XpPoints,
+    // set_xp(@s, amount) and add_xp(@s, amount) change the experience points of players, or their levels with Levels as a third argument
+    SetXp,
+    AddXp,
+    // leaderboard(kills, 10) shows the players with the highest scores of a per-player variable or objective on the sidebar
+    Leaderboard,
+    // sin(angle) and cos(angle) take a fixed-point angle in degrees, e.g sin(30000) returns 500.
+    // atan2(y, x) returns the fixed-point angle of a direction in degrees, between -180000 and 180000.
+    // They interpolate between the entries of generated lookup tables
+    Sin,
+    Cos,
+    Atan2,
+    // hash(a, b, c) mixes up to three ints into a pseudo-random int that is the same every time, missing arguments count as 0.
+    // noise(x, z, cell_size, seed) returns smooth value noise between 0 and 1000 by hashing a value for the corners
+    // of every cell and interpolating between them. The seed is optional
+    Hash,
+    Noise,
+    // min(a, b), max(a, b), abs(x) and clamp(x, low, high) work like their Rust counterparts on ints.
+    // pow(base, exponent) returns 0 for a negative exponent, and sqrt(x) returns the square root rounded down, or 0 if x is negative
+    Min,
+    Max,
+    Abs,
+    Clamp,
+    Pow,
+    Sqrt,
+    // random(min, max) returns a random int between min and max, both included. It runs the random command on versions
+    // that have it, see RANDOM_PACK_FORMAT, and steps a linear congruential generator in the math objective otherwise
+    Random,
+    // assert(cond) and assert(cond, "message") tell every player which function and line failed and return from the
+    // function if the condition is false. They're left out of release builds, where the condition isn't evaluated
+    Assert,
+    // sleep(ticks) runs the rest of the function after the given number of ticks, like `await ticks(n)`. The statements
+    // after it are moved into a block that is scheduled, so it can only be used directly in the body of a function
+    Sleep,
+    // error("message") tells every player which function and line failed and why, then returns from the function and
+    // stops the pack's tick functions for the rest of the tick, see IrCompiler::compile_error_hook
+    Error,
+    // entity_id(@s) returns the id of an entity, giving it the next free id and the <pack>.has_id tag the first time it is seen.
+    // Ids start at 1 and are never reused
+    EntityId,
+    // emit(Damage(5)) sends a message to the subscribers of every pack on the server, which run right away in the same context
+    Emit,
+    // settings_menu(target) shows the menu for changing config values to the targets, whose buttons only work for admins
+    SettingsMenu,
+    // teleport(@s, pos) teleports the targets to a vec3
+    Teleport,
+    // positioned(pos, func) runs a function without arguments positioned at a vec3, like `execute positioned`
+    Positioned,
+    // summon("minecraft:zombie", pos, "{NoAI:1b}") summons an entity with optional data and returns a selector of it.
+    // The entity is given a tag of its own call site, which is taken off the entity it summoned before
+    Summon,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "snapshot" => Some(Builtin::Snapshot),
+            "rollback" => Some(Builtin::Rollback),
+            "print" => Some(Builtin::Print),
+            "title" => Some(Builtin::Title),
+            "subtitle" => Some(Builtin::Subtitle),
+            "actionbar" => Some(Builtin::Actionbar),
+            "bossbar" => Some(Builtin::Bossbar),
+            "color" => Some(Builtin::Color),
+            "bold" => Some(Builtin::Bold),
+            "italic" => Some(Builtin::Italic),
+            "underlined" => Some(Builtin::Underlined),
+            "hover" => Some(Builtin::Hover),
+            "click_run" => Some(Builtin::ClickRun),
+            "click_suggest" => Some(Builtin::ClickSuggest),
+            "click_url" => Some(Builtin::ClickUrl),
+            "click_trigger" => Some(Builtin::ClickTrigger),
+            "click_function" => Some(Builtin::ClickFunction),
+            "selector" => Some(Builtin::Selector),
+            "score" => Some(Builtin::Score),
+            "forceload" => Some(Builtin::Forceload),
+            "unforceload" => Some(Builtin::Unforceload),
+            "unforceload_all" => Some(Builtin::UnforceloadAll),
+            "is_loaded" => Some(Builtin::IsLoaded),
+            "place_structure" => Some(Builtin::PlaceStructure),
+            "fill" => Some(Builtin::Fill),
+            "clone" => Some(Builtin::Clone),
+            "set_time" => Some(Builtin::SetTime),
+            "set_weather" => Some(Builtin::SetWeather),
+            "get_attribute" => Some(Builtin::GetAttribute),
+            "set_attribute" => Some(Builtin::SetAttribute),
+            "add_modifier" => Some(Builtin::AddModifier),
+            "remove_modifier" => Some(Builtin::RemoveModifier),
+            "give_effect" => Some(Builtin::GiveEffect),
+            "clear_effect" => Some(Builtin::ClearEffect),
+            "damage" => Some(Builtin::Damage),
+            "heal" => Some(Builtin::Heal),
+            "xp_levels" => Some(Builtin::XpLevels),
+            "xp_points" => Some(Builtin::XpPoints),
+            "set_xp" => Some(Builtin::SetXp),
+            "add_xp" => Some(Builtin::AddXp),
+            "leaderboard" => Some(Builtin::Leaderboard),
+            "sin" => Some(Builtin::Sin),
+            "cos" => Some(Builtin::Cos),
+            "atan2" => Some(Builtin::Atan2),
+            "hash" => Some(Builtin::Hash),
+            "noise" => Some(Builtin::Noise),
+            "min" => Some(Builtin::Min),
+            "max" => Some(Builtin::Max),
+            "abs" => Some(Builtin::Abs),
+            "clamp" => Some(Builtin::Clamp),
+            "pow" => Some(Builtin::Pow),
+            "sqrt" => Some(Builtin::Sqrt),
+            "random" => Some(Builtin::Random),
+            "assert" => Some(Builtin::Assert),
+            "sleep" => Some(Builtin::Sleep),
+            "error" => Some(Builtin::Error),
+            "entity_id" => Some(Builtin::EntityId),
+            "emit" => Some(Builtin::Emit),
+            "settings_menu" => Some(Builtin::SettingsMenu),
+            "teleport" => Some(Builtin::Teleport),
+            "positioned" => Some(Builtin::Positioned),
+            "summon" => Some(Builtin::Summon),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Builtin::Snapshot => "snapshot",
+            Builtin::Rollback => "rollback",
+            Builtin::Print => "print",
+            Builtin::Title => "title",
+            Builtin::Subtitle => "subtitle",
+            Builtin::Actionbar => "actionbar",
+            Builtin::Bossbar => "bossbar",
+            Builtin::Color => "color",
+            Builtin::Bold => "bold",
+            Builtin::Italic => "italic",
+            Builtin::Underlined => "underlined",
+            Builtin::Hover => "hover",
+            Builtin::ClickRun => "click_run",
+            Builtin::ClickSuggest => "click_suggest",
+            Builtin::ClickUrl => "click_url",
+            Builtin::ClickTrigger => "click_trigger",
+            Builtin::ClickFunction => "click_function",
+            Builtin::Selector => "selector",
+            Builtin::Score => "score",
+            Builtin::Forceload => "forceload",
+            Builtin::Unforceload => "unforceload",
+            Builtin::UnforceloadAll => "unforceload_all",
+            Builtin::IsLoaded => "is_loaded",
+            Builtin::PlaceStructure => "place_structure",
+            Builtin::Fill => "fill",
+            Builtin::Clone => "clone",
+            Builtin::SetTime => "set_time",
+            Builtin::SetWeather => "set_weather",
+            Builtin::GetAttribute => "get_attribute",
+            Builtin::SetAttribute => "set_attribute",
+            Builtin::AddModifier => "add_modifier",
+            Builtin::RemoveModifier => "remove_modifier",
+            Builtin::GiveEffect => "give_effect",
+            Builtin::ClearEffect => "clear_effect",
+            Builtin::Damage => "damage",
+            Builtin::Heal => "heal",
+            Builtin::XpLevels => "xp_levels",
+            Builtin::XpPoints => "xp_points",
+            Builtin::SetXp => "set_xp",
+            Builtin::AddXp => "add_xp",
+            Builtin::Leaderboard => "leaderboard",
+            Builtin::Sin => "sin",
+            Builtin::Cos => "cos",
+            Builtin::Atan2 => "atan2",
+            Builtin::Hash => "hash",
+            Builtin::Noise => "noise",
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Abs => "abs",
+            Builtin::Clamp => "clamp",
+            Builtin::Pow => "pow",
+            Builtin::Sqrt => "sqrt",
+            Builtin::Random => "random",
+            Builtin::Assert => "assert",
+            Builtin::Sleep => "sleep",
+            Builtin::Error => "error",
+            Builtin::EntityId => "entity_id",
+            Builtin::Emit => "emit",
+            Builtin::SettingsMenu => "settings_menu",
+            Builtin::Teleport => "teleport",
+            Builtin::Positioned => "positioned",
+            Builtin::Summon => "summon",
+        }
+    }
+
+    /// Checks if the builtin builds a text component rather than doing something on its own.
+    pub fn is_text_component(&self) -> bool {
+        matches!(
+            self,
+            Builtin::Color
+                | Builtin::Bold
+                | Builtin::Italic
+                | Builtin::Underlined
+                | Builtin::Hover
+                | Builtin::ClickRun
+                | Builtin::ClickSuggest
+                | Builtin::ClickUrl
+                | Builtin::ClickTrigger
+                | Builtin::ClickFunction
+                | Builtin::Selector
+                | Builtin::Score
+        )
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The times of day that set_time() accepts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeOfDay {
+    Day,
+    Noon,
+    Night,
+    Midnight,
+}
+
+impl TimeOfDay {
+    pub const NAMES: [&'static str; 4] = ["Day", "Noon", "Night", "Midnight"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Day" => Some(TimeOfDay::Day),
+            "Noon" => Some(TimeOfDay::Noon),
+            "Night" => Some(TimeOfDay::Night),
+            "Midnight" => Some(TimeOfDay::Midnight),
+            _ => None,
+        }
+    }
+
+    /// The name of the time in the `time set` command.
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            TimeOfDay::Day => "day",
+            TimeOfDay::Noon => "noon",
+            TimeOfDay::Night => "night",
+            TimeOfDay::Midnight => "midnight",
+        }
+    }
+}
+
+/// The kinds of weather that set_weather() accepts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    pub const NAMES: [&'static str; 3] = ["Clear", "Rain", "Thunder"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Clear" => Some(Weather::Clear),
+            "Rain" => Some(Weather::Rain),
+            "Thunder" => Some(Weather::Thunder),
+            _ => None,
+        }
+    }
+
+    /// The name of the weather in the `weather` command.
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Thunder => "thunder",
+        }
+    }
+}
+
+/// The entity attributes that the attribute builtins accept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntityAttribute {
+    MaxHealth,
+    MovementSpeed,
+    AttackDamage,
+    AttackSpeed,
+    AttackKnockback,
+    Armor,
+    ArmorToughness,
+    KnockbackResistance,
+    FollowRange,
+    FlyingSpeed,
+    Luck,
+}
+
+impl EntityAttribute {
+    pub const NAMES: [&'static str; 11] = [
+        "MaxHealth",
+        "MovementSpeed",
+        "AttackDamage",
+        "AttackSpeed",
+        "AttackKnockback",
+        "Armor",
+        "ArmorToughness",
+        "KnockbackResistance",
+        "FollowRange",
+        "FlyingSpeed",
+        "Luck",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MaxHealth" => Some(EntityAttribute::MaxHealth),
+            "MovementSpeed" => Some(EntityAttribute::MovementSpeed),
+            "AttackDamage" => Some(EntityAttribute::AttackDamage),
+            "AttackSpeed" => Some(EntityAttribute::AttackSpeed),
+            "AttackKnockback" => Some(EntityAttribute::AttackKnockback),
+            "Armor" => Some(EntityAttribute::Armor),
+            "ArmorToughness" => Some(EntityAttribute::ArmorToughness),
+            "KnockbackResistance" => Some(EntityAttribute::KnockbackResistance),
+            "FollowRange" => Some(EntityAttribute::FollowRange),
+            "FlyingSpeed" => Some(EntityAttribute::FlyingSpeed),
+            "Luck" => Some(EntityAttribute::Luck),
+            _ => None,
+        }
+    }
+
+    /// The id of the attribute in the `attribute` command.
+    pub fn id(&self) -> &'static str {
+        match self {
+            EntityAttribute::MaxHealth => "minecraft:generic.max_health",
+            EntityAttribute::MovementSpeed => "minecraft:generic.movement_speed",
+            EntityAttribute::AttackDamage => "minecraft:generic.attack_damage",
+            EntityAttribute::AttackSpeed => "minecraft:generic.attack_speed",
+            EntityAttribute::AttackKnockback => "minecraft:generic.attack_knockback",
+            EntityAttribute::Armor => "minecraft:generic.armor",
+            EntityAttribute::ArmorToughness => "minecraft:generic.armor_toughness",
+            EntityAttribute::KnockbackResistance => "minecraft:generic.knockback_resistance",
+            EntityAttribute::FollowRange => "minecraft:generic.follow_range",
+            EntityAttribute::FlyingSpeed => "minecraft:generic.flying_speed",
+            EntityAttribute::Luck => "minecraft:generic.luck",
+        }
+    }
+}
+
+/// The ways that an attribute modifier can change the value of an attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModifierOperation {
+    Add,
+    MultiplyBase,
+    Multiply,
+}
+
+impl ModifierOperation {
+    pub const NAMES: [&'static str; 3] = ["Add", "MultiplyBase", "Multiply"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Add" => Some(ModifierOperation::Add),
+            "MultiplyBase" => Some(ModifierOperation::MultiplyBase),
+            "Multiply" => Some(ModifierOperation::Multiply),
+            _ => None,
+        }
+    }
+
+    /// The name of the operation in the `attribute` command.
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            ModifierOperation::Add => "add",
+            ModifierOperation::MultiplyBase => "multiply_base",
+            ModifierOperation::Multiply => "multiply",
+        }
+    }
+}
+
+/// The status effects that the effect builtins accept. The id of an effect is its name in snake case, see [effect_id].
+pub const EFFECT_NAMES: [&str; 33] = [
+    "Speed",
+    "Slowness",
+    "Haste",
+    "MiningFatigue",
+    "Strength",
+    "InstantHealth",
+    "InstantDamage",
+    "JumpBoost",
+    "Nausea",
+    "Regeneration",
+    "Resistance",
+    "FireResistance",
+    "WaterBreathing",
+    "Invisibility",
+    "Blindness",
+    "NightVision",
+    "Hunger",
+    "Weakness",
+    "Poison",
+    "Wither",
+    "HealthBoost",
+    "Absorption",
+    "Saturation",
+    "Glowing",
+    "Levitation",
+    "Luck",
+    "Unluck",
+    "SlowFalling",
+    "ConduitPower",
+    "DolphinsGrace",
+    "BadOmen",
+    "HeroOfTheVillage",
+    "Darkness",
+];
+
+/// The most seconds and the highest amplifier that an effect can be given with.
+pub const MAX_EFFECT_SECONDS: i32 = 1000000;
+pub const MAX_EFFECT_AMPLIFIER: i32 = 255;
+
+/// Converts the name of an effect such as `FireResistance` to its id, `minecraft:fire_resistance`.
+pub fn effect_id(name: &str) -> String {
+    let mut id = String::from("minecraft:");
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            id.push('_');
+        }
+
+        id.push(c.to_ascii_lowercase());
+    }
+
+    id
+}
+
+/// Derives the UUID of an attribute modifier from its name, so that the same name always refers to the same modifier.
+pub fn modifier_uuid(pack_name: &str, name: &str) -> String {
+    // two rounds of 64-bit FNV-1a with different offsets, which unlike the std hasher stays the same between compiler versions
+    let hash = |offset: u64| {
+        format!("{}:{}", pack_name, name)
+            .bytes()
+            .fold(offset, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    };
+
+    let (high, low) = (hash(0xcbf29ce484222325), hash(0x84222325cbf29ce4));
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// Checks if a string is a decimal number that commands accept, e.g `0.15` or `-2`.
+pub fn is_valid_decimal(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+
+    match digits.split_once('.') {
+        Some((whole, fraction)) => {
+            !(whole.is_empty() && fraction.is_empty())
+                && whole.chars().all(|c| c.is_ascii_digit())
+                && fraction.chars().all(|c| c.is_ascii_digit())
+        }
+        None => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+    }
+}
+
+/// The damage types that damage() accepts, whose ids are their names in snake case like effects, see [effect_id].
+pub const DAMAGE_TYPE_NAMES: [&str; 20] = [
+    "Generic",
+    "GenericKill",
+    "Magic",
+    "IndirectMagic",
+    "Wither",
+    "OnFire",
+    "InFire",
+    "Lava",
+    "HotFloor",
+    "Freeze",
+    "Drown",
+    "Starve",
+    "Fall",
+    "FlyIntoWall",
+    "Cactus",
+    "SweetBerryBush",
+    "Cramming",
+    "InWall",
+    "LightningBolt",
+    "OutOfWorld",
+];
+
+/// The first pack format with the damage command (1.19.4), which damage() falls back to instant damage before.
+pub const DAMAGE_PACK_FORMAT: u32 = 12;
+
+/// The highest level of instant damage and instant health that damage() and heal() give, past which bigger amounts are
+/// dealt or healed with the same level.
+pub const MAX_INSTANT_AMPLIFIER: u32 = 8;
+
+/// The health that a level of instant damage and instant health changes, which doubles with each amplifier.
+pub const INSTANT_DAMAGE_HEALTH: i32 = 6;
+pub const INSTANT_HEALTH_HEALTH: i32 = 4;
+
+/// The level of instant damage that comes closest to dealing the amount without going over, or the lowest level if it
+/// deals less than that. Instant damage doesn't stack within a tick, so a single level has to be picked.
+pub fn instant_damage_amplifier(amount: i32) -> u32 {
+    (0..=MAX_INSTANT_AMPLIFIER)
+        .rev()
+        .find(|amplifier| amount >= INSTANT_DAMAGE_HEALTH << amplifier)
+        .unwrap_or(0)
+}
+
+/// The levels of instant health that heal the amount rounded to the nearest level, highest first. Instant health
+/// stacks, so every bit of the number of levels it takes is given separately, up to the bits that fit below
+/// [`MAX_INSTANT_AMPLIFIER`].
+pub fn instant_health_amplifiers(amount: i32) -> Vec<u32> {
+    let levels = (amount.saturating_add(INSTANT_HEALTH_HEALTH / 2) / INSTANT_HEALTH_HEALTH).clamp(0, (2 << MAX_INSTANT_AMPLIFIER) - 1);
+
+    (0..=MAX_INSTANT_AMPLIFIER)
+        .rev()
+        .filter(|amplifier| levels & (1 << amplifier) != 0)
+        .collect()
+}
+
+/// The units that set_xp() and add_xp() change experience in.
+pub const XP_UNIT_NAMES: [&str; 2] = ["Points", "Levels"];
+
+/// The most entries that the sidebar can show, which leaderboards are limited to.
+pub const MAX_LEADERBOARD_SIZE: i32 = 15;
+
+/// The first pack format with the random command (1.20.2), which random() falls back to a scoreboard generator before.
+pub const RANDOM_PACK_FORMAT: u32 = 18;
+
+/// The first pack format with function macros (1.20.2).
+pub const MACRO_PACK_FORMAT: u32 = 18;
+
+/// The pack format of the newest version (1.21), which packs target unless they're given another one.
+pub const LATEST_PACK_FORMAT: u32 = 48;
+
+/// Fixed-point numbers are ints that hold a number multiplied by this, e.g 1500 for 1.5.
+pub const FIXED_POINT_SCALE: i32 = 1000;
+
+/// The sine of every whole degree from 0 to 360 as fixed-point numbers, which sin() and cos() interpolate between.
+/// The entry for 360 degrees lets the last degree be interpolated without wrapping around.
+pub fn sine_table() -> Vec<i32> {
+    (0..=360)
+        .map(|degrees| ((degrees as f64).to_radians().sin() * FIXED_POINT_SCALE as f64).round() as i32)
+        .collect()
+}
+
+/// The number of steps in the arctangent table.
+pub const ARCTANGENT_STEPS: i32 = 100;
+
+/// The arctangent of every ratio from 0 to 1 in steps of 1/ARCTANGENT_STEPS, as fixed-point degrees.
+/// atan2() interpolates between them after reducing the direction to an angle between 0 and 45 degrees.
+pub fn arctangent_table() -> Vec<i32> {
+    (0..=ARCTANGENT_STEPS)
+        .map(|step| {
+            let ratio = step as f64 / ARCTANGENT_STEPS as f64;
+            (ratio.atan().to_degrees() * FIXED_POINT_SCALE as f64).round() as i32
+        })
+        .collect()
+}
+
+/// Checks if a string can be used as the name of a scoreboard objective.
+pub fn is_valid_objective(objective: &str) -> bool {
+    !objective.is_empty()
+        && objective
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+'))
+}
+
+/// Checks if a string can be used as an entity tag, which allows the same characters as an objective name.
+pub fn is_valid_tag(tag: &str) -> bool {
+    is_valid_objective(tag)
+}
+
+/// Turns a string into something that can be used in the path of a resource location, e.g for naming a generated function.
+pub fn sanitize_resource_path(path: &str) -> String {
+    path.chars()
+        .map(|c| match c {
+            'A'..='Z' => c.to_ascii_lowercase(),
+            _ if is_valid_resource_path(&c.to_string()) && c != '/' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// The largest radius that forceload() accepts, as a single forceload command can load at most 256 chunks.
+pub const MAX_FORCELOAD_RADIUS: i32 = 120;
+
+/// The most blocks that a single fill or clone command can change.
+pub const MAX_BLOCK_VOLUME: i64 = 32768;
+
+/// Checks if a string can be used as a plain key in an NBT path.
+pub fn is_valid_nbt_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Checks the parts of an NBT path that the parser leaves alone, which is that no key between its dots is left out,
+/// e.g `a..b` or `a.`. Dots inside brackets, braces and quoted keys aren't separators.
+pub fn is_valid_nbt_path(path: &str) -> bool {
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut after_dot = true;
+
+    for c in path.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_str => escaped = true,
+            '"' => in_str = !in_str,
+            _ if in_str => {}
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            '.' if depth == 0 => {
+                if after_dot {
+                    return false;
+                }
+
+                after_dot = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        after_dot = false;
+    }
+
+    !after_dot && !in_str
+}
+
+pub const TEXT_COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+/// Checks if a string is a color that text components accept, either by name or as a hex code like `#ff8800`.
+pub fn is_valid_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => TEXT_COLORS.contains(&color),
+    }
+}
+
+/// Turns the display name of an objective into the JSON text component that `scoreboard objectives add` takes. A name
+/// that starts with `{` or `[` is already a component, and gives `None` if it isn't valid JSON, while any other name is
+/// plain text.
+pub fn display_name_component(display: &str) -> Option<String> {
+    match display.starts_with(['{', '[']) {
+        true => serde_json::from_str::<serde_json::Value>(display).ok().map(|component| component.to_string()),
+        false => Some(serde_json::Value::from(display).to_string()),
+    }
+}
+
+/// Checks if a string can be used as the path of a resource location, e.g the `timer` in `pack:timer`.
+pub fn is_valid_resource_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.' | '/')
+        })
+}
+
+/// Checks if a string is a resource location like `minecraft:stick`, where the namespace may be left out.
+pub fn is_valid_resource_location(id: &str) -> bool {
+    match id.split_once(':') {
+        Some((namespace, path)) => {
+            !namespace.is_empty()
+                && !namespace.contains('/')
+                && is_valid_resource_path(namespace)
+                && is_valid_resource_path(path)
+        }
+        None => is_valid_resource_path(id),
+    }
+}
+
+/// Checks if a string is a block that commands like fill accept, e.g `minecraft:oak_stairs[facing=north]{...}`.
+/// Only the id is checked thoroughly, the block states and data just have to be enclosed in brackets.
+pub fn is_valid_block(block: &str) -> bool {
+    let (id, rest) = match block.find(['[', '{']) {
+        Some(idx) => block.split_at(idx),
+        None => (block, ""),
+    };
+
+    let rest_valid = match rest.find('{') {
+        Some(idx) => {
+            let (states, data) = rest.split_at(idx);
+            (states.is_empty() || (states.starts_with('[') && states.ends_with(']'))) && data.ends_with('}')
+        }
+        None => rest.is_empty() || rest.ends_with(']'),
+    };
+
+    is_valid_resource_location(id) && rest_valid
+}
+
+/// Checks if a target refers to at most one entity, which commands like attribute require.
+pub fn is_single_target(target: &str) -> bool {
+    match target.get(..2) {
+        Some("@a" | "@e") => target.contains("limit=1]") || target.contains("limit=1,"),
+        _ => is_valid_target(target),
+    }
+}
+
+/// Checks if a string can be used as the target of a command, which is either a selector like `@a[tag=x]` or a player name.
+pub fn is_valid_target(target: &str) -> bool {
+    match target.strip_prefix('@') {
+        Some(selector) => {
+            let mut chars = selector.chars();
+            let variable = chars.next();
+            let arguments = chars.as_str();
+
+            matches!(variable, Some('p' | 'a' | 'r' | 's' | 'e'))
+                && (arguments.is_empty() || (arguments.starts_with('[') && arguments.ends_with(']')))
+        }
+        None => {
+            !target.is_empty()
+                && target.len() <= 16
+                && target.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+    }
+}
+
+pub const SELECTOR_SORTS: [&str; 4] = ["nearest", "furthest", "random", "arbitrary"];
+pub const GAMEMODE_NAMES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+
+/// Checks the arguments of a selector like `@e[type=zombie, distance=..5]`, giving back what's wrong with the first one
+/// that isn't valid. Only the form of each value is checked, so e.g any resource location is accepted as a type.
+pub fn check_selector(selector: &str) -> Result<(), String> {
+    let arguments = match selector.get(2..) {
+        Some("") => return Ok(()),
+        Some(arguments) => arguments.strip_prefix('[').and_then(|arguments| arguments.strip_suffix(']')),
+        None => None,
+    };
+
+    let Some(arguments) = arguments else {
+        return Err("the arguments of a selector must be enclosed in brackets".to_string());
+    };
+
+    for argument in split_selector_arguments(arguments) {
+        let argument = argument.trim();
+
+        if argument.is_empty() && arguments.trim().is_empty() {
+            continue;
+        }
+
+        let Some((key, value)) = argument.split_once('=') else {
+            return Err(format!("'{}' is not of the form key=value", argument));
+        };
+
+        let (key, value) = (key.trim(), value.trim());
+        // most arguments can be negated, e.g `tag=!hidden`
+        let negated = value.strip_prefix('!').unwrap_or(value).trim();
+
+        let valid = match key {
+            "x" | "y" | "z" | "dx" | "dy" | "dz" => is_valid_decimal(value),
+            "distance" | "x_rotation" | "y_rotation" => is_valid_range(value, is_valid_decimal),
+            "level" => is_valid_range(value, |bound| bound.parse::<i32>().is_ok()),
+            "limit" => value.parse::<i32>().is_ok_and(|limit| limit > 0),
+            "sort" => SELECTOR_SORTS.contains(&value),
+            "gamemode" => GAMEMODE_NAMES.contains(&negated),
+            "type" => is_valid_resource_location(negated.strip_prefix('#').unwrap_or(negated)),
+            "predicate" => is_valid_resource_location(negated),
+            "tag" | "team" => negated.is_empty() || is_valid_tag(negated),
+            "name" => !negated.is_empty(),
+            "scores" | "advancements" => value.starts_with('{') && value.ends_with('}'),
+            "nbt" => negated.starts_with('{') && negated.ends_with('}'),
+            _ => return Err(format!("'{}' is not an argument that selectors take", key)),
+        };
+
+        if !valid {
+            return Err(format!("'{}' is not a valid value for {}", value, key));
+        }
+    }
+
+    Ok(())
+}
+
+/// Gives back the arguments of a selector as key and value pairs, e.g `[("tag", "enemy")]` for `@e[tag=enemy]`.
+/// The selector should already have been checked with check_selector.
+pub fn selector_arguments(selector: &str) -> Vec<(&str, &str)> {
+    match selector.get(2..).and_then(|arguments| arguments.strip_prefix('[')?.strip_suffix(']')) {
+        Some(arguments) => split_selector_arguments(arguments)
+            .into_iter()
+            .filter_map(|argument| argument.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Checks a range like `1..5`, `..5` or `1..`, or a single value, whose bounds are checked by the given function
+fn is_valid_range(range: &str, is_valid_bound: fn(&str) -> bool) -> bool {
+    match range.split_once("..") {
+        Some(("", "")) => false,
+        Some((min, max)) => (min.is_empty() || is_valid_bound(min)) && (max.is_empty() || is_valid_bound(max)),
+        None => is_valid_bound(range),
+    }
+}
+
+// Splits the arguments of a selector at the commas that aren't in the brackets or strings of a value, such as
+// `scores={a=1,b=2}`
+fn split_selector_arguments(arguments: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in arguments.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '{' if !in_string => depth += 1,
+            ']' | '}' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                parts.push(&arguments[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&arguments[start..]);
+    parts
+}
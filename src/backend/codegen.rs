@@ -1,67 +1,677 @@
-use std::{sync::atomic::{AtomicI32, Ordering}, fmt::{Display, Formatter}, collections::HashMap};
+use std::{sync::atomic::{AtomicI32, Ordering}, fmt::{Display, Formatter}, collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet}, str::FromStr, time::{Duration, Instant}};
 
-use crate::{data::{ResourceLocation, ScoreboardSlot, ScoreboardOperationType, Objective}, parser::Operation};
+use crate::{data::{ResourceLocation, ScoreboardSlot, ScoreboardOperationType, Objective, Symbol}, parser::{MatchPattern, Operation}};
 
-use super::ir::{IrFunction, Instruction, BinaryOperation, ValueLocation};
+use super::{ir::{self, FunctionHook, IrFunction, Instruction, BinaryOperation, ValueLocation}, types::LONG_BASE, validate::Validator, verify::{self, InternalError}, DefaultBackend};
+use crate::{error::CompileError, modules, registry::Registries, safety, timings::Timings, Config, Info};
+
+/// The function that creates the objectives that the pack's commands use, which comes first in the load tag.
+pub const OBJECTIVES_FUNCTION: &str = "zz_objectives/load";
 
 static ANON_FUNC_COUNT: AtomicI32 = AtomicI32::new(0);
 
+/// The strategy used to name the functions generated for anonymous blocks.
+/// Stable names make it easier to diff generated packs between releases.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamingScheme {
+    /// zz_anon_0, zz_anon_1, ... in the order the blocks appear within their function
+    Numbered,
+    /// zz_anon_<hash>, derived from the contents of the block
+    Hashed,
+    /// zz_anon_l<line>, derived from the source line the block starts on
+    Line,
+}
+
+impl FromStr for NamingScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "numbered" => Ok(NamingScheme::Numbered),
+            "hashed" => Ok(NamingScheme::Hashed),
+            "line" => Ok(NamingScheme::Line),
+            _ => Err(format!("unknown naming scheme '{}', expected one of: numbered, hashed, line", s)),
+        }
+    }
+}
+
+/// What checking a source found, see `CodeGen::check_src`
+pub struct CheckReport {
+    pub errors: Vec<CompileError>,
+    // mistakes the compiler made while generating the code, which are only looked for when the code is generated
+    pub internal_errors: Vec<InternalError>,
+    pub info: Info,
+    pub stats: CheckStats,
+}
+
+pub struct CheckStats {
+    // the lines of the source, including the files it imports
+    pub lines: usize,
+    pub functions: usize,
+    pub structs: usize,
+    // the commands of every function of the pack, if its code was generated
+    pub commands: Option<usize>,
+    pub duration: Duration,
+}
+
 pub struct CodeGen {
     pack_name: String,
+    naming: NamingScheme,
     functions: Vec<CompiledFunction>,
-    block_info: HashMap<usize, BlockInfo>
+    block_info: HashMap<usize, BlockInfo>,
+    // the loop blocks that the block being generated is in, from the outermost one in
+    loops: Vec<usize>,
+    loop_blocks: HashSet<usize>,
+    // the blocks of the function being generated that are scheduled, which run on their own rather than inside of the
+    // loops that they're created in
+    scheduled: HashSet<usize>,
+    // Anonymous blocks are emitted under their owning function's folder, e.g main/zz_anon_0
+    block_locations: HashMap<usize, ResourceLocation>,
+    anon_names: HashSet<String>,
+    anon_count: usize,
+    // how long the code of each function took to generate, including its blocks
+    function_timings: Vec<(String, Duration)>,
+    // the scores that the function being generated saves when it makes a recursive call, and the keys they're saved under
+    frame: Vec<(String, ScoreboardSlot)>,
+    // every objective that the generated commands read or write a score of, and the first function that did
+    objectives: BTreeMap<String, ResourceLocation>,
+    // the objectives that command literals create, e.g triggers, along with whether a load function creates them
+    created_objectives: Vec<(String, ResourceLocation, bool)>,
 }
 
 impl CodeGen {
-    pub fn new(pack_name: String) -> Self {
+    pub fn new(pack_name: String, naming: NamingScheme) -> Self {
         Self {
             pack_name,
+            naming,
             functions: Vec::new(),
-            block_info: HashMap::new()
+            block_info: HashMap::new(),
+            loops: Vec::new(),
+            loop_blocks: HashSet::new(),
+            scheduled: HashSet::new(),
+            block_locations: HashMap::new(),
+            anon_names: HashSet::new(),
+            anon_count: 0,
+            function_timings: Vec::new(),
+            frame: Vec::new(),
+            objectives: BTreeMap::new(),
+            created_objectives: Vec::new(),
         }
     }
 
+    /// Checks a source without writing a pack, which is quick enough for editors and pre-commit hooks. It's parsed and
+    /// validated, and with `generate` the code of the pack is generated too, which catches the mistakes the compiler
+    /// makes while generating it, but nothing is written
+    pub fn check_src(config: &Config, registries: Option<&Registries>, path: &str, src: &str, generate: bool) -> CheckReport {
+        let start = Instant::now();
+        let mut internal_errors = Vec::new();
+        let mut commands = None;
+
+        let (info, errors) = match generate {
+            true => match crate::compile_to_ir(config, registries, path, src, &mut Timings::default()) {
+                Ok((info, funcs, errors)) => {
+                    // calls to the functions that were left out would be reported as internal errors
+                    if errors.is_empty() {
+                        internal_errors = verify::verify(&config.pack, &funcs);
+                    }
+
+                    match DefaultBackend::generate(config, &funcs, &mut Timings::default()) {
+                        Ok(files) => {
+                            commands = Some(files
+                                .iter()
+                                .filter(|(path, _)| path.extension().is_some_and(|extension| extension == "mcfunction"))
+                                .map(|(_, contents)| contents.lines().count())
+                                .sum());
+                        }
+                        Err(errors) => internal_errors.extend(errors),
+                    }
+
+                    (info, errors)
+                }
+                Err((info, errors)) => (info, errors),
+            },
+            false => {
+                let (parser_output, sources) = modules::parse_program(path, src.to_string());
+
+                let mut validator = Validator::new(config.pack.clone(), registries, safety::expand_forbidden(&config.forbid));
+                validator.set_modules(sources.modules());
+                validator.set_target(config.pack_format, config.release);
+                validator.set_loop_budget(config.loop_budget.is_some());
+                let validator_output = validator.validate_program(&parser_output.ast);
+
+                let errors = parser_output.errors
+                    .into_iter()
+                    .map(CompileError::Parse)
+                    .chain(validator_output.errors.into_iter().map(CompileError::Validate))
+                    .collect();
+
+                (Info { types: validator_output.types, signatures: validator_output.global_functions, sources }, errors)
+            }
+        };
+
+        let stats = CheckStats {
+            lines: info.sources.src().lines().count(),
+            functions: info.signatures.len(),
+            // vec3 is built in
+            structs: info.types.iter().filter(|ty| ty.is_struct() && ty.to_string() != "vec3").count(),
+            commands,
+            duration: start.elapsed(),
+        };
+
+        CheckReport { errors, internal_errors, info, stats }
+    }
+
     pub fn dissolve(self) -> Vec<CompiledFunction> {
         self.functions
     }
 
+    /// Takes the functions that have been generated so far, leaving none behind
+    pub fn take_functions(&mut self) -> Vec<CompiledFunction> {
+        std::mem::take(&mut self.functions)
+    }
+
+    pub fn function_timings(&self) -> &[(String, Duration)] {
+        &self.function_timings
+    }
+
     pub fn compile_ir_functions(&mut self, funcs: &[IrFunction]) {
+        let start = self.functions.len();
+
         for func in funcs {
+            let start = Instant::now();
             self.compile_ir_function(func);
+            self.function_timings.push((func.objective().to_string(), start.elapsed()));
+        }
+
+        self.merge_private_functions(start, funcs);
+
+        let loads = funcs
+            .iter()
+            .filter(|func| func.hook() == Some(FunctionHook::Load))
+            .map(|func| ResourceLocation::new(self.pack_name.clone(), func.objective().to_string()))
+            .collect::<HashSet<ResourceLocation>>();
+
+        for func in &self.functions[start..] {
+            let mut created = Vec::new();
+
+            for action in &func.actions {
+                used_objectives(action, &func.name, &mut self.objectives, &mut created);
+            }
+
+            // only the commands of a load function itself always run, its blocks may not
+            let on_load = loads.contains(&func.name);
+            self.created_objectives
+                .extend(created.into_iter().map(|objective| (objective, func.name.clone(), on_load)));
+        }
+    }
+
+    /// Generates zz_objectives/load, which creates the objectives that the functions generated so far use as dummy
+    /// objectives, unless a command literal already creates them with a criterion of their own.
+    /// It has to be the first function of the load tag, so that the objectives exist before anything else runs.
+    pub fn objectives_function(&mut self) -> Option<CompiledFunction> {
+        let name = ResourceLocation::new(self.pack_name.clone(), OBJECTIVES_FUNCTION.to_string());
+
+        let actions = self
+            .objectives
+            .keys()
+            .filter(|objective| !self.created_objectives.iter().any(|(created, ..)| created == *objective))
+            .map(|objective| CommandAction::Literal(format!("scoreboard objectives add {} dummy", objective)))
+            .collect::<Vec<CommandAction>>();
+
+        if actions.is_empty() {
+            return None;
+        }
+
+        let mut created = Vec::new();
+        actions.iter().for_each(|action| used_objectives(action, &name, &mut BTreeMap::new(), &mut created));
+        self.created_objectives
+            .extend(created.into_iter().map(|objective| (objective, name.clone(), true)));
+
+        Some(CompiledFunction { name, actions })
+    }
+
+    /// Checks that every objective the generated commands use is created exactly once when the pack is loaded,
+    /// see `verify::verify_objectives`.
+    pub fn verify_objectives(&self) -> Vec<InternalError> {
+        verify::verify_objectives(&self.objectives, &self.created_objectives)
+    }
+
+    // Private functions that generated the same commands as another function, such as two that return the same constant,
+    // are merged into it and the calls to them are redirected, since no other pack calls them. Every function works on
+    // its own objectives, so functions are compared with their objectives and the names of their blocks replaced, and
+    // a function is only merged along with all of its blocks. The callers then use the objectives of the function that
+    // is kept to pass arguments and get the return value.
+    // Public, #[no_mangle] and #[weak] functions are always kept, and so are functions that replace a #[weak] one, that the
+    // game runs through a tag or that a command names, since only calls can be redirected. A private function that the
+    // pack never calls can only be run by hand, like main usually is, so it's kept too. So is a function whose objectives
+    // a command names, since they can't be renamed there
+    fn merge_private_functions(&mut self, start: usize, funcs: &[IrFunction]) {
+        let private = funcs
+            .iter()
+            .filter(|func| {
+                let attributes = func.signature().attributes();
+                !attributes.public
+                    && !attributes.no_mangle
+                    && !attributes.weak
+                    && !attributes.replaces_weak
+                    && func.hook().is_none()
+                    && func.message().is_none()
+            })
+            .map(|func| ResourceLocation::new(self.pack_name.clone(), func.objective().to_string()))
+            .collect::<HashSet<ResourceLocation>>();
+
+        let names = funcs
+            .iter()
+            .map(|func| ResourceLocation::new(self.pack_name.clone(), func.objective().to_string()))
+            .collect::<Vec<ResourceLocation>>();
+
+        loop {
+            let mut mentioned = HashSet::new();
+            let mut called = HashSet::new();
+            let mut texts = Vec::new();
+
+            for func in &self.functions[start..] {
+                let mut calls = HashSet::new();
+
+                for action in &func.actions {
+                    mentioned_words(action, &mut mentioned);
+                    called_functions(action, &mut calls);
+                    action_texts(action, &mut texts);
+                }
+
+                calls.remove(&func.name);
+                called.extend(calls);
+            }
+
+            let movable = |name: &ResourceLocation| {
+                private.contains(name)
+                    && called.contains(name)
+                    && !mentioned.contains(name.to_string().as_str())
+                    && !texts.iter().any(|text| mentions_objective(text, &name.path))
+            };
+
+            // the functions that are kept come first, so that the others are merged into them
+            let (pinned, movable): (Vec<&ResourceLocation>, Vec<&ResourceLocation>) = names
+                .iter()
+                .filter(|name| self.functions[start..].iter().any(|func| func.name == **name))
+                .partition(|name| !movable(name));
+
+            let mut kept: HashMap<Vec<(String, Vec<CommandAction>)>, &ResourceLocation> = HashMap::new();
+            let mut merged = Vec::new();
+
+            for name in pinned {
+                kept.entry(self.normalized_family(start, name)).or_insert(name);
+            }
+
+            for name in movable {
+                match kept.entry(self.normalized_family(start, name)) {
+                    Entry::Occupied(entry) => merged.push((name.clone(), (*entry.get()).clone())),
+                    Entry::Vacant(entry) => {
+                        entry.insert(name);
+                    }
+                }
+            }
+
+            if merged.is_empty() {
+                return;
+            }
+
+            let mut redirects = HashMap::new();
+            let mut objectives = HashMap::new();
+
+            for (name, target) in merged {
+                for func in &self.functions[start..] {
+                    if let Some(suffix) = family_suffix(&func.name, &name) {
+                        let path = format!("{}{}", target.path, suffix);
+                        redirects.insert(func.name.clone(), ResourceLocation::new(target.namespace.clone(), path));
+                    }
+                }
+
+                objectives.extend(family_objectives(&name.path).into_iter().zip(family_objectives(&target.path)));
+            }
+
+            self.functions.retain(|func| !redirects.contains_key(&func.name));
+
+            for func in &mut self.functions[start..] {
+                for action in &mut func.actions {
+                    redirect_calls(action, &redirects);
+                    rename_objectives(action, &objectives);
+                }
+            }
+        }
+    }
+
+    // The commands of a function and its blocks, in the order of their names, with the function's objectives and the
+    // names of its blocks replaced so that they're the same for functions that only differ by name
+    fn normalized_family(&self, start: usize, name: &ResourceLocation) -> Vec<(String, Vec<CommandAction>)> {
+        let placeholder = ResourceLocation::new(name.namespace.clone(), "*".to_string());
+        let objectives = family_objectives(&name.path).into_iter().zip(family_objectives("*")).collect();
+        let mut redirects = HashMap::new();
+        let mut family = Vec::new();
+
+        for func in &self.functions[start..] {
+            if let Some(suffix) = family_suffix(&func.name, name) {
+                let path = format!("{}{}", placeholder.path, suffix);
+                redirects.insert(func.name.clone(), ResourceLocation::new(placeholder.namespace.clone(), path));
+                family.push((suffix.to_string(), func.actions.clone()));
+            }
         }
+
+        for (_, actions) in &mut family {
+            for action in actions {
+                redirect_calls(action, &redirects);
+                rename_objectives(action, &objectives);
+            }
+        }
+
+        family.sort_by(|(a, _), (b, _)| a.cmp(b));
+        family
     }
 
     fn compile_ir_function(&mut self, func: &IrFunction) {
+        self.anon_count = 0;
+        self.anon_names.clear();
+        self.frame = frame_entries(func.objective(), func.body());
+        self.scheduled = scheduled_blocks(func.body());
+
+        let start = self.functions.len();
         self.compile_ir_sequence(func.objective(), func.body(), usize::MAX);
+
+        // a return from a block sets the return flag so that the blocks it's in return too, and the flag stays set after
+        // the function has returned, so it's cleared on entry before the next call reaches the checks for it
+        if func.body().iter().any(returns_from_block) {
+            let reset = CommandAction::SetScoreboardEntry {
+                entry: ScoreboardSlot::new(Objective(format!("{}.return", func.objective())), "flag".to_string()),
+                value: 0,
+            };
+
+            self.functions.last_mut().unwrap().actions.insert(0, reset);
+        }
+
+        self.deduplicate_blocks(start);
     }
 
-    fn compile_ir_sequence(&mut self, objective: &Objective, ir: &[Instruction], block_id: usize) {
-        let mut actions = Vec::new();
+    // Blocks that generated the same commands, such as the arms of a match that do the same thing, are collapsed into
+    // one function and the calls to the others are redirected to it. Collapsing blocks can make the blocks that call
+    // them identical too, so this repeats until nothing changes.
+    // Only the blocks of one function are compared, as blocks of different functions work on different scores anyway.
+    // Blocks that a command literal names, such as scheduled ones, are always kept since only calls can be redirected
+    fn deduplicate_blocks(&mut self, start: usize) {
+        loop {
+            // the function itself is generated last, after all of its blocks
+            let blocks = &self.functions[start..self.functions.len() - 1];
 
-        let ensure_control_flow = |this: &Self, block_id, actions: &mut Vec<CommandAction>| {
-            if let Some(block_info) = this.block_info.get(block_id) {
-                if block_info.returns {
-                    actions.push(CommandAction::ExecuteIf {
-                        condition: Condition::ScoreMatches {
-                            a: ScoreboardSlot::new(Objective(format!("{}.return", objective)), "flag".to_string()),
-                            b: 1
-                        },
-                        run: Box::new(CommandAction::Return)
-                    });
+            let mut mentioned = HashSet::new();
+            for func in blocks {
+                func.actions.iter().for_each(|action| mentioned_words(action, &mut mentioned));
+            }
+
+            let (pinned, movable): (Vec<&CompiledFunction>, Vec<&CompiledFunction>) = blocks
+                .iter()
+                .partition(|func| mentioned.contains(func.name.to_string().as_str()));
+
+            let mut kept: HashMap<&[CommandAction], &ResourceLocation> = HashMap::new();
+            let mut redirects = HashMap::new();
+
+            for func in pinned.into_iter().chain(movable) {
+                match kept.entry(&func.actions) {
+                    Entry::Occupied(entry) if !mentioned.contains(func.name.to_string().as_str()) => {
+                        redirects.insert(func.name.clone(), (*entry.get()).clone());
+                    }
+                    Entry::Occupied(_) => {}
+                    Entry::Vacant(entry) => {
+                        entry.insert(&func.name);
+                    }
                 }
+            }
 
-                if block_info.breaks {
-                    actions.push(CommandAction::ExecuteIf {
-                        condition: Condition::ScoreMatches {
-                            a: ScoreboardSlot::new(Objective(format!("{}.break", objective)), "flag".to_string()),
-                            b: 1
-                        },
-                        run: Box::new(CommandAction::Return)
+            if redirects.is_empty() {
+                return;
+            }
+
+            self.functions.retain(|func| !redirects.contains_key(&func.name));
+
+            for func in &mut self.functions[start..] {
+                func.actions.iter_mut().for_each(|action| redirect_calls(action, &redirects));
+            }
+        }
+    }
+
+    fn stack_storage(&self) -> ResourceLocation {
+        ResourceLocation::new(self.pack_name.clone(), "stack".to_string())
+    }
+
+    // The values that are kept in storage rather than in scores are saved alongside the frame in one go
+    fn compile_push_frame(&self, objective: &Objective) -> CommandAction {
+        let stack = self.stack_storage();
+        let mut actions = vec![CommandAction::Literal(format!("data modify storage {} frames prepend value {{}}", stack))];
+
+        for (key, entry) in &self.frame {
+            actions.push(CommandAction::StoreScoreInStorage {
+                entry: entry.clone(),
+                storage: stack.clone(),
+                path: format!("frames[0].{}", key),
+            });
+        }
+
+        actions.push(CommandAction::Literal(format!(
+            "data modify storage {} frames[0].values set from storage {} \"{}\"",
+            stack,
+            ir::values_storage(&self.pack_name),
+            objective
+        )));
+
+        CommandAction::Several(actions)
+    }
+
+    fn compile_pop_frame(&self, objective: &Objective) -> CommandAction {
+        let stack = self.stack_storage();
+        let mut actions = Vec::new();
+
+        for (key, entry) in &self.frame {
+            actions.push(CommandAction::LoadScoreFromStorage {
+                entry: entry.clone(),
+                storage: stack.clone(),
+                path: format!("frames[0].{}", key),
+            });
+        }
+
+        actions.push(CommandAction::Literal(format!(
+            "data modify storage {} \"{}\" set from storage {} frames[0].values",
+            ir::values_storage(&self.pack_name),
+            objective,
+            stack
+        )));
+        actions.push(CommandAction::Literal(format!("data remove storage {} frames[0]", stack)));
+
+        CommandAction::Several(actions)
+    }
+
+    fn create_block_location(&mut self, objective: &Objective, id: usize, line: usize, body: &[Instruction]) -> ResourceLocation {
+        let base = match self.naming {
+            NamingScheme::Numbered => format!("zz_anon_{}", self.anon_count),
+            NamingScheme::Hashed => format!("zz_anon_{:08x}", block_hash(id, body)),
+            NamingScheme::Line => format!("zz_anon_l{}", line),
+        };
+
+        self.anon_count += 1;
+
+        // several blocks can share a line or have identical bodies, so disambiguate them
+        let mut name = base.clone();
+        let mut n = 1;
+
+        while !self.anon_names.insert(name.clone()) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+
+        let location = ResourceLocation::new(self.pack_name.clone(), format!("{}/{}", objective, name));
+        self.block_locations.insert(id, location.clone());
+        location
+    }
+
+    fn block_location(&self, block_id: usize) -> ResourceLocation {
+        self.block_locations.get(&block_id).expect("block was not created").clone()
+    }
+
+    // Longs are split into a high and a low half (see types::LONG_BASE), so adding and subtracting them
+    // works on each half and then carries between them. Comparisons look at the high halves first and
+    // only fall back to the low halves when those are equal
+    fn compile_long_operation(objective: &Objective, source: &ValueLocation, target: &ValueLocation, op: BinaryOperation) -> CommandAction {
+        let a = ScoreboardSlot::parts(target, 2);
+        let b = ScoreboardSlot::parts(source, 2);
+
+        // both halves are combined the same way before carrying
+        let halves = |op: fn() -> ScoreboardOperationType| vec![
+            CommandAction::ScoreboardOperation { op: op(), a: a[0].clone(), b: b[0].clone() },
+            CommandAction::ScoreboardOperation { op: op(), a: a[1].clone(), b: b[1].clone() },
+        ];
+
+        let compare = |half: usize, op: ConditionOperator| Condition::ScoreCompare {
+            a: a[half].clone(),
+            b: b[half].clone(),
+            op,
+        };
+
+        match op {
+            BinaryOperation::Add => {
+                let overflow = || Condition::ScoreInRange {
+                    a: a[1].clone(),
+                    range: MatchPattern { min: Some(LONG_BASE), max: None },
+                };
+
+                let mut actions = halves(|| ScoreboardOperationType::Add);
+                actions.push(CommandAction::Execute {
+                    clauses: vec![ExecuteClause::If(overflow())],
+                    run: Box::new(CommandAction::ScoreboardAdd { entry: a[0].clone(), value: 1 }),
+                });
+                actions.push(CommandAction::Execute {
+                    clauses: vec![ExecuteClause::If(overflow())],
+                    run: Box::new(CommandAction::ScoreboardRemove { entry: a[1].clone(), value: LONG_BASE }),
+                });
+
+                CommandAction::Several(actions)
+            }
+            BinaryOperation::Subtract => {
+                let underflow = || Condition::ScoreInRange {
+                    a: a[1].clone(),
+                    range: MatchPattern { min: None, max: Some(-1) },
+                };
+
+                let mut actions = halves(|| ScoreboardOperationType::Subtract);
+                actions.push(CommandAction::Execute {
+                    clauses: vec![ExecuteClause::If(underflow())],
+                    run: Box::new(CommandAction::ScoreboardRemove { entry: a[0].clone(), value: 1 }),
+                });
+                actions.push(CommandAction::Execute {
+                    clauses: vec![ExecuteClause::If(underflow())],
+                    run: Box::new(CommandAction::ScoreboardAdd { entry: a[1].clone(), value: LONG_BASE }),
+                });
+
+                CommandAction::Several(actions)
+            }
+            _ => {
+                // the result can't be written into the target while its halves are still being compared
+                let result = ScoreboardSlot::new(objective.clone(), "long".to_string());
+                let set_result = |value| Box::new(CommandAction::SetScoreboardEntry { entry: result.clone(), value });
+
+                let mut actions = vec![];
+
+                let (strict, low) = match op {
+                    BinaryOperation::CheckEquals | BinaryOperation::NotEquals => (None, ConditionOperator::Equals),
+                    BinaryOperation::GreaterThan => (Some(ConditionOperator::GreaterThan), ConditionOperator::GreaterThan),
+                    BinaryOperation::GreaterThanOrEquals => (Some(ConditionOperator::GreaterThan), ConditionOperator::GreaterThanOrEquals),
+                    BinaryOperation::LessThan => (Some(ConditionOperator::LessThan), ConditionOperator::LessThan),
+                    BinaryOperation::LessThanOrEquals => (Some(ConditionOperator::LessThan), ConditionOperator::LessThanOrEquals),
+                    _ => unreachable!("operation {} is not supported on longs", op),
+                };
+
+                // != is the only comparison that holds unless both halves match
+                let (initial, matched) = match op {
+                    BinaryOperation::NotEquals => (1, 0),
+                    _ => (0, 1),
+                };
+
+                actions.push(CommandAction::SetScoreboardEntry { entry: result.clone(), value: initial });
+
+                if let Some(strict) = strict {
+                    actions.push(CommandAction::Execute {
+                        clauses: vec![ExecuteClause::If(compare(0, strict))],
+                        run: set_result(matched),
                     });
                 }
+
+                actions.push(CommandAction::Execute {
+                    clauses: vec![
+                        ExecuteClause::If(compare(0, ConditionOperator::Equals)),
+                        ExecuteClause::If(compare(1, low)),
+                    ],
+                    run: set_result(matched),
+                });
+
+                actions.push(CommandAction::ScoreboardOperation {
+                    op: ScoreboardOperationType::Set,
+                    a: a[0].clone(),
+                    b: result,
+                });
+
+                CommandAction::Several(actions)
             }
+        }
+    }
+
+    // Carries on the returns and breaks of a block that was just run in another one, which return as well if the
+    // block set the flag of either. A break's flag holds how deeply nested the loop it breaks out of is, so once the
+    // loop has been run from outside of it, the flag is cleared if the break was for that loop and carried on otherwise
+    fn carry_control_flow(&mut self, objective: &Objective, block_id: usize, called: usize, actions: &mut Vec<CommandAction>) {
+        let Some(mut called_info) = self.block_info.get(&called).cloned() else {
+            return;
         };
-    
+
+        let break_flag = ScoreboardSlot::new(Objective(format!("{}.break", objective)), "flag".to_string());
+        let depth = self.loops.len() + 1;
+
+        if self.loop_blocks.contains(&called) && called_info.breaks.remove(&depth) {
+            actions.push(CommandAction::Execute {
+                clauses: vec![ExecuteClause::If(Condition::ScoreMatches {
+                    a: break_flag.clone(),
+                    b: depth as i32
+                })],
+                run: Box::new(CommandAction::SetScoreboardEntry {
+                    entry: break_flag.clone(),
+                    value: 0,
+                })
+            });
+        }
+
+        if called_info.returns {
+            actions.push(CommandAction::Execute {
+                clauses: vec![ExecuteClause::If(Condition::ScoreMatches {
+                    a: ScoreboardSlot::new(Objective(format!("{}.return", objective)), "flag".to_string()),
+                    b: 1
+                })],
+                run: Box::new(CommandAction::Return)
+            });
+        }
+
+        if !called_info.breaks.is_empty() {
+            actions.push(CommandAction::Execute {
+                clauses: vec![ExecuteClause::If(Condition::ScoreInRange {
+                    a: break_flag,
+                    range: MatchPattern { min: Some(1), max: None }
+                })],
+                run: Box::new(CommandAction::Return)
+            });
+        }
+
+        let block_info = self.block_info.entry(block_id).or_default();
+        block_info.returns |= called_info.returns;
+        block_info.breaks.extend(called_info.breaks);
+    }
+
+    fn compile_ir_sequence(&mut self, objective: &Objective, ir: &[Instruction], block_id: usize) {
+        let mut actions = Vec::new();
+
         for instr in ir {
             actions.push(match instr {
                 Instruction::SetValueToValue { source, target } => {
@@ -104,83 +714,62 @@ impl CodeGen {
                             a: ScoreboardSlot::from(target),
                             b: ScoreboardSlot::from(source),
                         },
-                        BinaryOperation::GreaterThan => CommandAction::ExecuteIf {
-                            condition: Condition::ScoreCompare {
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::GreaterThan,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
-                        BinaryOperation::LessThan => CommandAction::ExecuteIf {
-                            condition: Condition::ScoreCompare {
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::LessThan,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
-                        BinaryOperation::GreaterThanOrEquals => CommandAction::ExecuteIf {
-                            condition: Condition::ScoreCompare {
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::GreaterThanOrEquals,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
-                        BinaryOperation::LessThanOrEquals => CommandAction::ExecuteIf {
-                            condition: Condition::ScoreCompare {
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::LessThanOrEquals,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
-                        BinaryOperation::CheckEquals => CommandAction::ExecuteIf {
-                            condition: Condition::ScoreCompare {
+                        BinaryOperation::GreaterThan
+                        | BinaryOperation::LessThan
+                        | BinaryOperation::GreaterThanOrEquals
+                        | BinaryOperation::LessThanOrEquals
+                        | BinaryOperation::CheckEquals
+                        | BinaryOperation::NotEquals => {
+                            let condition = Condition::ScoreCompare {
                                 a: ScoreboardSlot::from(target),
                                 b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::Equals,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
-                        BinaryOperation::NotEquals => CommandAction::ExecuteUnless {
-                            condition: Condition::ScoreCompare {
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                                op: ConditionOperator::Equals,
-                            },
-                            run: Box::new(CommandAction::SetScoreboardEntry { entry: ScoreboardSlot::from(target), value: 1 })
-                        },
+                                op: match *op {
+                                    BinaryOperation::GreaterThan => ConditionOperator::GreaterThan,
+                                    BinaryOperation::LessThan => ConditionOperator::LessThan,
+                                    BinaryOperation::GreaterThanOrEquals => ConditionOperator::GreaterThanOrEquals,
+                                    BinaryOperation::LessThanOrEquals => ConditionOperator::LessThanOrEquals,
+                                    _ => ConditionOperator::Equals,
+                                },
+                            };
+
+                            let condition = match op {
+                                BinaryOperation::NotEquals => ExecuteClause::Unless(condition),
+                                _ => ExecuteClause::If(condition),
+                            };
+
+                            // the success of the comparison is stored whether or not it holds, so the result is always
+                            // exactly 1 or 0 and bools can be checked with `matches 1` wherever they came from
+                            CommandAction::Execute {
+                                clauses: vec![ExecuteClause::StoreSuccess(ScoreboardSlot::from(target)), condition],
+                                run: Box::new(CommandAction::Noop),
+                            }
+                        }
                         BinaryOperation::And => CommandAction::ScoreboardOperation {
                             op: ScoreboardOperationType::Multiply,
                             a: ScoreboardSlot::from(target),
                             b: ScoreboardSlot::from(source),
                         },
-                        BinaryOperation::Or => CommandAction::Several(vec![
-                            CommandAction::ScoreboardOperation {
-                                op: ScoreboardOperationType::Add,
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                            },
-                            CommandAction::ScoreboardOperation {
-                                op: ScoreboardOperationType::Divide,
-                                a: ScoreboardSlot::from(target),
-                                b: ScoreboardSlot::from(source),
-                            },
-                        ]),
+                        // adding the two could give 2, so the larger of them is taken instead
+                        BinaryOperation::Or => CommandAction::ScoreboardOperation {
+                            op: ScoreboardOperationType::Max,
+                            a: ScoreboardSlot::from(target),
+                            b: ScoreboardSlot::from(source),
+                        },
                     }
                 },
+                Instruction::LongBinaryOperation { source, target, op } => {
+                    Self::compile_long_operation(objective, source, target, *op)
+                }
                 Instruction::ToggleValue { target } => CommandAction::Several(vec![
                     CommandAction::ScoreboardRemove {
                         entry: ScoreboardSlot::from(target),
                         value: 1,
                     },
-                    CommandAction::ExecuteIf {
-                        condition: Condition::ScoreMatches {
+                    CommandAction::Execute {
+                        clauses: vec![ExecuteClause::If(Condition::ScoreMatches {
                             a: ScoreboardSlot::from(target),
                             b: -1,
-                        },
+                        })],
                         run: Box::new(CommandAction::SetScoreboardEntry {
                             entry: ScoreboardSlot::from(target),
                             value: 1,
@@ -199,12 +788,17 @@ impl CodeGen {
                         },
                     }
                 }
-                Instruction::Call { function } => CommandAction::Call(function.clone()),
+                Instruction::Call { function, execute } => match execute {
+                    Some(execute) => CommandAction::Execute {
+                        clauses: vec![ExecuteClause::Raw(execute.clone())],
+                        run: Box::new(CommandAction::Call(function.clone())),
+                    },
+                    None => CommandAction::Call(function.clone()),
+                },
+                Instruction::PushFrame => self.compile_push_frame(objective),
+                Instruction::PopFrame => self.compile_pop_frame(objective),
                 Instruction::Return { source, size } => {
-                    self.block_info.entry(block_id).or_insert(BlockInfo {
-                        returns: true,
-                        breaks: false
-                    }).returns = true;
+                    self.block_info.entry(block_id).or_default().returns = true;
 
                     let mut actions = vec![];
 
@@ -213,7 +807,7 @@ impl CodeGen {
                             actions.push(CommandAction::ScoreboardOperation {
                                 op: ScoreboardOperationType::Set,
                                 a: ScoreboardSlot::from(ValueLocation::new(0, i, Objective(format!("{}.return", objective)))),
-                                b: ScoreboardSlot::from(ValueLocation::new(source.slot, source.offset + i, source.objective.clone())),
+                                b: ScoreboardSlot::from(source.offset(i)),
                             });
                         }
                     }
@@ -227,104 +821,142 @@ impl CodeGen {
 
                     CommandAction::Several(actions)
                 }
-                Instruction::Break => {
-                    self.block_info.entry(block_id).or_insert(BlockInfo {
-                        returns: false,
-                        breaks: true
-                    }).breaks = true;
-    
+                Instruction::Break { depth } => {
+                    let target = self.loops.len() - depth;
+                    self.block_info.entry(block_id).or_default().breaks.insert(target);
+
                     CommandAction::Several(vec![
                         CommandAction::SetScoreboardEntry {
                             entry: ScoreboardSlot::new(Objective(format!("{}.break", objective)), "flag".to_string()),
-                            value: 1,
+                            value: target as i32,
                         },
                         CommandAction::Return,
                     ])
                 }
-                Instruction::CreateBlock { id, is_loop, body } => {
+                Instruction::CreateBlock { id, is_loop, line, body } => {
+                    self.create_block_location(objective, *id, *line, body);
+
+                    // a loop is known to be one before its body is generated, as the blocks in it can run it again
+                    if *is_loop {
+                        self.loops.push(*id);
+                        self.loop_blocks.insert(*id);
+                    }
+
+                    let outer_loops = match self.scheduled.contains(id) {
+                        true => std::mem::take(&mut self.loops),
+                        false => Vec::new(),
+                    };
+
                     self.compile_ir_sequence(objective, body, *id);
 
-                    if let Some(block_info) = self.block_info.get_mut(id) {
-                        if *is_loop {
-                            block_info.breaks = false;
-                        }
+                    if self.scheduled.contains(id) {
+                        self.loops = outer_loops;
+                    }
+
+                    if *is_loop {
+                        self.loops.pop();
                     }
 
                     CommandAction::Noop
                 }
                 Instruction::EnterBlock { id } => {
                     let mut actions = vec![
-                        CommandAction::Call(ResourceLocation::new(self.pack_name.clone(), format!("b{}", id)))
+                        CommandAction::Call(self.block_location(*id))
                     ];
 
-                    // TODO: deduplicate
-                    let (returns, breaks) = if let Some(block_info) = self.block_info.get(id) {
-                        (block_info.returns, block_info.breaks)
-                    } else {
-                        (false, false)
-                    };
-
-                    if returns {
-                        self.block_info.entry(block_id).or_insert(BlockInfo {
-                            returns: true,
-                            breaks: false
-                        }).returns = true;
-                    }
-
-                    if breaks {
-                        self.block_info.entry(block_id).or_insert(BlockInfo {
-                            returns: false,
-                            breaks: true
-                        }).breaks = true;
-                    }
-
-                    ensure_control_flow(self, id, &mut actions);
+                    self.carry_control_flow(objective, block_id, *id, &mut actions);
 
                     CommandAction::Several(actions)
                 }
-                Instruction::IfValueMatchesRunBlock { source, value, block } => {
+                // the scheduled block runs on its own, so there's no control flow to carry over
+                Instruction::ScheduleBlock { id, ticks } => {
+                    CommandAction::Literal(format!("schedule function {} {}t append", self.block_location(*id), ticks))
+                }
+                // the block is run by the execute prefix rather than entered, so control flow isn't carried out of it either
+                Instruction::RunBlockAs { id, execute } => CommandAction::Execute {
+                    clauses: vec![ExecuteClause::Raw(execute.clone())],
+                    run: Box::new(CommandAction::Call(self.block_location(*id))),
+                },
+                Instruction::IfValueMatchesRunBlock { source, block, .. } | Instruction::IfValueInRangeRunBlock { source, block, .. } => {
+                    let condition = match instr {
+                        Instruction::IfValueMatchesRunBlock { value, .. } => Condition::ScoreMatches {
+                            a: ScoreboardSlot::from(source),
+                            b: *value
+                        },
+                        Instruction::IfValueInRangeRunBlock { range, .. } => Condition::ScoreInRange {
+                            a: ScoreboardSlot::from(source),
+                            range: *range
+                        },
+                        _ => unreachable!(),
+                    };
+
                     let mut actions = vec![
-                        CommandAction::ExecuteIf {
-                            condition: Condition::ScoreMatches {
-                                a: ScoreboardSlot::from(source),
-                                b: *value
-                            },
-                            run: Box::new(CommandAction::Call(ResourceLocation::new(self.pack_name.clone(), format!("b{}", block))))
+                        CommandAction::Execute {
+                            clauses: vec![ExecuteClause::If(condition)],
+                            run: Box::new(CommandAction::Call(self.block_location(*block)))
                         }
                     ];
 
-                    // TODO: deduplicate
-                    let (returns, breaks) = if let Some(block_info) = self.block_info.get(block) {
-                        (block_info.returns, block_info.breaks)
-                    } else {
-                        (false, false)
-                    };
-
-                    if returns {
-                        self.block_info.entry(block_id).or_insert(BlockInfo {
-                            returns: true,
-                            breaks: false
-                        }).returns = true;
-                    }
-
-                    if breaks {
-                        self.block_info.entry(block_id).or_insert(BlockInfo {
-                            returns: false,
-                            breaks: true
-                        }).breaks = true;
-                    }
-
-                    ensure_control_flow(self, block, &mut actions);
+                    self.carry_control_flow(objective, block_id, *block, &mut actions);
 
                     CommandAction::Several(actions)
                 }
                 Instruction::PlaceCommandLiteral(literal) => CommandAction::Literal(literal.clone()),
+                Instruction::SaveValueToStorage { source, storage, path } => CommandAction::StoreScoreInStorage {
+                    entry: ScoreboardSlot::from(source),
+                    storage: storage.clone(),
+                    path: path.clone(),
+                },
+                Instruction::SetValueToCommandSuccess { target, command } => CommandAction::Execute {
+                    clauses: vec![ExecuteClause::StoreSuccess(ScoreboardSlot::from(target))],
+                    run: Box::new(CommandAction::Literal(command.clone())),
+                },
+                Instruction::SetValueToCommandResult { target, command } => CommandAction::Execute {
+                    clauses: vec![ExecuteClause::StoreResult(ScoreboardSlot::from(target))],
+                    run: Box::new(CommandAction::Literal(command.clone())),
+                },
+                Instruction::SetValueToMatch { source, target, value, invert } => {
+                    let condition = Condition::ScoreMatches {
+                        a: ScoreboardSlot::from(source),
+                        b: *value,
+                    };
+
+                    CommandAction::Execute {
+                        clauses: vec![
+                            ExecuteClause::StoreSuccess(ScoreboardSlot::from(target)),
+                            match invert {
+                                true => ExecuteClause::Unless(condition),
+                                false => ExecuteClause::If(condition),
+                            },
+                        ],
+                        run: Box::new(CommandAction::Noop),
+                    }
+                }
+                Instruction::SetValueToConditionSuccess { target, condition } => CommandAction::Execute {
+                    clauses: vec![
+                        ExecuteClause::StoreSuccess(ScoreboardSlot::from(target)),
+                        ExecuteClause::Raw(condition.clone()),
+                    ],
+                    run: Box::new(CommandAction::Noop),
+                },
+                // a missing path would otherwise reset the score to 0
+                Instruction::LoadValueFromStorage { target, storage, path } => CommandAction::Execute {
+                    clauses: vec![ExecuteClause::If(Condition::Data {
+                        storage: storage.clone(),
+                        path: path.clone(),
+                    })],
+                    run: Box::new(CommandAction::LoadScoreFromStorage {
+                        entry: ScoreboardSlot::from(target),
+                        storage: storage.clone(),
+                        path: path.clone(),
+                    }),
+                },
             });
         }
     
         if block_id != usize::MAX {
             self.functions.push(CompiledFunction {
-                name: ResourceLocation::new(self.pack_name.clone(), format!("b{}", block_id)),
+                name: self.block_location(block_id),
                 actions
             });
         } else {
@@ -336,12 +968,348 @@ impl CodeGen {
     }
 }
 
-#[derive(Clone, Copy)]
+// Finds the objectives of the scores that an action reads or writes, and the ones that its command literals create.
+// Scores that command literals read or write aren't found, since the ones that sculk generates are also used by other
+// commands, and the ones that were written in the source are up to the code that wrote them
+fn used_objectives(
+    action: &CommandAction,
+    function: &ResourceLocation,
+    used: &mut BTreeMap<String, ResourceLocation>,
+    created: &mut Vec<String>,
+) {
+    let mut use_slot = |slot: &ScoreboardSlot| {
+        used.entry(slot.objective.to_string()).or_insert_with(|| function.clone());
+    };
+
+    match action {
+        CommandAction::SetScoreboardEntry { entry, .. }
+        | CommandAction::ScoreboardAdd { entry, .. }
+        | CommandAction::ScoreboardRemove { entry, .. }
+        | CommandAction::StoreScoreInStorage { entry, .. }
+        | CommandAction::LoadScoreFromStorage { entry, .. } => use_slot(entry),
+        CommandAction::ScoreboardOperation { a, b, .. } => {
+            use_slot(a);
+            use_slot(b);
+        }
+        CommandAction::Execute { clauses, run } => {
+            for clause in clauses {
+                match clause {
+                    ExecuteClause::StoreResult(entry) | ExecuteClause::StoreSuccess(entry) => use_slot(entry),
+                    ExecuteClause::If(condition) | ExecuteClause::Unless(condition) => match condition {
+                        Condition::ScoreCompare { a, b, .. } => {
+                            use_slot(a);
+                            use_slot(b);
+                        }
+                        Condition::ScoreMatches { a, .. } | Condition::ScoreInRange { a, .. } => use_slot(a),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+
+            used_objectives(run, function, used, created);
+        }
+        CommandAction::Several(actions) => {
+            actions.iter().for_each(|action| used_objectives(action, function, used, created))
+        }
+        CommandAction::Literal(literal) => {
+            if let Some(objective) = literal
+                .strip_prefix("scoreboard objectives add ")
+                .and_then(|rest| rest.split_whitespace().next())
+            {
+                created.push(objective.to_string());
+            }
+        }
+        CommandAction::Noop | CommandAction::Call(_) | CommandAction::Return => {}
+    }
+}
+
+// Every word of the commands that are written out as text, which is where functions are named other than in calls
+fn mentioned_words(action: &CommandAction, words: &mut HashSet<String>) {
+    match action {
+        CommandAction::Literal(literal) => words.extend(literal.split_whitespace().map(str::to_string)),
+        CommandAction::Execute { clauses, run } => {
+            for clause in clauses {
+                if let ExecuteClause::Raw(subcommands) = clause {
+                    words.extend(subcommands.split_whitespace().map(str::to_string));
+                }
+            }
+
+            mentioned_words(run, words);
+        }
+        CommandAction::Several(actions) => actions.iter().for_each(|action| mentioned_words(action, words)),
+        _ => {}
+    }
+}
+
+// The text of an action that isn't made of scores or calls, such as command literals and storage paths
+fn action_texts<'a>(action: &'a CommandAction, texts: &mut Vec<&'a str>) {
+    match action {
+        CommandAction::Literal(literal) => texts.push(literal),
+        CommandAction::StoreScoreInStorage { path, .. } | CommandAction::LoadScoreFromStorage { path, .. } => texts.push(path),
+        CommandAction::Execute { clauses, run } => {
+            for clause in clauses {
+                match clause {
+                    ExecuteClause::As(text) | ExecuteClause::At(text) | ExecuteClause::Raw(text) => texts.push(text),
+                    ExecuteClause::If(condition) | ExecuteClause::Unless(condition) => match condition {
+                        Condition::Entity(text) | Condition::Data { path: text, .. } => texts.push(text),
+                        Condition::Block { position, block } => texts.extend([position.as_str(), block.as_str()]),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+
+            action_texts(run, texts);
+        }
+        CommandAction::Several(actions) => actions.iter().for_each(|action| action_texts(action, texts)),
+        _ => {}
+    }
+}
+
+// Whether a text names the objective, or the objective of its return or break flag. The name has to stand on its own,
+// so "f" isn't found in "self" or in "a.f", but is in "\"f\".v0_0" and "f.return"
+fn mentions_objective(text: &str, objective: &str) -> bool {
+    let is_part = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+');
+
+    text.match_indices(objective).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + objective.len()..].chars().next();
+        !before.is_some_and(|c| is_part(c) || c == '.') && !after.is_some_and(is_part)
+    })
+}
+
+// What follows the name of a function in the name of one of its blocks, e.g "/zz_anon_0", or nothing for the function itself
+fn family_suffix<'a>(function: &'a ResourceLocation, name: &ResourceLocation) -> Option<&'a str> {
+    if function.namespace != name.namespace {
+        return None;
+    }
+
+    function.path.strip_prefix(name.path.as_str()).filter(|suffix| suffix.is_empty() || suffix.starts_with('/'))
+}
+
+// The objectives that a function keeps its values and its return and break flags in
+fn family_objectives(objective: &str) -> [Symbol; 3] {
+    [
+        Symbol::intern(objective),
+        Symbol::intern(&format!("{}.return", objective)),
+        Symbol::intern(&format!("{}.break", objective)),
+    ]
+}
+
+fn rename_objectives(action: &mut CommandAction, objectives: &HashMap<Symbol, Symbol>) {
+    let rename = |slot: &mut ScoreboardSlot| {
+        if let Some(objective) = objectives.get(&slot.objective) {
+            slot.objective = objective.clone();
+        }
+    };
+
+    match action {
+        CommandAction::SetScoreboardEntry { entry, .. }
+        | CommandAction::ScoreboardAdd { entry, .. }
+        | CommandAction::ScoreboardRemove { entry, .. }
+        | CommandAction::StoreScoreInStorage { entry, .. }
+        | CommandAction::LoadScoreFromStorage { entry, .. } => rename(entry),
+        CommandAction::ScoreboardOperation { a, b, .. } => {
+            rename(a);
+            rename(b);
+        }
+        CommandAction::Execute { clauses, run } => {
+            for clause in clauses {
+                match clause {
+                    ExecuteClause::StoreResult(entry) | ExecuteClause::StoreSuccess(entry) => rename(entry),
+                    ExecuteClause::If(condition) | ExecuteClause::Unless(condition) => match condition {
+                        Condition::ScoreCompare { a, b, .. } => {
+                            rename(a);
+                            rename(b);
+                        }
+                        Condition::ScoreMatches { a, .. } | Condition::ScoreInRange { a, .. } => rename(a),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+
+            rename_objectives(run, objectives);
+        }
+        CommandAction::Several(actions) => actions.iter_mut().for_each(|action| rename_objectives(action, objectives)),
+        _ => {}
+    }
+}
+
+fn called_functions(action: &CommandAction, functions: &mut HashSet<ResourceLocation>) {
+    match action {
+        CommandAction::Call(function) => {
+            functions.insert(function.clone());
+        }
+        CommandAction::Execute { run, .. } => called_functions(run, functions),
+        CommandAction::Several(actions) => actions.iter().for_each(|action| called_functions(action, functions)),
+        _ => {}
+    }
+}
+
+fn redirect_calls(action: &mut CommandAction, redirects: &HashMap<ResourceLocation, ResourceLocation>) {
+    match action {
+        CommandAction::Call(function) => {
+            if let Some(target) = redirects.get(function) {
+                *function = target.clone();
+            }
+        }
+        CommandAction::Execute { run, .. } => redirect_calls(run, redirects),
+        CommandAction::Several(actions) => actions.iter_mut().for_each(|action| redirect_calls(action, redirects)),
+        _ => {}
+    }
+}
+
+// Whether an instruction is a block that returns from the function, which a function's own return doesn't count as
+// The blocks that a sequence of instructions, including the blocks in it, schedules
+fn scheduled_blocks(body: &[Instruction]) -> HashSet<usize> {
+    body.iter()
+        .flat_map(|instr| match instr {
+            Instruction::ScheduleBlock { id, .. } => HashSet::from([*id]),
+            Instruction::CreateBlock { body, .. } => scheduled_blocks(body),
+            _ => HashSet::new(),
+        })
+        .collect()
+}
+
+fn returns_from_block(instr: &Instruction) -> bool {
+    fn returns(body: &[Instruction]) -> bool {
+        body.iter().any(|instr| match instr {
+            Instruction::Return { .. } => true,
+            Instruction::CreateBlock { body, .. } => returns(body),
+            _ => false,
+        })
+    }
+
+    match instr {
+        Instruction::CreateBlock { body, .. } => returns(body),
+        _ => false,
+    }
+}
+
+// The scores that hold the values of a function, including the ones of its blocks, and the flags that its returns and
+// breaks set. These are what a recursive call saves and restores, so functions that make none don't need them
+fn frame_entries(objective: &Objective, body: &[Instruction]) -> Vec<(String, ScoreboardSlot)> {
+    fn collect(body: &[Instruction], locations: &mut BTreeSet<ValueLocation>, flags: &mut BTreeSet<&'static str>) -> bool {
+        let mut pushes = false;
+
+        for instr in body {
+            match instr {
+                Instruction::SetValueToValue { source, target }
+                | Instruction::ValueBinaryOperation { source, target, .. } => {
+                    locations.extend([source.clone(), target.clone()]);
+                }
+                Instruction::LongBinaryOperation { source, target, .. } => {
+                    locations.extend([source.clone(), source.offset(1), target.clone(), target.offset(1)]);
+                }
+                Instruction::SetValueToConstant { target, .. }
+                | Instruction::ToggleValue { target }
+                | Instruction::ModifyValue { target, .. }
+                | Instruction::LoadValueFromStorage { target, .. }
+                | Instruction::SetValueToCommandSuccess { target, .. }
+                | Instruction::SetValueToCommandResult { target, .. }
+                | Instruction::SetValueToConditionSuccess { target, .. } => {
+                    locations.insert(target.clone());
+                }
+                Instruction::SetValueToMatch { source, target, .. } => {
+                    locations.extend([source.clone(), target.clone()]);
+                }
+                Instruction::IfValueMatchesRunBlock { source, .. }
+                | Instruction::IfValueInRangeRunBlock { source, .. }
+                | Instruction::SaveValueToStorage { source, .. } => {
+                    locations.insert(source.clone());
+                }
+                Instruction::Return { source, size } => {
+                    locations.extend(source.iter().flat_map(|source| (0..*size).map(|i| source.offset(i))));
+                    flags.insert("return");
+                }
+                Instruction::Break { .. } => {
+                    flags.insert("break");
+                }
+                Instruction::CreateBlock { body, .. } => pushes |= collect(body, locations, flags),
+                Instruction::PushFrame => pushes = true,
+                _ => {}
+            }
+        }
+
+        pushes
+    }
+
+    let mut locations = BTreeSet::new();
+    let mut flags = BTreeSet::new();
+
+    if !collect(body, &mut locations, &mut flags) {
+        return Vec::new();
+    }
+
+    let values = locations
+        .into_iter()
+        .filter(|location| !location.player && location.objective == *objective)
+        .map(|location| (format!("v{}_{}", location.slot, location.offset), ScoreboardSlot::from(location)));
+
+    let flags = flags.into_iter().map(|flag| {
+        (
+            format!("{}_flag", flag),
+            ScoreboardSlot::new(Objective(format!("{}.{}", objective, flag)), "flag".to_string()),
+        )
+    });
+
+    values.chain(flags).collect()
+}
+
+// Block ids are global to the whole program, so they're renumbered relative to the hashed block
+// to keep the hash from changing when unrelated blocks are added elsewhere
+fn block_hash(id: usize, body: &[Instruction]) -> u32 {
+    let text = body.iter().map(|instr| instr.to_string()).collect::<Vec<String>>().join("\n");
+    let mut ids = vec![id];
+    let mut normalized = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+
+    loop {
+        let next = ["block ", "B("]
+            .iter()
+            .filter_map(|prefix| rest.find(prefix).map(|idx| idx + prefix.len()))
+            .min();
+
+        let Some(start) = next else {
+            normalized.push_str(rest);
+            break;
+        };
+
+        normalized.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+
+        if let Ok(block) = rest[..digits].parse::<usize>() {
+            let local = match ids.iter().position(|b| *b == block) {
+                Some(local) => local,
+                None => {
+                    ids.push(block);
+                    ids.len() - 1
+                }
+            };
+
+            normalized.push_str(&local.to_string());
+        }
+
+        rest = &rest[digits..];
+    }
+
+    // FNV-1a, which unlike the std hasher is guaranteed to stay the same between compiler versions
+    normalized.bytes().fold(0x811c9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+#[derive(Clone, Default)]
 struct BlockInfo {
     returns: bool,
-    breaks: bool
+    // how deeply nested the loops that the block breaks out of are, see CodeGen::carry_control_flow
+    breaks: BTreeSet<usize>
 }
 
+// actions compare and hash by value, so that identical commands can be found cheaply
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum CommandAction {
     Noop,
     SetScoreboardEntry {
@@ -361,13 +1329,20 @@ pub enum CommandAction {
         entry: ScoreboardSlot,
         value: i32,
     },
-    ExecuteIf {
-        condition: Condition,
+    // An execute command made up of several clauses, which are written out in order before the action that it runs
+    Execute {
+        clauses: Vec<ExecuteClause>,
         run: Box<CommandAction>,
     },
-    ExecuteUnless {
-        condition: Condition,
-        run: Box<CommandAction>,
+    StoreScoreInStorage {
+        entry: ScoreboardSlot,
+        storage: ResourceLocation,
+        path: String,
+    },
+    LoadScoreFromStorage {
+        entry: ScoreboardSlot,
+        storage: ResourceLocation,
+        path: String,
     },
     Several(Vec<CommandAction>),
     Call(ResourceLocation),
@@ -375,6 +1350,60 @@ pub enum CommandAction {
     Literal(String),
 }
 
+impl CommandAction {
+    // An execute that runs another execute, or an action that is an execute itself, is written as one command,
+    // e.g "execute if ... store result ... run ..." rather than "execute if ... run execute store result ... run ..."
+    fn fmt_subcommands(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandAction::Execute { clauses, run } => {
+                for clause in clauses {
+                    write!(f, " {}", clause)?;
+                }
+
+                run.fmt_subcommands(f)
+            }
+            CommandAction::StoreScoreInStorage { entry, storage, path } => {
+                write!(f, " store result storage {} {} int 1 run scoreboard players get {}", storage, path, entry)
+            }
+            CommandAction::LoadScoreFromStorage { entry, storage, path } => {
+                write!(f, " store result score {} run data get storage {} {}", entry, storage, path)
+            }
+            // an execute that runs nothing ends with its last clause, e.g "execute store success score ... if ..."
+            CommandAction::Noop => Ok(()),
+            _ => write!(f, " run {}", self),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExecuteClause {
+    // as and at aren't generated yet, but backends and optimizations can rely on their shape like with Condition
+    #[allow(dead_code)]
+    As(String),
+    #[allow(dead_code)]
+    At(String),
+    If(Condition),
+    Unless(Condition),
+    StoreResult(ScoreboardSlot),
+    StoreSuccess(ScoreboardSlot),
+    // subcommands that were written in the source as they are, such as the ones a call is made with
+    Raw(String),
+}
+
+impl Display for ExecuteClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecuteClause::As(selector) => write!(f, "as {}", selector),
+            ExecuteClause::At(selector) => write!(f, "at {}", selector),
+            ExecuteClause::If(condition) => write!(f, "if {}", condition),
+            ExecuteClause::Unless(condition) => write!(f, "unless {}", condition),
+            ExecuteClause::StoreResult(entry) => write!(f, "store result score {}", entry),
+            ExecuteClause::StoreSuccess(entry) => write!(f, "store success score {}", entry),
+            ExecuteClause::Raw(subcommands) => write!(f, "{}", subcommands),
+        }
+    }
+}
+
 impl Display for CommandAction {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -391,11 +1420,9 @@ impl Display for CommandAction {
             CommandAction::ScoreboardRemove { entry, value } => {
                 write!(f, "scoreboard players remove {} {}", entry, value)
             }
-            CommandAction::ExecuteIf { condition, run } => {
-                write!(f, "execute if {} run {}", condition, run)
-            }
-            CommandAction::ExecuteUnless { condition, run } => {
-                write!(f, "execute unless {} run {}", condition, run)
+            CommandAction::Execute { .. } | CommandAction::StoreScoreInStorage { .. } | CommandAction::LoadScoreFromStorage { .. } => {
+                write!(f, "execute")?;
+                self.fmt_subcommands(f)
             }
             CommandAction::Several(actions) => {
                 write!(f, "{}", actions.iter().map(|a| format!("{}", a)).collect::<Vec<String>>().join("\n"))
@@ -407,6 +1434,8 @@ impl Display for CommandAction {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
 pub enum Condition {
     ScoreCompare {
         a: ScoreboardSlot,
@@ -416,7 +1445,26 @@ pub enum Condition {
     ScoreMatches {
         a: ScoreboardSlot,
         b: i32
-    }
+    },
+    ScoreInRange {
+        a: ScoreboardSlot,
+        range: MatchPattern
+    },
+    // the conditions below aren't generated from the IR yet, but backends and optimizations can rely on their shape
+    // rather than having to parse commands
+    #[allow(dead_code)]
+    Entity(String),
+    #[allow(dead_code)]
+    Block {
+        position: String,
+        block: String,
+    },
+    #[allow(dead_code)]
+    Predicate(ResourceLocation),
+    Data {
+        storage: ResourceLocation,
+        path: String,
+    },
 }
 
 impl Display for Condition {
@@ -428,10 +1476,18 @@ impl Display for Condition {
             Condition::ScoreMatches { a, b } => {
                 write!(f, "score {} matches {}", a, b)
             }
+            Condition::ScoreInRange { a, range } => {
+                write!(f, "score {} matches {}", a, range)
+            }
+            Condition::Entity(selector) => write!(f, "entity {}", selector),
+            Condition::Block { position, block } => write!(f, "block {} {}", position, block),
+            Condition::Predicate(predicate) => write!(f, "predicate {}", predicate),
+            Condition::Data { storage, path } => write!(f, "data storage {} {}", storage, path),
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ConditionOperator {
     LessThan,
     LessThanOrEquals,
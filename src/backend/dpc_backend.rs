@@ -42,6 +42,14 @@ fn dpc_codegen(
     let mut ir = IR::new();
     let mut blocks = HashMap::new();
     for function in functions {
+        if function.hook().is_some() {
+            panic!("Load and tick hooks are not supported yet");
+        }
+
+        if function.message().is_some() {
+            panic!("Message subscribers are not supported yet");
+        }
+
         let mut ret_len = 0;
         let mut defs = DefManager::new();
         let mut block = codegen_block(
@@ -123,7 +131,7 @@ fn codegen_block(
     let mut finished_calls = Vec::new();
     for (i, instr) in body.into_iter().enumerate() {
         let instr = match instr {
-            Instruction::CreateBlock { id, is_loop, body } => {
+            Instruction::CreateBlock { id, is_loop, body, .. } => {
                 if *is_loop {
                     panic!("Looping is not supported yet");
                 }
@@ -131,7 +139,10 @@ fn codegen_block(
                 blocks.insert(*id, subblock);
                 None
             }
-            Instruction::Call { function } => {
+            Instruction::Call { function, execute } => {
+                if execute.is_some() {
+                    panic!("Execute modifiers are not supported yet");
+                }
                 let entry = calls.entry(function.path.clone()).or_insert_with(|| {
                     CallBuilder::from_resource_loc(function.clone(), block.contents.len())
                 });
@@ -221,9 +232,27 @@ fn codegen_block(
                     ))),
                 })
             }
+            Instruction::SaveValueToStorage { .. } | Instruction::LoadValueFromStorage { .. } => {
+                panic!("Storage instructions are not supported yet")
+            }
+            Instruction::SetValueToCommandSuccess { .. }
+            | Instruction::SetValueToCommandResult { .. }
+            | Instruction::SetValueToMatch { .. }
+            | Instruction::SetValueToConditionSuccess { .. } => {
+                panic!("Storing the success or result of a command is not supported yet")
+            }
             Instruction::PlaceCommandLiteral(lit) => Some(InstrKind::Command {
                 command: lit.clone(),
             }),
+            Instruction::ScheduleBlock { .. } => {
+                panic!("Scheduling blocks is not supported yet")
+            }
+            Instruction::RunBlockAs { .. } => {
+                panic!("Running blocks as entities is not supported yet")
+            }
+            Instruction::LongBinaryOperation { .. } => {
+                panic!("Long arithmetic is not supported yet")
+            }
             Instruction::EnterBlock { id } => {
                 let block = blocks.get(id).expect("Block was not created");
                 // Workaround for DPC not having an inline block instruction
@@ -249,6 +278,26 @@ fn codegen_block(
                     body: Box::new(block.clone()),
                 })
             }
+            Instruction::IfValueInRangeRunBlock { source, range, block } => {
+                let block = blocks.get(block).expect("Block was not created");
+                let value = || Value::Mutable(source.get_val(func_sig));
+                let constant = |n| Value::Constant(DataTypeContents::Score(ScoreTypeContents::Score(n)));
+
+                let condition = match (range.min, range.max) {
+                    (Some(min), Some(max)) => Condition::And(
+                        Box::new(Condition::GreaterThanOrEqual(value(), constant(min))),
+                        Box::new(Condition::LessThanOrEqual(value(), constant(max))),
+                    ),
+                    (Some(min), None) => Condition::GreaterThanOrEqual(value(), constant(min)),
+                    (None, Some(max)) => Condition::LessThanOrEqual(value(), constant(max)),
+                    (None, None) => unreachable!(),
+                };
+
+                Some(InstrKind::If {
+                    condition,
+                    body: Box::new(block.clone()),
+                })
+            }
             Instruction::Return { source, size } => {
                 if let Some(source) = source {
                     for i in 0..*size {
@@ -342,6 +391,10 @@ impl DefManager {
 
 impl ValueLocation {
     fn get_reg(&self) -> Identifier {
+        if self.player {
+            panic!("Player variables are not supported yet");
+        }
+
         format!("sculk_val_{}", self).replace(' ', "_").into()
     }
 
@@ -1,4 +1,4 @@
-use super::type_pool::TypeKey;
+use super::{ir::FunctionHook, type_pool::{TypeKey, TypePool}};
 
 /// Represents a Sculk function's signature.
 /// A signature consists of the function's name, its argument parameters, and its return type.
@@ -6,25 +6,62 @@ use super::type_pool::TypeKey;
 #[derive(Debug, Clone)]
 pub struct FunctionSignature {
     name: String,
+    declared_name: String,
     args: Vec<ParamDef>,
     return_type: TypeKey,
     is_static: bool,
+    attributes: FunctionAttributes,
 }
 
 impl FunctionSignature {
-    pub fn new(name: String, args: Vec<ParamDef>, return_type: TypeKey, is_static: bool) -> Self {
+    pub fn new(
+        name: String,
+        args: Vec<ParamDef>,
+        return_type: TypeKey,
+        is_static: bool,
+        attributes: FunctionAttributes,
+    ) -> Self {
         Self {
+            declared_name: name.clone(),
             name,
             args,
             return_type,
             is_static,
+            attributes,
         }
     }
 
+    /// The name of the function in the pack, which overloads have the types of their parameters added to, e.g
+    /// "add.int.int".
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// The name the function was declared with, which overloads share.
+    pub fn declared_name(&self) -> &str {
+        &self.declared_name
+    }
+
+    /// The name of the function in the pack if it's overloaded, which is its name followed by the types of its
+    /// parameters, e.g "add.int.int". Characters that can't be in the name of a function are replaced with underscores.
+    pub fn overload_name(&self, types: &TypePool) -> String {
+        let mut name = self.declared_name.clone();
+
+        for param in &self.args {
+            let ty = param.ty.from(types).to_string().replace("[]", "_array").to_ascii_lowercase();
+            name.push('.');
+            name.extend(ty.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }));
+        }
+
+        name
+    }
+
+    /// Gives the function a different name in the pack, keeping the name it was declared with.
+    pub fn renamed(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
     pub fn return_type(&self) -> TypeKey {
         self.return_type.clone()
     }
@@ -36,6 +73,50 @@ impl FunctionSignature {
     pub fn is_static(&self) -> bool {
         self.is_static
     }
+
+    pub fn attributes(&self) -> &FunctionAttributes {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut FunctionAttributes {
+        &mut self.attributes
+    }
+}
+
+/// The modifiers applied to a function through attributes such as `#[execute("as @a")]`.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionAttributes {
+    /// An execute prefix (e.g "as @a at @s") that wraps every call to the function.
+    pub execute: Option<String>,
+    /// The id of an item whose use runs the function as the player that used it, from `#[on_use(item = "...")]`.
+    pub on_use: Option<String>,
+    /// Whether the function runs as a player whenever they take damage, from `#[on_damage_taken]`.
+    pub on_damage_taken: bool,
+    /// Whether the function runs as a player whenever they deal damage, from `#[on_damage_dealt]`.
+    pub on_damage_dealt: bool,
+    /// Whether the function runs whenever any pack emits the message it takes as its parameter, from `#[subscribe]`.
+    pub subscribe: bool,
+    /// A tag that the executing entity must have for the function to run, from `#[permission("tag")]`.
+    /// `#[require_op]` requires the pack's admin tag, which only operators can give out.
+    pub permission: Option<String>,
+    /// Whether the function was declared with `pub`. Public functions keep their resource location for other packs
+    /// to call, while private ones may be merged into other functions, see CodeGen::merge_private_functions.
+    pub public: bool,
+    /// Whether the function keeps the name it was declared with in the pack, from `#[no_mangle]`, rather than having
+    /// its module or the types of its parameters added to it. Like public functions, it's never merged into another.
+    pub no_mangle: bool,
+    /// Whether the function is a default that a function of the same name elsewhere in the program replaces, from
+    /// `#[weak]`. Like `#[no_mangle]`, it keeps the name it was declared with so that it can be replaced from any module.
+    pub weak: bool,
+    /// Whether the function replaces a `#[weak]` function of the same name. The calls that the weak function was
+    /// declared for are made by its name, so like the weak function, it keeps that name in the pack.
+    pub replaces_weak: bool,
+    /// Whether calls to the function are replaced with its body, from `#[inline]`. The function is still compiled on its
+    /// own for anything that doesn't call it directly, such as references to it and recursive calls.
+    pub inline: bool,
+    /// The function tag that the function is added to, so that the game runs it whenever the pack loads or every tick,
+    /// from `#[load]` or `#[tick]`.
+    pub hook: Option<FunctionHook>,
 }
 
 /// The definition of a function parameter.
@@ -1,16 +1,24 @@
-use std::{collections::HashMap, fmt::Display, sync::atomic::{AtomicUsize, Ordering}};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+};
+
+use indexmap::IndexMap;
+use serde_json::{json, Value};
 
 use crate::{
+    consteval,
     data::{Objective, ResourceLocation, ScoreboardOperationType, ScoreboardSlot},
-    parser::{Operation, ParserNode, ParserNodeKind},
+    parser::{ArmPattern, DataSource, MatchPattern, Operation, ParserNode, ParserNodeKind},
 };
 
 use super::{
-    function::FunctionSignature,
+    builtin::{self, Builtin, EntityAttribute, ModifierOperation, TimeOfDay, Weather},
+    function::{FunctionAttributes, FunctionSignature},
     resolve::{Resolution, ResolvedPart, Resolver},
     type_pool::{TypeKey, TypePool},
-    types::{FieldDef, SculkType, StructDef},
-    validate::{ScopeStack, TagPool},
+    types::{FieldDef, SculkType, StructDef, LONG_BASE},
+    validate::{is_budgeted_loop, ScopeStack, TagPool},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -18,6 +26,8 @@ pub struct ValueLocation {
     pub slot: usize,
     pub offset: usize,
     pub objective: Objective,
+    // if set, the value is held by the executing player (@s) rather than the fake player of the slot
+    pub player: bool,
 }
 
 impl ValueLocation {
@@ -26,6 +36,17 @@ impl ValueLocation {
             slot,
             offset,
             objective,
+            player: false,
+        }
+    }
+
+    // The location of a player variable, which lives in its own objective
+    pub fn player(objective: Objective) -> Self {
+        Self {
+            slot: 0,
+            offset: 0,
+            objective,
+            player: true,
         }
     }
 
@@ -34,6 +55,7 @@ impl ValueLocation {
             slot: self.slot,
             offset: self.offset + amt,
             objective: self.objective.clone(),
+            player: self.player,
         }
     }
 
@@ -42,18 +64,27 @@ impl ValueLocation {
             slot: 0,
             offset: 0,
             objective: Objective(String::new()),
+            player: false,
         }
     }
 }
 
 impl Display for ValueLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.player {
+            return write!(f, "@s {}", self.objective);
+        }
+
         write!(f, "{}.{} {}", self.slot, self.offset, self.objective)
     }
 }
 
 impl From<ValueLocation> for ScoreboardSlot {
     fn from(loc: ValueLocation) -> Self {
+        if loc.player {
+            return ScoreboardSlot::new(loc.objective, "@s".to_string());
+        }
+
         let offset = if loc.offset == 0 {
             String::new()
         } else {
@@ -69,6 +100,13 @@ impl From<&ValueLocation> for ScoreboardSlot {
     }
 }
 
+impl ScoreboardSlot {
+    // The entries of a value that takes up size consecutive slots starting at loc, such as the two halves of a long
+    pub fn parts(loc: &ValueLocation, size: usize) -> Vec<ScoreboardSlot> {
+        (0..size).map(|i| ScoreboardSlot::from(loc.offset(i))).collect()
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum BinaryOperation {
     Add,
@@ -126,6 +164,13 @@ pub enum Instruction {
         target: ValueLocation,
         op: BinaryOperation,
     },
+    // Performs target = target <op> source on two longs, carrying between their halves.
+    // Comparisons leave their result as a bool in the first slot of target
+    LongBinaryOperation {
+        source: ValueLocation,
+        target: ValueLocation,
+        op: BinaryOperation,
+    },
     // Performs target = !target. This assumes a boolean representation
     ToggleValue {
         target: ValueLocation,
@@ -140,29 +185,90 @@ pub enum Instruction {
         source: Option<ValueLocation>,
         size: usize, // the size of the return value
     },
-    // Keeps jumping out of blocks until the encapsulating loop block is found
-    Break,
-    // Calls a function at the given location
+    // Keeps jumping out of blocks until the loop block being broken out of is left. depth is how many loops it's
+    // nested in inside of that one, so 0 breaks out of the innermost loop
+    Break {
+        depth: usize,
+    },
+    // Calls a function at the given location, optionally wrapped in an execute prefix (e.g "as @a at @s")
     Call {
         function: ResourceLocation,
+        execute: Option<String>,
     },
+    // Saves every value of the function onto a stack in storage, before a call that may run the function again
+    // overwrites them. The arguments of the call are copied into the callee's parameters after this
+    PushFrame,
+    // Restores the values that the last PushFrame saved, once the call has returned
+    PopFrame,
     // Creates a new block with the given ID and body of instructions. is_loop indicates if it stops a break's propagation
+    // line is the source line the block starts on, used for naming the generated function
     CreateBlock {
         id: usize,
         is_loop: bool,
+        line: usize,
         body: Vec<Instruction>,
     },
     // Jumps to the start of the block with the given ID
     EnterBlock {
         id: usize,
     },
+    // Runs the block with the given ID after some ticks instead of now
+    ScheduleBlock {
+        id: usize,
+        ticks: u32,
+    },
+    // Runs the block with the given ID behind an execute prefix, e.g "as @e[tag=x] at @s" to run it as each entity
+    RunBlockAs {
+        id: usize,
+        execute: String,
+    },
     // Conditionally executes a block if source == value
     IfValueMatchesRunBlock {
         source: ValueLocation,
         value: i32,
         block: usize,
     },
+    // Runs the block if the value at source is within the range, which is never unbounded on both ends
+    IfValueInRangeRunBlock {
+        source: ValueLocation,
+        range: MatchPattern,
+        block: usize,
+    },
     PlaceCommandLiteral(String),
+    // Copies the value at source into an int at the given path of a data storage
+    SaveValueToStorage {
+        source: ValueLocation,
+        storage: ResourceLocation,
+        path: String,
+    },
+    // Sets target to the int at the given path of a data storage. target is left untouched if the path does not exist
+    LoadValueFromStorage {
+        target: ValueLocation,
+        storage: ResourceLocation,
+        path: String,
+    },
+    // Sets target to 1 if the command succeeds, otherwise 0
+    SetValueToCommandSuccess {
+        target: ValueLocation,
+        command: String,
+    },
+    // Sets target to the result of the command, such as the value that `attribute ... get` queries
+    SetValueToCommandResult {
+        target: ValueLocation,
+        command: String,
+    },
+    // Sets target to 1 if source == value, otherwise 0. invert flips the result, so it's 1 when they differ
+    SetValueToMatch {
+        source: ValueLocation,
+        target: ValueLocation,
+        value: i32,
+        invert: bool,
+    },
+    // Sets target to 1 if the execute subcommands hold, e.g "if loaded 0 0 0", otherwise 0
+    SetValueToConditionSuccess {
+        target: ValueLocation,
+        condition: String,
+    },
 }
 
 impl Instruction {
@@ -179,17 +285,25 @@ impl Instruction {
             ValueBinaryOperation { source, target, op } => {
                 write!(f, "{:indent$}op T({}) = T({}) {} S({})", "", target, target, op, source, indent = indent)
             }
+            LongBinaryOperation { source, target, op } => {
+                write!(f, "{:indent$}long op T({}) = T({}) {} S({})", "", target, target, op, source, indent = indent)
+            }
             ToggleValue { target } => write!(f, "{:indent$}op T({}) = !T({})", "", target, target, indent = indent),
             ModifyValue { target, value } => write!(f, "{:indent$}op T({}) += {}", "", target, value, indent = indent),
             Return { source, size } => write!(f, "{:indent$}return{}", "", match source {
                 Some(source) => format!(" S({}) SIZE={}", source, size),
                 None => String::new(),
             }, indent = indent),
-            Break => write!(f, "{:indent$}break", "", indent = indent),
-            Call { function } => write!(f, "{:indent$}call {}", "", function, indent = indent),
-            CreateBlock { id, is_loop, body } => {
+            Break { depth } => write!(f, "{:indent$}break {}", "", depth, indent = indent),
+            Call { function, execute } => write!(f, "{:indent$}call {}{}", "", function, match execute {
+                Some(execute) => format!(" EXECUTE({})", execute),
+                None => String::new(),
+            }, indent = indent),
+            PushFrame => write!(f, "{:indent$}push frame", "", indent = indent),
+            PopFrame => write!(f, "{:indent$}pop frame", "", indent = indent),
+            CreateBlock { id, is_loop, line, body } => {
                 writeln!(f)?;
-                writeln!(f, "{:indent$}block {} (loop: {})", "", id, is_loop, indent = indent)?;
+                writeln!(f, "{:indent$}block {} (loop: {}, line: {})", "", id, is_loop, line, indent = indent)?;
 
                 for instr in body {
                     instr.fmt_with_indent(f, indent + 2)?;
@@ -199,10 +313,40 @@ impl Instruction {
                 Ok(())
             }
             EnterBlock { id } => write!(f, "{:indent$}enter B({})", "", id, indent = indent),
+            ScheduleBlock { id, ticks } => write!(f, "{:indent$}schedule B({}) in {}t", "", id, ticks, indent = indent),
+            RunBlockAs { id, execute } => write!(f, "{:indent$}enter B({}) EXECUTE({})", "", id, execute, indent = indent),
             IfValueMatchesRunBlock { source, value, block } => {
                 write!(f, "{:indent$}if S({}) == {} then block {}", "", source, value, block, indent = indent)
             }
+            IfValueInRangeRunBlock { source, range, block } => {
+                write!(f, "{:indent$}if S({}) in {} then block {}", "", source, range, block, indent = indent)
+            }
             PlaceCommandLiteral(cmd) => write!(f, "{:indent$}/{}", "", cmd, indent = indent),
+            SaveValueToStorage { source, storage, path } => {
+                write!(f, "{:indent$}store S({}) -> {} {}", "", source, storage, path, indent = indent)
+            }
+            LoadValueFromStorage { target, storage, path } => {
+                write!(f, "{:indent$}load T({}) <- {} {}", "", target, storage, path, indent = indent)
+            }
+            SetValueToCommandSuccess { target, command } => {
+                write!(f, "{:indent$}set T({}) = success(/{})", "", target, command, indent = indent)
+            }
+            SetValueToCommandResult { target, command } => {
+                write!(f, "{:indent$}set T({}) = result(/{})", "", target, command, indent = indent)
+            }
+            SetValueToMatch { source, target, value, invert } => write!(
+                f,
+                "{:indent$}set T({}) = S({}) {} {}",
+                "",
+                target,
+                source,
+                if *invert { "!=" } else { "==" },
+                value,
+                indent = indent
+            ),
+            SetValueToConditionSuccess { target, condition } => {
+                write!(f, "{:indent$}set T({}) = success({})", "", target, condition, indent = indent)
+            }
         }
     }
 }
@@ -219,28 +363,75 @@ impl Display for Instruction {
 /// All functions will be compiled to their intermediate representation, before the final step (CodeGen) is run.
 pub struct IrCompiler<'a> {
     pack_name: String,
+    src: &'a str,
     types: TypePool,
     global_functions: HashMap<ResourceLocation, FunctionSignature>,
     tags: TagPool<'a>,
+    tables: IndexMap<String, Vec<i32>>,
+    // the number of loop iterations that may run in a tick before the rest are deferred, if loops are rate limited
+    loop_budget: Option<u32>,
+    // the pack format of the version the pack targets, which is the newest one if it's None
+    pack_format: Option<u32>,
+    // whether checks that are only meant for development, like assert(), are left out
+    release: bool,
     compiled_funcs: Vec<IrFunction>,
+    // functions that had errors during validation and aren't compiled, named like their objectives, e.g "Point.length"
+    skipped_functions: HashSet<String>,
+    // functions that zz_entry/load runs whenever the pack is loaded, named like their objectives
+    entry_points: Vec<String>,
+    // Block IDs are unique per program and always start from 0 so that compiling the same source twice yields the same IR
+    next_block_id: usize,
 }
 
 impl<'a> IrCompiler<'a> {
     pub fn new(
         pack_name: String,
+        src: &'a str,
         types: TypePool,
         global_functions: HashMap<ResourceLocation, FunctionSignature>,
         tags: TagPool<'a>,
+        tables: IndexMap<String, Vec<i32>>,
+        loop_budget: Option<u32>,
     ) -> Self {
         Self {
             pack_name,
+            src,
             types,
             global_functions,
             tags,
+            tables,
+            loop_budget,
+            pack_format: None,
+            release: false,
             compiled_funcs: Vec::new(),
+            skipped_functions: HashSet::new(),
+            entry_points: Vec::new(),
+            next_block_id: 0,
         }
     }
 
+    /// Leaves the given functions out of the program, along with any hooks that would run them.
+    /// The game refuses to load a function that calls one which doesn't exist, so the functions that call them are left
+    /// out as well, and so on for the functions that call those.
+    pub fn skip_functions(&mut self, names: HashSet<String>) {
+        self.skipped_functions = names;
+    }
+
+    /// Runs the given functions whenever the pack is loaded, in order. They must exist and take no parameters.
+    pub fn set_entry_points(&mut self, names: Vec<String>) {
+        self.entry_points = names;
+    }
+
+    /// Targets the version with the given pack format, so that builtins only use commands it has.
+    pub fn set_pack_format(&mut self, pack_format: Option<u32>) {
+        self.pack_format = pack_format;
+    }
+
+    /// Leaves out the checks that are only meant for development, like assert().
+    pub fn set_release(&mut self, release: bool) {
+        self.release = release;
+    }
+
     pub fn dissolve(
         self,
     ) -> (
@@ -260,26 +451,139 @@ impl<'a> IrCompiler<'a> {
     // Takes in the top-level node in the AST which is typically just a vector of functions and their bodies
     // In the future this will account for top-level statements as well
     pub fn visit_program(&mut self, program: &'a [ParserNode]) {
-        for node in program {
+        let mut player_defaults = Vec::new();
+        let mut schedules = Vec::new();
+        let mut machines = Vec::new();
+        let mut region_checks = Vec::new();
+        // the functions that run when an item is used, grouped by item in the order they were declared
+        let mut abilities: IndexMap<String, Vec<String>> = IndexMap::new();
+        let mut damage_taken_handlers = Vec::new();
+        let mut damage_dealt_handlers = Vec::new();
+        let mut subscribers = Vec::new();
+        let mut configs = Vec::new();
+        let instances = self.tags.instances().collect::<Vec<&'a ParserNode>>();
+
+        for node in program.iter().chain(instances) {
             match node.kind() {
+                ParserNodeKind::PlayerVariableDeclaration { name, default, display, .. } => {
+                    let default = match default.as_ref().map(|default| default.kind()) {
+                        Some(ParserNodeKind::NumberLiteral(n)) => *n,
+                        Some(ParserNodeKind::BoolLiteral(b)) => *b as i32,
+                        _ => 0,
+                    };
+
+                    // the display name was checked during validation
+                    let display = display.as_deref().map(|display| builtin::display_name_component(display).unwrap());
+
+                    player_defaults.push((name.clone(), default, display));
+                }
+                ParserNodeKind::Every { ticks, body } => {
+                    let name = format!("zz_every_{}", schedules.len());
+                    let schedule = format!("schedule function {}:{} {}t", self.pack_name, name, ticks);
+
+                    // rescheduling happens first so that returning early doesn't stop the cycle
+                    let func = self.compile_body_function(
+                        &name,
+                        vec![Instruction::PlaceCommandLiteral(schedule.clone())],
+                        body,
+                    );
+
+                    self.compiled_funcs.push(func);
+                    schedules.push(schedule);
+                }
+                ParserNodeKind::Region { name, from, to, on_enter, on_leave } => {
+                    region_checks.extend(self.compile_region(name, *from, *to, on_enter, on_leave));
+                }
+                ParserNodeKind::StateMachine { name, states, transitions } => {
+                    self.compile_state_machine(name, states, transitions);
+                    machines.push(name.clone());
+                }
+                ParserNodeKind::Commands(subcommands) => self.compile_commands(subcommands),
+                // the contents of tables were computed during validation
+                ParserNodeKind::Table { .. } => {}
+                // the files that were imported are already part of the program
+                ParserNodeKind::Import(_) => {}
+                // static asserts were checked during validation
+                ParserNodeKind::StaticAssert { .. } => {}
+                // variants are replaced with the ints they're stored as wherever they're used
+                ParserNodeKind::EnumDefinition { .. } => {}
+                // calls to the methods of traits are calls to the methods of structs once generic functions are instantiated
+                ParserNodeKind::TraitDefinition { .. } => {}
+                ParserNodeKind::Config { name, default, .. } => {
+                    let (default, is_bool) = match default.kind() {
+                        ParserNodeKind::NumberLiteral(n) => (*n, false),
+                        ParserNodeKind::BoolLiteral(b) => (*b as i32, true),
+                        _ => unreachable!(),
+                    };
+
+                    configs.push((name.clone(), default, is_bool));
+                }
+                // generic functions are compiled through their instances, which are named after the types they're for
+                ParserNodeKind::FunctionDeclaration { type_params, .. }
+                    if !type_params.is_empty() && self.tags.find_function_name(node).is_none() => {}
+                // weak functions that another function replaces and functions whose #[cfg] is false aren't compiled at all
+                ParserNodeKind::FunctionDeclaration { .. } if self.tags.is_left_out(node) => {}
                 ParserNodeKind::FunctionDeclaration { name, body, .. } => {
+                    // overloads are named after the types of their parameters
+                    let name = &self.tags.find_function_name(node).unwrap_or(name).to_string();
+
+                    if self.skipped_functions.contains(name) {
+                        continue;
+                    }
+
+                    let signature = self
+                        .global_functions
+                        .get(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
+                        .unwrap();
+
+                    if let Some(item) = &signature.attributes().on_use {
+                        abilities.entry(item.clone()).or_default().push(name.clone());
+                    }
+
+                    if signature.attributes().on_damage_taken {
+                        damage_taken_handlers.push(signature.clone());
+                    }
+
+                    if signature.attributes().on_damage_dealt {
+                        damage_dealt_handlers.push(signature.clone());
+                    }
+
+                    if signature.attributes().subscribe {
+                        subscribers.push(signature.clone());
+                    }
+
+                    let hook = signature.attributes().hook;
+
                     let mut builder = IrFunctionBuilder::new(
-                        self.global_functions
-                            .get(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
-                            .unwrap(),
+                        signature,
                         Objective(name.clone()),
                         self.pack_name.clone(),
+                        self.src,
+                        &mut self.next_block_id,
                         &self.global_functions,
                         &self.types,
                         &self.tags,
+                        self.loop_budget,
+                        self.pack_format,
+                        self.release,
                     );
 
                     builder.visit_node(body);
 
-                    self.compiled_funcs.push(builder.finish());
+                    let mut func = builder.finish();
+                    func.hook = hook;
+                    self.compiled_funcs.push(func);
                 }
-                ParserNodeKind::StructDefinition { name, members } => {
-                    for method in members.iter().filter(|m| m.is_func_declaration()) {
+                ParserNodeKind::StructDefinition { name, members, .. } => {
+                    let methods = members
+                        .iter()
+                        .filter(|m| {
+                            m.is_func_declaration()
+                                && !self.skipped_functions.contains(&format!("{}.{}", name, m.as_func_name()))
+                        })
+                        .collect::<Vec<_>>();
+
+                    for method in methods {
                         let mut builder = IrFunctionBuilder::new(
                             self.types
                                 .get_type_key(name)
@@ -290,9 +594,14 @@ impl<'a> IrCompiler<'a> {
                                 .unwrap(),
                             Objective(format!("{}.{}", name, method.as_func_name())),
                             self.pack_name.clone(),
+                            self.src,
+                            &mut self.next_block_id,
                             &self.global_functions,
                             &self.types,
                             &self.tags,
+                            self.loop_budget,
+                            self.pack_format,
+                            self.release,
                         );
 
                         builder.visit_node(method.as_func_body());
@@ -303,452 +612,3905 @@ impl<'a> IrCompiler<'a> {
                 _ => unreachable!(),
             }
         }
+
+        if !player_defaults.is_empty() {
+            self.compile_player_hooks(&player_defaults);
+        }
+
+        if !configs.is_empty() {
+            self.compile_config_hook(&configs);
+            self.compile_settings_menu(&configs);
+        }
+
+        if !schedules.is_empty() {
+            self.compile_schedule_hook(schedules);
+        }
+
+        if !machines.is_empty() {
+            self.compile_state_machine_hook(&machines);
+        }
+
+        if !self.tables.is_empty() {
+            self.compile_table_hook();
+        }
+
+        if !abilities.is_empty() {
+            self.compile_ability_hooks(&abilities);
+        }
+
+        if !damage_taken_handlers.is_empty() || !damage_dealt_handlers.is_empty() {
+            self.compile_damage_hooks(&[("taken", damage_taken_handlers), ("dealt", damage_dealt_handlers)]);
+        }
+
+        for subscriber in &subscribers {
+            self.compile_subscriber(subscriber);
+        }
+
+        self.compile_budget_hook();
+        self.compile_click_hook();
+        self.compile_helpers();
+        self.compile_setup_hook();
+
+        if !region_checks.is_empty() {
+            let func = self.generated_function("zz_regions/tick", region_checks, Some(FunctionHook::Tick));
+            self.compiled_funcs.push(func);
+        }
+
+        // after every other tick function has been made, since it takes them out of the tick tag
+        self.compile_error_hook();
+
+        if !self.entry_points.is_empty() {
+            self.compile_entry_hook();
+        }
+
+        self.leave_out_callers();
     }
-}
 
-/// A function that has been compiled into Sculk IR.
-#[derive(Debug)]
-pub struct IrFunction {
-    objective: Objective,
-    body: Vec<Instruction>,
-    signature: FunctionSignature,
-}
+    // Takes the functions that call a skipped function out of the compiled ones, until none of the rest call one that's
+    // been taken out
+    fn leave_out_callers(&mut self) {
+        let mut missing = self
+            .skipped_functions
+            .iter()
+            .map(|name| self.function_location(name))
+            .collect::<HashSet<ResourceLocation>>();
 
-impl IrFunction {
-    fn new(objective: Objective, body: Vec<Instruction>, signature: FunctionSignature) -> Self {
-        Self {
-            objective,
-            body,
-            signature,
+        while !missing.is_empty() {
+            let (callers, kept): (Vec<IrFunction>, Vec<IrFunction>) = std::mem::take(&mut self.compiled_funcs)
+                .into_iter()
+                .partition(|func| calls_any(func.body(), &missing));
+
+            self.compiled_funcs = kept;
+
+            missing = callers
+                .iter()
+                .map(|func| self.function_location(&func.objective().0))
+                .collect();
         }
     }
 
-    pub fn objective(&self) -> &Objective {
-        &self.objective
+    // Generates zz_entry/load, which calls the entry points. It's the last function added to the load tag, so that
+    // everything the pack sets up, such as the objectives of its functions, is there before they run
+    fn compile_entry_hook(&mut self) {
+        let body = self
+            .entry_points
+            .iter()
+            .map(|name| Instruction::Call {
+                function: self.function_location(name),
+                execute: None,
+            })
+            .collect();
+
+        let func = self.generated_function("zz_entry/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
     }
 
-    pub fn body(&self) -> &[Instruction] {
-        &self.body
+    // Compiles a block that isn't a function in the source, such as an every(n) block, to its own function.
+    // The prelude is emitted before the block's body
+    fn compile_body_function(&mut self, name: &str, prelude: Vec<Instruction>, body: &ParserNode) -> IrFunction {
+        let signature = self.generated_signature(name);
+
+        let mut builder = IrFunctionBuilder::new(
+            &signature,
+            Objective(name.to_string()),
+            self.pack_name.clone(),
+            self.src,
+            &mut self.next_block_id,
+            &self.global_functions,
+            &self.types,
+            &self.tags,
+            self.loop_budget,
+            self.pack_format,
+            self.release,
+        );
+
+        for instr in prelude {
+            builder.emit(instr);
+        }
+
+        builder.visit_node(body);
+        builder.finish()
     }
 
-    pub fn signature(&self) -> &FunctionSignature {
-        &self.signature
+    // Creates a function that the compiler generates without any corresponding source, such as a load hook
+    fn generated_function(&self, name: &str, body: Vec<Instruction>, hook: Option<FunctionHook>) -> IrFunction {
+        let mut func = IrFunction::new(Objective(name.to_string()), body, self.generated_signature(name));
+        func.hook = hook;
+        func
     }
-}
 
-static NEXT_BLOCK_ID: AtomicUsize = AtomicUsize::new(0);
+    fn generated_signature(&self, name: &str) -> FunctionSignature {
+        FunctionSignature::new(
+            name.to_string(),
+            Vec::new(),
+            self.types.none(),
+            false,
+            FunctionAttributes::default(),
+        )
+    }
 
-/// A helper struct that assists in building Sculk IR functions.
-struct IrFunctionBuilder<'a> {
-    body: Vec<Instruction>,
-    locals: HashMap<String, usize>,
-    next_slot: usize,
-    blocks: Vec<Vec<Instruction>>,
-    objective: Objective,
-    pack_name: String,
-    global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
-    types: &'a TypePool,
-    tags: &'a TagPool<'a>,
-    signature: &'a FunctionSignature,
-}
+    fn function_location(&self, name: &str) -> ResourceLocation {
+        ResourceLocation::new(self.pack_name.clone(), name.to_string())
+    }
 
-impl<'a> IrFunctionBuilder<'a> {
-    fn new(
-        signature: &'a FunctionSignature,
-        objective: Objective,
-        pack_name: String,
-        global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
-        types: &'a TypePool,
-        tags: &'a TagPool,
-    ) -> Self {
-        let mut s = Self {
-            body: Vec::new(),
-            locals: HashMap::new(),
-            next_slot: 0,
-            blocks: Vec::new(),
-            objective,
-            pack_name,
-            global_functions,
-            types,
-            tags,
-            signature,
-        };
+    // Generates the helper functions that the compiled functions call, such as the ones that run macro commands
+    fn compile_helpers(&mut self) {
+        let mut helpers: Vec<(String, Vec<String>)> = Vec::new();
 
-        // Give the first local indices to the function parameters
-        for (idx, param) in signature.params().iter().enumerate() {
-            s.locals.insert(param.name().to_owned(), idx);
-            s.next_slot += 1;
+        for (name, commands) in self.compiled_funcs.iter().flat_map(|func| &func.helpers) {
+            if !helpers.iter().any(|(helper, _)| helper == name) {
+                helpers.push((name.clone(), commands.clone()));
+            }
         }
 
-        s
+        for (name, commands) in helpers {
+            let body = commands.into_iter().map(Instruction::PlaceCommandLiteral).collect();
+            let func = self.generated_function(&name, body, None);
+            self.compiled_funcs.push(func);
+        }
     }
 
-    fn finish(self) -> IrFunction {
-        // TODO: avoid clone
-        IrFunction::new(self.objective, self.body, self.signature.clone())
-    }
+    // Generates zz_setup/load, which runs the setup commands that the compiled functions asked for
+    fn compile_setup_hook(&mut self) {
+        let mut commands = Vec::new();
 
-    fn emit(&mut self, instr: Instruction) {
-        match self.blocks.last_mut() {
-            Some(block) => block.push(instr),
-            None => self.body.push(instr),
+        for command in self.compiled_funcs.iter().flat_map(|func| &func.setup) {
+            if !commands.contains(command) {
+                commands.push(command.clone());
+            }
         }
+
+        if commands.is_empty() {
+            return;
+        }
+
+        let body = commands.into_iter().map(Instruction::PlaceCommandLiteral).collect();
+        let func = self.generated_function("zz_setup/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
     }
 
-    fn emit_value_copy(&mut self, target: ValueLocation, source: ValueLocation, size: usize) {
-        for i in 0..size {
-            let source =
-                ValueLocation::new(source.slot, source.offset + i, source.objective.clone());
-            let target =
-                ValueLocation::new(target.slot, target.offset + i, target.objective.clone());
+    // Resets the loop budget every tick, if any loop counts against it
+    fn compile_budget_hook(&mut self) {
+        let counter = budget_location(&self.pack_name);
+        let objective = format!("scoreboard objectives add {} dummy", counter.objective);
 
-            self.emit(Instruction::SetValueToValue { source, target });
+        if !self.compiled_funcs.iter().any(|func| func.setup.contains(&objective)) {
+            return;
         }
+
+        let body = vec![Instruction::SetValueToConstant { target: counter, constant: 0 }];
+        let func = self.generated_function("zz_budget/tick", body, Some(FunctionHook::Tick));
+        self.compiled_funcs.push(func);
     }
 
-    fn get_local(&mut self, name: &str) -> ValueLocation {
-        match self.locals.get(name) {
-            Some(idx) => ValueLocation::new(*idx, 0, self.objective.clone()),
-            None => {
-                let idx = self.next_slot;
-                self.next_slot += 1;
-                self.locals.insert(name.to_owned(), idx);
-                ValueLocation::new(idx, 0, self.objective.clone())
+    // Once error() has been used, zz_error/tick is the only function in the tick tag. It clears the error flag and runs
+    // the other tick functions one after another, skipping the rest once one of them has raised an error
+    fn compile_error_hook(&mut self) {
+        let flag = error_location(&self.pack_name);
+        let objective = format!("scoreboard objectives add {} dummy", flag.objective);
+
+        if !self.compiled_funcs.iter().any(|func| func.setup.contains(&objective)) {
+            return;
+        }
+
+        let mut body = vec![Instruction::SetValueToConstant { target: flag.clone(), constant: 0 }];
+        let slot = ScoreboardSlot::from(flag);
+
+        for func in &mut self.compiled_funcs {
+            if func.hook != Some(FunctionHook::Tick) {
+                continue;
             }
+
+            func.hook = None;
+            body.push(Instruction::Call {
+                function: ResourceLocation::new(self.pack_name.clone(), func.objective.to_string()),
+                execute: Some(format!("unless score {} {} matches 1", slot.entry, slot.objective)),
+            });
         }
-    }
 
-    fn get_free_location(&mut self) -> ValueLocation {
-        let slot = self.next_slot;
-        self.next_slot += 1;
-        ValueLocation::new(slot, 0, self.objective.clone())
+        let func = self.generated_function("zz_error/tick", body, Some(FunctionHook::Tick));
+        self.compiled_funcs.push(func);
     }
 
-    fn create_block(is_loop: bool, builder: &mut Self, emitted: impl FnOnce(usize, &mut Self)) -> usize {
-        let id = NEXT_BLOCK_ID.fetch_add(1, Ordering::Relaxed);
+    // Generates the plumbing for click_trigger(): zz_click/tick runs zz_click/dispatch as every player that has set the
+    // trigger objective, and re-enables the trigger for everyone since using it disables it. zz_click/dispatch runs the
+    // callback that the value belongs to, then resets the player's score
+    fn compile_click_hook(&mut self) {
+        let mut callbacks = Vec::new();
 
-        builder.blocks.push(Vec::new());
+        for callback in self.compiled_funcs.iter().flat_map(|func| &func.callbacks) {
+            if !callbacks.contains(callback) {
+                callbacks.push(callback.clone());
+            }
+        }
 
-        emitted(id, builder);
+        if callbacks.is_empty() {
+            return;
+        }
 
-        let body = builder.blocks.pop().unwrap();
+        callbacks.sort_by_key(|callback| callback_id(&self.global_functions, callback));
 
-        builder.emit(Instruction::CreateBlock { id, is_loop, body });
+        let objective = click_objective(&self.pack_name);
 
-        id
+        let mut dispatch = callbacks
+            .iter()
+            .map(|callback| {
+                Instruction::PlaceCommandLiteral(format!(
+                    "execute if score @s {} matches {} run function {}",
+                    objective,
+                    callback_id(&self.global_functions, callback),
+                    self.function_location(callback)
+                ))
+            })
+            .collect::<Vec<Instruction>>();
+
+        dispatch.push(Instruction::PlaceCommandLiteral(format!("scoreboard players set @s {} 0", objective)));
+
+        let tick = vec![
+            Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[scores={{{}=1..}}] at @s run function {}",
+                objective,
+                self.function_location("zz_click/dispatch")
+            )),
+            Instruction::PlaceCommandLiteral(format!("scoreboard players enable @a {}", objective)),
+        ];
+
+        for (name, body, hook) in [
+            ("zz_click/dispatch", dispatch, None),
+            ("zz_click/tick", tick, Some(FunctionHook::Tick)),
+        ] {
+            let func = self.generated_function(name, body, hook);
+            self.compiled_funcs.push(func);
+        }
     }
 
-    // Takes in a function's name, signature and its respective body in the AST, and compiles it into Sculk IR
-    fn compile_function(&mut self, body: &'a [ParserNode]) {
-        for node in body {
-            self.visit_node(node);
+    // Generates the plumbing for #[on_use]: every item used by an ability gets an objective with the item's `used` criterion,
+    // which the game increments whenever a player uses the item. zz_use/tick runs zz_use/<item> as every player whose score
+    // for an item is set, which calls the item's abilities and resets the score
+    fn compile_ability_hooks(&mut self, abilities: &IndexMap<String, Vec<String>>) {
+        let mut load = Vec::new();
+        let mut tick = Vec::new();
+
+        for (item, funcs) in abilities {
+            let key = item.replace(':', ".");
+            let objective = format!("{}.use.{}", self.pack_name, key);
+            let dispatch_name = format!("zz_use/{}", key);
+
+            load.push(Instruction::PlaceCommandLiteral(format!(
+                "scoreboard objectives add {} minecraft.used:{}",
+                objective, key
+            )));
+
+            tick.push(Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[scores={{{}=1..}}] at @s run function {}",
+                objective,
+                self.function_location(&dispatch_name)
+            )));
+
+            let mut dispatch = funcs
+                .iter()
+                .map(|func| Instruction::PlaceCommandLiteral(format!("function {}", self.function_location(func))))
+                .collect::<Vec<Instruction>>();
+
+            dispatch.push(Instruction::PlaceCommandLiteral(format!("scoreboard players set @s {} 0", objective)));
+
+            let func = self.generated_function(&dispatch_name, dispatch, None);
+            self.compiled_funcs.push(func);
+        }
+
+        for (name, body, hook) in [
+            ("zz_use/load", load, FunctionHook::Load),
+            ("zz_use/tick", tick, FunctionHook::Tick),
+        ] {
+            let func = self.generated_function(name, body, Some(hook));
+            self.compiled_funcs.push(func);
         }
     }
 
-    fn visit_node(&mut self, node: &ParserNode) -> ValueLocation {
-        match node.kind() {
-            ParserNodeKind::NumberLiteral(n) => self.visit_number_literal(*n),
-            ParserNodeKind::BoolLiteral(b) => self.visit_bool_literal(*b),
-            ParserNodeKind::Identifier(_) => self.visit_identifier(node),
-            ParserNodeKind::VariableDeclaration { name, expr, .. } => {
-                self.visit_variable_declaration(name.as_identifier(), expr);
-                ValueLocation::dummy()
+    // Generates the plumbing for #[on_damage_taken] and #[on_damage_dealt]. The game adds the damage a player takes or deals
+    // to the objectives with the damage_taken and damage_dealt statistics, so a score above 0 means it changed since the last tick.
+    // zz_damage/tick runs zz_damage/<event> as those players, which passes the score to every handler that takes it and resets it
+    fn compile_damage_hooks(&mut self, events: &[(&str, Vec<FunctionSignature>)]) {
+        let mut load = Vec::new();
+        let mut tick = Vec::new();
+
+        for (event, handlers) in events.iter().filter(|(_, handlers)| !handlers.is_empty()) {
+            let objective = Objective(format!("{}.damage_{}", self.pack_name, event));
+            let dispatch_name = format!("zz_damage/{}", event);
+
+            load.push(Instruction::PlaceCommandLiteral(format!(
+                "scoreboard objectives add {} minecraft.custom:minecraft.damage_{}",
+                objective, event
+            )));
+
+            tick.push(Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[scores={{{}=1..}}] at @s run function {}",
+                objective,
+                self.function_location(&dispatch_name)
+            )));
+
+            let mut dispatch = Vec::new();
+
+            for handler in handlers {
+                if !handler.params().is_empty() {
+                    dispatch.push(Instruction::SetValueToValue {
+                        source: ValueLocation::player(objective.clone()),
+                        target: ValueLocation::new(0, 0, Objective(handler.name().to_string())),
+                    });
+                }
+
+                dispatch.push(Instruction::Call {
+                    function: self.function_location(handler.name()),
+                    execute: handler.attributes().execute.clone(),
+                });
+            }
+
+            dispatch.push(Instruction::SetValueToConstant {
+                target: ValueLocation::player(objective),
+                constant: 0,
+            });
+
+            let func = self.generated_function(&dispatch_name, dispatch, None);
+            self.compiled_funcs.push(func);
+        }
+
+        for (name, body, hook) in [
+            ("zz_damage/load", load, FunctionHook::Load),
+            ("zz_damage/tick", tick, FunctionHook::Tick),
+        ] {
+            let func = self.generated_function(name, body, Some(hook));
+            self.compiled_funcs.push(func);
+        }
+    }
+
+    // Generates zz_bus/<subscriber>, which is added to the function tag of the subscriber's message.
+    // It reads the newest message of its kind from the bus into the subscriber's parameter before calling it
+    fn compile_subscriber(&mut self, subscriber: &FunctionSignature) {
+        let message_def = subscriber.params()[0].param_type().from(&self.types).as_struct_def();
+        let key = builtin::sanitize_resource_path(message_def.name());
+        let mut body = Vec::new();
+
+        for field in message_def.fields() {
+            let target = ValueLocation::new(0, message_def.field_offset(field.name()), Objective(subscriber.name().to_string()));
+
+            // fields that another pack's declaration of the message doesn't have are left as 0
+            body.push(Instruction::SetValueToConstant {
+                target: target.clone(),
+                constant: 0,
+            });
+            body.push(Instruction::LoadValueFromStorage {
+                target,
+                storage: bus_storage(),
+                path: format!("{}[-1].{}", key, field.name()),
+            });
+        }
+
+        body.push(Instruction::Call {
+            function: self.function_location(subscriber.name()),
+            execute: subscriber.attributes().execute.clone(),
+        });
+
+        let mut func = self.generated_function(&format!("zz_bus/{}", subscriber.name()), body, None);
+        func.message = Some(key);
+        self.compiled_funcs.push(func);
+    }
+
+    // Generates zz_config/load, which writes the default of every config value that isn't in storage yet, so that values
+    // an admin has changed are kept, and then reads every config value from storage into its score
+    fn compile_config_hook(&mut self, configs: &[(String, i32, bool)]) {
+        let storage = config_storage(&self.pack_name);
+        let mut body = vec![Instruction::PlaceCommandLiteral(format!(
+            "scoreboard objectives add {}.config dummy",
+            self.pack_name
+        ))];
+
+        for (index, (name, default, _)) in configs.iter().enumerate() {
+            body.push(Instruction::PlaceCommandLiteral(format!(
+                "execute unless data storage {} {} run data modify storage {} {} set value {}",
+                storage, name, storage, name, default
+            )));
+            body.push(Instruction::LoadValueFromStorage {
+                target: config_location(&self.pack_name, index),
+                storage: storage.clone(),
+                path: name.clone(),
+            });
+        }
+
+        let func = self.generated_function("zz_config/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
+    }
+
+    // Generates the settings menu, which lists every config value with buttons that change it.
+    // The buttons set the <pack>.settings trigger objective, which zz_settings/tick handles for admins (players with the
+    // <pack>.admin tag) by running zz_settings/dispatch. Running `/trigger <pack>.settings` on its own sets the score to 1,
+    // which opens the menu, while 2 + 2i toggles or increments the config value at index i and 3 + 2i decrements it.
+    // Changed values are written back to storage so that they're kept when the pack is reloaded
+    fn compile_settings_menu(&mut self, configs: &[(String, i32, bool)]) {
+        let objective = settings_objective(&self.pack_name);
+        let admin_tag = format!("{}.admin", self.pack_name);
+        let storage = config_storage(&self.pack_name);
+        let show = self.function_location("zz_settings/show");
+
+        let button = |label: &str, color: &str, value: usize| {
+            json!({
+                "text": label,
+                "color": color,
+                "clickEvent": { "action": "run_command", "value": format!("/trigger {} set {}", objective, value) },
+            })
+        };
+
+        let mut menu = vec![Instruction::PlaceCommandLiteral(format!(
+            "tellraw @s {}",
+            json!({ "text": format!("{} settings", self.pack_name), "bold": true })
+        ))];
+        let mut dispatch = Vec::new();
+
+        for (index, (name, _, is_bool)) in configs.iter().enumerate() {
+            let location = config_location(&self.pack_name, index);
+            let slot = ScoreboardSlot::from(&location);
+            let (increment, decrement) = (2 + 2 * index, 3 + 2 * index);
+
+            if *is_bool {
+                for (state, color, value) in [("on", "green", 1), ("off", "red", 0)] {
+                    menu.push(Instruction::PlaceCommandLiteral(format!(
+                        "execute if score {} matches {} run tellraw @s {}",
+                        slot,
+                        value,
+                        json!(["", { "text": format!("{}: ", name) }, { "text": state, "color": color }, " ", button("[toggle]", "yellow", increment)])
+                    )));
+                }
+
+                dispatch.push(Instruction::PlaceCommandLiteral(format!(
+                    "execute if score @s {} matches {} store success score {} if score {} matches 0",
+                    objective, increment, slot, slot
+                )));
+            } else {
+                menu.push(Instruction::PlaceCommandLiteral(format!(
+                    "tellraw @s {}",
+                    json!([
+                        "",
+                        { "text": format!("{}: ", name) },
+                        { "score": { "name": slot.entry.as_str(), "objective": slot.objective.to_string() }, "color": "aqua" },
+                        " ",
+                        button("[-]", "red", decrement),
+                        " ",
+                        button("[+]", "green", increment),
+                    ])
+                )));
+
+                for (value, change) in [(increment, "add"), (decrement, "remove")] {
+                    dispatch.push(Instruction::PlaceCommandLiteral(format!(
+                        "execute if score @s {} matches {} run scoreboard players {} {} 1",
+                        objective, value, change, slot
+                    )));
+                }
+            }
+
+            dispatch.push(Instruction::SaveValueToStorage {
+                source: location,
+                storage: storage.clone(),
+                path: name.clone(),
+            });
+        }
+
+        // the menu is shown again after every change so that the new value can be seen
+        dispatch.push(Instruction::PlaceCommandLiteral(format!("function {}", show)));
+        dispatch.push(Instruction::PlaceCommandLiteral(format!("scoreboard players set @s {} 0", objective)));
+
+        let load = vec![Instruction::PlaceCommandLiteral(format!("scoreboard objectives add {} trigger", objective))];
+
+        let tick = vec![
+            Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[tag={},scores={{{}=1..}}] run function {}",
+                admin_tag,
+                objective,
+                self.function_location("zz_settings/dispatch")
+            )),
+            Instruction::PlaceCommandLiteral(format!("scoreboard players enable @a[tag={}] {}", admin_tag, objective)),
+        ];
+
+        for (name, body, hook) in [
+            ("zz_settings/load", load, Some(FunctionHook::Load)),
+            ("zz_settings/show", menu, None),
+            ("zz_settings/dispatch", dispatch, None),
+            ("zz_settings/tick", tick, Some(FunctionHook::Tick)),
+        ] {
+            let func = self.generated_function(name, body, hook);
+            self.compiled_funcs.push(func);
+        }
+    }
+
+    // Generates the plumbing for the commands block. Running `/trigger <pack>` on its own sets the score to 1, which shows
+    // the help message, while `/trigger <pack> set <2 + i>` runs sub-command i. zz_commands/tick runs zz_commands/dispatch
+    // as every player that has set the objective, and zz_commands/run does the same for a sub-command given by name as a
+    // macro argument, e.g `/function <pack>:zz_commands/run {command:"heal"}`. Anything that isn't a sub-command shows the help
+    fn compile_commands(&mut self, subcommands: &[ParserNode]) {
+        let objective = commands_objective(&self.pack_name);
+        let storage = values_storage(&self.pack_name);
+        let help = self.function_location("zz_commands/help");
+
+        let mut menu = vec![Instruction::PlaceCommandLiteral(format!(
+            "tellraw @s {}",
+            json!({ "text": format!("{} commands", self.pack_name), "bold": true })
+        ))];
+        let mut dispatch = vec![Instruction::PlaceCommandLiteral(format!(
+            "execute if score @s {} matches 1 run function {}",
+            objective, help
+        ))];
+        let mut run = vec![Instruction::PlaceCommandLiteral(format!(
+            "$data modify storage {} command set value \"$(command)\"",
+            storage
+        ))];
+
+        for (index, subcommand) in subcommands.iter().enumerate() {
+            let (name, function, description) = match subcommand.kind() {
+                ParserNodeKind::Subcommand { name, function, description } => (name, function, description),
+                _ => unreachable!(),
+            };
+
+            let function = &self.tags.find_function_name(subcommand).unwrap_or(function).to_string();
+            let value = 2 + index;
+            let trigger = format!("/trigger {} set {}", objective, value);
+            let signature = self
+                .global_functions
+                .get(&ResourceLocation::new(self.pack_name.clone(), function.clone()))
+                .unwrap();
+            let call = match &signature.attributes().execute {
+                Some(execute) => format!("execute {} run function {}", execute, self.function_location(function)),
+                None => format!("function {}", self.function_location(function)),
+            };
+
+            menu.push(Instruction::PlaceCommandLiteral(format!(
+                "tellraw @s {}",
+                json!([
+                    "",
+                    {
+                        "text": name,
+                        "color": "green",
+                        "clickEvent": { "action": "run_command", "value": trigger },
+                        "hoverEvent": { "action": "show_text", "contents": trigger },
+                    },
+                    description.as_ref().map(|description| format!(" - {}", description)).unwrap_or_default(),
+                ])
+            )));
+
+            dispatch.push(Instruction::PlaceCommandLiteral(format!(
+                "execute if score @s {} matches {} run {}",
+                objective, value, call
+            )));
+
+            run.push(Instruction::PlaceCommandLiteral(format!(
+                "execute if data storage {} {{command:\"{}\"}} run return run {}",
+                storage, name, call
+            )));
+        }
+
+        dispatch.push(Instruction::PlaceCommandLiteral(format!(
+            "execute if score @s {} matches {}.. run function {}",
+            objective,
+            subcommands.len() + 2,
+            help
+        )));
+        dispatch.push(Instruction::PlaceCommandLiteral(format!("scoreboard players set @s {} 0", objective)));
+
+        run.push(Instruction::PlaceCommandLiteral(format!("function {}", help)));
+
+        let load = vec![Instruction::PlaceCommandLiteral(format!("scoreboard objectives add {} trigger", objective))];
+
+        let tick = vec![
+            Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[scores={{{}=1..}}] at @s run function {}",
+                objective,
+                self.function_location("zz_commands/dispatch")
+            )),
+            Instruction::PlaceCommandLiteral(format!("scoreboard players enable @a {}", objective)),
+        ];
+
+        for (name, body, hook) in [
+            ("zz_commands/load", load, Some(FunctionHook::Load)),
+            ("zz_commands/help", menu, None),
+            ("zz_commands/dispatch", dispatch, None),
+            ("zz_commands/run", run, None),
+            ("zz_commands/tick", tick, Some(FunctionHook::Tick)),
+        ] {
+            let func = self.generated_function(name, body, hook);
+            self.compiled_funcs.push(func);
+        }
+    }
+
+    // Generates zz_every/load, which starts the cycle of every self-rescheduling function when the pack is loaded.
+    // Scheduling replaces any pending schedule of the same function, so reloading doesn't start a cycle twice
+    fn compile_schedule_hook(&mut self, schedules: Vec<String>) {
+        let body = schedules
+            .into_iter()
+            .map(|schedule| Instruction::PlaceCommandLiteral(format!("{} replace", schedule)))
+            .collect();
+
+        let func = self.generated_function("zz_every/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
+    }
+
+    // Generates the functions that keep player variables working:
+    // zz_players/load creates an objective per variable, and zz_players/tick runs zz_players/init
+    // as every player that hasn't joined before, which sets their variables to the declared defaults
+    fn compile_player_hooks(&mut self, defaults: &[(String, i32, Option<String>)]) {
+        let joined_tag = format!("{}.joined", self.pack_name);
+        let init = self.function_location("zz_players/init");
+
+        let load = defaults
+            .iter()
+            .flat_map(|(name, _, display)| {
+                let objective = player_objective(&self.pack_name, name);
+
+                // an objective that already exists keeps its old display name unless it's changed
+                let add = format!("scoreboard objectives add {} dummy", objective);
+                let modify = display
+                    .as_ref()
+                    .map(|display| format!("scoreboard objectives modify {} displayname {}", objective, display));
+
+                std::iter::once(add).chain(modify)
+            })
+            .map(Instruction::PlaceCommandLiteral)
+            .collect();
+
+        let mut init_body = defaults
+            .iter()
+            .map(|(name, default, _)| Instruction::SetValueToConstant {
+                target: ValueLocation::player(player_objective(&self.pack_name, name)),
+                constant: *default,
+            })
+            .collect::<Vec<Instruction>>();
+
+        init_body.push(Instruction::PlaceCommandLiteral(format!("tag @s add {}", joined_tag)));
+
+        let tick = vec![Instruction::PlaceCommandLiteral(format!(
+            "execute as @a[tag=!{}] run function {}",
+            joined_tag, init
+        ))];
+
+        for (name, body, hook) in [
+            ("zz_players/load", load, Some(FunctionHook::Load)),
+            ("zz_players/init", init_body, None),
+            ("zz_players/tick", tick, Some(FunctionHook::Tick)),
+        ] {
+            let func = self.generated_function(name, body, hook);
+            self.compiled_funcs.push(func);
+        }
+    }
+
+    // A state machine keeps the index of its current state in the pack's states objective, under a fake player named after it.
+    // Going to a state calls zz_sm_<machine>_to_<state>, which dispatches on the current state to the function of
+    // the matching transition. That function runs the current state's on_exit, updates the score and runs the new state's on_enter.
+    // Changing to a state that the current state has no transition to does nothing
+    fn compile_state_machine(&mut self, machine: &str, states: &[ParserNode], transitions: &[ParserNode]) {
+        let mut state_names = Vec::new();
+
+        for state in states {
+            let (name, on_enter, on_exit) = match state.kind() {
+                ParserNodeKind::State { name, on_enter, on_exit } => (name, on_enter, on_exit),
+                _ => unreachable!(),
+            };
+
+            for (hook, body) in [("enter", on_enter), ("exit", on_exit)] {
+                if let Some(body) = body {
+                    let func = self.compile_body_function(&format!("zz_sm_{}_{}_{}", machine, name, hook), Vec::new(), body);
+                    self.compiled_funcs.push(func);
+                }
+            }
+
+            state_names.push((name.clone(), on_enter.is_some(), on_exit.is_some()));
+        }
+
+        let states = states_objective(&self.pack_name);
+        let set_state = |index: usize| {
+            Instruction::PlaceCommandLiteral(format!("scoreboard players set {} {} {}", machine, states, index))
+        };
+        let call = |this: &Self, name: String| Instruction::Call {
+            function: this.function_location(&name),
+            execute: None,
+        };
+
+        // entering the first state when the machine is first loaded
+        let mut init = vec![set_state(0)];
+
+        if state_names[0].1 {
+            init.push(call(self, format!("zz_sm_{}_{}_enter", machine, state_names[0].0)));
+        }
+
+        let func = self.generated_function(&format!("zz_sm_{}_init", machine), init, None);
+        self.compiled_funcs.push(func);
+
+        for (to_index, (to, has_enter, _)) in state_names.iter().enumerate() {
+            // the current state is copied so that a transition which has already run can't be matched again
+            let mut dispatch = vec![Instruction::PlaceCommandLiteral(format!(
+                "scoreboard players operation {}.from {} = {} {}",
+                machine, states, machine, states
+            ))];
+
+            for transition in transitions {
+                let from = match transition.kind() {
+                    ParserNodeKind::StateTransition { from, to: target } if target == to => from,
+                    _ => continue,
+                };
+
+                let from_index = state_names.iter().position(|(name, ..)| name == from).unwrap();
+                let has_exit = state_names[from_index].2;
+                let name = format!("zz_sm_{}_{}_to_{}", machine, from, to);
+
+                let mut body = Vec::new();
+
+                if has_exit {
+                    body.push(call(self, format!("zz_sm_{}_{}_exit", machine, from)));
+                }
+
+                body.push(set_state(to_index));
+
+                if *has_enter {
+                    body.push(call(self, format!("zz_sm_{}_{}_enter", machine, to)));
+                }
+
+                dispatch.push(Instruction::PlaceCommandLiteral(format!(
+                    "execute if score {}.from {} matches {} run function {}",
+                    machine,
+                    states,
+                    from_index,
+                    self.function_location(&name)
+                )));
+
+                let func = self.generated_function(&name, body, None);
+                self.compiled_funcs.push(func);
+            }
+
+            let func = self.generated_function(&format!("zz_sm_{}_to_{}", machine, to), dispatch, None);
+            self.compiled_funcs.push(func);
+        }
+    }
+
+    // Compiles a region's handlers and returns the checks that zz_regions/tick runs for it.
+    // Players inside a region carry a tag, so entering and leaving are detected by comparing the tag to their position.
+    // The handlers run as and at the player that entered or left
+    fn compile_region(
+        &mut self,
+        name: &str,
+        from: (i32, i32, i32),
+        to: (i32, i32, i32),
+        on_enter: &Option<Box<ParserNode>>,
+        on_leave: &Option<Box<ParserNode>>,
+    ) -> Vec<Instruction> {
+        let tag = format!("{}.region.{}", self.pack_name, name);
+        let volume = format!(
+            "x={},y={},z={},dx={},dy={},dz={}",
+            from.0.min(to.0),
+            from.1.min(to.1),
+            from.2.min(to.2),
+            (to.0 - from.0).abs(),
+            (to.1 - from.1).abs(),
+            (to.2 - from.2).abs()
+        );
+
+        for (hook, body, tag_command) in [
+            ("enter", on_enter, format!("tag @s add {}", tag)),
+            ("leave", on_leave, format!("tag @s remove {}", tag)),
+        ] {
+            let func_name = format!("zz_region_{}_{}", name, hook);
+            let prelude = vec![Instruction::PlaceCommandLiteral(tag_command)];
+
+            let func = match body {
+                Some(body) => self.compile_body_function(&func_name, prelude, body),
+                None => self.generated_function(&func_name, prelude, None),
+            };
+
+            self.compiled_funcs.push(func);
+        }
+
+        vec![
+            Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[tag={}] at @s unless entity @s[{}] run function {}",
+                tag,
+                volume,
+                self.function_location(&format!("zz_region_{}_leave", name))
+            )),
+            Instruction::PlaceCommandLiteral(format!(
+                "execute as @a[tag=!{},{}] at @s run function {}",
+                tag,
+                volume,
+                self.function_location(&format!("zz_region_{}_enter", name))
+            )),
+        ]
+    }
+
+    // Generates zz_sm/load, which creates the objective holding the state of every machine,
+    // and puts machines that don't have a state yet into their first one
+    fn compile_state_machine_hook(&mut self, machines: &[String]) {
+        let states = states_objective(&self.pack_name);
+        let mut body = vec![Instruction::PlaceCommandLiteral(format!("scoreboard objectives add {} dummy", states))];
+
+        for machine in machines {
+            body.push(Instruction::PlaceCommandLiteral(format!(
+                "execute unless score {} {} matches 0.. run function {}",
+                machine,
+                states,
+                self.function_location(&format!("zz_sm_{}_init", machine))
+            )));
+        }
+
+        let func = self.generated_function("zz_sm/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
+    }
+
+    // Generates zz_tables/load, which writes every table to storage as an int array so that its elements can be read by index.
+    // Only the first TABLE_CHUNK_LEN elements of a table are written when the pack loads, so that large tables don't
+    // make loading lag. The rest are appended a chunk at a time by zz_tables/chunk_1, chunk_2, ..., each of which runs
+    // a tick after the one before it, so they can't be read until a few ticks after the pack has loaded
+    fn compile_table_hook(&mut self) {
+        let storage = tables_storage(&self.pack_name);
+        let array = |values: &[i32]| format!("[I;{}]", values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(","));
+
+        let mut body = Vec::new();
+        let mut chunks = Vec::new();
+
+        for (name, values) in &self.tables {
+            let mut parts = values.chunks(TABLE_CHUNK_LEN);
+
+            body.push(Instruction::PlaceCommandLiteral(format!(
+                "data modify storage {} {} set value {}",
+                storage,
+                name,
+                array(parts.next().unwrap_or_default())
+            )));
+
+            // an int array can't be appended to in one go, so the chunk is written next to it and its elements are
+            // appended from there
+            for part in parts {
+                chunks.push(vec![
+                    Instruction::PlaceCommandLiteral(format!("data modify storage {} zz_chunk set value {}", storage, array(part))),
+                    Instruction::PlaceCommandLiteral(format!("data modify storage {} {} append from storage {} zz_chunk[]", storage, name, storage)),
+                ]);
+            }
+        }
+
+        if !chunks.is_empty() {
+            body.push(Instruction::PlaceCommandLiteral(format!("schedule function {} 1t", self.function_location("zz_tables/chunk_1"))));
+        }
+
+        let count = chunks.len();
+
+        for (i, mut chunk) in chunks.into_iter().enumerate() {
+            let next = match i + 1 < count {
+                true => format!("schedule function {} 1t", self.function_location(&format!("zz_tables/chunk_{}", i + 2))),
+                false => format!("data remove storage {} zz_chunk", storage),
+            };
+
+            chunk.push(Instruction::PlaceCommandLiteral(next));
+
+            let func = self.generated_function(&format!("zz_tables/chunk_{}", i + 1), chunk, None);
+            self.compiled_funcs.push(func);
+        }
+
+        let func = self.generated_function("zz_tables/load", body, Some(FunctionHook::Load));
+        self.compiled_funcs.push(func);
+    }
+}
+
+/// The function tags that a generated function can be added to, so that the game runs it without it being called.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionHook {
+    // minecraft:load, runs whenever the datapack is (re)loaded
+    Load,
+    // minecraft:tick, runs every game tick
+    Tick,
+}
+
+impl FunctionHook {
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            FunctionHook::Load => "load",
+            FunctionHook::Tick => "tick",
+        }
+    }
+}
+
+// Returns the parts of a match arm's range that the ranges in covered don't include, then adds the range to covered.
+// The bounds are widened to i64 so that stepping past the ends of the int range doesn't overflow
+pub(super) fn claim_range(pattern: MatchPattern, covered: &mut Vec<(i64, i64)>) -> Vec<MatchPattern> {
+    let min = pattern.min.map_or(i32::MIN as i64, |min| min as i64);
+    let max = pattern.max.map_or(i32::MAX as i64, |max| max as i64);
+
+    let mut uncovered = Vec::new();
+    let mut start = min;
+
+    covered.sort();
+
+    for &(covered_min, covered_max) in covered.iter() {
+        if covered_min > max {
+            break;
+        }
+
+        if covered_min > start {
+            uncovered.push((start, covered_min - 1));
+        }
+
+        start = start.max(covered_max + 1);
+    }
+
+    if start <= max {
+        uncovered.push((start, max));
+    }
+
+    covered.push((min, max));
+
+    uncovered
+        .into_iter()
+        .map(|(min, max)| MatchPattern {
+            min: (min != i32::MIN as i64).then_some(min as i32),
+            max: (max != i32::MAX as i64).then_some(max as i32),
+        })
+        .collect()
+}
+
+// Pairs the names of the ints given to int_args_command with the arguments at the same index, without any offset
+fn int_args<'n>(names: &[&'n str]) -> Vec<(&'n str, usize, i32)> {
+    names.iter().enumerate().map(|(i, name)| (*name, i, 0)).collect()
+}
+
+// The storage that emitted messages are passed through, which is shared by every pack so that they can talk to each other.
+// Each message has a function tag of the same name in it, which every pack adds its subscribers to
+fn bus_storage() -> ResourceLocation {
+    ResourceLocation::new("sculk".to_string(), "bus".to_string())
+}
+
+// Config values are read into the scores v0, v1, ... of the <pack>.config objective, in the order they were declared
+fn config_location(pack_name: &str, index: usize) -> ValueLocation {
+    ValueLocation::new(index, 0, Objective(format!("{}.config", pack_name)))
+}
+
+fn settings_objective(pack_name: &str) -> String {
+    format!("{}.settings", pack_name)
+}
+
+// The trigger objective of the commands block, which is named after the pack so that players run `/trigger <pack>`
+fn commands_objective(pack_name: &str) -> String {
+    pack_name.to_string()
+}
+
+fn config_storage(pack_name: &str) -> ResourceLocation {
+    ResourceLocation::new(pack_name.to_string(), "config".to_string())
+}
+
+/// The most elements of a table that are written to storage by a single command, see compile_table_hook.
+const TABLE_CHUNK_LEN: usize = 4096;
+
+fn tables_storage(pack_name: &str) -> ResourceLocation {
+    ResourceLocation::new(pack_name.to_string(), "tables".to_string())
+}
+
+// The number of loop iterations that have run this tick, when loops are rate limited
+fn budget_location(pack_name: &str) -> ValueLocation {
+    ValueLocation::new(0, 0, Objective(format!("{}.budget", pack_name)))
+}
+
+// The objective that holds the current state of every state machine, named after the pack so that machines with the same
+// name in two packs don't share their state
+fn states_objective(pack_name: &str) -> Objective {
+    Objective(format!("{}.states", pack_name))
+}
+
+// A player variable has an objective of its own, which is named after the pack so that common names like "deaths" don't
+// collide with the objectives of other packs
+fn player_objective(pack_name: &str, name: &str) -> Objective {
+    Objective(format!("{}.{}", pack_name, name))
+}
+
+fn error_location(pack_name: &str) -> ValueLocation {
+    ValueLocation::new(0, 0, Objective(format!("{}.error", pack_name)))
+}
+
+pub(super) fn values_storage(pack_name: &str) -> ResourceLocation {
+    ResourceLocation::new(pack_name.to_string(), "values".to_string())
+}
+
+// Strings and arrays still take up a slot like any other value, but their contents are kept in storage under a path
+// named after that slot, since scores can only hold numbers
+pub(super) fn storage_path(location: &ValueLocation) -> String {
+    format!("\"{}\".v{}_{}", location.objective, location.slot, location.offset)
+}
+
+// The values of functions that are waiting for an await to be over, see IrFunctionBuilder::emit_keep_values
+fn awaits_storage(pack_name: &str) -> ResourceLocation {
+    ResourceLocation::new(pack_name.to_string(), "awaits".to_string())
+}
+
+// Collects the values of a function that a sequence of instructions, including the blocks in it, mentions in slots
+// below the given one. Each is paired with whether it's the storage behind a string or array rather than its score
+fn mentioned_values(body: &[Instruction], objective: &Objective, below: usize, values: &mut BTreeSet<(ValueLocation, bool)>) {
+    for instr in body {
+        let (locations, literal) = match instr {
+            Instruction::SetValueToValue { source, target }
+            | Instruction::ValueBinaryOperation { source, target, .. }
+            | Instruction::SetValueToMatch { source, target, .. } => (vec![source.clone(), target.clone()], None),
+            Instruction::LongBinaryOperation { source, target, .. } => {
+                (vec![source.clone(), source.offset(1), target.clone(), target.offset(1)], None)
+            }
+            Instruction::SetValueToConstant { target, .. }
+            | Instruction::ToggleValue { target }
+            | Instruction::ModifyValue { target, .. }
+            | Instruction::LoadValueFromStorage { target, .. } => (vec![target.clone()], None),
+            Instruction::IfValueMatchesRunBlock { source, .. }
+            | Instruction::IfValueInRangeRunBlock { source, .. }
+            | Instruction::SaveValueToStorage { source, .. } => (vec![source.clone()], None),
+            Instruction::Return { source, size } => {
+                (source.iter().flat_map(|source| (0..*size).map(|i| source.offset(i))).collect(), None)
+            }
+            Instruction::SetValueToCommandSuccess { target, command } | Instruction::SetValueToCommandResult { target, command } => {
+                (vec![target.clone()], Some(command))
+            }
+            Instruction::SetValueToConditionSuccess { target, condition } => (vec![target.clone()], Some(condition)),
+            Instruction::PlaceCommandLiteral(literal) | Instruction::RunBlockAs { execute: literal, .. } => (Vec::new(), Some(literal)),
+            Instruction::Call { execute, .. } => (Vec::new(), execute.as_ref()),
+            Instruction::CreateBlock { body, .. } => {
+                mentioned_values(body, objective, below, values);
+                continue;
+            }
+            _ => continue,
+        };
+
+        values.extend(locations.into_iter().map(|location| (location, false)));
+
+        if let Some(literal) = literal {
+            literal_values(literal, objective, values);
+        }
+    }
+
+    values.retain(|(location, _)| !location.player && location.objective == *objective && location.slot < below);
+}
+
+// Collects the values of a function that a command literal names, which it does by the storage path of a string or
+// array, or by the score of any other value, either as "<entry> <objective>" or as the name and objective of JSON text
+fn literal_values(literal: &str, objective: &Objective, values: &mut BTreeSet<(ValueLocation, bool)>) {
+    fn number(s: &str) -> Option<(usize, &str)> {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        Some((s[..end].parse().ok()?, &s[end..]))
+    }
+
+    let name = objective.to_string();
+
+    for (i, _) in literal.match_indices(&name) {
+        let (before, after) = (&literal[..i], &literal[i + name.len()..]);
+
+        let path = after.strip_prefix("\".v").filter(|_| before.ends_with('"'));
+        if let Some((slot, rest)) = path.and_then(number) {
+            if let Some((offset, _)) = rest.strip_prefix('_').and_then(number) {
+                values.insert((ValueLocation::new(slot, offset, objective.clone()), true));
+            }
+        }
+
+        let Some(entry) = before.strip_suffix(' ').or_else(|| before.strip_suffix("\",\"objective\":\"")) else {
+            continue;
+        };
+        let entry = &entry[entry.rfind(|c: char| !c.is_ascii_alphanumeric() && c != '.').map_or(0, |i| i + 1)..];
+
+        if let Some((slot, rest)) = entry.strip_prefix('v').and_then(number) {
+            let offset = match rest.strip_prefix('.').and_then(number) {
+                Some((offset, "")) => offset,
+                _ if rest.is_empty() => 0,
+                _ => continue,
+            };
+
+            values.insert((ValueLocation::new(slot, offset, objective.clone()), false));
+        }
+    }
+}
+
+// The objective of the scores that the math helpers work with, which is the pack's own so that packs running them in
+// the same tick don't share their arguments and scratch values
+fn math_objective(pack_name: &str) -> Objective {
+    Objective(format!("{}.math", pack_name))
+}
+
+// Whether a sequence of instructions, including the blocks in it, calls any of the given functions
+fn calls_any(body: &[Instruction], functions: &HashSet<ResourceLocation>) -> bool {
+    body.iter().any(|instr| match instr {
+        Instruction::Call { function, .. } => functions.contains(function),
+        Instruction::CreateBlock { body, .. } => calls_any(body, functions),
+        _ => false,
+    })
+}
+
+// The scores that the math helpers take their arguments in and return their results in
+fn math_location(pack_name: &str, index: usize) -> ValueLocation {
+    ValueLocation::new(index, 0, math_objective(pack_name))
+}
+
+fn click_objective(pack_name: &str) -> String {
+    format!("{}.click", pack_name)
+}
+
+// A click callback is told apart by the value that its text component sets the trigger objective to,
+// which is its position among the pack's functions sorted by name so that it doesn't change between compilations
+fn callback_id(global_functions: &HashMap<ResourceLocation, FunctionSignature>, name: &str) -> usize {
+    1 + global_functions
+        .keys()
+        .filter(|location| location.path.as_str() < name)
+        .count()
+}
+
+/// A function that has been compiled into Sculk IR.
+#[derive(Debug)]
+pub struct IrFunction {
+    objective: Objective,
+    body: Vec<Instruction>,
+    signature: FunctionSignature,
+    hook: Option<FunctionHook>,
+    // commands that must run when the pack loads for this function to work, such as creating a bossbar it uses
+    setup: Vec<String>,
+    // functions that this function's text components run on click through the trigger objective
+    callbacks: Vec<String>,
+    // the names and commands of functions that this function calls, which are generated once per pack
+    helpers: Vec<(String, Vec<String>)>,
+    // the message whose function tag this function is added to, so that it runs whenever the message is emitted
+    message: Option<String>,
+}
+
+impl IrFunction {
+    fn new(objective: Objective, body: Vec<Instruction>, signature: FunctionSignature) -> Self {
+        Self {
+            objective,
+            body,
+            signature,
+            hook: None,
+            setup: Vec::new(),
+            callbacks: Vec::new(),
+            helpers: Vec::new(),
+            message: None,
+        }
+    }
+
+    pub fn objective(&self) -> &Objective {
+        &self.objective
+    }
+
+    pub fn body(&self) -> &[Instruction] {
+        &self.body
+    }
+
+    pub fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+
+    pub fn hook(&self) -> Option<FunctionHook> {
+        self.hook
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+/// A helper struct that assists in building Sculk IR functions.
+struct IrFunctionBuilder<'a> {
+    body: Vec<Instruction>,
+    locals: HashMap<String, usize>,
+    next_slot: usize,
+    blocks: Vec<Vec<Instruction>>,
+    objective: Objective,
+    pack_name: String,
+    src: &'a str,
+    next_block_id: &'a mut usize,
+    global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
+    types: &'a TypePool,
+    tags: &'a TagPool<'a>,
+    signature: &'a FunctionSignature,
+    loop_budget: Option<u32>,
+    pack_format: Option<u32>,
+    release: bool,
+    // the execute subcommands that the next call runs behind, from run_as
+    call_prefix: Option<String>,
+    // the labels of the loops that are being visited, from the outermost one in
+    loops: Vec<Option<String>>,
+    // the block that the statements after the loop being visited were put in, and the value that's set while the loop
+    // is deferred, when --loop-budget may defer it, see visit_block
+    after_loop: Option<(usize, ValueLocation)>,
+    setup: Vec<String>,
+    callbacks: Vec<String>,
+    helpers: Vec<(String, Vec<String>)>,
+    // the JSON text of the function's text variables, which is built where they're declared
+    texts: HashMap<String, Value>,
+}
+
+impl<'a> IrFunctionBuilder<'a> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        signature: &'a FunctionSignature,
+        objective: Objective,
+        pack_name: String,
+        src: &'a str,
+        next_block_id: &'a mut usize,
+        global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
+        types: &'a TypePool,
+        tags: &'a TagPool,
+        loop_budget: Option<u32>,
+        pack_format: Option<u32>,
+        release: bool,
+    ) -> Self {
+        let mut s = Self {
+            body: Vec::new(),
+            locals: HashMap::new(),
+            next_slot: 0,
+            blocks: Vec::new(),
+            objective,
+            pack_name,
+            src,
+            next_block_id,
+            global_functions,
+            types,
+            tags,
+            signature,
+            loop_budget,
+            pack_format,
+            release,
+            call_prefix: None,
+            loops: Vec::new(),
+            after_loop: None,
+            setup: Vec::new(),
+            callbacks: Vec::new(),
+            helpers: Vec::new(),
+            texts: HashMap::new(),
+        };
+
+        // Give the first local indices to the function parameters
+        for (idx, param) in signature.params().iter().enumerate() {
+            s.locals.insert(param.name().to_owned(), idx);
+            s.next_slot += 1;
+        }
+
+        // the function stops before doing anything if the executing entity lacks the permission,
+        // and the message telling them so is sent by a helper that every such function shares
+        if let Some(tag) = &signature.attributes().permission {
+            let helper = "zz_permission/denied".to_string();
+
+            s.helpers.push((
+                helper.clone(),
+                vec![format!(
+                    "tellraw @s {}",
+                    json!({ "text": "You don't have permission to do that", "color": "red" })
+                )],
+            ));
+
+            s.emit(Instruction::PlaceCommandLiteral(format!(
+                "execute unless entity @s[tag={}] run return run function {}",
+                tag,
+                ResourceLocation::new(s.pack_name.clone(), helper)
+            )));
+        }
+
+        s
+    }
+
+    fn finish(self) -> IrFunction {
+        // TODO: avoid clone
+        let mut func = IrFunction::new(self.objective, self.body, self.signature.clone());
+        func.setup = self.setup;
+        func.callbacks = self.callbacks;
+        func.helpers = self.helpers;
+        func
+    }
+
+    fn emit(&mut self, instr: Instruction) {
+        match self.blocks.last_mut() {
+            Some(block) => block.push(instr),
+            None => self.body.push(instr),
+        }
+    }
+
+    // copies a value of the given type, moving the storage behind any strings and arrays along with the scores
+    fn emit_value_copy(&mut self, target: ValueLocation, source: ValueLocation, ty: TypeKey) {
+        let storage_slots = ty.from(&self.types).storage_slots(&self.types);
+
+        for (i, in_storage) in storage_slots.into_iter().enumerate() {
+            let source = source.offset(i);
+            let target = target.offset(i);
+
+            if in_storage {
+                let storage = values_storage(&self.pack_name);
+                self.emit(Instruction::PlaceCommandLiteral(format!(
+                    "data modify storage {} {} set from storage {} {}",
+                    storage,
+                    storage_path(&target),
+                    storage,
+                    storage_path(&source)
+                )));
+            } else {
+                self.emit(Instruction::SetValueToValue { source, target });
+            }
+        }
+    }
+
+    fn get_local(&mut self, name: &str) -> ValueLocation {
+        match self.locals.get(name) {
+            Some(idx) => ValueLocation::new(*idx, 0, self.objective.clone()),
+            None => {
+                let idx = self.next_slot;
+                self.next_slot += 1;
+                self.locals.insert(name.to_owned(), idx);
+                ValueLocation::new(idx, 0, self.objective.clone())
+            }
+        }
+    }
+
+    fn get_free_location(&mut self) -> ValueLocation {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        ValueLocation::new(slot, 0, self.objective.clone())
+    }
+
+    fn create_block(is_loop: bool, node: &ParserNode, builder: &mut Self, emitted: impl FnOnce(usize, &mut Self)) -> usize {
+        let id = *builder.next_block_id;
+        *builder.next_block_id += 1;
+        let line = builder.src[..node.span().start].matches('\n').count() + 1;
+
+        builder.blocks.push(Vec::new());
+
+        emitted(id, builder);
+
+        let body = builder.blocks.pop().unwrap();
+
+        builder.emit(Instruction::CreateBlock { id, is_loop, line, body });
+
+        id
+    }
+
+    // Takes in a function's name, signature and its respective body in the AST, and compiles it into Sculk IR
+    fn compile_function(&mut self, body: &'a [ParserNode]) {
+        for node in body {
+            self.visit_node(node);
+        }
+    }
+
+    fn visit_node(&mut self, node: &ParserNode) -> ValueLocation {
+        let value = self.visit_node_kind(node);
+
+        match self.tags.find_optional(node) {
+            Some(optional) => self.wrap_optional(node, value, optional),
+            None => value,
+        }
+    }
+
+    fn visit_node_kind(&mut self, node: &ParserNode) -> ValueLocation {
+        match node.kind() {
+            ParserNodeKind::NumberLiteral(n) => self.visit_number_literal(*n),
+            ParserNodeKind::BoolLiteral(b) => self.visit_bool_literal(*b),
+            ParserNodeKind::Identifier(_) => self.visit_identifier(node),
+            ParserNodeKind::VariableDeclaration { name, expr, .. } => {
+                self.visit_variable_declaration(name.as_identifier(), expr);
+                ValueLocation::dummy()
             }
             ParserNodeKind::VariableAssignment { path, expr } => {
                 self.visit_variable_assignment(path, expr);
                 ValueLocation::dummy()
             }
-            ParserNodeKind::Expression(expr) => self.visit_node(expr),
-            ParserNodeKind::Operation(lhs, rhs, op) => self.visit_binary_operation(lhs, rhs, *op),
-            ParserNodeKind::Unary(expr, op) => self.visit_unary_operation(expr, *op),
-            ParserNodeKind::OpEquals { path, expr, op } => {
-                self.visit_operation_equals(path, expr, *op);
-                ValueLocation::dummy()
+            ParserNodeKind::DataPath { source, path } => self.visit_data_path(source, path),
+            ParserNodeKind::Expression(expr) => self.visit_node(expr),
+            ParserNodeKind::Operation(lhs, rhs, op) => self.visit_binary_operation(lhs, rhs, *op),
+            ParserNodeKind::Unary(expr, op) => self.visit_unary_operation(expr, *op),
+            ParserNodeKind::Cast { expr, .. } => self.visit_cast(node, expr),
+            // null only has a value once it's wrapped into the optional it's used as
+            ParserNodeKind::Null => ValueLocation::dummy(),
+            // selectors are put into the commands that use them, so they have no value of their own
+            ParserNodeKind::SelectorLiteral(_) => ValueLocation::dummy(),
+            // as is text, which is built into JSON text by the builtins that show it
+            ParserNodeKind::TextLiteral { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Coalesce { expr, default } => self.visit_coalesce(expr, default),
+            ParserNodeKind::Conditional { cond, then, otherwise } => self.visit_conditional(node, cond, then, otherwise),
+            ParserNodeKind::IfLet {
+                name,
+                expr,
+                body,
+                else_body,
+            } => {
+                self.visit_if_let(name, expr, body, else_body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::OpEquals { path, expr, op } => {
+                self.visit_operation_equals(path, expr, *op);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::FunctionCall { .. } => match self.visit_function_call(node) {
+                Some(target) => target,
+                None => ValueLocation::dummy(),
+            },
+            ParserNodeKind::RunAs { selector, call } => {
+                self.call_prefix = Some(format!("as {}", self.target_arg(selector)));
+
+                match self.visit_function_call(call) {
+                    Some(target) => target,
+                    None => ValueLocation::dummy(),
+                }
+            }
+            ParserNodeKind::Block(body) => {
+                self.visit_block(body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::Return(expr) => {
+                self.visit_return(expr);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::Break(label) => {
+                self.visit_break(label);
+                ValueLocation::dummy()
+            }
+            // awaits split the block they're in, so they're handled when visiting it
+            ParserNodeKind::Await(_) => unreachable!(),
+            ParserNodeKind::If {
+                cond,
+                body,
+                else_body,
+                ..
+            } => {
+                self.visit_if(cond, body, else_body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::For {
+                attributes,
+                label,
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                self.visit_for(attributes, label, init, cond, step, body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::Match { expr, arms } => {
+                self.visit_match(expr, arms);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::CommandLiteral(cmd) => {
+                self.emit(Instruction::PlaceCommandLiteral(cmd.to_owned()));
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::CommandResult(cmd) => {
+                let target = self.get_free_location();
+
+                self.emit(Instruction::SetValueToCommandResult {
+                    target: target.clone(),
+                    command: cmd.to_owned(),
+                });
+
+                target
+            }
+            ParserNodeKind::ForEntities { selector, body, .. } => {
+                self.visit_for_entities(selector, body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::ContextBlock { subcommands, body } => {
+                self.visit_context_block(subcommands, body);
+                ValueLocation::dummy()
+            }
+            ParserNodeKind::MemberAccess { expr, member } => match self.tags.find_resolution(node).map(Resolution::last) {
+                Some(ResolvedPart::Variant(_, value)) => self.visit_number_literal(*value),
+                _ => self.visit_member_access(expr, member.as_identifier()),
+            },
+            ParserNodeKind::Index { expr, index } => self.visit_index(expr, index),
+            ParserNodeKind::ArrayLiteral(elements) => self.visit_array_literal(elements),
+            ParserNodeKind::Tuple(elements) => self.visit_tuple(node, elements),
+            ParserNodeKind::TupleDeclaration { names, expr } => {
+                self.visit_tuple_declaration(names, expr);
+                ValueLocation::dummy()
+            }
+            // the below nodes don't need any work, they've been handled by previous phases of compilation
+            ParserNodeKind::Program(_) => ValueLocation::dummy(),
+            ParserNodeKind::TypedIdentifier { .. } => ValueLocation::dummy(),
+            ParserNodeKind::StringLiteral(text) => self.visit_string_literal(text),
+            ParserNodeKind::Attribute { .. } => ValueLocation::dummy(),
+            ParserNodeKind::NamedArgument { .. } => ValueLocation::dummy(),
+            ParserNodeKind::FunctionDeclaration { .. } => ValueLocation::dummy(),
+            ParserNodeKind::StructDefinition { .. } => ValueLocation::dummy(),
+            ParserNodeKind::EnumDefinition { .. } => ValueLocation::dummy(),
+            ParserNodeKind::TraitDefinition { .. } => ValueLocation::dummy(),
+            ParserNodeKind::MethodSignature { .. } => ValueLocation::dummy(),
+            ParserNodeKind::PlayerVariableDeclaration { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Every { .. } => ValueLocation::dummy(),
+            ParserNodeKind::StateMachine { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Region { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Commands(_) | ParserNodeKind::Subcommand { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Table { .. } | ParserNodeKind::Config { .. } => ValueLocation::dummy(),
+            ParserNodeKind::State { .. } => ValueLocation::dummy(),
+            ParserNodeKind::StateTransition { .. } => ValueLocation::dummy(),
+            ParserNodeKind::Import(_) => ValueLocation::dummy(),
+            ParserNodeKind::StaticAssert { .. } => ValueLocation::dummy(),
+            ParserNodeKind::ChangeState { machine, state } => {
+                self.emit(Instruction::Call {
+                    function: ResourceLocation::new(self.pack_name.clone(), format!("zz_sm_{}_to_{}", machine, state)),
+                    execute: None,
+                });
+                ValueLocation::dummy()
+            }
+        }
+    }
+
+    fn visit_number_literal(&mut self, n: i32) -> ValueLocation {
+        let target = self.get_free_location();
+
+        self.emit(Instruction::SetValueToConstant {
+            target: target.clone(),
+            constant: n,
+        });
+
+        target
+    }
+
+    fn visit_bool_literal(&mut self, b: bool) -> ValueLocation {
+        let target = self.get_free_location();
+
+        self.emit(Instruction::SetValueToConstant {
+            target: target.clone(),
+            constant: if b { 1 } else { 0 },
+        });
+
+        target
+    }
+
+    fn visit_identifier(&mut self, identifier: &ParserNode) -> ValueLocation {
+        // a function that's named without being called is a reference to it, which holds the function's number
+        if let Some(ResolvedPart::GlobalFunction(name)) = self.tags.find_resolution(identifier).map(Resolution::last) {
+            return self.visit_number_literal(self.tags.function_ref_id(name));
+        }
+
+        // identifiers are only tagged with a resolution if they refer to something other than a local
+        let source = match self.tags.find_resolution(identifier) {
+            Some(resolution) => self.resolve_location(resolution),
+            None => self.get_local(identifier.as_identifier()),
+        };
+        let target = self.get_free_location();
+        let ty = self.tags.get_type(identifier);
+
+        self.emit_value_copy(target.clone(), source, ty);
+
+        target
+    }
+
+    fn visit_variable_declaration(&mut self, name: &str, expr: &ParserNode) {
+        // the values that text shows are evaluated here, so it shows them as they were when it was declared
+        if self.tags.get_type(expr) == self.types.text() {
+            let text = self.visit_text_component(expr);
+            self.texts.insert(name.to_string(), text);
+            return;
+        }
+
+        let source = self.visit_node(expr);
+        let target = self.get_local(name);
+
+        self.emit_value_copy(
+            target,
+            source,
+            self.tags.get_value_type(expr),
+        );
+    }
+
+    // Copies a plain value into a new optional and marks it as present. Null is wrapped into an optional that isn't
+    // present, whose value is set to 0 all the same so that copying the optional never reads a score that isn't set
+    fn wrap_optional(&mut self, node: &ParserNode, value: ValueLocation, optional: TypeKey) -> ValueLocation {
+        let target = self.get_free_location();
+        let ty = self.tags.get_type(node);
+        let size = optional.from(self.types).total_size(self.types) - 1;
+        let present = ty != self.types.null();
+
+        if present {
+            self.emit_value_copy(target.clone(), value, ty);
+        } else {
+            for offset in 0..size {
+                self.emit(Instruction::SetValueToConstant {
+                    target: target.offset(offset),
+                    constant: 0,
+                });
+            }
+        }
+
+        self.emit(Instruction::SetValueToConstant {
+            target: target.offset(size),
+            constant: present as i32,
+        });
+
+        target
+    }
+
+    // The value of the optional is copied out first, and replaced with the default if the optional turns out to be
+    // missing, so the default is only computed when it's needed
+    fn visit_coalesce(&mut self, expr: &ParserNode, default: &ParserNode) -> ValueLocation {
+        let optional = self.visit_node(expr);
+        let inner = self.tags.get_type(default);
+        let size = inner.from(self.types).total_size(self.types);
+        let target = self.get_free_location();
+
+        self.emit_value_copy(target.clone(), optional.clone(), inner);
+
+        let block = Self::create_block(false, default, self, |_, builder| {
+            let value = builder.visit_node(default);
+            builder.emit_value_copy(target.clone(), value, inner);
+        });
+
+        self.emit(Instruction::IfValueMatchesRunBlock {
+            source: optional.offset(size),
+            value: 0,
+            block,
+        });
+
+        target
+    }
+
+    // Each value is computed in a block of its own, so that only the chosen one is evaluated
+    fn visit_conditional(&mut self, node: &ParserNode, cond: &ParserNode, then: &ParserNode, otherwise: &ParserNode) -> ValueLocation {
+        let ty = self.tags.get_type(node);
+        let target = self.get_free_location();
+
+        let [then_block, otherwise_block] = [then, otherwise].map(|branch| {
+            Self::create_block(false, branch, self, |_, builder| {
+                let value = builder.visit_node(branch);
+                builder.emit_value_copy(target.clone(), value, ty);
+            })
+        });
+
+        let cond = self.visit_node(cond);
+
+        self.emit(Instruction::IfValueMatchesRunBlock {
+            source: cond.clone(),
+            value: 1,
+            block: then_block,
+        });
+
+        self.emit(Instruction::IfValueMatchesRunBlock {
+            source: cond,
+            value: 0,
+            block: otherwise_block,
+        });
+
+        target
+    }
+
+    // The value is bound to the name inside the block of the body, which only runs if the optional is present
+    fn visit_if_let(&mut self, name: &str, expr: &ParserNode, body: &ParserNode, else_body: &Option<Box<ParserNode>>) {
+        let optional = self.visit_node(expr);
+        let inner = self.types.inner_of(self.tags.get_type(expr)).unwrap();
+        let size = inner.from(self.types).total_size(self.types);
+
+        let true_body = Self::create_block(false, body, self, |_, builder| {
+            let target = builder.get_local(name);
+            builder.emit_value_copy(target, optional.clone(), inner);
+            builder.visit_node(body);
+        });
+
+        let else_body = else_body.as_ref().map(|else_body| {
+            Self::create_block(false, else_body, self, |_, builder| {
+                builder.visit_node(else_body);
+            })
+        });
+
+        self.emit(Instruction::IfValueMatchesRunBlock {
+            source: optional.offset(size),
+            value: 1,
+            block: true_body,
+        });
+
+        if let Some(else_body) = else_body {
+            self.emit(Instruction::IfValueMatchesRunBlock {
+                source: optional.offset(size),
+                value: 0,
+                block: else_body,
+            });
+        }
+    }
+
+    // The elements of a tuple are laid out one after another in the same slot, like the fields of a struct
+    fn visit_tuple(&mut self, node: &ParserNode, elements: &[ParserNode]) -> ValueLocation {
+        let target = self.get_free_location();
+        let offsets = self.tags.get_type(node).from(self.types).as_tuple_def().unwrap().offsets(self.types);
+
+        for (element, offset) in elements.iter().zip(offsets) {
+            let source = self.visit_node(element);
+            self.emit_value_copy(target.offset(offset), source, self.tags.get_type(element));
+        }
+
+        target
+    }
+
+    fn visit_tuple_declaration(&mut self, names: &[String], expr: &ParserNode) {
+        let source = self.visit_node(expr);
+        let def = self.tags.get_type(expr).from(self.types).as_tuple_def().unwrap();
+
+        for ((name, element), offset) in names.iter().zip(def.elements()).zip(def.offsets(self.types)) {
+            if name == "_" {
+                continue;
+            }
+
+            let target = self.get_local(name);
+            self.emit_value_copy(target, source.offset(offset), *element);
+        }
+    }
+
+    fn visit_variable_assignment(&mut self, path: &ParserNode, expr: &ParserNode) {
+        if let ParserNodeKind::Index { expr: array, index } = path.kind() {
+            return self.visit_element_assignment(array, index, expr);
+        }
+
+        if let ParserNodeKind::DataPath { source: data, path } = path.kind() {
+            let source = self.visit_node(expr);
+            return self.emit_data_path_write(data, path, source);
+        }
+
+        let source = self.visit_node(expr);
+        let resolution = self.tags.get_resolution(path);
+        let target = self.resolve_location(resolution);
+
+        self.emit_value_copy(
+            target,
+            source,
+            self.tags.get_value_type(expr),
+        );
+    }
+
+    fn visit_string_literal(&mut self, text: &str) -> ValueLocation {
+        let target = self.get_free_location();
+
+        self.emit(Instruction::PlaceCommandLiteral(format!(
+            "data modify storage {} {} set value {}",
+            values_storage(&self.pack_name),
+            storage_path(&target),
+            Value::from(text)
+        )));
+
+        target
+    }
+
+    // There's no command that compares two values in storage, but overwriting one with the other only succeeds
+    // if they're different, so the left string is copied into a scratch path and overwritten with the right one
+    fn visit_string_comparison(&mut self, lhs: &ParserNode, rhs: &ParserNode, equals: bool) -> ValueLocation {
+        let lhs = self.visit_node(lhs);
+        let rhs = self.visit_node(rhs);
+        let storage = values_storage(&self.pack_name);
+        let target = self.get_free_location();
+
+        self.emit(Instruction::PlaceCommandLiteral(format!(
+            "data modify storage {} compare set from storage {} {}",
+            storage,
+            storage,
+            storage_path(&lhs)
+        )));
+        self.emit(Instruction::SetValueToCommandSuccess {
+            target: target.clone(),
+            command: format!("data modify storage {} compare set from storage {} {}", storage, storage, storage_path(&rhs)),
+        });
+
+        if equals {
+            self.emit(Instruction::ToggleValue { target: target.clone() });
+        }
+
+        target
+    }
+
+    fn visit_binary_operation(
+        &mut self,
+        lhs: &ParserNode,
+        rhs: &ParserNode,
+        op: Operation,
+    ) -> ValueLocation {
+        if self.tags.get_type(lhs) == self.types.string() {
+            return self.visit_string_comparison(lhs, rhs, op == Operation::CheckEquals);
+        }
+
+        if matches!(op, Operation::BitAnd | Operation::BitOr | Operation::BitXor | Operation::ShiftLeft | Operation::ShiftRight) {
+            return self.visit_bitwise_operation(lhs, rhs, op);
+        }
+
+        let op = match op {
+            Operation::Add => BinaryOperation::Add,
+            Operation::Subtract => BinaryOperation::Subtract,
+            Operation::Multiply => BinaryOperation::Multiply,
+            Operation::Divide => BinaryOperation::Divide,
+            Operation::Modulo => BinaryOperation::Modulo,
+            Operation::CheckEquals => BinaryOperation::CheckEquals,
+            Operation::NotEquals => BinaryOperation::NotEquals,
+            Operation::GreaterThan => BinaryOperation::GreaterThan,
+            Operation::GreaterThanOrEquals => BinaryOperation::GreaterThanOrEquals,
+            Operation::LessThan => BinaryOperation::LessThan,
+            Operation::LessThanOrEquals => BinaryOperation::LessThanOrEquals,
+            Operation::And => BinaryOperation::And,
+            Operation::Or => BinaryOperation::Or,
+            _ => unreachable!(),
+        };
+
+        let long = self.tags.get_type(lhs) == self.types.long();
+        let vec3 = self.tags.get_type(lhs) == self.types.vec3();
+        let target = self.visit_node(lhs);
+        let source = self.visit_node(rhs);
+
+        // each component of a vec3 is combined with the same component of the other, or with the int it's scaled by
+        if vec3 {
+            let scaled = self.tags.get_type(rhs) == self.types.int();
+
+            for i in 0..3 {
+                self.emit(Instruction::ValueBinaryOperation {
+                    source: if scaled { source.clone() } else { source.offset(i) },
+                    target: target.offset(i),
+                    op,
+                });
+            }
+        } else if long {
+            self.emit(Instruction::LongBinaryOperation {
+                source,
+                target: target.clone(),
+                op,
+            });
+        } else {
+            self.emit(Instruction::ValueBinaryOperation {
+                source,
+                target: target.clone(),
+                op,
+            });
+        }
+
+        target
+    }
+
+    // Scoreboards can't work with bits, so shifting by a constant multiplies or divides by a power of two, which wraps
+    // and rounds down like the shift would. Everything else goes through the math helpers, see push_bitwise_helper.
+    // Shift amounts are masked to 0..31 like Java's
+    fn visit_bitwise_operation(&mut self, lhs: &ParserNode, rhs: &ParserNode, op: Operation) -> ValueLocation {
+        let target = self.visit_node(lhs);
+
+        let amount = match rhs.unwrap_expression().kind() {
+            ParserNodeKind::NumberLiteral(n) => Some(n & 31),
+            _ => None,
+        };
+
+        // 2^31 doesn't fit in an int, so only a left shift can use it, as the int it wraps around to
+        let scale = match (op, amount) {
+            (Operation::ShiftLeft, Some(amount)) => Some((1i32.wrapping_shl(amount as u32), BinaryOperation::Multiply)),
+            (Operation::ShiftRight, Some(amount)) if amount < 31 => Some((1 << amount, BinaryOperation::Divide)),
+            _ => None,
+        };
+
+        if let Some((constant, op)) = scale {
+            let source = self.get_free_location();
+
+            self.emit(Instruction::SetValueToConstant { target: source.clone(), constant });
+            self.emit(Instruction::ValueBinaryOperation { source, target: target.clone(), op });
+
+            return target;
+        }
+
+        let source = self.visit_node(rhs);
+        let helper = self.push_bitwise_helper(op);
+
+        self.emit_value_copy(math_location(&self.pack_name, 0), target.clone(), self.types.int());
+        self.emit_value_copy(math_location(&self.pack_name, 1), source, self.types.int());
+        self.emit(Instruction::Call {
+            function: ResourceLocation::new(self.pack_name.clone(), helper),
+            execute: None,
+        });
+        self.emit_value_copy(target.clone(), math_location(&self.pack_name, 0), self.types.int());
+
+        target
+    }
+
+    fn visit_unary_operation(&mut self, expr: &ParserNode, op: Operation) -> ValueLocation {
+        let target = self.visit_node(expr);
+
+        match op {
+            // toggling the value would only be right for one that's exactly 0 or 1, so the result is whether it's 0,
+            // which is always one of the two. Any other value counts as true, just like it does when cast to a bool
+            Operation::Not => {
+                let result = self.get_free_location();
+
+                self.emit(Instruction::SetValueToMatch {
+                    source: target,
+                    target: result.clone(),
+                    value: 0,
+                    invert: false,
+                });
+
+                return result;
+            }
+            Operation::Negate => {
+                let n = self.get_free_location();
+
+                self.emit(Instruction::SetValueToConstant {
+                    target: n.clone(),
+                    constant: -1,
+                });
+
+                self.emit(Instruction::ValueBinaryOperation {
+                    source: n,
+                    target: target.clone(),
+                    op: BinaryOperation::Multiply,
+                });
+            }
+            _ => unreachable!(),
+        }
+
+        target
+    }
+
+    // Bools and enums are already stored as ints, so those casts only change the type, see Validator::is_valid_cast
+    fn visit_cast(&mut self, node: &ParserNode, expr: &ParserNode) -> ValueLocation {
+        let (from, to) = (self.tags.get_type(expr), self.tags.get_type(node));
+        let value = self.visit_node(expr);
+
+        if from == to {
+            return value;
+        }
+
+        if to == self.types.bool() {
+            let target = self.get_free_location();
+
+            self.emit(Instruction::SetValueToMatch {
+                source: value,
+                target: target.clone(),
+                value: 0,
+                invert: true,
+            });
+
+            return target;
+        }
+
+        if to == self.types.long() {
+            return self.widen_to_long(value);
+        }
+
+        if from == self.types.long() {
+            // high * LONG_BASE + low, which wraps around like an int would
+            let base = self.get_free_location();
+
+            self.emit(Instruction::SetValueToConstant {
+                target: base.clone(),
+                constant: LONG_BASE,
+            });
+            self.emit(Instruction::ValueBinaryOperation {
+                source: base,
+                target: value.clone(),
+                op: BinaryOperation::Multiply,
+            });
+            self.emit(Instruction::ValueBinaryOperation {
+                source: value.offset(1),
+                target: value.clone(),
+                op: BinaryOperation::Add,
+            });
+        }
+
+        value
+    }
+
+    fn visit_operation_equals(&mut self, path: &ParserNode, expr: &ParserNode, op: Operation) {
+        let target = match path.kind() {
+            ParserNodeKind::DataPath { .. } => self.visit_node(path),
+            _ => {
+                let resolution = self.tags.get_resolution(path);
+                self.resolve_location(resolution)
+            }
+        };
+
+        // adding or subtracting a constant is a single command, rather than setting a temporary value to it first.
+        // Subtracting is adding the negated constant and the other way around, which the smallest int doesn't have
+        let modify = match (expr.unwrap_expression().kind(), op) {
+            (ParserNodeKind::NumberLiteral(n), Operation::Add) if *n != i32::MIN => Some(*n),
+            (ParserNodeKind::NumberLiteral(n), Operation::Subtract) => n.checked_neg(),
+            _ => None,
+        };
+
+        if let Some(value) = modify {
+            self.emit(Instruction::ModifyValue { target: target.clone(), value });
+        } else {
+            let source = self.visit_node(expr);
+
+            let op = match op {
+                Operation::Add => BinaryOperation::Add,
+                Operation::Subtract => BinaryOperation::Subtract,
+                Operation::Multiply => BinaryOperation::Multiply,
+                Operation::Divide => BinaryOperation::Divide,
+                Operation::Modulo => BinaryOperation::Modulo,
+                _ => unreachable!(),
+            };
+
+            if self.tags.get_type(expr) == self.types.long() {
+                self.emit(Instruction::LongBinaryOperation { source, target: target.clone(), op });
+            } else {
+                self.emit(Instruction::ValueBinaryOperation { source, target: target.clone(), op });
+            }
+        }
+
+        if let ParserNodeKind::DataPath { source, path } = path.kind() {
+            self.emit_data_path_write(source, path, target);
+        }
+    }
+
+    // A path that doesn't exist, or doesn't hold a number, is read as 0, which is what a failed command's result is
+    fn visit_data_path(&mut self, source: &DataSource, path: &str) -> ValueLocation {
+        let target = self.get_free_location();
+
+        self.emit(Instruction::SetValueToCommandResult {
+            target: target.clone(),
+            command: format!("data get {} {}", source, path),
+        });
+
+        target
+    }
+
+    // The value is written as an int, creating the path if it doesn't exist. Entities can't be written to if they're
+    // players, which makes the command fail
+    fn emit_data_path_write(&mut self, data: &DataSource, path: &str, source: ValueLocation) {
+        match data {
+            DataSource::Storage(storage) => {
+                let storage = match storage.split_once(':') {
+                    Some((namespace, storage)) => ResourceLocation::new(namespace.to_string(), storage.to_string()),
+                    None => ResourceLocation::new("minecraft".to_string(), storage.clone()),
+                };
+
+                self.emit(Instruction::SaveValueToStorage {
+                    source,
+                    storage,
+                    path: path.to_string(),
+                });
+            }
+            DataSource::Entity(selector) => {
+                self.emit(Instruction::PlaceCommandLiteral(format!(
+                    "execute store result entity {} {} int 1 run scoreboard players get {}",
+                    selector,
+                    path,
+                    ScoreboardSlot::from(&source)
+                )));
+            }
+        }
+    }
+
+    // Splits an int into the halves of a long. Scoreboard division and modulo round down,
+    // so the low half ends up between 0 and LONG_BASE - 1 even for negative values
+    fn visit_long_conversion(&mut self, arg: &ParserNode) -> ValueLocation {
+        let value = self.visit_node(arg);
+        self.widen_to_long(value)
+    }
+
+    fn widen_to_long(&mut self, value: ValueLocation) -> ValueLocation {
+        let target = self.get_free_location();
+        let base = self.get_free_location();
+
+        self.emit(Instruction::SetValueToConstant {
+            target: base.clone(),
+            constant: LONG_BASE,
+        });
+
+        for (half, op) in [BinaryOperation::Divide, BinaryOperation::Modulo].into_iter().enumerate() {
+            self.emit(Instruction::SetValueToValue {
+                source: value.clone(),
+                target: target.offset(half),
+            });
+            self.emit(Instruction::ValueBinaryOperation {
+                source: base.clone(),
+                target: target.offset(half),
+                op,
+            });
+        }
+
+        target
+    }
+
+    fn visit_function_call(&mut self, node: &ParserNode) -> Option<ValueLocation> {
+        let (expr, params) = node.as_function_call();
+        let resolution = self.tags.get_resolution(node);
+
+        // taken before the arguments are visited, since only this call runs behind the prefix
+        let prefix = self.call_prefix.take();
+
+        if let ResolvedPart::Builtin(builtin) = resolution.last() {
+            return self.visit_builtin_call(*builtin, node, params);
+        }
+
+        if let ResolvedPart::Constructor(ty) = resolution.last() {
+            if *ty == self.types.long() {
+                return Some(self.visit_long_conversion(&params[0]));
+            }
+        }
+
+        let mut args = vec![];
+        let mut receiver = None;
+
+        if let ResolvedPart::Method(ty, name) = resolution.last() {
+            if !ty.from(&self.types).as_struct_def().function(&name).unwrap().is_static() {
+                let node = expr.unwrap_expression().as_member_receiver();
+                receiver = Some((node, *ty));
+                args.push(self.visit_node(node)); // self parameter
+            }
+        }
+
+        args.extend(params.iter().map(|arg| self.visit_node(arg)));
+
+        if let ResolvedPart::Reference(ty, name) = resolution.last() {
+            return self.visit_reference_call(node, *ty, name, args);
+        }
+
+        let func_objective;
+        let func_signature;
+        let handle_return;
+
+        match resolution.last() {
+            ResolvedPart::GlobalFunction(name) => {
+                let func_location = ResourceLocation::new(self.pack_name.clone(), name.clone());
+
+                func_objective = Objective(name.clone());
+                func_signature = self.global_functions.get(&func_location).unwrap();
+                handle_return = func_signature.return_type() != self.types.none();
+            }
+            ResolvedPart::Method(ty, name) => {
+                let struct_def = ty.from(&self.types).as_struct_def();
+
+                func_objective = Objective(format!("{}.{}", struct_def.name(), name));
+                func_signature = struct_def.function(name).unwrap();
+                handle_return = func_signature.return_type() != self.types.none();
             }
-            ParserNodeKind::FunctionCall { .. } => match self.visit_function_call(node) {
-                Some(target) => target,
-                None => ValueLocation::dummy(),
-            },
-            ParserNodeKind::Block(body) => {
-                self.visit_block(body);
-                ValueLocation::dummy()
+            // Constructors are a special case. To the user they seem like regular functions,
+            // but on the IR level, they construct a new value in-place
+            ResolvedPart::Constructor(ty) => {
+                let struct_def = ty.from(&self.types).as_struct_def();
+                let target = self.get_free_location();
+
+                for (param, arg) in struct_def.constructor().params().iter().zip(args) {
+                    self.emit_value_copy(
+                        ValueLocation::new(
+                            target.slot,
+                            target.offset + struct_def.field_offset(param.name()),
+                            target.objective.clone(),
+                        ),
+                        arg,
+                        param.param_type(),
+                    );
+                }
+
+                return Some(target);
             }
-            ParserNodeKind::Return(expr) => {
-                self.visit_return(expr);
-                ValueLocation::dummy()
+            _ => unreachable!(),
+        }
+
+        // calls that run behind execute subcommands, or that could end up inlining the same function again, are left as calls
+        let attributes = func_signature.attributes();
+        let inline = self.tags.find_inline_call(node).filter(|_| {
+            prefix.is_none()
+                && attributes.execute.is_none()
+                && attributes.permission.is_none()
+                && !self.tags.is_recursive_call(node)
+        });
+
+        if let Some(declaration) = inline {
+            return self.visit_inline_call(declaration, func_signature, args);
+        }
+
+        // a recursive call overwrites the values of the function that's running, including the parameters
+        // it's about to pass arguments through
+        let recursive = self.tags.is_recursive_call(node);
+
+        if recursive {
+            self.emit(Instruction::PushFrame);
+        }
+
+        for (i, (param, arg)) in func_signature.params().iter().zip(args).enumerate() {
+            let target = ValueLocation::new(i, 0, func_objective.clone());
+
+            self.emit_value_copy(
+                target,
+                arg,
+                param.param_type(),
+            );
+        }
+
+        let execute = match (prefix, &func_signature.attributes().execute) {
+            (Some(prefix), Some(execute)) => Some(format!("{} {}", prefix, execute)),
+            (prefix, execute) => prefix.or(execute.clone()),
+        };
+
+        self.emit(Instruction::Call {
+            function: ResourceLocation::new(self.pack_name.clone(), func_objective.0.clone()),
+            execute,
+        });
+
+        // self is passed by value, so any changes the method made to it are copied back into the variable it was called on
+        let receiver = receiver
+            .filter(|(node, _)| matches!(node.kind(), ParserNodeKind::Identifier(_) | ParserNodeKind::MemberAccess { .. }))
+            .and_then(|(node, ty)| self.tags.find_resolution(node).map(|resolution| (resolution, ty)));
+
+        match (receiver, recursive) {
+            (Some((resolution, ty)), false) => {
+                let target = self.resolve_location(resolution);
+
+                self.emit_value_copy(target, ValueLocation::new(0, 0, func_objective.clone()), ty);
+            }
+            // popping the frame restores the caller's self, so the callee's is set aside next to its return value first
+            (Some((resolution, ty)), true) => {
+                let returned_self = ValueLocation::new(1, 0, Objective(format!("{}.return", func_objective.0)));
+
+                self.emit_value_copy(returned_self.clone(), ValueLocation::new(0, 0, func_objective.clone()), ty);
+                self.emit(Instruction::PopFrame);
+
+                let target = self.resolve_location(resolution);
+                self.emit_value_copy(target, returned_self, ty);
+            }
+            (None, true) => self.emit(Instruction::PopFrame),
+            (None, false) => {}
+        }
+
+        if handle_return {
+            let target = self.get_free_location();
+            let source =
+                ValueLocation::new(0, 0, Objective(format!("{}.return", func_objective.0)));
+
+            self.emit_value_copy(
+                target.clone(),
+                source,
+                self.tags.get_type(node),
+            );
+
+            Some(target)
+        } else {
+            None
+        }
+    }
+
+    // Visits the body of an #[inline] function in place of a call to it. Its parameters and locals are given slots of the
+    // caller's objective, and the value of the return at the end of its body, the only one it can have, is the value of the call
+    fn visit_inline_call(
+        &mut self,
+        declaration: &ParserNode,
+        signature: &FunctionSignature,
+        args: Vec<ValueLocation>,
+    ) -> Option<ValueLocation> {
+        let body = match declaration.as_func_body().kind() {
+            ParserNodeKind::Block(body) => body,
+            _ => unreachable!(),
+        };
+
+        // the names of the callee's locals only refer to its own values
+        let locals = std::mem::take(&mut self.locals);
+        let texts = std::mem::take(&mut self.texts);
+
+        for (param, arg) in signature.params().iter().zip(args) {
+            // arguments are evaluated into values of their own, which the callee can change without the caller seeing it
+            let slot = match arg.objective == self.objective && arg.offset == 0 {
+                true => arg.slot,
+                false => {
+                    let target = self.get_free_location();
+                    self.emit_value_copy(target.clone(), arg, param.param_type());
+                    target.slot
+                }
+            };
+
+            self.locals.insert(param.name().to_string(), slot);
+        }
+
+        let mut result = None;
+
+        for (i, statement) in body.iter().enumerate() {
+            match statement.kind() {
+                ParserNodeKind::Return(expr) if i == body.len() - 1 => {
+                    result = expr.as_ref().map(|expr| self.visit_node(expr));
+                }
+                _ => {
+                    self.visit_node(statement);
+                }
+            }
+        }
+
+        self.locals = locals;
+        self.texts = texts;
+
+        result
+    }
+
+    // A reference holds the number of the function it refers to among the ones referenced with its type, so the call is
+    // a block for each of them that's only run if the reference holds its number. A recursive call saves the caller's
+    // values around each call rather than around all of them, as the reference has to be restored before the next check
+    fn visit_reference_call(
+        &mut self,
+        node: &ParserNode,
+        ty: TypeKey,
+        name: &str,
+        args: Vec<ValueLocation>,
+    ) -> Option<ValueLocation> {
+        let function_def = ty.from(self.types).as_function_def().unwrap();
+        let reference = self.get_local(name);
+        let recursive = self.tags.is_recursive_call(node);
+        let target = (function_def.return_type() != self.types.none()).then(|| self.get_free_location());
+
+        for function in self.tags.function_refs(ty) {
+            let location = ResourceLocation::new(self.pack_name.clone(), function.to_string());
+            let signature = &self.global_functions[&location];
+            let (args, target) = (args.clone(), target.clone());
+
+            let block = Self::create_block(false, node, self, |_, builder| {
+                if recursive {
+                    builder.emit(Instruction::PushFrame);
+                }
+
+                for (i, (param, arg)) in signature.params().iter().zip(args).enumerate() {
+                    builder.emit_value_copy(ValueLocation::new(i, 0, Objective(function.to_string())), arg, param.param_type());
+                }
+
+                builder.emit(Instruction::Call {
+                    function: location.clone(),
+                    execute: signature.attributes().execute.clone(),
+                });
+
+                if recursive {
+                    builder.emit(Instruction::PopFrame);
+                }
+
+                if let Some(target) = target {
+                    let source = ValueLocation::new(0, 0, Objective(format!("{}.return", function)));
+                    builder.emit_value_copy(target, source, function_def.return_type());
+                }
+            });
+
+            self.emit(Instruction::IfValueMatchesRunBlock {
+                source: reference.clone(),
+                value: self.tags.function_ref_id(function),
+                block,
+            });
+        }
+
+        target
+    }
+
+    fn visit_builtin_call(&mut self, builtin: Builtin, node: &ParserNode, args: &[ParserNode]) -> Option<ValueLocation> {
+        match builtin {
+            Builtin::Snapshot | Builtin::Rollback => {
+                let name = match args[0].unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(name) => name,
+                    _ => unreachable!(),
+                };
+
+                let storage = ResourceLocation::new(self.pack_name.clone(), "snapshots".to_string());
+
+                for arg in &args[1..] {
+                    let resolution = self.tags.get_resolution(arg.unwrap_expression());
+                    let location = self.resolve_location(resolution);
+                    let size = resolution
+                        .find_assignable_type(self.types)
+                        .unwrap()
+                        .from(self.types)
+                        .total_size(self.types);
+
+                    for i in 0..size {
+                        // values spanning several slots get one entry per slot, e.g snap.pos.0, snap.pos.1
+                        let path = match size {
+                            1 => format!("{}.{}", name, resolution.path()),
+                            _ => format!("{}.{}.{}", name, resolution.path(), i),
+                        };
+                        let value = location.offset(i);
+
+                        self.emit(match builtin {
+                            Builtin::Snapshot => Instruction::SaveValueToStorage {
+                                source: value,
+                                storage: storage.clone(),
+                                path,
+                            },
+                            Builtin::Rollback => Instruction::LoadValueFromStorage {
+                                target: value,
+                                storage: storage.clone(),
+                                path,
+                            },
+                            _ => unreachable!(),
+                        });
+                    }
+                }
+            }
+            Builtin::Print | Builtin::Title | Builtin::Subtitle | Builtin::Actionbar | Builtin::Bossbar => {
+                // the id of a bossbar is a string just like a target is. A print() without a target is tagged with the one it's sent to
+                let (first, parts) = match self.tags.find_selector(node) {
+                    Some(target) if builtin == Builtin::Print => (target.to_string(), args),
+                    _ => (self.target_arg(&args[0]), &args[1..]),
+                };
+
+                let text = self.visit_text_components(parts).to_string();
+
+                let command = match builtin {
+                    Builtin::Print => format!("tellraw {} {}", first, text),
+                    Builtin::Title => format!("title {} title {}", first, text),
+                    Builtin::Subtitle => format!("title {} subtitle {}", first, text),
+                    Builtin::Actionbar => format!("title {} actionbar {}", first, text),
+                    Builtin::Bossbar => {
+                        let id = ResourceLocation::new(self.pack_name.clone(), first.clone());
+                        self.setup.push(format!("bossbar add {} \"\"", id));
+                        format!("bossbar set {} name {}", id, text)
+                    }
+                    _ => unreachable!(),
+                };
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
+            }
+            Builtin::Forceload | Builtin::Unforceload => {
+                let (action, helper) = match builtin {
+                    Builtin::Forceload => ("add", "zz_forceload/add"),
+                    _ => ("remove", "zz_forceload/remove"),
+                };
+
+                let radius = match args.get(2).map(|arg| arg.unwrap_expression().kind()) {
+                    Some(ParserNodeKind::NumberLiteral(radius)) => *radius,
+                    _ => 0,
+                };
+
+                // the opposite corners of the square of blocks whose chunks are affected
+                let command = self.int_args_command(
+                    helper,
+                    &args[..2],
+                    &[("x1", 0, -radius), ("z1", 1, -radius), ("x2", 0, radius), ("z2", 1, radius)],
+                    &[],
+                    |coordinates| format!("forceload {} {}", action, coordinates.join(" ")),
+                );
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
+            }
+            Builtin::UnforceloadAll => {
+                self.emit(Instruction::PlaceCommandLiteral("forceload remove all".to_string()));
+            }
+            Builtin::IsLoaded => {
+                let command = self.int_args_command(
+                    "zz_forceload/is_loaded",
+                    args,
+                    &int_args(&["x", "y", "z"]),
+                    &[],
+                    |coordinates| format!("execute if loaded {}", coordinates.join(" ")),
+                );
+
+                let target = self.get_free_location();
+
+                // with constant coordinates the check is a plain condition, otherwise it's a call to the macro helper
+                self.emit(match command.strip_prefix("execute ") {
+                    Some(condition) => Instruction::SetValueToConditionSuccess {
+                        target: target.clone(),
+                        condition: condition.to_string(),
+                    },
+                    None => Instruction::SetValueToCommandSuccess {
+                        target: target.clone(),
+                        command,
+                    },
+                });
+
+                return Some(target);
+            }
+            Builtin::PlaceStructure | Builtin::Fill | Builtin::Clone => {
+                let string = |arg: &ParserNode| match arg.unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(value) => value.clone(),
+                    _ => unreachable!(),
+                };
+
+                let command = match builtin {
+                    Builtin::PlaceStructure => self.int_args_command(
+                        "zz_world/place_structure",
+                        &args[1..],
+                        &int_args(&["x", "y", "z"]),
+                        &[("id", &string(&args[0]))],
+                        |values| format!("place template {} {}", values[3], values[..3].join(" ")),
+                    ),
+                    Builtin::Fill => self.int_args_command(
+                        "zz_world/fill",
+                        &args[..6],
+                        &int_args(&["x1", "y1", "z1", "x2", "y2", "z2"]),
+                        &[("block", &string(&args[6]))],
+                        |values| format!("fill {} {}", values[..6].join(" "), values[6]),
+                    ),
+                    _ => self.int_args_command(
+                        "zz_world/clone",
+                        args,
+                        &int_args(&["x1", "y1", "z1", "x2", "y2", "z2", "x", "y", "z"]),
+                        &[],
+                        |values| format!("clone {}", values.join(" ")),
+                    ),
+                };
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
+            }
+            Builtin::SetTime => {
+                let time = TimeOfDay::from_name(args[0].unwrap_expression().as_identifier()).unwrap();
+                self.emit(Instruction::PlaceCommandLiteral(format!("time set {}", time.command_name())));
+            }
+            Builtin::SetWeather => {
+                let weather = Weather::from_name(args[0].unwrap_expression().as_identifier()).unwrap();
+
+                let command = match args.get(1) {
+                    Some(_) => self.int_args_command(
+                        "zz_world/weather",
+                        &args[1..],
+                        &int_args(&["duration"]),
+                        &[("weather", weather.command_name())],
+                        |values| format!("weather {} {}t", values[1], values[0]),
+                    ),
+                    None => format!("weather {}", weather.command_name()),
+                };
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
+            }
+            Builtin::GetAttribute | Builtin::SetAttribute | Builtin::AddModifier | Builtin::RemoveModifier => {
+                let string = |arg: &ParserNode| match arg.unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(value) => Some(value.clone()),
+                    ParserNodeKind::NumberLiteral(n) => Some(n.to_string()),
+                    _ => None,
+                };
+
+                let target = self.target_arg(&args[0]);
+                let attribute = EntityAttribute::from_name(args[1].unwrap_expression().as_identifier()).unwrap();
+                let prefix = format!("attribute {} {}", target, attribute.id());
+
+                match builtin {
+                    Builtin::GetAttribute => {
+                        let command = match args.get(2) {
+                            Some(scale) => format!("{} get {}", prefix, string(scale).unwrap()),
+                            None => format!("{} get", prefix),
+                        };
+
+                        let result = self.get_free_location();
+
+                        self.emit(Instruction::SetValueToCommandResult {
+                            target: result.clone(),
+                            command,
+                        });
+
+                        return Some(result);
+                    }
+                    Builtin::SetAttribute => {
+                        let command = match string(&args[2]) {
+                            Some(value) => format!("{} base set {}", prefix, value),
+                            None => self.int_args_command(
+                                "zz_attribute/set",
+                                &args[2..],
+                                &int_args(&["value"]),
+                                &[("prefix", &prefix)],
+                                |values| format!("{} base set {}", values[1], values[0]),
+                            ),
+                        };
+
+                        self.emit(Instruction::PlaceCommandLiteral(command));
+                    }
+                    _ => {
+                        let name = string(&args[2]).unwrap();
+                        let uuid = builtin::modifier_uuid(&self.pack_name, &name);
+
+                        // adding a modifier fails if one with the same UUID exists, so any previous one is removed first
+                        self.emit(Instruction::PlaceCommandLiteral(format!("{} modifier remove {}", prefix, uuid)));
+
+                        if let Builtin::AddModifier = builtin {
+                            let operation = ModifierOperation::from_name(args[4].unwrap_expression().as_identifier()).unwrap();
+                            let prefix = format!("{} modifier add {} {}", prefix, uuid, Value::from(name));
+
+                            let command = match string(&args[3]) {
+                                Some(amount) => format!("{} {} {}", prefix, amount, operation.command_name()),
+                                None => self.int_args_command(
+                                    "zz_attribute/add_modifier",
+                                    &args[3..4],
+                                    &int_args(&["amount"]),
+                                    &[("prefix", &prefix), ("operation", operation.command_name())],
+                                    |values| format!("{} {} {}", values[1], values[0], values[2]),
+                                ),
+                            };
+
+                            self.emit(Instruction::PlaceCommandLiteral(command));
+                        }
+                    }
+                }
+            }
+            Builtin::GiveEffect | Builtin::ClearEffect => {
+                let target = self.target_arg(&args[0]);
+
+                let effect = args.get(1).map(|effect| builtin::effect_id(effect.unwrap_expression().as_identifier()));
+
+                let command = match (builtin, effect) {
+                    (Builtin::GiveEffect, Some(effect)) => {
+                        let prefix = format!("effect give {} {}", target, effect);
+
+                        let hide_particles = match args.get(4).map(|arg| arg.unwrap_expression().kind()) {
+                            Some(ParserNodeKind::BoolLiteral(true)) => "true",
+                            _ => "false",
+                        };
+
+                        // the arguments that are left out are passed as strings, so that every call can share the same helper
+                        let mut ints = int_args(&["seconds", "amplifier"]);
+                        ints.truncate(args.len().min(4) - 2);
+
+                        let mut strings = vec![("prefix", prefix.as_str())];
+
+                        if ints.len() < 2 {
+                            strings.push(("amplifier", "0"));
+                        }
+
+                        strings.push(("hide_particles", hide_particles));
+
+                        let order = ints
+                            .iter()
+                            .map(|(name, _, _)| *name)
+                            .chain(strings.iter().map(|(name, _)| *name))
+                            .collect::<Vec<&str>>();
+
+                        match args.len() {
+                            2 => prefix.clone(),
+                            _ => self.int_args_command("zz_effect/give", &args[2..args.len().min(4)], &ints, &strings, |values| {
+                                let value = |name: &str| &values[order.iter().position(|n| *n == name).unwrap()];
+
+                                format!(
+                                    "{} {} {} {}",
+                                    value("prefix"),
+                                    value("seconds"),
+                                    value("amplifier"),
+                                    value("hide_particles")
+                                )
+                            }),
+                        }
+                    }
+                    (_, Some(effect)) => format!("effect clear {} {}", target, effect),
+                    _ => format!("effect clear {}", target),
+                };
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
+            }
+            Builtin::Damage | Builtin::Heal => {
+                let target = self.target_arg(&args[0]);
+
+                if builtin == Builtin::Damage && self.pack_format.is_none_or(|format| format >= builtin::DAMAGE_PACK_FORMAT) {
+                    let damage_type = builtin::effect_id(args.get(2).map_or("Generic", |arg| arg.unwrap_expression().as_identifier()));
+
+                    // the damage command only takes a single entity
+                    let prefix = match builtin::is_single_target(&target) {
+                        true => format!("damage {}", target),
+                        false => format!("execute as {} run damage @s", target),
+                    };
+
+                    let command = self.int_args_command(
+                        "zz_damage/deal",
+                        &args[1..2],
+                        &int_args(&["amount"]),
+                        &[("prefix", &prefix), ("damage_type", &damage_type)],
+                        |values| format!("{} {} {}", values[1], values[0], values[2]),
+                    );
+
+                    self.emit(Instruction::PlaceCommandLiteral(command));
+                    return None;
+                }
+
+                let effect = match builtin {
+                    Builtin::Damage => "minecraft:instant_damage",
+                    _ => "minecraft:instant_health",
+                };
+
+                match args[1].unwrap_expression().kind() {
+                    ParserNodeKind::NumberLiteral(amount) => {
+                        let amplifiers = match builtin {
+                            Builtin::Damage => vec![builtin::instant_damage_amplifier(*amount)],
+                            _ => builtin::instant_health_amplifiers(*amount),
+                        };
+
+                        for amplifier in amplifiers {
+                            self.emit(Instruction::PlaceCommandLiteral(format!(
+                                "effect give {} {} 1 {} true",
+                                target, effect, amplifier
+                            )));
+                        }
+                    }
+                    _ => {
+                        let amount = self.visit_node(&args[1]);
+                        let helper = self.push_instant_effect_helper(builtin, effect);
+
+                        self.emit_value_copy(math_location(&self.pack_name, 0), amount, self.types.int());
+                        self.emit(Instruction::Call {
+                            function: ResourceLocation::new(self.pack_name.clone(), helper),
+                            execute: Some(format!("as {}", target)),
+                        });
+                    }
+                }
+            }
+            Builtin::XpLevels | Builtin::XpPoints | Builtin::SetXp | Builtin::AddXp => {
+                let target = self.target_arg(&args[0]);
+
+                let unit = match builtin {
+                    Builtin::XpLevels => "levels",
+                    Builtin::XpPoints => "points",
+                    _ => match args.get(2).map(|unit| unit.unwrap_expression().as_identifier()) {
+                        Some("Levels") => "levels",
+                        _ => "points",
+                    },
+                };
+
+                match builtin {
+                    Builtin::XpLevels | Builtin::XpPoints => {
+                        let result = self.get_free_location();
+
+                        self.emit(Instruction::SetValueToCommandResult {
+                            target: result.clone(),
+                            command: format!("xp query {} {}", target, unit),
+                        });
+
+                        return Some(result);
+                    }
+                    _ => {
+                        let action = match builtin {
+                            Builtin::SetXp => "set",
+                            _ => "add",
+                        };
+
+                        let prefix = format!("xp {} {}", action, target);
+
+                        let command = self.int_args_command(
+                            "zz_xp/change",
+                            &args[1..2],
+                            &int_args(&["amount"]),
+                            &[("prefix", &prefix), ("unit", unit)],
+                            |values| format!("{} {} {}", values[1], values[0], values[2]),
+                        );
+
+                        self.emit(Instruction::PlaceCommandLiteral(command));
+                    }
+                }
+            }
+            Builtin::Leaderboard => {
+                let objective = match args[0].unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(objective) => objective.clone(),
+                    _ => player_objective(&self.pack_name, args[0].unwrap_expression().as_identifier()).to_string(),
+                };
+
+                let size = match args[1].unwrap_expression().kind() {
+                    ParserNodeKind::NumberLiteral(size) => *size,
+                    _ => unreachable!(),
+                };
+
+                let helper = format!("zz_leaderboard/{}_{}", builtin::sanitize_resource_path(&objective), size);
+                let commands = self.leaderboard_commands(&objective, size);
+
+                self.helpers.push((helper.clone(), commands));
+                self.emit(Instruction::PlaceCommandLiteral(format!("function {}", ResourceLocation::new(self.pack_name.clone(), helper))));
+            }
+            Builtin::Sin
+            | Builtin::Cos
+            | Builtin::Atan2
+            | Builtin::Hash
+            | Builtin::Noise
+            | Builtin::Min
+            | Builtin::Max
+            | Builtin::Abs
+            | Builtin::Clamp
+            | Builtin::Pow
+            | Builtin::Sqrt => {
+                let values = args.iter().map(|arg| self.visit_node(arg)).collect::<Vec<ValueLocation>>();
+
+                // optional arguments default to 0
+                let param_count = match builtin {
+                    Builtin::Hash => 3,
+                    Builtin::Noise => 4,
+                    _ => values.len(),
+                };
+
+                match builtin {
+                    Builtin::Hash | Builtin::Noise => self.push_noise_helpers(),
+                    Builtin::Sin | Builtin::Cos | Builtin::Atan2 => self.push_trig_helpers(),
+                    _ => self.push_arithmetic_helpers(),
+                }
+
+                // the helpers take their arguments in v0, v1, ... of the math objective, and return in v0
+                for (i, value) in values.into_iter().enumerate() {
+                    self.emit_value_copy(math_location(&self.pack_name, i), value, self.types.int());
+                }
+
+                for i in args.len()..param_count {
+                    self.emit(Instruction::SetValueToConstant {
+                        target: math_location(&self.pack_name, i),
+                        constant: 0,
+                    });
+                }
+
+                self.emit(Instruction::Call {
+                    function: ResourceLocation::new(self.pack_name.clone(), format!("zz_math/{}", builtin.name())),
+                    execute: None,
+                });
+
+                let result = self.get_free_location();
+                self.emit_value_copy(result.clone(), math_location(&self.pack_name, 0), self.types.int());
+
+                return Some(result);
+            }
+            Builtin::Error => {
+                let message = match args[0].unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(message) => message,
+                    _ => unreachable!(),
+                };
+
+                let line = self.src[..node.span().start].matches('\n').count() + 1;
+                let text = format!("Error in {} on line {}: {}", self.objective, line, message);
+                let flag = error_location(&self.pack_name);
+
+                self.setup.push(format!("scoreboard objectives add {} dummy", flag.objective));
+                self.emit(Instruction::PlaceCommandLiteral(format!("tellraw @a {}", json!({ "text": text, "color": "red" }))));
+                self.emit(Instruction::SetValueToConstant { target: flag, constant: 1 });
+                self.emit(Instruction::Return { source: None, size: 0 });
+            }
+            // sleeps split the block they're in before they would be visited, see visit_block, and keep the values
+            // that the rest of the block uses like an await does
+            Builtin::Sleep => return None,
+            Builtin::Assert => {
+                if self.release {
+                    return None;
+                }
+
+                let cond = self.visit_node(&args[0]);
+                let line = self.src[..node.span().start].matches('\n').count() + 1;
+
+                let mut text = format!("Assertion failed in {} on line {}", self.objective, line);
+
+                if let Some(ParserNodeKind::StringLiteral(message)) = args.get(1).map(|message| message.unwrap_expression().kind()) {
+                    text.push_str(&format!(": {}", message));
+                }
+
+                let block = Self::create_block(false, node, self, |_, builder| {
+                    builder.emit(Instruction::PlaceCommandLiteral(format!("tellraw @a {}", json!({ "text": text, "color": "red" }))));
+                    builder.emit(Instruction::Return { source: None, size: 0 });
+                });
+
+                self.emit(Instruction::IfValueMatchesRunBlock {
+                    source: cond,
+                    value: 0,
+                    block,
+                });
+            }
+            Builtin::Random => {
+                let result = match self.pack_format {
+                    Some(pack_format) if pack_format < builtin::RANDOM_PACK_FORMAT => {
+                        let values = args.iter().map(|arg| self.visit_node(arg)).collect::<Vec<ValueLocation>>();
+
+                        self.push_random_helper();
+
+                        for (i, value) in values.into_iter().enumerate() {
+                            self.emit_value_copy(math_location(&self.pack_name, i), value, self.types.int());
+                        }
+
+                        self.emit(Instruction::Call {
+                            function: ResourceLocation::new(self.pack_name.clone(), "zz_math/random".to_string()),
+                            execute: None,
+                        });
+
+                        let result = self.get_free_location();
+                        self.emit_value_copy(result.clone(), math_location(&self.pack_name, 0), self.types.int());
+                        result
+                    }
+                    _ => {
+                        let command = self.int_args_command("zz_random/value", args, &int_args(&["min", "max"]), &[], |values| {
+                            format!("random value {}..{}", values[0], values[1])
+                        });
+
+                        let result = self.get_free_location();
+                        self.emit(Instruction::SetValueToCommandResult {
+                            target: result.clone(),
+                            command,
+                        });
+                        result
+                    }
+                };
+
+                return Some(result);
+            }
+            Builtin::SettingsMenu => {
+                let target = self.target_arg(&args[0]);
+
+                self.emit(Instruction::PlaceCommandLiteral(format!(
+                    "execute as {} run function {}",
+                    target,
+                    ResourceLocation::new(self.pack_name.clone(), "zz_settings/show".to_string())
+                )));
             }
-            ParserNodeKind::Break => {
-                self.emit(Instruction::Break);
-                ValueLocation::dummy()
+            Builtin::Teleport => {
+                let target = self.target_arg(&args[0]);
+                let command = self.vec3_command("zz_world/teleport", &args[1], &[("target", &target)], |values| {
+                    format!("tp {} {}", values[3], values[..3].join(" "))
+                });
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
             }
-            ParserNodeKind::If {
-                cond,
-                body,
-                else_body,
-                ..
-            } => {
-                self.visit_if(cond, body, else_body);
-                ValueLocation::dummy()
+            Builtin::Positioned => {
+                let callback = args[1].unwrap_expression();
+                let callback = self.tags.find_function_name(callback).unwrap_or(callback.as_identifier()).to_string();
+                let function = ResourceLocation::new(self.pack_name.clone(), callback).to_string();
+
+                let command = self.vec3_command("zz_world/positioned", &args[0], &[("function", &function)], |values| {
+                    format!("execute positioned {} run function {}", values[..3].join(" "), values[3])
+                });
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
             }
-            ParserNodeKind::For {
-                init,
-                cond,
-                step,
-                body,
-            } => {
-                self.visit_for(init, cond, step, body);
-                ValueLocation::dummy()
+            Builtin::Summon => {
+                let entity = match args[0].unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(entity) => entity.clone(),
+                    _ => unreachable!(),
+                };
+
+                let tag = self.tags.get_summon_tag(node).to_string();
+                let data = match args.get(2).map(|data| data.unwrap_expression().kind()) {
+                    Some(ParserNodeKind::StringLiteral(data)) if data.trim_start_matches('{').trim_start().starts_with('}') => {
+                        format!("{{Tags:[{}]}}", Value::from(tag.as_str()))
+                    }
+                    Some(ParserNodeKind::StringLiteral(data)) => format!("{{Tags:[{}],{}", Value::from(tag.as_str()), &data[1..]),
+                    _ => format!("{{Tags:[{}]}}", Value::from(tag.as_str())),
+                };
+
+                self.emit(Instruction::PlaceCommandLiteral(format!("tag @e[tag={}] remove {}", tag, tag)));
+
+                let command = self.vec3_command("zz_world/summon", &args[1], &[("entity", &entity), ("data", &data)], |values| {
+                    format!("summon {} {} {}", values[3], values[..3].join(" "), values[4])
+                });
+
+                self.emit(Instruction::PlaceCommandLiteral(command));
             }
-            ParserNodeKind::CommandLiteral(cmd) => {
-                self.emit(Instruction::PlaceCommandLiteral(cmd.to_owned()));
-                ValueLocation::dummy()
+            Builtin::Emit => {
+                let message = self.visit_node(&args[0]);
+                let message_def = self.tags.get_type(&args[0]).from(self.types).as_struct_def();
+                let key = builtin::sanitize_resource_path(message_def.name());
+
+                // the message is appended to a queue so that subscribers which emit messages themselves don't overwrite it
+                self.emit(Instruction::PlaceCommandLiteral(format!("data modify storage {} {} append value {{}}", bus_storage(), key)));
+
+                for field in message_def.fields() {
+                    self.emit(Instruction::SaveValueToStorage {
+                        source: message.offset(message_def.field_offset(field.name())),
+                        storage: bus_storage(),
+                        path: format!("{}[-1].{}", key, field.name()),
+                    });
+                }
+
+                self.emit(Instruction::PlaceCommandLiteral(format!("function #{}/{}", bus_storage(), key)));
+                self.emit(Instruction::PlaceCommandLiteral(format!("data remove storage {} {}[-1]", bus_storage(), key)));
             }
-            ParserNodeKind::MemberAccess { expr, member } => {
-                self.visit_member_access(expr, member.as_identifier())
+            Builtin::EntityId => {
+                let target = self.target_arg(&args[0]);
+
+                let objective = format!("{}.id", self.pack_name);
+                let helper = "zz_entity_id/get".to_string();
+
+                self.setup.push(format!("scoreboard objectives add {} dummy", objective));
+
+                // runs as the entity, and returns its id after giving it one if it doesn't have one yet
+                self.helpers.push((
+                    helper.clone(),
+                    vec![
+                        format!("execute if score @s {} matches 1.. run return run scoreboard players get @s {}", objective, objective),
+                        format!("scoreboard players add #next {} 1", objective),
+                        format!("scoreboard players operation @s {} = #next {}", objective, objective),
+                        format!("tag @s add {}.has_id", self.pack_name),
+                        format!("return run scoreboard players get @s {}", objective),
+                    ],
+                ));
+
+                let result = self.get_free_location();
+
+                self.emit(Instruction::SetValueToCommandResult {
+                    target: result.clone(),
+                    command: format!("execute as {} run function {}", target, ResourceLocation::new(self.pack_name.clone(), helper)),
+                });
+
+                return Some(result);
             }
-            // the below nodes don't need any work, they've been handled by previous phases of compilation
-            ParserNodeKind::Program(_) => ValueLocation::dummy(),
-            ParserNodeKind::TypedIdentifier { .. } => ValueLocation::dummy(),
-            ParserNodeKind::FunctionDeclaration { .. } => ValueLocation::dummy(),
-            ParserNodeKind::StructDefinition { .. } => ValueLocation::dummy(),
+            // text component builders are only visited as part of the builtins above
+            _ => unreachable!(),
         }
+
+        None
     }
 
-    fn visit_number_literal(&mut self, n: i32) -> ValueLocation {
-        let target = self.get_free_location();
+    // Adds the helpers behind sin(), cos() and atan2(), and the setup that writes their lookup tables to storage.
+    // zz_math/lerp reads the entry at #i of the table named in storage and the one after it,
+    // and puts the value #f thousandths of the way between them in #r
+    fn push_trig_helpers(&mut self) {
+        let math = math_objective(&self.pack_name);
+        let tables = tables_storage(&self.pack_name);
+        let args = ResourceLocation::new(self.pack_name.clone(), "args".to_string());
+        let function = |name: &str| ResourceLocation::new(self.pack_name.clone(), format!("zz_math/{}", name));
+        let scale = builtin::FIXED_POINT_SCALE;
 
-        self.emit(Instruction::SetValueToConstant {
-            target: target.clone(),
-            constant: n,
-        });
+        self.setup.push(format!("scoreboard objectives add {math} dummy"));
 
-        target
+        for (name, values) in [("zz_sin", builtin::sine_table()), ("zz_atan", builtin::arctangent_table())] {
+            self.setup.push(format!(
+                "data modify storage {} {} set value [I;{}]",
+                tables,
+                name,
+                values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",")
+            ));
+        }
+
+        let lerp = vec![
+            format!("execute store result storage {} math_lerp.index int 1 run scoreboard players get #i {math}", args),
+            format!("scoreboard players add #i {math} 1"),
+            format!("execute store result storage {} math_lerp.next int 1 run scoreboard players get #i {math}", args),
+            format!("function {} with storage {} math_lerp", function("lerp_lookup"), args),
+            format!("scoreboard players operation #b {math} -= #a {math}"),
+            format!("scoreboard players operation #b {math} *= #f {math}"),
+            format!("scoreboard players set #c {math} {}", scale),
+            format!("scoreboard players operation #b {math} /= #c {math}"),
+            format!("scoreboard players operation #r {math} = #a {math}"),
+            format!("scoreboard players operation #r {math} += #b {math}"),
+        ];
+
+        let lerp_lookup = ["a", "b"]
+            .iter()
+            .zip(["index", "next"])
+            .map(|(score, arg)| format!("$execute store result score #{} {math} run data get storage {} $(table)[$({})]", score, tables, arg))
+            .collect();
+
+        // the angle is wrapped to a single turn, and split into a whole degree and the thousandths past it
+        let sin = vec![
+            format!("scoreboard players set #c {math} {}", 360 * scale),
+            format!("scoreboard players operation v0 {math} %= #c {math}"),
+            format!("scoreboard players set #c {math} {}", scale),
+            format!("scoreboard players operation #i {math} = v0 {math}"),
+            format!("scoreboard players operation #i {math} /= #c {math}"),
+            format!("scoreboard players operation #f {math} = v0 {math}"),
+            format!("scoreboard players operation #f {math} %= #c {math}"),
+            format!("data modify storage {} math_lerp.table set value \"zz_sin\"", args),
+            format!("function {}", function("lerp")),
+            format!("scoreboard players operation v0 {math} = #r {math}"),
+        ];
+
+        let cos = vec![
+            format!("scoreboard players set #c {math} {}", 360 * scale),
+            format!("scoreboard players operation v0 {math} %= #c {math}"),
+            format!("scoreboard players add v0 {math} {}", 90 * scale),
+            format!("function {}", function("sin")),
+        ];
+
+        // the smaller of |y| and |x| is divided by the larger to get a ratio between 0 and 1, whose arctangent is an angle
+        // between 0 and 45 degrees. Both are scaled down first if the larger one is big enough for the division to overflow.
+        // The angle is then mirrored into the octant that the direction is in
+        let mut atan2 = vec![
+            format!("scoreboard players set #n {math} -1"),
+            format!("scoreboard players operation #y {math} = v0 {math}"),
+            format!("scoreboard players operation #ay {math} = v0 {math}"),
+            format!("execute if score #ay {math} matches ..-1 run scoreboard players operation #ay {math} *= #n {math}"),
+            format!("scoreboard players operation #ax {math} = v1 {math}"),
+            format!("execute if score #ax {math} matches ..-1 run scoreboard players operation #ax {math} *= #n {math}"),
+            format!("scoreboard players operation #hi {math} = #ax {math}"),
+            format!("scoreboard players operation #hi {math} > #ay {math}"),
+            format!("scoreboard players operation #lo {math} = #ax {math}"),
+            format!("scoreboard players operation #lo {math} < #ay {math}"),
+            format!("execute if score #hi {math} matches 0 run return run scoreboard players set v0 {math} 0"),
+            format!("scoreboard players set #c {math} 100"),
+        ];
+
+        let ratio_scale = builtin::ARCTANGENT_STEPS * scale;
+        let max_dividend = i32::MAX / ratio_scale;
+
+        for _ in 0..3 {
+            for score in ["lo", "hi"] {
+                atan2.push(format!(
+                    "execute if score #hi {math} matches {}.. run scoreboard players operation #{} {math} /= #c {math}",
+                    max_dividend + 1,
+                    score
+                ));
+            }
+        }
+
+        atan2.extend([
+            format!("scoreboard players operation #t {math} = #lo {math}"),
+            format!("scoreboard players set #c {math} {}", ratio_scale),
+            format!("scoreboard players operation #t {math} *= #c {math}"),
+            format!("scoreboard players operation #t {math} /= #hi {math}"),
+            format!("scoreboard players set #c {math} {}", scale),
+            format!("scoreboard players operation #i {math} = #t {math}"),
+            format!("scoreboard players operation #i {math} /= #c {math}"),
+            format!("scoreboard players operation #f {math} = #t {math}"),
+            format!("scoreboard players operation #f {math} %= #c {math}"),
+            format!("data modify storage {} math_lerp.table set value \"zz_atan\"", args),
+            format!("function {}", function("lerp")),
+            format!("scoreboard players operation v0 {math} = #r {math}"),
+            format!("scoreboard players set #c {math} {}", 90 * scale),
+            format!("execute if score #ay {math} > #ax {math} run scoreboard players operation v0 {math} *= #n {math}"),
+            format!("execute if score #ay {math} > #ax {math} run scoreboard players operation v0 {math} += #c {math}"),
+            format!("scoreboard players set #c {math} {}", 180 * scale),
+            format!("execute if score v1 {math} matches ..-1 run scoreboard players operation v0 {math} *= #n {math}"),
+            format!("execute if score v1 {math} matches ..-1 run scoreboard players operation v0 {math} += #c {math}"),
+            format!("execute if score #y {math} matches ..-1 run scoreboard players operation v0 {math} *= #n {math}"),
+        ]);
+
+        for (name, commands) in [("lerp", lerp), ("lerp_lookup", lerp_lookup), ("sin", sin), ("cos", cos), ("atan2", atan2)] {
+            self.helpers.push((format!("zz_math/{}", name), commands));
+        }
     }
 
-    fn visit_bool_literal(&mut self, b: bool) -> ValueLocation {
-        let target = self.get_free_location();
+    // Adds the helpers behind min(), max(), abs(), clamp(), pow() and sqrt().
+    // zz_math/pow squares the base for every bit of the exponent, multiplying it into the result when the bit is set.
+    // zz_math/sqrt uses Newton's method, starting from x / 2 + 1 which is never below the root, and stops once the next
+    // guess doesn't get any smaller
+    fn push_arithmetic_helpers(&mut self) {
+        let math = math_objective(&self.pack_name);
+        let function = |name: &str| ResourceLocation::new(self.pack_name.clone(), format!("zz_math/{}", name));
 
-        self.emit(Instruction::SetValueToConstant {
-            target: target.clone(),
-            constant: if b { 1 } else { 0 },
-        });
+        self.setup.push(format!("scoreboard objectives add {math} dummy"));
 
-        target
+        let min = vec![format!("scoreboard players operation v0 {math} < v1 {math}")];
+        let max = vec![format!("scoreboard players operation v0 {math} > v1 {math}")];
+
+        let abs = vec![
+            format!("scoreboard players set #n {math} -1"),
+            format!("execute if score v0 {math} matches ..-1 run scoreboard players operation v0 {math} *= #n {math}"),
+        ];
+
+        let clamp = vec![
+            format!("scoreboard players operation v0 {math} > v1 {math}"),
+            format!("scoreboard players operation v0 {math} < v2 {math}"),
+        ];
+
+        let pow = vec![
+            format!("scoreboard players operation #pb {math} = v0 {math}"),
+            format!("scoreboard players operation #pe {math} = v1 {math}"),
+            format!("scoreboard players set v0 {math} 1"),
+            format!("execute if score #pe {math} matches ..-1 run return run scoreboard players set v0 {math} 0"),
+            format!("execute if score #pe {math} matches 1.. run function {}", function("pow_step")),
+        ];
+
+        let pow_step = vec![
+            format!("scoreboard players set #c {math} 2"),
+            format!("scoreboard players operation #pr {math} = #pe {math}"),
+            format!("scoreboard players operation #pr {math} %= #c {math}"),
+            format!("execute if score #pr {math} matches 1 run scoreboard players operation v0 {math} *= #pb {math}"),
+            format!("scoreboard players operation #pe {math} /= #c {math}"),
+            format!("scoreboard players operation #pb {math} *= #pb {math}"),
+            format!("execute if score #pe {math} matches 1.. run function {}", function("pow_step")),
+        ];
+
+        let sqrt = vec![
+            format!("execute if score v0 {math} matches ..0 run return run scoreboard players set v0 {math} 0"),
+            format!("scoreboard players operation #sn {math} = v0 {math}"),
+            format!("scoreboard players operation #sx {math} = v0 {math}"),
+            format!("scoreboard players set #c {math} 2"),
+            format!("scoreboard players operation #sx {math} /= #c {math}"),
+            format!("scoreboard players add #sx {math} 1"),
+            format!("function {}", function("sqrt_step")),
+            format!("scoreboard players operation v0 {math} = #sx {math}"),
+        ];
+
+        let sqrt_step = vec![
+            format!("scoreboard players operation #sy {math} = #sn {math}"),
+            format!("scoreboard players operation #sy {math} /= #sx {math}"),
+            format!("scoreboard players operation #sy {math} += #sx {math}"),
+            format!("scoreboard players operation #sy {math} /= #c {math}"),
+            format!("execute if score #sy {math} >= #sx {math} run return 0"),
+            format!("scoreboard players operation #sx {math} = #sy {math}"),
+            format!("function {}", function("sqrt_step")),
+        ];
+
+        for (name, commands) in [
+            ("min", min),
+            ("max", max),
+            ("abs", abs),
+            ("clamp", clamp),
+            ("pow", pow),
+            ("pow_step", pow_step),
+            ("sqrt", sqrt),
+            ("sqrt_step", sqrt_step),
+        ] {
+            self.helpers.push((format!("zz_math/{}", name), commands));
+        }
     }
 
-    fn visit_identifier(&mut self, identifier: &ParserNode) -> ValueLocation {
-        let source = self.get_local(identifier.as_identifier());
-        let target = self.get_free_location();
-        let size = self.tags.get_type(identifier).from(&self.types).total_size(&self.types);
+    // Adds the helper behind random() for versions without the random command. It steps a linear congruential generator
+    // whose state is kept in #seed, which is seeded from the world's seed the first time the pack loads. Only the high
+    // bits of each step are random enough to use, so two steps are put together into 30 bits, which are then brought
+    // into the range from v0 to v1
+    fn push_random_helper(&mut self) {
+        let math = math_objective(&self.pack_name);
+        self.setup.extend([
+            format!("scoreboard objectives add {math} dummy"),
+            format!("execute unless score #seed {math} matches -2147483648.. store result score #seed {math} run seed"),
+        ]);
 
-        self.emit_value_copy(target.clone(), source, size);
+        let mut random = vec![format!("scoreboard players set #r {math} 0")];
 
-        target
+        for _ in 0..2 {
+            random.extend([
+                format!("scoreboard players set #c {math} 1103515245"),
+                format!("scoreboard players operation #seed {math} *= #c {math}"),
+                format!("scoreboard players add #seed {math} 12345"),
+                format!("scoreboard players operation #q {math} = #seed {math}"),
+                format!("scoreboard players set #c {math} 65536"),
+                format!("scoreboard players operation #q {math} /= #c {math}"),
+                format!("scoreboard players set #c {math} 32768"),
+                format!("scoreboard players operation #q {math} %= #c {math}"),
+                format!("scoreboard players operation #r {math} *= #c {math}"),
+                format!("scoreboard players operation #r {math} += #q {math}"),
+            ]);
+        }
+
+        random.extend([
+            format!("scoreboard players operation #span {math} = v1 {math}"),
+            format!("scoreboard players operation #span {math} -= v0 {math}"),
+            format!("scoreboard players add #span {math} 1"),
+            format!("scoreboard players operation #r {math} %= #span {math}"),
+            format!("scoreboard players operation v0 {math} += #r {math}"),
+        ]);
+
+        self.helpers.push(("zz_math/random".to_string(), random));
     }
 
-    fn visit_variable_declaration(&mut self, name: &str, expr: &ParserNode) {
-        let source = self.visit_node(expr);
-        let target = self.get_local(name);
+    // Adds the helper that gives instant damage or instant health for an amount in v0 of the math objective to the entity running it,
+    // picking the levels like instant_damage_amplifier() and instant_health_amplifiers() do for constant amounts.
+    // Instant health goes through the bits of the number of levels from highest to lowest, taking each one that fits
+    fn push_instant_effect_helper(&mut self, builtin: Builtin, effect: &str) -> String {
+        let math = math_objective(&self.pack_name);
+        self.setup.push(format!("scoreboard objectives add {math} dummy"));
 
-        self.emit_value_copy(
-            target,
-            source,
-            self.tags
-                .get_type(expr)
-                .from(&self.types)
-                .total_size(&self.types),
-        );
+        let max = builtin::MAX_INSTANT_AMPLIFIER;
+
+        let (name, commands) = match builtin {
+            Builtin::Damage => {
+                let health = builtin::INSTANT_DAMAGE_HEALTH;
+
+                let commands = (0..=max)
+                    .map(|amplifier| {
+                        let min = match amplifier {
+                            0 => 1,
+                            _ => health << amplifier,
+                        };
+                        let range = match amplifier == max {
+                            true => format!("{}..", min),
+                            false => format!("{}..{}", min, (health << (amplifier + 1)) - 1),
+                        };
+
+                        format!("execute if score v0 {math} matches {} run effect give @s {} 1 {} true", range, effect, amplifier)
+                    })
+                    .collect();
+
+                ("zz_health/damage", commands)
+            }
+            _ => {
+                let health = builtin::INSTANT_HEALTH_HEALTH;
+
+                // the amount is rounded to the nearest level, which every entity the helper runs as starts from
+                let mut commands = vec![
+                    format!("scoreboard players operation #levels {math} = v0 {math}"),
+                    format!("scoreboard players add #levels {math} {}", health / 2),
+                    format!("scoreboard players set #c {math} {}", health),
+                    format!("scoreboard players operation #levels {math} /= #c {math}"),
+                    format!(
+                        "execute if score #levels {math} matches {}.. run scoreboard players set #levels {math} {}",
+                        2 << max,
+                        (2 << max) - 1
+                    ),
+                ];
+
+                for amplifier in (0..=max).rev() {
+                    let levels = 1 << amplifier;
+
+                    commands.extend([
+                        format!("execute if score #levels {math} matches {}.. run effect give @s {} 1 {} true", levels, effect, amplifier),
+                        format!("execute if score #levels {math} matches {}.. run scoreboard players remove #levels {math} {}", levels, levels),
+                    ]);
+                }
+
+                ("zz_health/heal", commands)
+            }
+        };
+
+        self.helpers.push((name.to_string(), commands));
+        name.to_string()
     }
 
-    fn visit_variable_assignment(&mut self, path: &ParserNode, expr: &ParserNode) {
-        let source = self.visit_node(expr);
-        let resolution = self.tags.get_resolution(path);
-        let target = self.resolve_location(resolution);
+    // Adds the helper behind a bitwise operation and returns its name. Like the other math helpers, it takes its operands
+    // in v0 and v1 of the math objective and returns in v0.
+    // Shifts multiply or divide by a power of two that's looked up from the masked amount. The others go through the
+    // bits from lowest to highest: the lowest bit of each operand is its remainder by 2, which is combined and added to
+    // the result at the place of the bit before both operands are halved. Since the operands are halved rounding down,
+    // they end up as -1 or 0 for the sign bit, whose place wraps around to -2^31 after doubling 31 times
+    fn push_bitwise_helper(&mut self, op: Operation) -> String {
+        let math = math_objective(&self.pack_name);
+        self.setup.push(format!("scoreboard objectives add {math} dummy"));
 
-        self.emit_value_copy(
-            target,
-            source,
-            self.tags
-                .get_type(expr)
-                .from(&self.types)
-                .total_size(&self.types),
-        );
+        let (name, commands) = match op {
+            Operation::ShiftLeft | Operation::ShiftRight => {
+                let mut commands = vec![
+                    format!("scoreboard players operation #n {math} = v1 {math}"),
+                    format!("scoreboard players set #c {math} 32"),
+                    format!("scoreboard players operation #n {math} %= #c {math}"),
+                ];
+
+                commands.extend((0..32).map(|amount: u32| {
+                    format!(
+                        "execute if score #n {math} matches {} run scoreboard players set #p {math} {}",
+                        amount,
+                        1i32.wrapping_shl(amount)
+                    )
+                }));
+
+                if op == Operation::ShiftLeft {
+                    commands.push(format!("scoreboard players operation v0 {math} *= #p {math}"));
+                    ("shift_left", commands)
+                } else {
+                    // dividing by -2^31 wouldn't round the same way, but shifting by 31 leaves only the sign
+                    commands.extend([
+                        format!("execute if score #n {math} matches ..30 run scoreboard players operation v0 {math} /= #p {math}"),
+                        format!("execute if score #n {math} matches 31 if score v0 {math} matches ..-1 run scoreboard players set v0 {math} -1"),
+                        format!("execute if score #n {math} matches 31 if score v0 {math} matches 0.. run scoreboard players set v0 {math} 0"),
+                    ]);
+                    ("shift_right", commands)
+                }
+            }
+            _ => {
+                let (name, combine) = match op {
+                    Operation::BitAnd => ("and", vec![format!("scoreboard players operation #x {math} *= #y {math}")]),
+                    Operation::BitOr => ("or", vec![
+                        format!("scoreboard players operation #x {math} += #y {math}"),
+                        format!("execute if score #x {math} matches 2 run scoreboard players set #x {math} 1"),
+                    ]),
+                    Operation::BitXor => ("xor", vec![
+                        format!("scoreboard players operation #x {math} += #y {math}"),
+                        format!("scoreboard players operation #x {math} %= #c {math}"),
+                    ]),
+                    _ => unreachable!(),
+                };
+
+                let mut commands = vec![
+                    format!("scoreboard players operation #a {math} = v0 {math}"),
+                    format!("scoreboard players operation #b {math} = v1 {math}"),
+                    format!("scoreboard players set v0 {math} 0"),
+                    format!("scoreboard players set #p {math} 1"),
+                    format!("scoreboard players set #c {math} 2"),
+                ];
+
+                for _ in 0..32 {
+                    commands.extend([
+                        format!("scoreboard players operation #x {math} = #a {math}"),
+                        format!("scoreboard players operation #x {math} %= #c {math}"),
+                        format!("scoreboard players operation #y {math} = #b {math}"),
+                        format!("scoreboard players operation #y {math} %= #c {math}"),
+                    ]);
+                    commands.extend(combine.iter().cloned());
+                    commands.extend([
+                        format!("scoreboard players operation #x {math} *= #p {math}"),
+                        format!("scoreboard players operation v0 {math} += #x {math}"),
+                        format!("scoreboard players operation #a {math} /= #c {math}"),
+                        format!("scoreboard players operation #b {math} /= #c {math}"),
+                        format!("scoreboard players operation #p {math} *= #c {math}"),
+                    ]);
+                }
+
+                (name, commands)
+            }
+        };
+
+        let helper = format!("zz_math/{}", name);
+        self.helpers.push((helper.clone(), commands));
+        helper
     }
 
-    fn visit_binary_operation(
-        &mut self,
-        lhs: &ParserNode,
-        rhs: &ParserNode,
-        op: Operation,
-    ) -> ValueLocation {
-        let op = match op {
-            Operation::Add => BinaryOperation::Add,
-            Operation::Subtract => BinaryOperation::Subtract,
-            Operation::Multiply => BinaryOperation::Multiply,
-            Operation::Divide => BinaryOperation::Divide,
-            Operation::Modulo => BinaryOperation::Modulo,
-            Operation::CheckEquals => BinaryOperation::CheckEquals,
-            Operation::NotEquals => BinaryOperation::NotEquals,
-            Operation::GreaterThan => BinaryOperation::GreaterThan,
-            Operation::GreaterThanOrEquals => BinaryOperation::GreaterThanOrEquals,
-            Operation::LessThan => BinaryOperation::LessThan,
-            Operation::LessThanOrEquals => BinaryOperation::LessThanOrEquals,
-            Operation::And => BinaryOperation::And,
-            Operation::Or => BinaryOperation::Or,
-            _ => unreachable!(),
+    // Adds the helpers behind hash() and noise(). Scoreboards can't xor or shift, so zz_math/hash mixes each argument in by
+    // multiplying with large odd constants, which wraps around like Java ints do, and adding the high bits back to the low ones.
+    // zz_math/noise hashes the corners of the cell that the position is in to values between 0 and 1000,
+    // and interpolates between them with a smoothstep of how far the position is into the cell
+    fn push_noise_helpers(&mut self) {
+        let math = math_objective(&self.pack_name);
+        let function = |name: &str| ResourceLocation::new(self.pack_name.clone(), format!("zz_math/{}", name));
+        let scale = builtin::FIXED_POINT_SCALE;
+
+        self.setup.push(format!("scoreboard objectives add {math} dummy"));
+
+        let mut hash = vec![format!("scoreboard players set #h {math} 374761393")];
+
+        for arg in 0..3 {
+            hash.extend([
+                format!("scoreboard players operation #m {math} = v{} {math}", arg),
+                format!("scoreboard players set #c {math} 668265263"),
+                format!("scoreboard players operation #m {math} *= #c {math}"),
+                format!("scoreboard players operation #h {math} += #m {math}"),
+                format!("scoreboard players set #c {math} 1274126177"),
+                format!("scoreboard players operation #h {math} *= #c {math}"),
+                format!("scoreboard players operation #m {math} = #h {math}"),
+                format!("scoreboard players set #c {math} 65536"),
+                format!("scoreboard players operation #m {math} /= #c {math}"),
+                format!("scoreboard players operation #h {math} += #m {math}"),
+            ]);
+        }
+
+        hash.push(format!("scoreboard players operation v0 {math} = #h {math}"));
+
+        let mut noise = vec![
+            format!("scoreboard players operation #nx {math} = v0 {math}"),
+            format!("scoreboard players operation #nz {math} = v1 {math}"),
+            format!("scoreboard players operation #cell {math} = v2 {math}"),
+            format!("scoreboard players operation #seed {math} = v3 {math}"),
+        ];
+
+        // splits the position into the cell it is in and a smoothstep of how far into the cell it is, in thousandths
+        for axis in ["x", "z"] {
+            noise.extend([
+                format!("scoreboard players operation #c{} {math} = #n{} {math}", axis, axis),
+                format!("scoreboard players operation #c{} {math} /= #cell {math}", axis),
+                format!("scoreboard players operation #f{} {math} = #n{} {math}", axis, axis),
+                format!("scoreboard players operation #f{} {math} %= #cell {math}", axis),
+                format!("scoreboard players set #c {math} {}", scale),
+                format!("scoreboard players operation #f{} {math} *= #c {math}", axis),
+                format!("scoreboard players operation #f{} {math} /= #cell {math}", axis),
+                format!("scoreboard players operation #s{} {math} = #f{} {math}", axis, axis),
+                format!("scoreboard players operation #s{} {math} *= #f{} {math}", axis, axis),
+                format!("scoreboard players operation #s{} {math} /= #c {math}", axis),
+                format!("scoreboard players set #u {math} {}", 3 * scale),
+                format!("scoreboard players operation #u {math} -= #f{} {math}", axis),
+                format!("scoreboard players operation #u {math} -= #f{} {math}", axis),
+                format!("scoreboard players operation #s{} {math} *= #u {math}", axis),
+                format!("scoreboard players operation #s{} {math} /= #c {math}", axis),
+            ]);
+        }
+
+        for (corner, dx, dz) in [("00", 0, 0), ("10", 1, 0), ("01", 0, 1), ("11", 1, 1)] {
+            noise.extend([
+                format!("scoreboard players operation v0 {math} = #cx {math}"),
+                format!("scoreboard players add v0 {math} {}", dx),
+                format!("scoreboard players operation v1 {math} = #cz {math}"),
+                format!("scoreboard players add v1 {math} {}", dz),
+                format!("scoreboard players operation v2 {math} = #seed {math}"),
+                format!("function {}", function("hash")),
+                format!("scoreboard players set #c {math} {}", scale + 1),
+                format!("scoreboard players operation #n{} {math} = v0 {math}", corner),
+                format!("scoreboard players operation #n{} {math} %= #c {math}", corner),
+            ]);
+        }
+
+        // target = a + (b - a) * t / 1000
+        let lerp = |target: &str, a: &str, b: &str, t: &str| {
+            vec![
+                format!("scoreboard players operation #d {math} = {} {math}", b),
+                format!("scoreboard players operation #d {math} -= {} {math}", a),
+                format!("scoreboard players operation #d {math} *= {} {math}", t),
+                format!("scoreboard players set #c {math} {}", scale),
+                format!("scoreboard players operation #d {math} /= #c {math}"),
+                format!("scoreboard players operation {} {math} = {} {math}", target, a),
+                format!("scoreboard players operation {} {math} += #d {math}", target),
+            ]
         };
 
-        let target = self.visit_node(lhs);
-        let source = self.visit_node(rhs);
+        noise.extend(lerp("#top", "#n00", "#n10", "#sx"));
+        noise.extend(lerp("#bottom", "#n01", "#n11", "#sx"));
+        noise.extend(lerp("v0", "#top", "#bottom", "#sz"));
 
-        self.emit(Instruction::ValueBinaryOperation {
-            source,
-            target: target.clone(),
-            op,
-        });
+        for (name, commands) in [("hash", hash), ("noise", noise)] {
+            self.helpers.push((format!("zz_math/{}", name), commands));
+        }
+    }
 
-        target
+    // Builds the body of the function that updates a leaderboard. The top players are copied one by one to a separate objective
+    // shown on the sidebar: every round finds the highest score of the players that aren't shown yet and shows one player with it,
+    // so that ties don't push the board past its size
+    fn leaderboard_commands(&mut self, objective: &str, size: i32) -> Vec<String> {
+        let display = format!("{}.lb.{}", self.pack_name, objective);
+        let shown = format!("{}.lb_shown", self.pack_name);
+        let next = format!("{}.lb_next", self.pack_name);
+
+        self.setup.push(format!("scoreboard objectives add {} dummy {}", display, json!({ "text": objective })));
+        self.setup.push("scoreboard objectives add zz_lb dummy".to_string());
+
+        let mut commands = vec![
+            format!("scoreboard players reset * {}", display),
+            format!("tag @a remove {}", shown),
+        ];
+
+        for _ in 0..size {
+            commands.extend([
+                format!("scoreboard players set #max zz_lb {}", i32::MIN),
+                format!("execute as @a[tag=!{}] run scoreboard players operation #max zz_lb > @s {}", shown, objective),
+                format!("execute as @a[tag=!{}] if score @s {} = #max zz_lb run tag @s add {}", shown, objective, next),
+                format!("execute as @a[tag={},limit=1] run tag @s add {}", next, shown),
+                format!("execute as @a[tag={},tag={}] run scoreboard players operation @s {} = @s {}", next, shown, display, objective),
+                format!("tag @a remove {}", next),
+            ]);
+        }
+
+        commands.push(format!("scoreboard objectives setdisplay sidebar {}", display));
+        commands
     }
 
-    fn visit_unary_operation(&mut self, expr: &ParserNode, op: Operation) -> ValueLocation {
-        let target = self.visit_node(expr);
+    // Builds a command that takes ints such as block coordinates, each of which is an argument plus a constant offset, followed by strings.
+    // Constant ints are put in the command directly. Otherwise they are saved to data storage
+    // and the command is run by a helper function that inserts them and the strings with a macro
+    fn int_args_command(
+        &mut self,
+        helper: &str,
+        args: &[ParserNode],
+        ints: &[(&str, usize, i32)],
+        strings: &[(&str, &str)],
+        build: impl Fn(&[String]) -> String,
+    ) -> String {
+        let constants = ints
+            .iter()
+            .map(|(_, arg, offset)| match args[*arg].unwrap_expression().kind() {
+                ParserNodeKind::NumberLiteral(n) => Some((n + offset).to_string()),
+                _ => None,
+            })
+            .collect::<Option<Vec<String>>>();
 
-        match op {
-            Operation::Negate => {
-                let n = self.get_free_location();
+        if let Some(mut constants) = constants {
+            constants.extend(strings.iter().map(|(_, value)| value.to_string()));
+            return build(&constants);
+        }
 
-                self.emit(Instruction::SetValueToConstant {
-                    target: n.clone(),
-                    constant: -1,
-                });
+        let values = args.iter().map(|arg| self.visit_node(arg)).collect::<Vec<ValueLocation>>();
 
-                self.emit(Instruction::ValueBinaryOperation {
-                    source: n,
-                    target: target.clone(),
-                    op: BinaryOperation::Multiply,
-                });
+        let ints = ints
+            .iter()
+            .map(|(name, arg, offset)| match offset {
+                0 => (*name, values[*arg].clone()),
+                _ => {
+                    let temp = self.get_free_location();
+                    self.emit_value_copy(temp.clone(), values[*arg].clone(), self.types.int());
+                    self.emit(Instruction::ModifyValue {
+                        target: temp.clone(),
+                        value: *offset,
+                    });
+                    (*name, temp)
+                }
+            })
+            .collect::<Vec<(&str, ValueLocation)>>();
+
+        self.macro_command(helper, &ints, strings, build)
+    }
+
+    // Builds a command that takes the components of a vec3 as ints, see int_args_command. The components of a vec3
+    // that's constructed in place, e.g `vec3(0, 64, 0)`, are taken from the arguments of its constructor
+    fn vec3_command(
+        &mut self,
+        helper: &str,
+        position: &ParserNode,
+        strings: &[(&str, &str)],
+        build: impl Fn(&[String]) -> String,
+    ) -> String {
+        let position = position.unwrap_expression();
+        let axes = ["x", "y", "z"];
+
+        if let ParserNodeKind::FunctionCall { args, .. } = position.kind() {
+            let constructed = matches!(self.tags.find_resolution(position).map(|r| r.last()), Some(ResolvedPart::Constructor(_)));
+
+            if constructed && args.iter().all(|arg| matches!(arg.unwrap_expression().kind(), ParserNodeKind::NumberLiteral(_))) {
+                return self.int_args_command(helper, args, &int_args(&axes), strings, build);
             }
-            Operation::Not => {
-                self.emit(Instruction::ToggleValue {
-                    target: target.clone(),
-                });
+        }
+
+        let value = self.visit_node(position);
+        let ints = axes
+            .iter()
+            .enumerate()
+            .map(|(i, axis)| (*axis, value.offset(i)))
+            .collect::<Vec<(&str, ValueLocation)>>();
+
+        self.macro_command(helper, &ints, strings, build)
+    }
+
+    // Builds a command that is run by a helper function, which inserts the given ints and strings into it with a macro
+    // after they're saved to data storage
+    fn macro_command(
+        &mut self,
+        helper: &str,
+        ints: &[(&str, ValueLocation)],
+        strings: &[(&str, &str)],
+        build: impl Fn(&[String]) -> String,
+    ) -> String {
+        let storage = ResourceLocation::new(self.pack_name.clone(), "args".to_string());
+        let key = helper.trim_start_matches("zz_").replace('/', "_");
+
+        for (name, value) in ints {
+            self.emit(Instruction::SaveValueToStorage {
+                source: value.clone(),
+                storage: storage.clone(),
+                path: format!("{}.{}", key, name),
+            });
+        }
+
+        for (name, value) in strings {
+            self.emit(Instruction::PlaceCommandLiteral(format!(
+                "data modify storage {} {}.{} set value {}",
+                storage,
+                key,
+                name,
+                Value::from(*value)
+            )));
+        }
+
+        let macro_args = ints
+            .iter()
+            .map(|(name, _)| name)
+            .chain(strings.iter().map(|(name, _)| name))
+            .map(|name| format!("$({})", name))
+            .collect::<Vec<String>>();
+
+        // a function's success is only known from a return, so the helper returns the result of its command
+        self.helpers.push((helper.to_string(), vec![format!("$return run {}", build(&macro_args))]));
+
+        format!("function {} with storage {} {}", ResourceLocation::new(self.pack_name.clone(), helper.to_string()), storage, key)
+    }
+
+    // Builds the JSON text shown by a builtin like print(). Several parts are put in an array whose first element is empty,
+    // so that the style of the first part isn't inherited by the rest
+    fn visit_text_components(&mut self, parts: &[ParserNode]) -> Value {
+        match parts {
+            [part] => self.visit_text_component(part),
+            _ => {
+                let mut components = vec![Value::from("")];
+                components.extend(parts.iter().map(|part| self.visit_text_component(part)));
+                Value::Array(components)
             }
-            _ => unreachable!(),
         }
+    }
 
-        target
+    // the target of a builtin, which the validator made sure is either a string or a selector value
+    fn target_arg(&self, arg: &ParserNode) -> String {
+        let arg = arg.unwrap_expression();
+
+        match arg.kind() {
+            ParserNodeKind::StringLiteral(target) => target.clone(),
+            _ => self.tags.find_selector(arg).unwrap().to_string(),
+        }
     }
 
-    fn visit_operation_equals(&mut self, path: &ParserNode, expr: &ParserNode, op: Operation) {
-        let resolution = self.tags.get_resolution(path);
-        let target = self.resolve_location(resolution);
-        let source = self.visit_node(expr);
+    fn visit_text_component(&mut self, part: &ParserNode) -> Value {
+        let inner = part.unwrap_expression();
+
+        if let ParserNodeKind::StringLiteral(text) = inner.kind() {
+            return json!({ "text": text });
+        }
+
+        if let ParserNodeKind::FunctionCall { args, .. } = inner.kind() {
+            if let Some(ResolvedPart::Builtin(builtin)) = self.tags.find_resolution(inner).map(|r| r.last()) {
+                if builtin.is_text_component() {
+                    return self.visit_text_builder(*builtin, args);
+                }
+            }
+        }
+
+        if let Some(selector) = self.tags.find_selector(inner) {
+            return json!({ "selector": selector });
+        }
+
+        if self.tags.get_type(inner) == self.types.text() {
+            return match inner.kind() {
+                ParserNodeKind::TextLiteral { parts, styles } => self.visit_text_literal(parts, styles),
+                _ => self.texts[inner.as_identifier()].clone(),
+            };
+        }
+
+        let value = self.visit_node(part);
+        if self.tags.get_type(part) == self.types.string() {
+            return json!({ "nbt": storage_path(&value), "storage": values_storage(&self.pack_name).to_string() });
+        }
+
+        // any other value is evaluated and shown through a score component
+        let slot = ScoreboardSlot::from(value);
+
+        json!({ "score": { "name": slot.entry.as_str(), "objective": slot.objective.as_str() } })
+    }
+
+    // The styles are set on the first element of the array, which the other parts inherit them from
+    fn visit_text_literal(&mut self, parts: &[ParserNode], styles: &[String]) -> Value {
+        let mut root = match styles {
+            [] => Value::from(""),
+            _ => json!({ "text": "" }),
+        };
+
+        for style in styles {
+            root[style] = json!(true);
+        }
+
+        let mut components = vec![root];
+        components.extend(parts.iter().map(|part| self.visit_text_component(part)));
+        Value::Array(components)
+    }
+
+    fn visit_text_builder(&mut self, builtin: Builtin, args: &[ParserNode]) -> Value {
+        let string = |index: usize| match args[index].unwrap_expression().kind() {
+            ParserNodeKind::StringLiteral(value) => value.clone(),
+            _ => unreachable!(),
+        };
+
+        let (mut component, key, value) = match builtin {
+            Builtin::Selector => return json!({ "selector": self.target_arg(&args[0]) }),
+            Builtin::Score => return self.visit_text_component(&args[0]),
+            Builtin::Color => (self.visit_text_component(&args[1]), "color", json!(string(0))),
+            Builtin::Bold => (self.visit_text_component(&args[0]), "bold", json!(true)),
+            Builtin::Italic => (self.visit_text_component(&args[0]), "italic", json!(true)),
+            Builtin::Underlined => (self.visit_text_component(&args[0]), "underlined", json!(true)),
+            Builtin::Hover => {
+                let contents = self.visit_text_component(&args[0]);
+                let component = self.visit_text_component(&args[1]);
+
+                (component, "hoverEvent", json!({ "action": "show_text", "contents": contents }))
+            }
+            Builtin::ClickRun | Builtin::ClickSuggest | Builtin::ClickUrl => {
+                let action = match builtin {
+                    Builtin::ClickRun => "run_command",
+                    Builtin::ClickSuggest => "suggest_command",
+                    _ => "open_url",
+                };
+
+                (self.visit_text_component(&args[1]), "clickEvent", json!({ "action": action, "value": string(0) }))
+            }
+            Builtin::ClickTrigger | Builtin::ClickFunction => {
+                let callback = args[0].unwrap_expression();
+                let callback = self.tags.find_function_name(callback).unwrap_or(callback.as_identifier()).to_string();
+
+                let command = match builtin {
+                    Builtin::ClickTrigger => {
+                        let objective = click_objective(&self.pack_name);
+                        let id = callback_id(self.global_functions, &callback);
 
-        let op = match op {
-            Operation::Add => BinaryOperation::Add,
-            Operation::Subtract => BinaryOperation::Subtract,
-            Operation::Multiply => BinaryOperation::Multiply,
-            Operation::Divide => BinaryOperation::Divide,
-            Operation::Modulo => BinaryOperation::Modulo,
+                        self.setup.push(format!("scoreboard objectives add {} trigger", objective));
+                        self.callbacks.push(callback);
+
+                        format!("/trigger {} set {}", objective, id)
+                    }
+                    _ => format!("/function {}", ResourceLocation::new(self.pack_name.clone(), callback)),
+                };
+
+                (self.visit_text_component(&args[1]), "clickEvent", json!({ "action": "run_command", "value": command }))
+            }
             _ => unreachable!(),
         };
 
-        self.emit(Instruction::ValueBinaryOperation { source, target, op });
+        component[key] = value;
+        component
     }
 
-    fn visit_function_call(&mut self, node: &ParserNode) -> Option<ValueLocation> {
-        let (expr, params) = node.as_function_call();
-        let resolution = self.tags.get_resolution(node);
+    fn visit_block(&mut self, body: &[ParserNode]) {
+        for (i, node) in body.iter().enumerate() {
+            // the statements after an await become a block that is scheduled to run once the wait is over. Awaits can
+            // only be directly in a function's body, but the function may run again before then, so the values that
+            // were set before the await and that the block uses are kept aside until it runs
+            if let Some(ticks) = self.await_ticks(node) {
+                let rest = &body[i + 1..];
+                let defined = self.next_slot;
+                let mut kept = BTreeSet::new();
 
-        let mut args = vec![];
+                let continuation = Self::create_block(false, node, self, |id, builder| {
+                    builder.visit_block(rest);
 
-        if let ResolvedPart::Method(ty, name) = resolution.last() {
-            if !ty.from(&self.types).as_struct_def().function(&name).unwrap().is_static() {
-                args.push(self.visit_node(expr)); // self parameter
-            }
-        }
+                    mentioned_values(builder.blocks.last().unwrap(), &builder.objective, defined, &mut kept);
+                    let restore = builder.restore_kept_values(id, &kept);
+                    builder.blocks.last_mut().unwrap().splice(0..0, restore);
+                });
 
-        args.extend(params.iter().map(|arg| self.visit_node(arg)));
+                self.emit_keep_values(continuation, &kept);
+                self.emit(Instruction::ScheduleBlock {
+                    id: continuation,
+                    ticks,
+                });
 
-        let func_objective;
-        let func_signature;
-        let handle_return;
+                return;
+            }
 
-        match resolution.last() {
-            ResolvedPart::GlobalFunction(name) => {
-                let func_location = ResourceLocation::new(self.pack_name.clone(), name.clone());
+            // the same goes for a loop that --loop-budget may defer, which validation only allows where an await could
+            // be. The statements after it only run once it's over, which is either now or on the tick it finishes on
+            if self.loop_budget.is_some() && is_budgeted_loop(node.unwrap_expression()) && i + 1 < body.len() {
+                let rest = &body[i + 1..];
+                let after = Self::create_block(false, node, self, |_, builder| {
+                    builder.visit_block(rest);
+                });
+                let deferred = self.get_free_location();
 
-                func_objective = Objective(name.clone());
-                func_signature = self.global_functions.get(&func_location).unwrap();
-                handle_return = func_signature.return_type() != self.types.none();
-            }
-            ResolvedPart::Method(ty, name) => {
-                let struct_def = ty.from(&self.types).as_struct_def();
+                self.emit(Instruction::SetValueToConstant {
+                    target: deferred.clone(),
+                    constant: 0,
+                });
 
-                func_objective = Objective(format!("{}.{}", struct_def.name(), name));
-                func_signature = struct_def.function(name).unwrap();
-                handle_return = func_signature.return_type() != self.types.none();
-            }
-            // Constructors are a special case. To the user they seem like regular functions,
-            // but on the IR level, they construct a new value in-place
-            ResolvedPart::Constructor(ty) => {
-                let struct_def = ty.from(&self.types).as_struct_def();
-                let target = self.get_free_location();
+                self.after_loop = Some((after, deferred.clone()));
+                self.visit_node(node);
 
-                for (param, arg) in struct_def.constructor().params().iter().zip(args) {
-                    self.emit_value_copy(
-                        ValueLocation::new(
-                            target.slot,
-                            target.offset + struct_def.field_offset(param.name()),
-                            target.objective.clone(),
-                        ),
-                        arg,
-                        param.param_type().from(&self.types).total_size(&self.types),
-                    );
-                }
+                self.emit(Instruction::IfValueMatchesRunBlock {
+                    source: deferred,
+                    value: 0,
+                    block: after,
+                });
 
-                return Some(target);
+                return;
             }
-            _ => unreachable!(),
+
+            self.visit_node(node);
         }
+    }
 
-        for (i, (param, arg)) in func_signature.params().iter().zip(args).enumerate() {
-            let target = ValueLocation::new(i, 0, func_objective.clone());
+    // The list in storage that the values kept for a scheduled block are added to, with an entry for every time it's
+    // scheduled. Blocks that are scheduled for the same number of ticks run in the order they were scheduled in, so
+    // the block always takes the first entry
+    fn kept_values_list(&self, block: usize) -> String {
+        format!("\"{}\".b{}", self.objective, block)
+    }
 
-            self.emit_value_copy(
-                target,
-                arg,
-                param.param_type().from(&self.types).total_size(&self.types),
-            );
+    fn emit_keep_values(&mut self, block: usize, kept: &BTreeSet<(ValueLocation, bool)>) {
+        if kept.is_empty() {
+            return;
         }
 
-        self.emit(Instruction::Call {
-            function: ResourceLocation::new(self.pack_name.clone(), func_objective.0.clone()),
-        });
+        let storage = awaits_storage(&self.pack_name);
+        let list = self.kept_values_list(block);
 
-        if handle_return {
-            let target = self.get_free_location();
-            let source =
-                ValueLocation::new(0, 0, Objective(format!("{}.return", func_objective.0)));
+        self.emit(Instruction::PlaceCommandLiteral(format!("data modify storage {} {} append value {{}}", storage, list)));
 
-            self.emit_value_copy(
-                target.clone(),
-                source,
-                self.tags
-                    .get_type(node)
-                    .from(&self.types)
-                    .total_size(&self.types),
-            );
+        for (location, in_storage) in kept {
+            match in_storage {
+                true => self.emit(Instruction::PlaceCommandLiteral(format!(
+                    "data modify storage {} {}[-1].s{}_{} set from storage {} {}",
+                    storage,
+                    list,
+                    location.slot,
+                    location.offset,
+                    values_storage(&self.pack_name),
+                    storage_path(location)
+                ))),
+                false => self.emit(Instruction::SaveValueToStorage {
+                    source: location.clone(),
+                    storage: storage.clone(),
+                    path: format!("{}[-1].v{}_{}", list, location.slot, location.offset),
+                }),
+            }
+        }
+    }
 
-            Some(target)
-        } else {
-            None
+    // The instructions that a scheduled block starts with to take back the values that were kept for it
+    fn restore_kept_values(&self, block: usize, kept: &BTreeSet<(ValueLocation, bool)>) -> Vec<Instruction> {
+        if kept.is_empty() {
+            return Vec::new();
         }
+
+        let storage = awaits_storage(&self.pack_name);
+        let list = self.kept_values_list(block);
+
+        let mut restore: Vec<Instruction> = kept
+            .iter()
+            .map(|(location, in_storage)| match in_storage {
+                true => Instruction::PlaceCommandLiteral(format!(
+                    "data modify storage {} {} set from storage {} {}[0].s{}_{}",
+                    values_storage(&self.pack_name),
+                    storage_path(location),
+                    storage,
+                    list,
+                    location.slot,
+                    location.offset
+                )),
+                false => Instruction::LoadValueFromStorage {
+                    target: location.clone(),
+                    storage: storage.clone(),
+                    path: format!("{}[0].v{}_{}", list, location.slot, location.offset),
+                },
+            })
+            .collect();
+
+        restore.push(Instruction::PlaceCommandLiteral(format!("data remove storage {} {}[0]", storage, list)));
+        restore
     }
 
-    fn visit_block(&mut self, body: &[ParserNode]) {
-        for node in body {
-            self.visit_node(node);
+    // The number of ticks that a statement waits for before the rest of its block runs, if it's an await or a sleep()
+    fn await_ticks(&self, node: &ParserNode) -> Option<u32> {
+        let node = node.unwrap_expression();
+
+        match node.kind() {
+            ParserNodeKind::Await(ticks) => Some(*ticks as u32),
+            ParserNodeKind::FunctionCall { args, .. } => match (self.tags.find_resolution(node).map(|r| r.last()), args.as_slice()) {
+                (Some(ResolvedPart::Builtin(Builtin::Sleep)), [ticks]) => match ticks.unwrap_expression().kind() {
+                    ParserNodeKind::NumberLiteral(n) => Some(*n as u32),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
         }
     }
 
@@ -756,7 +4518,26 @@ impl<'a> IrFunctionBuilder<'a> {
         match expr {
             Some(expr) => {
                 let source = self.visit_node(expr);
-                let size = self.tags.get_type(expr).from(&self.types).total_size(&self.types);
+                let ty = self.tags.get_value_type(expr).from(&self.types);
+                let size = ty.total_size(&self.types);
+
+                // the return instruction only copies scores, so values in storage are moved to where the caller reads them first
+                for (i, in_storage) in ty.storage_slots(&self.types).into_iter().enumerate() {
+                    if !in_storage {
+                        continue;
+                    }
+
+                    let storage = values_storage(&self.pack_name);
+                    let target = ValueLocation::new(0, i, Objective(format!("{}.return", self.objective)));
+
+                    self.emit(Instruction::PlaceCommandLiteral(format!(
+                        "data modify storage {} {} set from storage {} {}",
+                        storage,
+                        storage_path(&target),
+                        storage,
+                        storage_path(&source.offset(i))
+                    )));
+                }
 
                 self.emit(Instruction::Return {
                     source: Some(source),
@@ -772,18 +4553,46 @@ impl<'a> IrFunctionBuilder<'a> {
         }
     }
 
+    // The body is run as and at each entity, so @s in it is the entity and the fields of the loop variable are its
+    // scores. It's a function of its own that the game runs once per entity, so nothing is carried out of it
+    fn visit_for_entities(&mut self, selector: &str, body: &ParserNode) {
+        let block = Self::create_block(false, body, self, |_, builder| {
+            builder.visit_node(body);
+        });
+
+        self.emit(Instruction::RunBlockAs {
+            id: block,
+            execute: format!("as {} at @s", selector),
+        });
+    }
+
+    fn visit_context_block(&mut self, subcommands: &[(String, String)], body: &ParserNode) {
+        let block = Self::create_block(false, body, self, |_, builder| {
+            builder.visit_node(body);
+        });
+
+        self.emit(Instruction::RunBlockAs {
+            id: block,
+            execute: subcommands
+                .iter()
+                .map(|(subcommand, selector)| format!("{} {}", subcommand, selector))
+                .collect::<Vec<String>>()
+                .join(" "),
+        });
+    }
+
     fn visit_if(
         &mut self,
         cond: &ParserNode,
         body: &ParserNode,
         else_body: &Option<Box<ParserNode>>,
     ) {
-        let true_body = Self::create_block(false, self, |_, builder| {
+        let true_body = Self::create_block(false, body, self, |_, builder| {
             builder.visit_node(body);
         });
 
         let else_body = else_body.as_ref().map(|else_body| {
-            Self::create_block(false, self, |_, builder| {
+            Self::create_block(false, else_body, self, |_, builder| {
                 builder.visit_node(else_body);
             })
         });
@@ -808,25 +4617,102 @@ impl<'a> IrFunctionBuilder<'a> {
         }
     }
 
+    // Every arm only gets the values that the arms before it don't match, so at most one arm's block runs
+    // without having to remember whether an arm has run already. Arms that can't match anything are left out
+    fn visit_match(&mut self, expr: &ParserNode, arms: &[(ArmPattern, ParserNode)]) {
+        let value = self.visit_node(expr);
+        let mut covered = Vec::new();
+
+        for (pattern, body) in arms {
+            // a variant matches the int it's stored as
+            let pattern = match pattern {
+                ArmPattern::Range(range) => *range,
+                ArmPattern::Variant { variant, .. } => {
+                    let value = self.tags.get_type(expr).from(self.types).as_enum_def().unwrap().variant_value(variant);
+                    MatchPattern { min: value, max: value }
+                }
+            };
+            let ranges = claim_range(pattern, &mut covered);
+
+            if ranges.is_empty() {
+                continue;
+            }
+
+            let block = Self::create_block(false, body, self, |_, builder| {
+                builder.visit_node(body);
+            });
+
+            for range in ranges {
+                match range.min.is_none() && range.max.is_none() {
+                    true => self.emit(Instruction::EnterBlock { id: block }),
+                    false => self.emit(Instruction::IfValueInRangeRunBlock {
+                        source: value.clone(),
+                        range,
+                        block,
+                    }),
+                }
+            }
+        }
+    }
+
     fn visit_for(
         &mut self,
+        attributes: &[ParserNode],
+        label: &Option<String>,
         init: &ParserNode,
         cond: &ParserNode,
         step: &ParserNode,
         body: &ParserNode,
     ) {
+        // a #[unroll] loop's bounds were checked during validation, and an argument is the number of iterations per block
+        let unroll = attributes.iter().find_map(|attribute| match attribute.kind() {
+            ParserNodeKind::Attribute { name, args } if name == "unroll" => match args.first().map(ParserNode::kind) {
+                Some(ParserNodeKind::NumberLiteral(n)) => Some(Some(*n as u32)),
+                _ => Some(None),
+            },
+            _ => None,
+        });
+
+        if let Some(per_block) = unroll {
+            self.visit_unrolled_for(per_block, label, init, cond, step, body);
+            return;
+        }
+
         self.visit_node(init);
 
-        let looping_body = Self::create_block(true, self, |id, builder| {
+        // the number of iterations a #[spread] loop runs per tick, which were checked during validation
+        let spread = attributes.iter().find_map(|attribute| match attribute.kind() {
+            ParserNodeKind::Attribute { name, args } if name == "spread" => match args[0].kind() {
+                ParserNodeKind::NumberLiteral(n) => Some((*n as u32, self.get_free_location())),
+                _ => unreachable!(),
+            },
+            _ => None,
+        });
+
+        if let Some((_, counter)) = &spread {
+            self.emit(Instruction::SetValueToConstant {
+                target: counter.clone(),
+                constant: 0,
+            });
+        }
+
+        let looping_body = Self::create_block(true, body, self, |id, builder| {
+            builder.loops.push(label.clone());
             builder.visit_node(body);
+            builder.loops.pop();
             builder.visit_node(step);
 
             let cond = builder.visit_node(cond);
+            let block = match (spread, builder.loop_budget) {
+                (Some((per_tick, counter)), _) => builder.create_spread_guard(id, counter, per_tick, body),
+                (None, Some(budget)) => builder.create_budget_guard(id, budget, body),
+                (None, None) => id,
+            };
 
             builder.emit(Instruction::IfValueMatchesRunBlock {
                 source: cond,
                 value: 1,
-                block: id,
+                block,
             });
         });
 
@@ -838,31 +4724,320 @@ impl<'a> IrFunctionBuilder<'a> {
         });
     }
 
+    // Puts the iterations of a loop with constant bounds one after another. Without a number of iterations per block
+    // they're all put inline, otherwise the ones that don't fill a block are put inline and the rest are run by a loop
+    // over blocks that each hold that many iterations, so the condition is only checked once per block
+    fn visit_unrolled_for(
+        &mut self,
+        per_block: Option<u32>,
+        label: &Option<String>,
+        init: &ParserNode,
+        cond: &ParserNode,
+        step: &ParserNode,
+        body: &ParserNode,
+    ) {
+        self.visit_node(init);
+
+        let iterations = consteval::trip_count(init, cond, step).unwrap();
+
+        let per_block = match per_block {
+            Some(per_block) if per_block < iterations => per_block,
+            // the step after the last iteration is left out, since nothing can read the variable after the loop
+            _ => {
+                for i in 0..iterations {
+                    if i > 0 {
+                        self.visit_node(step);
+                    }
+
+                    self.visit_node(body);
+                }
+
+                return;
+            }
+        };
+
+        for _ in 0..iterations % per_block {
+            self.visit_node(body);
+            self.visit_node(step);
+        }
+
+        let looping_body = Self::create_block(true, body, self, |id, builder| {
+            builder.loops.push(label.clone());
+
+            for _ in 0..per_block {
+                builder.visit_node(body);
+                builder.visit_node(step);
+            }
+
+            builder.loops.pop();
+
+            let cond = builder.visit_node(cond);
+            let block = match builder.loop_budget {
+                Some(budget) => builder.create_budget_guard(id, budget, body),
+                None => id,
+            };
+
+            builder.emit(Instruction::IfValueMatchesRunBlock {
+                source: cond,
+                value: 1,
+                block,
+            });
+        });
+
+        self.emit(Instruction::EnterBlock { id: looping_body });
+    }
+
+    // The label was checked during validation, and a label that's used again by a nested loop refers to that one
+    fn visit_break(&mut self, label: &Option<String>) {
+        let depth = match label {
+            Some(label) => self.loops.iter().rev().position(|l| l.as_ref() == Some(label)).unwrap(),
+            None => 0,
+        };
+
+        self.emit(Instruction::Break { depth });
+    }
+
+    // Counts an iteration of the loop against the pack's budget for the tick, which zz_budget/tick resets.
+    // Deferred iterations run as the server, and the statements after the loop run once the iterations that are
+    // resumed finish it without being deferred again
+    fn create_budget_guard(&mut self, loop_id: usize, budget: u32, body: &ParserNode) -> usize {
+        let counter = budget_location(&self.pack_name);
+
+        self.setup.push(format!("scoreboard objectives add {} dummy", counter.objective));
+        self.emit(Instruction::ModifyValue {
+            target: counter.clone(),
+            value: 1,
+        });
+
+        // the deferred iterations are resumed by a block of their own, which runs on its own like the function does, so
+        // it's where a break out of the loop stops and where the statements after the loop are run from
+        let after_loop = self.after_loop.take();
+        let resume = Self::create_block(false, body, self, |_, builder| {
+            if let Some((_, deferred)) = &after_loop {
+                builder.emit(Instruction::SetValueToConstant {
+                    target: deferred.clone(),
+                    constant: 0,
+                });
+            }
+
+            builder.emit(Instruction::EnterBlock { id: loop_id });
+
+            if let Some((after, deferred)) = &after_loop {
+                builder.emit(Instruction::IfValueMatchesRunBlock {
+                    source: deferred.clone(),
+                    value: 0,
+                    block: *after,
+                });
+            }
+        });
+
+        self.create_iteration_guard(loop_id, counter, budget, resume, after_loop.map(|(_, deferred)| deferred), body)
+    }
+
+    // Counts an iteration of a #[spread] loop, which runs a set number of iterations per tick. The loop's state is
+    // already kept in the function's scores between ticks, so carrying on with it only needs its own counter reset
+    fn create_spread_guard(&mut self, loop_id: usize, counter: ValueLocation, per_tick: u32, body: &ParserNode) -> usize {
+        self.emit(Instruction::ModifyValue {
+            target: counter.clone(),
+            value: 1,
+        });
+
+        let resume = Self::create_block(false, body, self, |_, builder| {
+            builder.emit(Instruction::SetValueToConstant {
+                target: counter.clone(),
+                constant: 0,
+            });
+            builder.emit(Instruction::EnterBlock { id: loop_id });
+        });
+
+        self.create_iteration_guard(loop_id, counter, per_tick, resume, None, body)
+    }
+
+    // Creates a block that runs the next iteration of a loop if its counter is still under the budget, or schedules
+    // the resume block for the next tick otherwise, setting deferred if given so that what follows the loop waits
+    fn create_iteration_guard(
+        &mut self,
+        loop_id: usize,
+        counter: ValueLocation,
+        budget: u32,
+        resume: usize,
+        deferred: Option<ValueLocation>,
+        body: &ParserNode,
+    ) -> usize {
+        let defer = Self::create_block(false, body, self, |_, builder| {
+            if let Some(deferred) = deferred {
+                builder.emit(Instruction::SetValueToConstant {
+                    target: deferred,
+                    constant: 1,
+                });
+            }
+
+            builder.emit(Instruction::ScheduleBlock { id: resume, ticks: 1 });
+        });
+
+        // the deferring check comes first since running the next iteration changes the counter
+        Self::create_block(false, body, self, |_, builder| {
+            builder.emit(Instruction::IfValueInRangeRunBlock {
+                source: counter.clone(),
+                range: MatchPattern { min: Some(budget as i32), max: None },
+                block: defer,
+            });
+            builder.emit(Instruction::IfValueInRangeRunBlock {
+                source: counter,
+                range: MatchPattern { min: None, max: Some(budget as i32 - 1) },
+                block: loop_id,
+            });
+        })
+    }
+
     fn visit_member_access(&mut self, expr: &ParserNode, member: &str) -> ValueLocation {
+        let expr_type = self.tags.get_type(expr).from(self.types).as_struct_def();
+
+        if expr_type.entity().is_some() {
+            let source = self.entity_field_location(expr_type, member);
+            let target = self.get_free_location();
+
+            self.emit_value_copy(target.clone(), source, expr_type.field(member).unwrap().field_type());
+            return target;
+        }
+
         let source = self.visit_node(expr);
         let target = self.get_free_location();
-        let expr_type = self.tags.get_type(expr).from(&self.types).as_struct_def();
         
         self.emit_value_copy(
             target.clone(),
             ValueLocation::new(source.slot, source.offset + expr_type.field_offset(member), source.objective.clone()),
-            expr_type.field(member).unwrap().field_type().from(&self.types).total_size(&self.types)
+            expr_type.field(member).unwrap().field_type()
+        );
+
+        target
+    }
+
+    // Reads an element of a table or an array from storage. An index that's past the end reads 0, while a negative one counts from the end
+    fn visit_index(&mut self, expr: &ParserNode, index: &ParserNode) -> ValueLocation {
+        // tables aren't values, so they're the only indexed expressions that weren't given a type during validation
+        let (helper, storage, path) = match self.tags.find_type(expr) {
+            Some(_) => {
+                let array = self.visit_node(expr);
+                ("zz_arrays/get", values_storage(&self.pack_name), storage_path(&array))
+            }
+            None => (
+                "zz_tables/get",
+                tables_storage(&self.pack_name),
+                expr.unwrap_expression().as_identifier().to_string(),
+            ),
+        };
+
+        let command = self.int_args_command(
+            helper,
+            std::slice::from_ref(index),
+            &int_args(&["index"]),
+            &[("path", &path)],
+            |values| format!("data get storage {} {}[{}]", storage, values[1], values[0]),
+        );
+
+        let target = self.get_free_location();
+
+        self.emit(Instruction::SetValueToCommandResult {
+            target: target.clone(),
+            command,
+        });
+
+        target
+    }
+
+    // Writes an element of an array in place. The value goes through a scratch path in storage so that the element
+    // can be set from it when the index is only known at runtime
+    fn visit_element_assignment(&mut self, array: &ParserNode, index: &ParserNode, expr: &ParserNode) {
+        let value = self.visit_node(expr);
+        let resolution = self.tags.get_resolution(array);
+        let path = storage_path(&self.resolve_location(resolution));
+        let storage = values_storage(&self.pack_name);
+
+        self.emit(Instruction::SaveValueToStorage {
+            source: value,
+            storage: storage.clone(),
+            path: "element".to_string(),
+        });
+
+        let command = self.int_args_command(
+            "zz_arrays/set",
+            std::slice::from_ref(index),
+            &int_args(&["index"]),
+            &[("path", &path)],
+            |values| format!("data modify storage {} {}[{}] set from storage {} element", storage, values[1], values[0], storage),
         );
 
+        self.emit(Instruction::PlaceCommandLiteral(command));
+    }
+
+    // Creates the list with the constant elements already in place, then stores the others into it
+    fn visit_array_literal(&mut self, elements: &[ParserNode]) -> ValueLocation {
+        let target = self.get_free_location();
+        let storage = values_storage(&self.pack_name);
+        let path = storage_path(&target);
+
+        let constant = |element: &ParserNode| match element.unwrap_expression().kind() {
+            ParserNodeKind::NumberLiteral(n) => Some(*n),
+            ParserNodeKind::BoolLiteral(b) => Some(*b as i32),
+            _ => None,
+        };
+
+        let initial = elements.iter().map(|element| constant(element).unwrap_or(0)).collect::<Vec<i32>>();
+
+        self.emit(Instruction::PlaceCommandLiteral(format!(
+            "data modify storage {} {} set value {}",
+            storage,
+            path,
+            json!(initial)
+        )));
+
+        for (i, element) in elements.iter().enumerate().filter(|(_, element)| constant(element).is_none()) {
+            let value = self.visit_node(element);
+
+            self.emit(Instruction::SaveValueToStorage {
+                source: value,
+                storage: storage.clone(),
+                path: format!("{}[{}]", path, i),
+            });
+        }
+
         target
     }
 
+    // Each field of an attached struct has an objective of its own, which holds the field of every entity
+    fn entity_field_location(&mut self, struct_def: &StructDef, field: &str) -> ValueLocation {
+        let objective = format!("{}.{}", struct_def.name(), field);
+        self.setup.push(format!("scoreboard objectives add {} dummy", objective));
+
+        ValueLocation::player(Objective(objective))
+    }
+
     fn resolve_location(&mut self, resolution: &Resolution) -> ValueLocation {
         let mut offset = 0;
         let mut slot = 0;
 
         for part in resolution.iter() {
             match part {
+                // the variable of a loop over entities isn't a local, as its fields are scores on the entity
+                ResolvedPart::Variable(ty, _)
+                    if matches!(ty.from(self.types), SculkType::Struct(def) if def.entity().is_some()) => {}
                 ResolvedPart::Variable(_, name) => {
                     slot = self.get_local(name).slot;
                 }
+                ResolvedPart::PlayerVariable(_, name) => {
+                    return ValueLocation::player(player_objective(&self.pack_name, name));
+                }
+                ResolvedPart::ConfigValue(index, _) => {
+                    return config_location(&self.pack_name, *index);
+                }
                 ResolvedPart::Field(ty, name) => {
-                    let struct_def = ty.from(&self.types).as_struct_def();
+                    let struct_def = ty.from(self.types).as_struct_def();
+
+                    if struct_def.entity().is_some() {
+                        return self.entity_field_location(struct_def, name);
+                    }
+
                     offset += struct_def.field_offset(name);
                 }
                 _ => unreachable!(),
@@ -1,9 +1,17 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::Config;
+use indexmap::IndexMap;
 
-use self::{codegen::CodeGen, ir::IrFunction, type_pool::TypePool};
+use crate::{timings::Timings, Config};
 
+use self::{
+    codegen::{CodeGen, CompiledFunction},
+    ir::{FunctionHook, IrFunction},
+    type_pool::TypePool,
+    verify::InternalError,
+};
+
+pub mod builtin;
 pub mod codegen;
 pub mod dpc_backend;
 pub mod function;
@@ -12,6 +20,7 @@ pub mod resolve;
 pub mod type_pool;
 pub mod types;
 pub mod validate;
+pub mod verify;
 
 pub trait Backend {
     fn compile(config: &Config, ir: &[IrFunction], types: &TypePool);
@@ -19,31 +28,160 @@ pub trait Backend {
 
 pub struct DefaultBackend;
 
-impl Backend for DefaultBackend {
-    fn compile(config: &Config, ir: &[IrFunction], types: &TypePool) {
-        let mut codegen = CodeGen::new(config.pack.clone());
+impl DefaultBackend {
+    /// Generates the contents of every output file without writing anything to disk.
+    /// The files are ordered the same way for the same input, which is what makes builds reproducible.
+    /// In debug builds, or with --verify-ir, the objectives of the generated commands are checked too, and any
+    /// mistake in them is returned instead.
+    pub fn generate(config: &Config, ir: &[IrFunction], timings: &mut Timings) -> Result<Vec<(PathBuf, String)>, Vec<InternalError>> {
+        let mut codegen = CodeGen::new(config.pack.clone(), config.naming);
         codegen.compile_ir_functions(ir);
 
-        let compiled_funcs = codegen.dissolve();
+        for (name, duration) in codegen.function_timings() {
+            timings.record_function(name.clone(), *duration);
+        }
+
+        let objectives = codegen.objectives_function();
+        Self::verify_objectives(config, &codegen)?;
+
+        let has_objectives = objectives.is_some();
+
+        let mut files = codegen
+            .dissolve()
+            .into_iter()
+            .chain(objectives)
+            .map(|func| Self::function_file(config, func))
+            .collect::<Vec<(PathBuf, String)>>();
+
+        files.extend(Self::tag_files(config, ir, has_objectives));
+        Ok(files)
+    }
+
+    /// Generates and writes the files of one function at a time, so that only the code of the function being generated
+    /// is held in memory rather than the code of the whole pack. The files are the same as the ones `generate` makes,
+    /// except that private functions aren't merged, since that needs the code of every function. The objectives are
+    /// checked like with `generate`, but only once the functions have been written, so the tags aren't written then.
+    pub fn stream(config: &Config, ir: &[IrFunction], timings: &mut Timings) -> Result<(), Vec<InternalError>> {
+        let mut codegen = CodeGen::new(config.pack.clone(), config.naming);
+
+        for func in ir {
+            codegen.compile_ir_functions(std::slice::from_ref(func));
+
+            Self::write(
+                codegen
+                    .take_functions()
+                    .into_iter()
+                    .map(|func| Self::function_file(config, func))
+                    .collect(),
+            );
+        }
+
+        for (name, duration) in codegen.function_timings() {
+            timings.record_function(name.clone(), *duration);
+        }
+
+        let objectives = codegen.objectives_function();
+        Self::verify_objectives(config, &codegen)?;
+
+        let has_objectives = objectives.is_some();
+        Self::write(objectives.into_iter().map(|func| Self::function_file(config, func)).collect());
+        Self::write(Self::tag_files(config, ir, has_objectives));
+        Ok(())
+    }
+
+    fn verify_objectives(config: &Config, codegen: &CodeGen) -> Result<(), Vec<InternalError>> {
+        if !cfg!(debug_assertions) && !config.verify_ir {
+            return Ok(());
+        }
+
+        let errors = codegen.verify_objectives();
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    fn function_file(config: &Config, func: CompiledFunction) -> (PathBuf, String) {
+        (
+            Path::new(&config.pack).join(format!("{}.mcfunction", func.name().path)),
+            func.to_string(),
+        )
+    }
+
+    // the tags that hook functions into load and tick, and that subscribe them to messages.
+    // zz_objectives/load comes before every other load function, since they may use the objectives it creates
+    fn tag_files(config: &Config, ir: &[IrFunction], objectives: bool) -> Vec<(PathBuf, String)> {
+        let mut files = Vec::new();
+
+        for hook in [FunctionHook::Load, FunctionHook::Tick] {
+            let first = match hook {
+                FunctionHook::Load if objectives => Some(codegen::OBJECTIVES_FUNCTION),
+                _ => None,
+            };
+
+            let values = first
+                .into_iter()
+                .chain(ir.iter().filter(|func| func.hook() == Some(hook)).map(|func| func.objective().0.as_str()))
+                .map(|name| format!("{}:{}", config.pack, name))
+                .collect::<Vec<String>>();
 
-        for func in compiled_funcs {
-            let namespace_path = Path::new(&config.pack);
+            if !values.is_empty() {
+                files.push((
+                    Path::new("minecraft/tags/functions").join(format!("{}.json", hook.tag_name())),
+                    serde_json::json!({ "values": values }).to_string(),
+                ));
+            }
+        }
+
+        // function tags are merged across datapacks, so every pack's subscribers run when a message is emitted
+        let mut subscribers: IndexMap<&str, Vec<String>> = IndexMap::new();
+
+        for func in ir {
+            if let Some(message) = func.message() {
+                subscribers
+                    .entry(message)
+                    .or_default()
+                    .push(format!("{}:{}", config.pack, func.objective()));
+            }
+        }
 
-            if let Err(err) = std::fs::create_dir_all(&namespace_path) {
+        for (message, values) in subscribers {
+            files.push((
+                Path::new("sculk/tags/functions/bus").join(format!("{}.json", message)),
+                serde_json::json!({ "values": values }).to_string(),
+            ));
+        }
+
+        files
+    }
+
+    /// Writes the generated files to disk.
+    pub fn write(files: Vec<(PathBuf, String)>) {
+        for (func_path, contents) in files {
+            // anonymous functions live in a subfolder named after the function they belong to, and tags in the minecraft namespace
+            if let Err(err) = std::fs::create_dir_all(func_path.parent().unwrap()) {
                 println!("failed to create namespace directory: {}", err);
                 return;
             }
 
-            if let Err(err) = std::fs::write(
-                format!(
-                    "{}.mcfunction",
-                    namespace_path.join(func.name().path.clone()).display()
-                ),
-                func.to_string(),
-            ) {
+            if let Err(err) = std::fs::write(&func_path, contents) {
                 println!("failed to write function file: {}", err);
                 return;
             }
         }
     }
 }
+
+impl Backend for DefaultBackend {
+    fn compile(config: &Config, ir: &[IrFunction], types: &TypePool) {
+        match Self::generate(config, ir, &mut Timings::default()) {
+            Ok(files) => Self::write(files),
+            Err(internal_errors) => {
+                for err in internal_errors {
+                    println!("{}", err);
+                }
+            }
+        }
+    }
+}
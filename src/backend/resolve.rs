@@ -1,11 +1,14 @@
 use std::{collections::HashMap, ops::Index};
 
+use indexmap::IndexMap;
+
 use crate::{
     data::ResourceLocation,
     parser::{ParserNode, ParserNodeKind},
 };
 
 use super::{
+    builtin::Builtin,
     function::FunctionSignature,
     type_pool::{TypeKey, TypePool},
     validate::ScopeStack,
@@ -16,20 +19,35 @@ use super::{
 pub struct Resolver<'a> {
     pack_name: &'a str,
     global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
+    // the functions declared with each name, see Validator::find_overloads
+    overloads: &'a HashMap<String, Vec<ResourceLocation>>,
+    // the generic functions by name, see Validator::instantiate
+    generic_functions: &'a HashMap<String, &'a ParserNode>,
+    player_variables: &'a HashMap<String, TypeKey>,
+    configs: &'a IndexMap<String, TypeKey>,
     types: &'a TypePool,
     scope_stack: &'a ScopeStack,
 }
 
 impl<'a> Resolver<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pack_name: &'a str,
         global_functions: &'a HashMap<ResourceLocation, FunctionSignature>,
+        overloads: &'a HashMap<String, Vec<ResourceLocation>>,
+        generic_functions: &'a HashMap<String, &'a ParserNode>,
+        player_variables: &'a HashMap<String, TypeKey>,
+        configs: &'a IndexMap<String, TypeKey>,
         types: &'a TypePool,
         scope_stack: &'a ScopeStack,
     ) -> Self {
         Self {
             pack_name,
             global_functions,
+            overloads,
+            generic_functions,
+            player_variables,
+            configs,
             types,
             scope_stack,
         }
@@ -64,20 +82,24 @@ impl<'a> Resolver<'a> {
             ParserNodeKind::Identifier(name) => {
                 let (variable, global_func, ty) = (
                     self.scope_stack.find_variable_type(name),
-                    self.global_functions.get(&ResourceLocation::new(
-                        self.pack_name.to_string(),
-                        name.to_string(),
-                    )),
+                    (self.overloads.contains_key(name) || self.generic_functions.contains_key(name)).then_some(()),
                     self.types.get_type_key(name),
                 );
 
                 resolution.0.push(match (variable, global_func, ty) {
                     (Some(type_key), None, None) => ResolvedPart::Variable(type_key, name.clone()),
                     (None, Some(_), None) => ResolvedPart::GlobalFunction(name.clone()),
+                    // the selector() text component builder has the name of the selector type, which has no constructor
+                    (None, None, Some(_)) if name == "selector" => ResolvedPart::Builtin(Builtin::Selector),
                     (None, None, Some(type_key)) => ResolvedPart::Type(type_key),
-                    (None, None, None) => {
-                        return Err(ResolutionError::UnresolvedIdentifier(name.clone()))
-                    }
+                    // local variables shadow player variables, which are checked against functions and types when declared
+                    (None, None, None) if self.types.is_generic_struct(name) => ResolvedPart::GenericStruct(name.clone()),
+                    (None, None, None) => match (self.player_variables.get(name), self.configs.get_full(name), Builtin::from_name(name)) {
+                        (Some(type_key), _, _) => ResolvedPart::PlayerVariable(*type_key, name.clone()),
+                        (None, Some((index, _, _)), _) => ResolvedPart::ConfigValue(index, name.clone()),
+                        (None, None, Some(builtin)) => ResolvedPart::Builtin(builtin),
+                        (None, None, None) => return Err(ResolutionError::UnresolvedIdentifier(name.clone())),
+                    },
                     _ => {
                         return Err(ResolutionError::AmbiguousIdentifier {
                             name: name.clone(),
@@ -115,7 +137,19 @@ impl<'a> Resolver<'a> {
                             return Err(ResolutionError::CannotCallMember(name.to_string()));
                         }
                     }
-                    ResolvedPart::Type(ty) => ResolvedPart::Constructor(ty),
+                    // a method of a variable, e.g "p.length()", rather than the return value of a call like "p.length()()"
+                    ResolvedPart::Method(ty, name) if matches!(expr.kind(), ParserNodeKind::MemberAccess { .. }) => {
+                        ResolvedPart::Method(ty, name)
+                    }
+                    // enums don't have constructors, their values are made by naming a variant
+                    ResolvedPart::Type(ty) if ty.from(self.types).as_enum_def().is_none() => {
+                        ResolvedPart::Constructor(ty)
+                    }
+                    ResolvedPart::Builtin(builtin) => ResolvedPart::Builtin(builtin),
+                    ResolvedPart::GenericStruct(name) => ResolvedPart::GenericStruct(name),
+                    ResolvedPart::Variable(ty, name) if ty.from(self.types).as_function_def().is_some() => {
+                        ResolvedPart::Reference(ty, name)
+                    }
                     _ => return Err(ResolutionError::CannotCallExpression),
                 });
 
@@ -132,6 +166,9 @@ impl<'a> Resolver<'a> {
                 self.resolve_inner(resolution, expr)?;
 
                 match resolution.0.last().unwrap() {
+                    ResolvedPart::Variable(ty, _) if !ty.from(self.types).is_struct() => {
+                        Err(ResolutionError::CannotAccessMember(member.to_string()))
+                    }
                     ResolvedPart::Variable(ty, _) => {
                         let struct_def = ty.from(&self.types).as_struct_def();
 
@@ -201,14 +238,23 @@ impl<'a> Resolver<'a> {
                             return Err(ResolutionError::CannotAccessMember(name.to_string()));
                         }
 
-                        let function = self
-                            .global_functions
-                            .get(&ResourceLocation::new(
-                                self.pack_name.to_string(),
-                                name.to_string(),
-                            ))
-                            .unwrap();
-                        let return_type_key = function.return_type();
+                        // nor is the instance of a generic function
+                        if !self.overloads.contains_key(name) {
+                            return Err(ResolutionError::CannotAccessMember(member.to_string()));
+                        }
+
+                        // the overload that's called isn't known until the types of the arguments are, so the member
+                        // can only be found if every overload returns the same type
+                        let return_types = self.overloads[name]
+                            .iter()
+                            .map(|location| self.global_functions[location].return_type())
+                            .collect::<Vec<TypeKey>>();
+
+                        if return_types.iter().any(|ty| *ty != return_types[0]) {
+                            return Err(ResolutionError::CannotAccessMember(member.to_string()));
+                        }
+
+                        let return_type_key = return_types[0];
                         let return_type_def = return_type_key.from(&self.types).as_struct_def();
 
                         if return_type_def.field(&member).is_some()
@@ -225,6 +271,15 @@ impl<'a> Resolver<'a> {
                             ))
                         }
                     }
+                    ResolvedPart::Type(ty) if ty.from(self.types).as_enum_def().is_some() => {
+                        match ty.from(self.types).as_enum_def().unwrap().variant_value(member) {
+                            Some(value) => {
+                                resolution.0.push(ResolvedPart::Variant(*ty, value));
+                                Ok(())
+                            }
+                            None => Err(ResolutionError::MemberDoesNotExist(*ty, member.to_string())),
+                        }
+                    }
                     ResolvedPart::Type(ty) => {
                         let struct_def = ty.from(&self.types).as_struct_def();
 
@@ -248,6 +303,8 @@ impl<'a> Resolver<'a> {
                     _ => Err(ResolutionError::CannotAccessMember(member.to_string())),
                 }
             }
+            // tables are read-only, and their elements are ints, which have no members
+            ParserNodeKind::Index { .. } => Err(ResolutionError::TableElementNotAssignable),
             _ => unreachable!(),
         }
     }
@@ -256,11 +313,22 @@ impl<'a> Resolver<'a> {
 #[derive(Clone, Debug)]
 pub enum ResolvedPart {
     Variable(TypeKey, String),
+    PlayerVariable(TypeKey, String),
+    // a config value and its position among the pack's config values, which is the score it's read into
+    ConfigValue(usize, String),
     GlobalFunction(String),
     Field(TypeKey, String),
     Method(TypeKey, String),
     Type(TypeKey),
+    // a variant of an enum and the int it's stored as
+    Variant(TypeKey, i32),
     Constructor(TypeKey),
+    Builtin(Builtin),
+    // a call through a variable that holds a reference to a function, see SculkType::Function
+    Reference(TypeKey, String),
+    // a generic struct, which is only a type once its constructor is called and its type arguments are inferred from
+    // the arguments, see Validator::instantiate_struct
+    GenericStruct(String),
 }
 
 pub struct Resolution(Vec<ResolvedPart>);
@@ -268,7 +336,7 @@ pub struct Resolution(Vec<ResolvedPart>);
 impl Resolution {
     pub fn find_assignable_type(&self, pool: &TypePool) -> Option<TypeKey> {
         match self.0.last().unwrap() {
-            ResolvedPart::Variable(ty, _) => Some(*ty),
+            ResolvedPart::Variable(ty, _) | ResolvedPart::PlayerVariable(ty, _) => Some(*ty),
             ResolvedPart::Field(ty, name) => Some(
                 ty.from(pool)
                     .as_struct_def()
@@ -287,6 +355,26 @@ impl Resolution {
     pub fn last(&self) -> &ResolvedPart {
         self.0.last().unwrap()
     }
+
+    /// Replaces the part that the resolution ends with, e.g with the overload of a function that a call resolved to
+    pub fn replace_last(&mut self, part: ResolvedPart) {
+        *self.0.last_mut().unwrap() = part;
+    }
+
+    /// The path of the resolved value as it was written, e.g "a.b.c"
+    pub fn path(&self) -> String {
+        self.0
+            .iter()
+            .filter_map(|part| match part {
+                ResolvedPart::Variable(_, name)
+                | ResolvedPart::PlayerVariable(_, name)
+                | ResolvedPart::ConfigValue(_, name)
+                | ResolvedPart::Field(_, name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<&str>>()
+            .join(".")
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -302,4 +390,5 @@ pub enum ResolutionError {
     CannotAccessMember(String),
     MethodNotStatic(String),
     StaticMethodDoesNotExist(TypeKey, String),
+    TableElementNotAssignable,
 }
@@ -1,8 +1,8 @@
 use std::{collections::HashMap, fmt::Display};
 
-use crate::backend::types::SculkType;
+use crate::backend::types::{FieldDef, FunctionTypeDef, SculkType, StructDef, TupleDef};
 
-use super::function::{FunctionSignature, ParamDef};
+use super::function::{FunctionAttributes, FunctionSignature, ParamDef};
 
 /// The collection of all types in a Sculk program, including built-in types.
 /// This is constructed during the validation phase and used throughout compilation.
@@ -12,6 +12,18 @@ use super::function::{FunctionSignature, ParamDef};
 pub struct TypePool {
     type_map: HashMap<String, usize>,
     types: Vec<SculkType>,
+    // the generic structs by name, which are only types once they're given type arguments, e.g `Pair<int>`
+    generic_structs: HashMap<String, GenericStruct>,
+    // the instances of generic structs whose fields are being found, so one that contains itself isn't instantiated forever
+    instantiating: Vec<String>,
+    finalized: bool,
+}
+
+// The declaration of a generic struct, whose field types are written in terms of its type parameters
+#[derive(Debug)]
+struct GenericStruct {
+    params: Vec<String>,
+    fields: Vec<(String, String)>,
 }
 
 impl TypePool {
@@ -19,6 +31,9 @@ impl TypePool {
         Self {
             type_map: HashMap::new(),
             types: Vec::new(),
+            generic_structs: HashMap::new(),
+            instantiating: Vec::new(),
+            finalized: false,
         }
     }
 
@@ -29,6 +44,26 @@ impl TypePool {
         type_pool.insert("unknown".to_string(), SculkType::Unknown);
         type_pool.insert("int".to_string(), SculkType::Integer);
         type_pool.insert("bool".to_string(), SculkType::Bool);
+        type_pool.insert("string".to_string(), SculkType::String);
+        type_pool.insert("long".to_string(), SculkType::Long);
+        type_pool.insert("null".to_string(), SculkType::Null);
+        type_pool.insert("selector".to_string(), SculkType::Selector);
+        type_pool.insert("text".to_string(), SculkType::Text);
+
+        // a position or direction, which is a struct like any other so that its fields, constructor and copies come for
+        // free. Its fields are laid out and its constructor is added when the pool is finalized
+        let mut vec3 = StructDef::new_empty("vec3".to_string());
+
+        for axis in ["x", "y", "z"] {
+            vec3.add_field(FieldDef::new(axis.to_string(), type_pool.int())).unwrap();
+        }
+
+        type_pool.insert("vec3".to_string(), SculkType::Struct(vec3));
+
+        // only values that fit in a score can be put in an array
+        for element in [SculkType::Integer, SculkType::Bool] {
+            type_pool.insert(format!("{}[]", element), SculkType::Array(Box::new(element)));
+        }
 
         type_pool
     }
@@ -47,6 +82,14 @@ impl TypePool {
         self.get_type_key("bool").unwrap()
     }
 
+    pub fn string(&self) -> TypeKey {
+        self.get_type_key("string").unwrap()
+    }
+
+    pub fn long(&self) -> TypeKey {
+        self.get_type_key("long").unwrap()
+    }
+
     pub fn none(&self) -> TypeKey {
         self.get_type_key("none").unwrap()
     }
@@ -55,6 +98,300 @@ impl TypePool {
         self.get_type_key("unknown").unwrap()
     }
 
+    pub fn null(&self) -> TypeKey {
+        self.get_type_key("null").unwrap()
+    }
+
+    pub fn selector(&self) -> TypeKey {
+        self.get_type_key("selector").unwrap()
+    }
+
+    pub fn text(&self) -> TypeKey {
+        self.get_type_key("text").unwrap()
+    }
+
+    pub fn vec3(&self) -> TypeKey {
+        self.get_type_key("vec3").unwrap()
+    }
+
+    // The type of an array of the given element type, if values of that type can be put in an array
+    pub fn array_of(&self, element: TypeKey) -> Option<TypeKey> {
+        self.get_type_key(&format!("{}[]", element.from(self)))
+    }
+
+    // The type of the elements of the given type, if it is an array
+    pub fn element_of(&self, array: TypeKey) -> Option<TypeKey> {
+        match array.from(self) {
+            SculkType::Array(element) => self.get_type_key(&element.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The type of an optional of the given type, which is added to the pool the first time it's needed.
+    /// Only ints, bools, longs and enums can be optional, which are kept in scores like the flag that says whether the
+    /// value is present, and whose definitions don't change once they're in the pool
+    pub fn optional_of(&mut self, inner: TypeKey) -> Option<TypeKey> {
+        let ty = inner.from(self);
+
+        if !matches!(ty, SculkType::Integer | SculkType::Bool | SculkType::Long | SculkType::Enum(_)) {
+            return None;
+        }
+
+        let name = format!("{}?", ty);
+
+        if let Some(key) = self.get_type_key(&name) {
+            return Some(key);
+        }
+
+        self.insert(name.clone(), SculkType::Optional(Box::new(ty.clone())));
+        self.get_type_key(&name)
+    }
+
+    /// The type that the given type wraps, if it is an optional
+    pub fn inner_of(&self, optional: TypeKey) -> Option<TypeKey> {
+        match optional.from(self) {
+            SculkType::Optional(inner) => self.get_type_key(&inner.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The type of a tuple of the given element types, which is added to the pool the first time it's needed
+    pub fn tuple_of(&mut self, elements: Vec<TypeKey>) -> TypeKey {
+        let def = TupleDef::new(elements, self);
+
+        match self.get_type_key(def.name()) {
+            Some(key) => key,
+            None => {
+                let name = def.name().to_string();
+                self.insert(name.clone(), SculkType::Tuple(def));
+                self.get_type_key(&name).unwrap()
+            }
+        }
+    }
+
+    /// The type of a reference to functions with the given parameter and return types, which is added to the pool the
+    /// first time it's needed
+    pub fn function_of(&mut self, params: Vec<TypeKey>, return_type: TypeKey) -> TypeKey {
+        let def = FunctionTypeDef::new(params, return_type, self);
+
+        match self.get_type_key(def.name()) {
+            Some(key) => key,
+            None => {
+                let name = def.name().to_string();
+                self.insert(name.clone(), SculkType::Function(def));
+                self.get_type_key(&name).unwrap()
+            }
+        }
+    }
+
+    /// Adds a generic struct, given the names of its type parameters and the names of its fields and their types
+    pub fn add_generic_struct(&mut self, name: String, params: Vec<String>, fields: Vec<(String, String)>) {
+        self.generic_structs.insert(name, GenericStruct { params, fields });
+    }
+
+    pub fn is_generic_struct(&self, name: &str) -> bool {
+        self.generic_structs.contains_key(name)
+    }
+
+    /// The type parameters of a generic struct and the types of its fields as they're written, in the order they were
+    /// declared
+    pub fn generic_struct(&self, name: &str) -> Option<(Vec<String>, Vec<String>)> {
+        self.generic_structs
+            .get(name)
+            .map(|generic| (generic.params.clone(), generic.fields.iter().map(|(_, ty)| ty.clone()).collect()))
+    }
+
+    /// Replaces the names of type parameters in the name of a type with the names of the types they stand for, e.g
+    /// `(T, int)[]` with T bound to bool is `(bool, int)[]`
+    pub fn substitute(name: &str, bindings: &[(String, String)]) -> String {
+        let mut substituted = String::with_capacity(name.len());
+        let mut word = String::new();
+
+        for c in name.chars().chain(std::iter::once(' ')) {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+
+            match bindings.iter().find(|(param, _)| *param == word) {
+                Some((_, ty)) => substituted.push_str(ty),
+                None => substituted.push_str(&word),
+            }
+
+            word.clear();
+            substituted.push(c);
+        }
+
+        substituted.pop(); // the space that ended the last word
+        substituted
+    }
+
+    /// Finds a type by the name it's written with. Tuple, optional and function types, and the instances of generic
+    /// structs, are only added to the pool once they're needed, so the name of a tuple of types that exist, e.g
+    /// `(int, Point)`, adds that tuple type
+    pub fn find_type(&mut self, name: &str) -> Option<TypeKey> {
+        if let Some(key) = self.get_type_key(name) {
+            return Some(key);
+        }
+
+        // the return type of a function type may be optional itself, so it's checked for before a trailing ?
+        if let Some(rest) = name.strip_prefix("fn(") {
+            let end = Self::closing_parens(rest)?;
+            let params = match rest[..end].trim() {
+                "" => Vec::new(),
+                params => self.find_types(params)?,
+            };
+
+            let return_type = match rest[end + 1..].trim() {
+                "" => self.none(),
+                rest => self.find_type(rest.strip_prefix("->")?.trim())?,
+            };
+
+            return Some(self.function_of(params, return_type));
+        }
+
+        if let Some(inner) = name.strip_suffix('?') {
+            let inner = self.find_type(inner)?;
+            return self.optional_of(inner);
+        }
+
+        if let Some((base, args)) = name.strip_suffix('>').and_then(|name| name.split_once('<')) {
+            return self.instantiate(base.trim(), args);
+        }
+
+        let inner = name.strip_prefix('(')?.strip_suffix(')')?;
+        let elements = self.find_types(inner)?;
+
+        Some(self.tuple_of(elements))
+    }
+
+    // Adds the instance of a generic struct with the given type arguments, whose name is written with the names the
+    // types are displayed with so the same instance is found however its arguments were spaced
+    fn instantiate(&mut self, base: &str, args: &str) -> Option<TypeKey> {
+        let args = self.find_types(args)?;
+        let generic = self.generic_structs.get(base)?;
+
+        if args.len() != generic.params.len() {
+            return None;
+        }
+
+        let args = args.iter().map(|arg| arg.from(self).to_string()).collect::<Vec<String>>();
+        let name = format!("{}<{}>", base, args.join(", "));
+
+        if let Some(key) = self.get_type_key(&name) {
+            return Some(key);
+        }
+
+        if self.instantiating.contains(&name) {
+            return None;
+        }
+
+        let bindings = generic.params.iter().cloned().zip(args).collect::<Vec<(String, String)>>();
+        let fields = generic.fields.clone();
+        let mut def = StructDef::new_empty(name.clone());
+
+        self.instantiating.push(name.clone());
+
+        for (field, ty) in fields {
+            let ty = self.find_type(&Self::substitute(&ty, &bindings));
+
+            match ty {
+                Some(ty) if ty != self.none() && ty != self.unknown() => {
+                    def.add_field(FieldDef::new(field, ty)).ok()?;
+                }
+                _ => {
+                    self.instantiating.pop();
+                    return None;
+                }
+            }
+        }
+
+        self.instantiating.pop();
+
+        // once the pool is finalized, the structs the instance's fields could be are all laid out
+        if self.finalized {
+            let mut offset = 0;
+            let mut offsets = Vec::new();
+
+            for field in def.fields() {
+                offsets.push(offset);
+                offset += field.field_type().from(self).total_size(self);
+            }
+
+            def.field_offsets = offsets;
+        }
+
+        self.insert(name.clone(), SculkType::Struct(def));
+        let key = self.get_type_key(&name).unwrap();
+
+        if self.finalized {
+            let constructor = self.constructor_of(key);
+            key.from_mut(self).as_struct_def_mut().set_constructor(constructor);
+        }
+
+        Some(key)
+    }
+
+    // Finds the types of a list of them separated by commas, see split_list
+    fn find_types(&mut self, list: &str) -> Option<Vec<TypeKey>> {
+        let mut types = Vec::new();
+
+        for name in Self::split_list(list) {
+            let ty = self.find_type(name)?;
+
+            if ty == self.none() || ty == self.unknown() {
+                return None;
+            }
+
+            types.push(ty);
+        }
+
+        Some(types)
+    }
+
+    /// Splits a list of type names at the commas that aren't inside a nested tuple, function type or list of type
+    /// arguments, e.g `int, (int, bool), Pair<int, int>` into three names
+    pub fn split_list(list: &str) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        let mut previous = ' ';
+
+        for (i, c) in list.char_indices().chain(std::iter::once((list.len(), ','))) {
+            match c {
+                '(' | '<' => depth += 1,
+                // the > of a function type's -> doesn't close anything
+                ')' => depth -= 1,
+                '>' if previous != '-' => depth -= 1,
+                ',' if depth == 0 => {
+                    names.push(list[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+
+            previous = c;
+        }
+
+        names
+    }
+
+    // The index of the ) that closes a ( which was just before the start of the given text
+    fn closing_parens(text: &str) -> Option<usize> {
+        let mut depth = 0;
+
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return Some(i),
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
     pub fn get_type_key(&self, name: &str) -> Option<TypeKey> {
         self.type_map.get(name).map(|id| TypeKey(*id))
     }
@@ -72,40 +409,60 @@ impl TypePool {
     }
 
     pub fn finalize(&mut self) {
-        let mut offsets_by_index = Vec::new();
+        // a struct's size depends on the sizes of the structs it contains, which may not have been laid out yet,
+        // so the offsets are recomputed until they stop changing. Each pass settles at least one more level of nesting
+        for _ in 0..self.types.len() {
+            let mut offsets_by_index = Vec::new();
 
-        for i in 0..self.types.len() {
-            if let SculkType::Struct(def) = &self.types[i] {
-                let mut offset = 0;
-                let mut offsets = Vec::new();
+            for i in 0..self.types.len() {
+                if let SculkType::Struct(def) = &self.types[i] {
+                    let mut offset = 0;
+                    let mut offsets = Vec::new();
 
-                for field in def.fields() {
-                    offsets.push(offset);
-                    offset += field.field_type().from(self).total_size(self);
+                    for field in def.fields() {
+                        offsets.push(offset);
+                        offset += field.field_type().from(self).total_size(self);
+                    }
+
+                    if offsets != def.field_offsets {
+                        offsets_by_index.push((i, offsets));
+                    }
                 }
+            }
 
-                offsets_by_index.push((i, offsets));
+            if offsets_by_index.is_empty() {
+                break;
             }
-        }
 
-        for (i, offsets) in offsets_by_index {
-            if let SculkType::Struct(def) = &mut self.types[i] {
-                def.field_offsets = offsets;
+            for (i, offsets) in offsets_by_index {
+                if let SculkType::Struct(def) = &mut self.types[i] {
+                    def.field_offsets = offsets;
+                }
             }
         }
 
-        for ty in &mut self.types {
-            if let SculkType::Struct(ref mut def) = ty {
-                def.set_constructor(FunctionSignature::new(
-                    ".ctor".to_string(),
-                    def.fields()
-                        .map(|f| ParamDef::new(f.name().to_string(), f.field_type()))
-                        .collect(),
-                    TypeKey(self.type_map[def.name()]),
-                    true,
-                ));
+        for i in 0..self.types.len() {
+            if let SculkType::Struct(_) = &self.types[i] {
+                let constructor = self.constructor_of(TypeKey(i));
+                TypeKey(i).from_mut(self).as_struct_def_mut().set_constructor(constructor);
             }
         }
+
+        self.finalized = true;
+    }
+
+    fn constructor_of(&self, ty: TypeKey) -> FunctionSignature {
+        FunctionSignature::new(
+            ".ctor".to_string(),
+            ty.from(self)
+                .as_struct_def()
+                .fields()
+                .map(|f| ParamDef::new(f.name().to_string(), f.field_type()))
+                .collect(),
+            ty,
+            true,
+            FunctionAttributes::default(),
+        )
     }
 }
 
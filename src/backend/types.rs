@@ -14,9 +14,38 @@ pub enum SculkType {
     Unknown,
     Integer,
     Bool,
+    // kept in storage rather than in a score, see ir::storage_path
+    String,
+    // a list of ints or bools that is kept in storage like a string
+    Array(Box<SculkType>),
+    // a 64-bit int emulated with two scores, the high half at offset 0 and the low half at offset 1.
+    // The low half is always kept between 0 and LONG_BASE - 1
+    Long,
     Struct(StructDef),
+    // stored as the index of the variant in a single score
+    Enum(EnumDef),
+    // an unnamed group of values laid out one after another like the fields of a struct, e.g `(int, bool)`
+    Tuple(TupleDef),
+    // a value that may be missing, e.g `int?`, laid out as the value followed by a score that is 1 if it's present,
+    // see TypePool::optional_of
+    Optional(Box<SculkType>),
+    // the type of null, which only makes sense where an optional is expected
+    Null,
+    // selects entities, e.g `@e[type=zombie]`. Selectors are known at compile time and are put into the commands that
+    // use them, so they take up no scores
+    Selector,
+    // formatted text such as `text!{"Hello ", name, bold}`, which is turned into JSON text where it's declared and put
+    // into the commands that show it, so it takes up no scores either
+    Text,
+    // a reference to a function, e.g `fn(int) -> int`, stored as a number that picks the function from the ones
+    // referenced with the same type, see TagPool::function_ref_id
+    Function(FunctionTypeDef),
 }
 
+/// The value at which the low half of a long carries over into the high half.
+/// Two low halves can be added without overflowing a score, which is what the carry checks rely on.
+pub const LONG_BASE: i32 = 1_000_000_000;
+
 impl SculkType {
     pub fn is_none(&self) -> bool {
         match self {
@@ -39,6 +68,27 @@ impl SculkType {
         }
     }
 
+    pub fn as_enum_def(&self) -> Option<&EnumDef> {
+        match self {
+            SculkType::Enum(def) => Some(def),
+            _ => None,
+        }
+    }
+
+    pub fn as_tuple_def(&self) -> Option<&TupleDef> {
+        match self {
+            SculkType::Tuple(def) => Some(def),
+            _ => None,
+        }
+    }
+
+    pub fn as_function_def(&self) -> Option<&FunctionTypeDef> {
+        match self {
+            SculkType::Function(def) => Some(def),
+            _ => None,
+        }
+    }
+
     pub fn as_struct_def_mut(&mut self) -> &mut StructDef {
         match self {
             SculkType::Struct(def) => def,
@@ -48,11 +98,41 @@ impl SculkType {
 
     pub fn total_size(&self, types: &TypePool) -> usize {
         match self {
-            SculkType::Integer | SculkType::Bool => 1,
+            SculkType::Integer
+            | SculkType::Bool
+            | SculkType::String
+            | SculkType::Array(_)
+            | SculkType::Enum(_)
+            | SculkType::Function(_) => 1,
+            SculkType::Long => 2,
             SculkType::Struct(def) => match def.field_offsets.last() {
                 Some(last) => last + def.fields.index(def.fields.len() - 1).ty.from(types).total_size(types),
                 None => 0,
             },
+            SculkType::Tuple(def) => def.elements.iter().map(|element| element.from(types).total_size(types)).sum(),
+            SculkType::Optional(inner) => inner.total_size(types) + 1,
+            SculkType::Null | SculkType::Selector | SculkType::Text => 0,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Whether each of the slots that a value of this type takes up is kept in storage, in the order of their offsets.
+    pub fn storage_slots(&self, types: &TypePool) -> Vec<bool> {
+        match self {
+            SculkType::Integer | SculkType::Bool | SculkType::Enum(_) | SculkType::Function(_) => vec![false],
+            SculkType::String | SculkType::Array(_) => vec![true],
+            SculkType::Long => vec![false, false],
+            SculkType::Struct(def) => def
+                .fields()
+                .flat_map(|field| field.field_type().from(types).storage_slots(types))
+                .collect(),
+            SculkType::Tuple(def) => def
+                .elements
+                .iter()
+                .flat_map(|element| element.from(types).storage_slots(types))
+                .collect(),
+            SculkType::Optional(inner) => inner.storage_slots(types).into_iter().chain([false]).collect(),
+            SculkType::Null | SculkType::Selector | SculkType::Text => Vec::new(),
             _ => unreachable!(),
         }
     }
@@ -65,7 +145,17 @@ impl PartialEq for SculkType {
         match (self, other) {
             (Integer, Integer) => true,
             (Bool, Bool) => true,
+            (String, String) => true,
+            (Array(element1), Array(element2)) => element1 == element2,
+            (Long, Long) => true,
             (Struct(def1), Struct(def2)) => def1.name == def2.name, // TODO: Namespacing
+            (Enum(def1), Enum(def2)) => def1.name == def2.name,
+            (Tuple(def1), Tuple(def2)) => def1.elements == def2.elements,
+            (Optional(inner1), Optional(inner2)) => inner1 == inner2,
+            (Null, Null) => true,
+            (Selector, Selector) => true,
+            (Text, Text) => true,
+            (Function(def1), Function(def2)) => def1.params == def2.params && def1.return_type == def2.return_type,
             _ => false,
         }
     }
@@ -80,7 +170,17 @@ impl Display for SculkType {
             Unknown => write!(f, "unknown"),
             Integer => write!(f, "int"),
             Bool => write!(f, "bool"),
+            String => write!(f, "string"),
+            Array(element) => write!(f, "{}[]", element),
+            Long => write!(f, "long"),
             Struct(def) => write!(f, "{}", def.name),
+            Enum(def) => write!(f, "{}", def.name),
+            Tuple(def) => write!(f, "{}", def.name),
+            Optional(inner) => write!(f, "{}?", inner),
+            Null => write!(f, "null"),
+            Selector => write!(f, "selector"),
+            Text => write!(f, "text"),
+            Function(def) => write!(f, "{}", def.name),
         }
     }
 }
@@ -94,6 +194,8 @@ pub struct StructDef {
     // Typically never None, but Option is needed since the constructor is added only after the struct is registered in a type pool
     constructor: Option<FunctionSignature>,
     pub(super) field_offsets: Vec<usize>,
+    // the selector of the entities that an attached struct's fields are scores on, see ParserNodeKind::StructDefinition
+    entity: Option<String>,
 }
 
 impl StructDef {
@@ -104,6 +206,7 @@ impl StructDef {
             functions: IndexMap::new(),
             constructor: None, // this will be set upon registration
             field_offsets: Vec::new(),
+            entity: None,
         }
     }
 
@@ -137,12 +240,16 @@ impl StructDef {
         while !field_types.is_empty() {
             let field_type = field_types.pop().unwrap().from(types);
 
-            if let SculkType::Struct(def) = field_type {
-                if def.name == self.name {
-                    return true;
-                }
+            match field_type {
+                SculkType::Struct(def) => {
+                    if def.name == self.name {
+                        return true;
+                    }
 
-                field_types.extend(def.fields.values().map(|f| f.ty.clone()));
+                    field_types.extend(def.fields.values().map(|f| f.ty.clone()));
+                }
+                SculkType::Tuple(def) => field_types.extend(def.elements.iter().copied()),
+                _ => {}
             }
         }
 
@@ -193,6 +300,14 @@ impl StructDef {
     pub fn field_offset(&self, name: &str) -> usize {
         self.field_offsets[self.field_idx(name).unwrap()]
     }
+
+    pub fn entity(&self) -> Option<&str> {
+        self.entity.as_deref()
+    }
+
+    pub fn set_entity(&mut self, selector: String) {
+        self.entity = Some(selector);
+    }
 }
 
 /// The definition of a struct field in Sculk.
@@ -215,3 +330,102 @@ impl FieldDef {
         self.ty.clone()
     }
 }
+
+/// The definition of an enum in Sculk.
+#[derive(Debug, Clone)]
+pub struct EnumDef {
+    name: String,
+    variants: Vec<String>,
+}
+
+impl EnumDef {
+    pub fn new(name: String, variants: Vec<String>) -> Self {
+        Self { name, variants }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn variants(&self) -> &[String] {
+        &self.variants
+    }
+
+    /// The int that the variant is stored as, which is its index in the definition
+    pub fn variant_value(&self, name: &str) -> Option<i32> {
+        self.variants.iter().position(|variant| variant == name).map(|i| i as i32)
+    }
+}
+
+/// The definition of a tuple type in Sculk, which is named after its elements, e.g `(int, bool)`.
+#[derive(Debug, Clone)]
+pub struct TupleDef {
+    name: String,
+    elements: Vec<TypeKey>,
+}
+
+impl TupleDef {
+    pub fn new(elements: Vec<TypeKey>, types: &TypePool) -> Self {
+        let name = format!(
+            "({})",
+            elements.iter().map(|element| element.from(types).to_string()).collect::<Vec<String>>().join(", ")
+        );
+
+        Self { name, elements }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn elements(&self) -> &[TypeKey] {
+        &self.elements
+    }
+
+    /// The offset of each element within the tuple, in order
+    pub fn offsets(&self, types: &TypePool) -> Vec<usize> {
+        let mut offset = 0;
+
+        self.elements
+            .iter()
+            .map(|element| {
+                let start = offset;
+                offset += element.from(types).total_size(types);
+                start
+            })
+            .collect()
+    }
+}
+
+/// The parameter and return types of the functions that a function reference can refer to.
+#[derive(Debug, Clone)]
+pub struct FunctionTypeDef {
+    name: String,
+    params: Vec<TypeKey>,
+    return_type: TypeKey,
+}
+
+impl FunctionTypeDef {
+    pub fn new(params: Vec<TypeKey>, return_type: TypeKey, types: &TypePool) -> Self {
+        let params_name = params.iter().map(|param| param.from(types).to_string()).collect::<Vec<String>>().join(", ");
+
+        let name = match return_type == types.none() {
+            true => format!("fn({})", params_name),
+            false => format!("fn({}) -> {}", params_name, return_type.from(types)),
+        };
+
+        Self { name, params, return_type }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn params(&self) -> &[TypeKey] {
+        &self.params
+    }
+
+    pub fn return_type(&self) -> TypeKey {
+        self.return_type
+    }
+}
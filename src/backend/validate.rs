@@ -1,51 +1,174 @@
-use std::{cell::Cell, collections::HashMap, env::ArgsOs, ops::Range, rc::Rc};
+use std::{cell::Cell, collections::{HashMap, HashSet}, env::ArgsOs, ops::Range, rc::Rc};
 
 use by_address::ByAddress;
+use indexmap::IndexMap;
 
 use crate::{
     backend::type_pool::{TypeKey, TypePool},
-    backend::types::{FieldDef, SculkType, StructDef},
+    backend::types::{EnumDef, FieldDef, SculkType, StructDef},
+    consteval::{self, ConstEvalErrorKind, Value},
     data::ResourceLocation,
-    parser::{Operation, ParserNode, ParserNodeKind},
+    parser::{ArmPattern, DataSource, Operation, ParserNode, ParserNodeKind, TableContents},
+    registry::{self, Registries, RegistryKind},
+    safety,
 };
 
 use super::{
-    function::{FunctionSignature, ParamDef},
+    builtin::{self, Builtin},
+    function::{FunctionAttributes, FunctionSignature, ParamDef},
+    ir::{self, FunctionHook},
     resolve::{self, ResolvedPart, ResolutionError, Resolver, Resolution},
 };
 
+/// The most elements a table may have, as all of them are written to storage within a few ticks of the pack loading.
+pub const MAX_TABLE_LEN: i32 = 65536;
+
+// How many instances of generic functions deep an instance may be made from, which stops one that calls itself with a
+// bigger type every time, e.g with a tuple of its parameter, from being instantiated forever
+const MAX_INSTANCE_DEPTH: usize = 8;
+
+// the names of the type parameters of a generic function or struct and the names of the types they stand for
+type TypeBindings = Vec<(String, String)>;
+
 // The validation stage happens right after the parser produces an AST
 // In this phase, we perform type checking, make sure statements don't appear illegally (e.g break when not in a loop),
 // and collect struct definitions and function declarations
 pub struct Validator<'a> {
     pack_name: String,
     global_functions: HashMap<ResourceLocation, FunctionSignature>,
+    // the functions declared with each name, which is more than one for overloaded functions
+    overloads: HashMap<String, Vec<ResourceLocation>>,
+    // the generic functions by name, which are only added to the global functions once for each combination of types
+    // they're called with, see Validator::instantiate
+    generic_functions: HashMap<String, &'a ParserNode>,
+    // the types that the type parameters of the generic function being validated stand for
+    type_bindings: TypeBindings,
+    // the instances of generic functions that haven't been validated yet, with the types their type parameters stand for
+    // and how many instances deep they were made from
+    pending_instances: Vec<(&'a ParserNode, TypeBindings, usize)>,
+    // how many instances deep the instance being validated was made from
+    instance_depth: usize,
+    // the modules of the files that were imported and the spans of their sources, see modules::Sources
+    modules: Vec<(String, Range<usize>)>,
+    player_variables: HashMap<String, TypeKey>,
+    // config values in the order they were declared, which is the order of the scores that hold them
+    configs: IndexMap<String, TypeKey>,
+    // the default values of the config values, which static asserts can use
+    config_defaults: HashMap<String, Value>,
+    // the constants that describe the targeted version, see consteval::target_constants
+    target: HashMap<String, Value>,
+    // the states of every state machine, in the order they were declared
+    state_machines: HashMap<String, Vec<String>>,
+    regions: Vec<String>,
+    // the names of the sub-commands declared so far, set once the commands block is reached since a pack only has one
+    subcommands: Option<Vec<String>>,
+    // the elements of every table, computed when the program is scanned
+    tables: IndexMap<String, Vec<i32>>,
+    // the struct types that were declared as messages
+    messages: Vec<TypeKey>,
+    // the struct types that are attached to entities, in the order they were declared
+    entity_structs: Vec<TypeKey>,
+    // the methods of every trait, which aren't owned by any struct
+    traits: HashMap<String, Vec<FunctionSignature>>,
+    // the struct types and the traits they implement
+    implementations: Vec<(TypeKey, String)>,
     current_return_type: Option<TypeKey>,
+    // the objective of the function being validated, e.g "main" or "Point.length"
+    current_function: Option<String>,
+    // every call made from a function, as the objectives of the caller and the callee, used to find recursive calls
+    calls: Vec<(String, String, &'a ParserNode)>,
+    // every call made through a function reference, as the objective of the caller and the type of the reference. They
+    // can call any function referenced with that type, which isn't known until the whole program has been visited
+    reference_calls: Vec<(Option<String>, TypeKey, &'a ParserNode)>,
+    // the declarations of #[inline] functions by their names in the pack, which calls to them are tagged with
+    inline_functions: HashMap<String, &'a ParserNode>,
+    // the statements directly in the body of the function being validated, which are the only ones that can await
+    // and the last of which is the only place a #[spread] loop may be
+    current_body: Option<&'a [ParserNode]>,
     types: TypePool,
     tags: TagPool<'a>,
     scope_stack: ScopeStack,
     errors: ValidationErrorList,
     current_struct: Option<TypeKey>,
+    registries: Option<&'a Registries>,
+    // the commands that command literals may not run, see safety::expand_forbidden
+    forbidden_commands: Vec<String>,
+    // whether loops defer the iterations that go over the budget of a tick to the next one, see --loop-budget
+    loop_budget: bool,
 }
 
 impl<'a> Validator<'a> {
-    pub fn new(pack_name: String) -> Self {
+    pub fn new(pack_name: String, registries: Option<&'a Registries>, forbidden_commands: Vec<String>) -> Self {
         Self {
             pack_name,
             global_functions: HashMap::new(),
+            overloads: HashMap::new(),
+            generic_functions: HashMap::new(),
+            type_bindings: Vec::new(),
+            pending_instances: Vec::new(),
+            instance_depth: 0,
+            modules: Vec::new(),
+            player_variables: HashMap::new(),
+            configs: IndexMap::new(),
+            config_defaults: HashMap::new(),
+            target: consteval::target_constants(None, false),
+            state_machines: HashMap::new(),
+            regions: Vec::new(),
+            subcommands: None,
+            tables: IndexMap::new(),
+            messages: Vec::new(),
+            entity_structs: Vec::new(),
+            traits: HashMap::new(),
+            implementations: Vec::new(),
             current_return_type: None,
+            current_function: None,
+            calls: Vec::new(),
+            reference_calls: Vec::new(),
+            inline_functions: HashMap::new(),
+            current_body: None,
             types: TypePool::new_with_primitives(),
             tags: TagPool::new(),
             scope_stack: ScopeStack::new(),
             errors: ValidationErrorList::new(),
             current_struct: None,
+            registries,
+            forbidden_commands,
+            loop_budget: false,
         }
     }
 
+    /// Namespaces the functions declared in the given spans of the program by the module they're from, e.g a function
+    /// `add` in the module "util.math" is named "util.math.add" in the pack.
+    pub fn set_modules(&mut self, modules: Vec<(String, Range<usize>)>) {
+        self.modules = modules;
+    }
+
+    /// Targets the version with the given pack format, which the `TARGET` constants of #[cfg] conditions and static
+    /// assertions describe, along with whether it's a release build.
+    pub fn set_target(&mut self, pack_format: Option<u32>, release: bool) {
+        self.target = consteval::target_constants(pack_format, release);
+    }
+
+    /// Limits the iterations that loops run per tick as --loop-budget does, which only loops that the rest of their
+    /// function can wait for are allowed with, see is_budgeted_loop.
+    pub fn set_loop_budget(&mut self, loop_budget: bool) {
+        self.loop_budget = loop_budget;
+    }
+
     pub fn validate_program(mut self, ast: &'a ParserNode) -> ValidatorOutput {
+        self.scan_cfg_conditions(ast.as_program());
+        self.scan_enum_defs(ast.as_program());
         self.scan_struct_defs(ast.as_program());
+        self.scan_trait_defs(ast.as_program());
         self.scan_func_defs(ast.as_program());
+        self.scan_player_variables(ast.as_program());
+        self.scan_configs(ast.as_program());
+        self.scan_state_machines(ast.as_program());
+        self.scan_tables(ast.as_program());
         self.visit_node(ast);
+        self.visit_instances();
+        self.link_reference_calls();
+        self.tag_recursive_calls();
 
         self.dissolve()
     }
@@ -56,6 +179,7 @@ impl<'a> Validator<'a> {
             types: self.types,
             errors: self.errors.dissolve(),
             tags: self.tags,
+            tables: self.tables,
         }
     }
 
@@ -68,6 +192,15 @@ impl<'a> Validator<'a> {
 
                 self.types.none()
             }
+            // the body of a generic function is only validated for each of its instances, see Validator::visit_instances
+            ParserNodeKind::FunctionDeclaration { type_params, .. }
+                if !type_params.is_empty() && self.type_bindings.is_empty() =>
+            {
+                self.types.none()
+            }
+            // a weak function that's replaced is left out of the pack, see Validator::scan_func_defs, as is a function whose
+            // #[cfg] condition is false, see Validator::scan_cfg_conditions
+            ParserNodeKind::FunctionDeclaration { .. } if self.tags.is_left_out(node) => self.types.none(),
             ParserNodeKind::FunctionDeclaration {
                 name,
                 body,
@@ -81,12 +214,22 @@ impl<'a> Validator<'a> {
                     );
                 }
 
+                let name = self.tags.find_function_name(node).unwrap_or(name).to_string();
+
                 let func_signature = match self.current_struct {
-                    Some(ty) => ty.from(&self.types).as_struct_def().function(name).unwrap(),
+                    Some(ty) => ty.from(&self.types).as_struct_def().function(&name).unwrap(),
                     None => self.global_functions.get(&ResourceLocation::new(self.pack_name.clone(), name.clone())).unwrap(),
                 };
 
                 self.current_return_type = Some(func_signature.return_type());
+                self.current_function = Some(match self.current_struct {
+                    Some(ty) => format!("{}.{}", ty.from(&self.types).as_struct_def().name(), name),
+                    None => name.clone(),
+                });
+                self.current_body = match body.kind() {
+                    ParserNodeKind::Block(statements) => Some(statements),
+                    _ => None,
+                };
 
                 if func_signature.return_type() != self.types.none()
                     && !self.check_node_returns(body)
@@ -109,10 +252,147 @@ impl<'a> Validator<'a> {
                 self.visit_node(body);
 
                 self.current_return_type = None;
+                self.current_function = None;
+                self.current_body = None;
                 self.scope_stack.pop();
 
                 self.types.none()
             }
+            ParserNodeKind::Every { ticks, body } => {
+                // a scheduled function must be at least one tick in the future
+                if *ticks < 1 {
+                    self.errors.add(ValidationErrorKind::InvalidTickInterval(*ticks), node.span());
+                }
+
+                self.visit_generated_body(body);
+
+                self.types.none()
+            }
+            ParserNodeKind::StateMachine { states, .. } => {
+                for state in states {
+                    self.visit_node(state);
+                }
+
+                self.types.none()
+            }
+            ParserNodeKind::State { on_enter, on_exit, .. } => {
+                for body in on_enter.iter().chain(on_exit) {
+                    self.visit_generated_body(body);
+                }
+
+                self.types.none()
+            }
+            ParserNodeKind::Region { name, on_enter, on_leave, .. } => {
+                if self.regions.contains(name) {
+                    self.errors.add(
+                        ValidationErrorKind::RegionAlreadyDefined(name.clone()),
+                        node.span(),
+                    );
+                }
+
+                self.regions.push(name.clone());
+
+                for body in on_enter.iter().chain(on_leave) {
+                    self.visit_generated_body(body);
+                }
+
+                self.types.none()
+            }
+            ParserNodeKind::Commands(subcommands) => {
+                if self.subcommands.is_some() {
+                    self.errors.add(ValidationErrorKind::CommandsAlreadyDefined, node.span());
+                    return self.types.none();
+                }
+
+                self.subcommands = Some(Vec::new());
+
+                for subcommand in subcommands {
+                    self.visit_node(subcommand);
+                }
+
+                self.types.none()
+            }
+            ParserNodeKind::Subcommand { name, function, .. } => {
+                let names = self.subcommands.as_mut().unwrap();
+
+                if names.contains(name) {
+                    self.errors.add(
+                        ValidationErrorKind::SubcommandAlreadyDefined(name.clone()),
+                        node.span(),
+                    );
+                }
+
+                names.push(name.clone());
+
+                if let Some(signature) = self.find_parameterless_overload(function) {
+                    if signature.name() != function {
+                        self.tags.tag_function_name(node, signature.name().to_string());
+                    }
+                }
+
+                match self.find_overloads(function).first() {
+                    Some(_) if self.find_parameterless_overload(function).is_none() => {
+                        self.errors.add(
+                            ValidationErrorKind::SubcommandWithParameters(function.clone()),
+                            node.span(),
+                        );
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.errors.add(
+                            ValidationErrorKind::UnknownFunction(function.clone()),
+                            node.span(),
+                        );
+                    }
+                }
+
+                self.types.none()
+            }
+            // transitions are checked when the state machines are scanned
+            ParserNodeKind::StateTransition { .. } => self.types.none(),
+            // tables are computed when the program is scanned
+            ParserNodeKind::Table { .. } => self.types.none(),
+            // config values are handled when the program is scanned
+            ParserNodeKind::Config { .. } => self.types.none(),
+            // as are enums
+            ParserNodeKind::EnumDefinition { .. } => self.types.none(),
+            // and traits, whose methods have no bodies
+            ParserNodeKind::TraitDefinition { .. } | ParserNodeKind::MethodSignature { .. } => self.types.none(),
+            // imports are resolved before validation, see modules::parse_program
+            ParserNodeKind::Import(_) => self.types.none(),
+            ParserNodeKind::StaticAssert { expr, message } => {
+                self.check_static_assert(node, expr, message);
+                self.types.none()
+            }
+            ParserNodeKind::Index { expr, index } => self.visit_index(expr, index),
+            ParserNodeKind::ArrayLiteral(elements) => self.visit_array_literal(node, elements),
+            ParserNodeKind::Tuple(elements) => self.visit_tuple(elements),
+            ParserNodeKind::TupleDeclaration { names, expr } => {
+                self.visit_tuple_declaration(node, names, expr);
+                self.types.none()
+            }
+            ParserNodeKind::ChangeState { machine, state } => {
+                match self.state_machines.get(machine) {
+                    Some(states) if !states.contains(state) => {
+                        self.errors.add(
+                            ValidationErrorKind::UnknownState {
+                                machine: machine.clone(),
+                                state: state.clone(),
+                            },
+                            node.span(),
+                        );
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.errors.add(
+                            ValidationErrorKind::UnknownStateMachine(machine.clone()),
+                            node.span(),
+                        );
+                    }
+                }
+
+                self.types.none()
+            }
             ParserNodeKind::Block(nodes) => {
                 self.scope_stack.push();
 
@@ -167,13 +447,61 @@ impl<'a> Validator<'a> {
 
                 self.types.none()
             }
+            ParserNodeKind::Match { expr, arms } => {
+                let expr_type = self.visit_node(expr);
+
+                match expr_type.from(&self.types).as_enum_def() {
+                    Some(_) => self.check_enum_arms(node, expr_type, arms),
+                    None if expr_type == self.types.int() => {
+                        let mut covered = Vec::new();
+
+                        for (pattern, body) in arms {
+                            match pattern {
+                                ArmPattern::Variant { enum_name, variant } => self.errors.add(
+                                    ValidationErrorKind::UnexpectedArmPattern {
+                                        expected: expr_type,
+                                        pattern: format!("{}.{}", enum_name, variant),
+                                    },
+                                    body.span(),
+                                ),
+                                // an arm whose values the arms before it all match would never run
+                                ArmPattern::Range(range) => {
+                                    if ir::claim_range(*range, &mut covered).is_empty() {
+                                        self.errors.add(ValidationErrorKind::UnreachableMatchArm(pattern.to_string()), body.span());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    None => self.errors.add(
+                        ValidationErrorKind::ExpectedIntInMatch(expr_type),
+                        expr.span(),
+                    ),
+                }
+
+                for (_, body) in arms {
+                    self.scope_stack.push();
+                    self.visit_node(body);
+                    self.scope_stack.pop();
+                }
+
+                self.types.none()
+            }
             ParserNodeKind::For {
+                attributes,
+                label,
                 init,
                 cond,
                 step,
                 body,
             } => {
-                self.scope_stack.push_loop();
+                self.check_loop_attributes(node, attributes);
+
+                if self.loop_budget && is_budgeted_loop(node) {
+                    self.check_budgeted_loop(node);
+                }
+
+                self.scope_stack.push_loop(label.clone());
 
                 self.visit_node(init);
 
@@ -188,16 +516,86 @@ impl<'a> Validator<'a> {
 
                 self.visit_node(step);
 
+                // the loop variable of an unrolled loop is given its values at compile time
+                if node.has_attribute("unroll") {
+                    self.scope_stack.last_mut().is_unrolled = true;
+                }
+
+                self.visit_node(body);
+
+                self.scope_stack.pop();
+
+                self.types.none()
+            }
+            ParserNodeKind::ForEntities { name, selector, body } => {
+                if let Err(reason) = builtin::check_selector(selector) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidSelector {
+                            selector: selector.clone(),
+                            reason,
+                        },
+                        node.span(),
+                    );
+                }
+
+                // the loop variable is the first attached struct whose entities are the only ones the selector can pick
+                let arguments = selector_filters(selector);
+                let attached = self.entity_structs.iter().copied().find(|ty| {
+                    let entity = ty.from(&self.types).as_struct_def().entity().unwrap();
+                    selector_filters(entity).iter().all(|filter| arguments.contains(filter))
+                });
+
+                self.scope_stack.push_entity_loop();
+
+                match attached {
+                    Some(ty) => self.scope_stack.register_variable(name.clone(), ty),
+                    None => {
+                        self.scope_stack.register_variable(name.clone(), self.types.selector());
+                        self.scope_stack.register_selector(name.clone(), "@s".to_string());
+                    }
+                }
+
                 self.visit_node(body);
 
                 self.scope_stack.pop();
 
                 self.types.none()
             }
-            ParserNodeKind::Break => {
+            ParserNodeKind::ContextBlock { subcommands, body } => {
+                for (_, selector) in subcommands {
+                    if let Err(reason) = builtin::check_selector(selector) {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidSelector {
+                                selector: selector.clone(),
+                                reason,
+                            },
+                            node.span(),
+                        );
+                    }
+                }
+
+                // the body is its own function like the body of a loop over entities, which nothing in it can leave early
+                self.scope_stack.push_entity_loop();
+                self.visit_node(body);
+                self.scope_stack.pop();
+
+                self.types.none()
+            }
+            ParserNodeKind::Await(_) => {
+                self.check_await(node);
+                self.types.none()
+            }
+            ParserNodeKind::Break(label) => {
                 if !self.scope_stack.is_in_loop() {
                     self.errors
                         .add(ValidationErrorKind::CannotBreakOutsideLoop, node.span());
+                } else if let Some(label) = label.as_ref().filter(|label| !self.scope_stack.is_in_loop_labeled(label)) {
+                    self.errors
+                        .add(ValidationErrorKind::UnknownLoopLabel(label.clone()), node.span());
+                } else if self.scope_stack.breaks_out_of_entity_loop(label.as_deref()) {
+                    self.errors.add(ValidationErrorKind::ControlFlowInEntityLoop, node.span());
+                } else if self.scope_stack.breaks_out_of_unrolled_loop(label.as_deref()) {
+                    self.errors.add(ValidationErrorKind::BreakInUnrolledLoop, node.span());
                 }
 
                 self.types.none()
@@ -206,7 +604,48 @@ impl<'a> Validator<'a> {
             ParserNodeKind::BoolLiteral(_) => self.types.bool(),
             ParserNodeKind::Identifier(ident) => {
                 match self.scope_stack.find_variable_type(ident) {
+                    // selectors are put into the commands that use them, so the one a variable holds has to be known
+                    Some(ty) if ty == self.types.selector() => {
+                        match self.scope_stack.find_selector(ident) {
+                            Some(selector) => self.tags.tag_selector(node, selector.to_string()),
+                            None => self.errors.add(ValidationErrorKind::UnknownSelector(ident.clone()), node.span()),
+                        }
+
+                        ty
+                    }
+                    // as is the text that a text variable holds
+                    Some(ty) if ty == self.types.text() => {
+                        if self.scope_stack.is_known_text(ident) {
+                            self.tags.tag_text(node);
+                        } else {
+                            self.errors.add(ValidationErrorKind::UnknownText, node.span());
+                        }
+
+                        ty
+                    }
+                    // the fields of an attached struct are scores on the entity, so there's no struct value to use
+                    Some(ty) if self.entity_structs.contains(&ty) => {
+                        self.errors.add(ValidationErrorKind::EntityStructNotValue(ident.clone()), node.span());
+                        ty
+                    }
                     Some(ty) => ty,
+                    // a function that's named without being called is a reference to it
+                    None if self.overloads.contains_key(ident) => self.visit_function_ref(node, ident),
+                    // a generic function is only compiled for the types it's called with, so there's no one function to refer to
+                    None if self.generic_functions.contains_key(ident) => {
+                        self.errors.add(ValidationErrorKind::GenericFunctionReference(ident.clone()), node.span());
+                        self.types.unknown()
+                    }
+                    None if self.player_variables.contains_key(ident) => {
+                        let resolution = self.resolver().resolve(node).unwrap();
+                        self.tags.tag_resolution(node, resolution);
+                        self.player_variables[ident]
+                    }
+                    None if self.configs.contains_key(ident) => {
+                        let resolution = self.resolver().resolve(node).unwrap();
+                        self.tags.tag_resolution(node, resolution);
+                        self.configs[ident]
+                    }
                     None => {
                         self.errors.add(
                             ValidationErrorKind::UnknownVariable(ident.clone()),
@@ -217,6 +656,12 @@ impl<'a> Validator<'a> {
                 }
             }
             ParserNodeKind::TypedIdentifier { .. } => self.types.none(),
+            // player variables are handled when the program is scanned
+            ParserNodeKind::PlayerVariableDeclaration { .. } => self.types.none(),
+            ParserNodeKind::StringLiteral(_) => self.types.string(),
+            // attributes are handled when the function signatures are scanned
+            ParserNodeKind::Attribute { .. } => self.types.none(),
+            ParserNodeKind::NamedArgument { .. } => self.types.none(),
             ParserNodeKind::VariableDeclaration { name, expr, ty } => {
                 let name = name.as_identifier();
 
@@ -228,7 +673,7 @@ impl<'a> Validator<'a> {
                 }
 
                 let specified_type = match ty {
-                    Some(ty) => self.types.get_type_key(ty).or_else(|| {
+                    Some(ty) => self.find_type(ty).or_else(|| {
                         self.errors
                             .add(ValidationErrorKind::UnknownType(ty.clone()), node.span());
                         Some(self.types.unknown())
@@ -236,10 +681,24 @@ impl<'a> Validator<'a> {
                     None => None,
                 };
 
-                let expr_type = self.visit_node(expr);
+                let errors = self.errors.len();
+
+                // an empty array has no type of its own, so it takes the type the variable is declared with
+                let expr_type = match (specified_type, expr.unwrap_expression().kind()) {
+                    (Some(specified_type), ParserNodeKind::ArrayLiteral(elements))
+                        if elements.is_empty() && self.types.element_of(specified_type).is_some() =>
+                    {
+                        self.tags.tag_type(expr, specified_type);
+                        specified_type
+                    }
+                    _ => self.visit_node(expr),
+                };
 
-                if let Some(specified_type) = specified_type {
-                    if specified_type != expr_type {
+                // a variable that's declared as optional keeps that type, even if it starts out with a plain value
+                let variable_type = match specified_type {
+                    Some(specified_type) if specified_type == self.types.unknown() => expr_type,
+                    Some(specified_type) if self.accepts(specified_type, expr_type, expr) => specified_type,
+                    Some(specified_type) => {
                         self.errors.add(
                             ValidationErrorKind::VariableAssignmentTypeMismatch {
                                 expected: specified_type,
@@ -248,14 +707,59 @@ impl<'a> Validator<'a> {
                             },
                             node.span(),
                         );
+
+                        expr_type
+                    }
+                    None if expr_type == self.types.null() => {
+                        self.errors.add(ValidationErrorKind::NullWithoutOptional, expr.span());
+                        expr_type
+                    }
+                    // expressions that failed to validate may have no value because of that, which was already reported
+                    None if expr_type == self.types.none() && self.errors.len() == errors => {
+                        self.errors.add(ValidationErrorKind::VariableWithoutValue(name.to_string()), expr.span());
+                        self.types.unknown()
+                    }
+                    None => expr_type,
+                };
+
+                self.scope_stack.register_variable(name.to_string(), variable_type);
+
+                if variable_type == self.types.selector() {
+                    if let Some(selector) = self.tags.find_selector(expr.unwrap_expression()) {
+                        self.scope_stack.register_selector(name.to_string(), selector.to_string());
                     }
                 }
 
-                self.scope_stack.register_variable(name.to_string(), expr_type);
+                if variable_type == self.types.text() && self.tags.is_text(expr.unwrap_expression()) {
+                    self.scope_stack.register_text(name.to_string());
+                }
 
                 self.types.none()
             }
             ParserNodeKind::VariableAssignment { path, expr } => {
+                if let ParserNodeKind::Index { expr: array, index } = path.kind() {
+                    self.visit_element_assignment(node, array, index, expr);
+                    return self.types.none();
+                }
+
+                if let ParserNodeKind::DataPath { .. } = path.kind() {
+                    let ty = self.visit_node(path);
+                    let expr_type = self.visit_node(expr);
+
+                    if !self.accepts(ty, expr_type, expr) {
+                        self.errors.add(
+                            ValidationErrorKind::VariableAssignmentTypeMismatch {
+                                expected: ty,
+                                actual: expr_type,
+                                expr_span: expr.span(),
+                            },
+                            node.span(),
+                        );
+                    }
+
+                    return self.types.none();
+                }
+
                 let resolution = match self.resolver().resolve(path) {
                     Ok(resolution) => resolution,
                     Err(err) => {
@@ -267,10 +771,17 @@ impl<'a> Validator<'a> {
                 };
 
                 let expr_type = self.visit_node(expr);
+                self.check_unrolled_variable(path);
 
                 match resolution.find_assignable_type(&self.types) {
+                    Some(ty) if ty == self.types.selector() => {
+                        self.errors.add(ValidationErrorKind::SelectorNotAssignable, node.span());
+                    }
+                    Some(ty) if ty == self.types.text() => {
+                        self.errors.add(ValidationErrorKind::TextNotAssignable, node.span());
+                    }
                     Some(ty) => {
-                        if ty != expr_type {
+                        if !self.accepts(ty, expr_type, expr) {
                             self.errors.add(
                                 ValidationErrorKind::VariableAssignmentTypeMismatch {
                                     expected: ty,
@@ -282,10 +793,12 @@ impl<'a> Validator<'a> {
                         }
                     }
                     None => {
-                        self.errors.add(
-                            ValidationErrorKind::NotAssignable,
-                            node.span(),
-                        );
+                        let kind = match resolution.last() {
+                            ResolvedPart::ConfigValue(_, name) => ValidationErrorKind::ConfigValueNotAssignable(name.clone()),
+                            _ => ValidationErrorKind::NotAssignable,
+                        };
+
+                        self.errors.add(kind, node.span());
                     }
                 }
 
@@ -293,12 +806,20 @@ impl<'a> Validator<'a> {
                 self.types.none()
             }
             ParserNodeKind::Return(expr) => {
+                if self.scope_stack.is_in_entity_loop() {
+                    self.errors.add(ValidationErrorKind::ControlFlowInEntityLoop, node.span());
+                } else if self.in_inline_function()
+                    && !self.current_body.and_then(|body| body.last()).is_some_and(|tail| std::ptr::eq(tail, node))
+                {
+                    self.errors.add(ValidationErrorKind::ControlFlowInInlineFunction, node.span());
+                }
+
                 match self.current_return_type.clone() {
                     Some(expected_type) => {
                         if let Some(return_expr) = expr {
                             let expr_type = self.visit_node(return_expr);
 
-                            if expr_type != expected_type {
+                            if !self.accepts(expected_type, expr_type, return_expr) {
                                 self.errors.add(
                                     ValidationErrorKind::ReturnTypeMismatch {
                                         expected: expected_type.clone(),
@@ -325,6 +846,35 @@ impl<'a> Validator<'a> {
 
                 self.types.none()
             }
+            ParserNodeKind::RunAs { selector, call } => {
+                match self.target_arg(selector) {
+                    Some(target) if builtin::is_valid_target(&target) => {}
+                    Some(target) => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinValue {
+                            value: target,
+                            expected: "a selector or player name",
+                        },
+                        selector.span(),
+                    ),
+                    None => self.errors.add(
+                        ValidationErrorKind::InvalidRunAs { expected: "a selector or player name" },
+                        selector.span(),
+                    ),
+                }
+
+                let ty = self.visit_node(call);
+
+                // the call is wrapped in an execute command, which only calls to functions in the pack can be
+                match self.tags.find_resolution(call).map(|resolution| resolution.last()) {
+                    Some(ResolvedPart::GlobalFunction(_) | ResolvedPart::Method(..)) | None => {}
+                    Some(_) => self.errors.add(
+                        ValidationErrorKind::InvalidRunAs { expected: "a call to a function or method" },
+                        call.span(),
+                    ),
+                }
+
+                ty
+            }
             ParserNodeKind::FunctionCall {
                 expr,
                 args: arg_nodes,
@@ -339,73 +889,19 @@ impl<'a> Validator<'a> {
                     }
                 };
 
-                let (expected_types, ret_type, param_names) = {
-                    let func_signature = match &callee.last() {
-                        ResolvedPart::GlobalFunction(name) => self
-                            .global_functions
-                            .get(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
-                            .unwrap(),
-                        ResolvedPart::Method(ty, name) => ty
-                            .from(&self.types)
-                            .as_struct_def()
-                            .function(&name)
-                            .unwrap(),
-                        ResolvedPart::Constructor(ty) => {
-                            ty.from(&self.types).as_struct_def().constructor()
-                        }
-                        _ => unreachable!(),
-                    };
-
-                    let expected_types = func_signature
-                        .params()
-                        .iter()
-                        .map(|param| param.param_type())
-                        .collect::<Vec<TypeKey>>();
-
-                    let ret_type = func_signature.return_type();
-
-                    let param_names = func_signature
-                        .params()
-                        .iter()
-                        .map(|param| param.name().to_string())
-                        .collect::<Vec<String>>();
-
-                    (expected_types, ret_type, param_names)
-                };
-
-                let param_count = expected_types.len();
-
-                if arg_nodes.len() < param_count {
-                    let missing_count = param_count - arg_nodes.len();
-                    let missing = &param_names[param_count - missing_count..];
-
-                    self.errors.add(
-                        ValidationErrorKind::NotEnoughArguments {
-                            callee_span: expr.span(),
-                            missing: missing.to_vec(),
-                        },
-                        node.span(),
-                    );
-                }
-
-                for (i, (arg, expected_type)) in arg_nodes.iter().zip(expected_types).enumerate() {
-                    let arg_type = self.visit_node(arg);
-
-                    if arg_type != expected_type {
-                        self.errors.add(
-                            ValidationErrorKind::FunctionCallArgTypeMismatch {
-                                name: param_names[i].to_string(),
-                                expected: expected_type,
-                                actual: arg_type,
-                            },
-                            arg.span(),
-                        );
+                match callee.last() {
+                    ResolvedPart::Builtin(builtin) => {
+                        let builtin = *builtin;
+                        self.tags.tag_resolution(node, callee);
+                        self.visit_builtin_call(builtin, node, arg_nodes)
                     }
+                    // longs have no literals, so long(n) widens an int rather than constructing a struct
+                    ResolvedPart::Constructor(ty) if *ty == self.types.long() => {
+                        self.tags.tag_resolution(node, callee);
+                        self.visit_long_conversion(node, arg_nodes)
+                    }
+                    _ => self.visit_function_call(node, expr, arg_nodes, callee),
                 }
-
-                self.tags.tag_resolution(node, callee);
-
-                ret_type
             }
             ParserNodeKind::Expression(expr) => self.visit_node(expr),
             ParserNodeKind::Operation(lhs, rhs, op) => {
@@ -413,6 +909,45 @@ impl<'a> Validator<'a> {
                 let rhs_type = self.visit_node(rhs);
 
                 match op {
+                    // strings can only be checked for equality with each other
+                    Operation::CheckEquals | Operation::NotEquals
+                        if lhs_type == self.types.string() && rhs_type == self.types.string() =>
+                    {
+                        self.types.bool()
+                    }
+                    // longs support the operations that can be carried out half by half
+                    Operation::Add | Operation::Subtract
+                        if lhs_type == self.types.long() && rhs_type == self.types.long() =>
+                    {
+                        self.types.long()
+                    }
+                    Operation::CheckEquals
+                    | Operation::NotEquals
+                    | Operation::GreaterThan
+                    | Operation::LessThan
+                    | Operation::GreaterThanOrEquals
+                    | Operation::LessThanOrEquals
+                        if lhs_type == self.types.long() && rhs_type == self.types.long() =>
+                    {
+                        self.types.bool()
+                    }
+                    // vec3s are added and subtracted component by component, and scaled by an int
+                    Operation::Add | Operation::Subtract
+                        if lhs_type == self.types.vec3() && rhs_type == self.types.vec3() =>
+                    {
+                        self.types.vec3()
+                    }
+                    Operation::Multiply | Operation::Divide
+                        if lhs_type == self.types.vec3() && rhs_type == self.types.int() =>
+                    {
+                        self.types.vec3()
+                    }
+                    // enums can only be checked for equality with values of the same enum
+                    Operation::CheckEquals | Operation::NotEquals
+                        if lhs_type == rhs_type && lhs_type.from(&self.types).as_enum_def().is_some() =>
+                    {
+                        self.types.bool()
+                    }
                     Operation::CheckEquals
                     | Operation::NotEquals
                     | Operation::GreaterThan
@@ -478,16 +1013,34 @@ impl<'a> Validator<'a> {
                     }
                 }
             }
-            ParserNodeKind::OpEquals { path, expr, .. } => {
+            ParserNodeKind::OpEquals { path, expr, op } => {
                 let expr_type = self.visit_node(expr);
 
-                if expr_type != self.types.int() {
+                if expr_type != self.types.int() && expr_type != self.types.long() {
                     self.errors.add(
                         ValidationErrorKind::ArithmeticUnsupported { ty: expr_type },
                         expr.span(),
                     );
                 }
 
+                // the int at the path is read into a score, changed and written back
+                if let ParserNodeKind::DataPath { .. } = path.kind() {
+                    let ty = self.visit_node(path);
+
+                    if expr_type == self.types.long() {
+                        self.errors.add(
+                            ValidationErrorKind::OperationTypeMismatch {
+                                lhs: ty,
+                                rhs: expr_type,
+                                op: *op,
+                            },
+                            node.span(),
+                        );
+                    }
+
+                    return self.types.none();
+                }
+
                 let resolution = match self.resolver().resolve(path) {
                     Ok(resolution) => resolution,
                     Err(err) => {
@@ -498,15 +1051,37 @@ impl<'a> Validator<'a> {
                     }
                 };
 
+                self.check_unrolled_variable(path);
+
                 match resolution.find_assignable_type(&self.types) {
+                    // longs can only be added to and subtracted from each other
+                    Some(ty) if ty == self.types.long() && matches!(op, Operation::Add | Operation::Subtract) => {
+                        if expr_type != ty {
+                            self.errors.add(
+                                ValidationErrorKind::OperationTypeMismatch {
+                                    lhs: ty,
+                                    rhs: expr_type,
+                                    op: *op,
+                                },
+                                node.span(),
+                            );
+                        }
+                    }
                     Some(ty) => {
-                        let ty = ty;
-
                         if ty != self.types.int() {
                             self.errors.add(
                                 ValidationErrorKind::ArithmeticUnsupported { ty },
                                 node.span(),
                             );
+                        } else if expr_type == self.types.long() {
+                            self.errors.add(
+                                ValidationErrorKind::OperationTypeMismatch {
+                                    lhs: ty,
+                                    rhs: expr_type,
+                                    op: *op,
+                                },
+                                node.span(),
+                            );
                         }
                     }
                     None => {
@@ -521,8 +1096,21 @@ impl<'a> Validator<'a> {
                 self.types.none()
             }
             ParserNodeKind::MemberAccess { expr, member } => {
-                self.visit_node(expr);
-                
+                // the name of a type isn't a value, so it's left alone when one of its members is named, e.g Color.Red
+                // and neither is a variable of an attached struct, whose fields are read from the entity instead
+                match expr.unwrap_expression().kind() {
+                    ParserNodeKind::Identifier(name) if self.types.has_type(name) => {}
+                    ParserNodeKind::Identifier(name)
+                        if self.scope_stack.find_variable_type(name).is_some_and(|ty| self.entity_structs.contains(&ty)) =>
+                    {
+                        let ty = self.scope_stack.find_variable_type(name).unwrap();
+                        self.tags.tag_type(expr, ty);
+                    }
+                    _ => {
+                        self.visit_node(expr);
+                    }
+                }
+
                 match self.resolver().resolve(node) {
                     Ok(resolution) => match resolution.last() {
                         ResolvedPart::Field(ty, name) => ty
@@ -531,6 +1119,11 @@ impl<'a> Validator<'a> {
                             .field(&name)
                             .unwrap()
                             .field_type(),
+                        ResolvedPart::Variant(ty, _) => {
+                            let ty = *ty;
+                            self.tags.tag_resolution(node, resolution);
+                            ty
+                        }
                         ResolvedPart::Method(_, _) => {
                             self.errors.add(
                                 ValidationErrorKind::CannotReferenceMethodAsValue,
@@ -549,9 +1142,191 @@ impl<'a> Validator<'a> {
                     }
                 }
             }
-            ParserNodeKind::Unary(expr, _) => self.visit_node(expr),
-            ParserNodeKind::CommandLiteral(_) => self.types.none(),
-            ParserNodeKind::StructDefinition { name, members } => {
+            ParserNodeKind::Unary(expr, op) => {
+                let ty = self.visit_node(expr);
+
+                match op {
+                    Operation::Not => {
+                        if ty != self.types.bool() && ty != self.types.unknown() {
+                            self.errors.add(ValidationErrorKind::NotOperatorTypeMismatch(ty), node.span());
+                        }
+
+                        self.types.bool()
+                    }
+                    _ => ty,
+                }
+            }
+            ParserNodeKind::Null => self.types.null(),
+            ParserNodeKind::TextLiteral { parts, .. } => {
+                for part in parts {
+                    self.visit_text_component(part);
+                }
+
+                self.tags.tag_text(node);
+                self.types.text()
+            }
+            ParserNodeKind::DataPath { source, path } => {
+                match source {
+                    DataSource::Storage(storage) if !builtin::is_valid_resource_location(storage) => {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinValue {
+                                value: storage.clone(),
+                                expected: "the resource location of a storage, e.g pack:config",
+                            },
+                            node.span(),
+                        );
+                    }
+                    DataSource::Entity(selector) => {
+                        if let Err(reason) = builtin::check_selector(selector) {
+                            self.errors.add(
+                                ValidationErrorKind::InvalidSelector {
+                                    selector: selector.clone(),
+                                    reason,
+                                },
+                                node.span(),
+                            );
+                        } else if !builtin::is_single_target(selector) {
+                            self.errors.add(
+                                ValidationErrorKind::InvalidBuiltinValue {
+                                    value: selector.clone(),
+                                    expected: "a selector that refers to a single entity",
+                                },
+                                node.span(),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+
+                if !builtin::is_valid_nbt_path(path) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinValue {
+                            value: path.clone(),
+                            expected: "an NBT path, e.g items[0].count",
+                        },
+                        node.span(),
+                    );
+                }
+
+                self.types.int()
+            }
+            ParserNodeKind::SelectorLiteral(selector) => {
+                if let Err(reason) = builtin::check_selector(selector) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidSelector {
+                            selector: selector.clone(),
+                            reason,
+                        },
+                        node.span(),
+                    );
+                }
+
+                self.tags.tag_selector(node, selector.clone());
+                self.types.selector()
+            }
+            ParserNodeKind::Conditional { cond, then, otherwise } => {
+                let cond_type = self.visit_node(cond);
+
+                if cond_type != self.types.bool() && cond_type != self.types.unknown() {
+                    self.errors.add(ValidationErrorKind::ExpectedBoolInConditional(cond_type), cond.span());
+                }
+
+                let then_type = self.visit_node(then);
+                let otherwise_type = self.visit_node(otherwise);
+
+                self.conditional_type(node, (then, then_type), (otherwise, otherwise_type))
+            }
+            ParserNodeKind::Coalesce { expr, default } => {
+                let expr_type = self.visit_node(expr);
+                let default_type = self.visit_node(default);
+
+                let inner = match self.types.inner_of(expr_type) {
+                    Some(inner) => inner,
+                    None => {
+                        if expr_type != self.types.unknown() {
+                            self.errors.add(ValidationErrorKind::ExpectedOptional(expr_type), expr.span());
+                        }
+
+                        return self.types.unknown();
+                    }
+                };
+
+                if default_type != inner && default_type != self.types.unknown() {
+                    self.errors.add(
+                        ValidationErrorKind::CoalesceDefaultMismatch {
+                            expected: inner,
+                            actual: default_type,
+                        },
+                        default.span(),
+                    );
+                }
+
+                inner
+            }
+            ParserNodeKind::IfLet {
+                name,
+                expr,
+                body,
+                else_body,
+            } => {
+                let expr_type = self.visit_node(expr);
+
+                let inner = match self.types.inner_of(expr_type) {
+                    Some(inner) => inner,
+                    None => {
+                        if expr_type != self.types.unknown() {
+                            self.errors.add(ValidationErrorKind::ExpectedOptional(expr_type), expr.span());
+                        }
+
+                        self.types.unknown()
+                    }
+                };
+
+                // locals are kept by name, so the value can't be bound to a name that's already taken
+                if self.scope_stack.variable_exists(name) {
+                    self.errors.add(ValidationErrorKind::VariableAlreadyDefined(name.clone()), node.span());
+                }
+
+                self.scope_stack.push();
+                self.scope_stack.register_variable(name.clone(), inner);
+                self.visit_node(body);
+                self.scope_stack.pop();
+
+                if let Some(body) = else_body {
+                    self.scope_stack.push();
+                    self.visit_node(body);
+                    self.scope_stack.pop();
+                }
+
+                self.types.none()
+            }
+            ParserNodeKind::Cast { expr, ty } => {
+                let from = self.visit_node(expr);
+
+                let to = match self.find_type(ty) {
+                    Some(to) => to,
+                    None => {
+                        self.errors.add(ValidationErrorKind::UnknownType(ty.clone()), node.span());
+                        return self.types.unknown();
+                    }
+                };
+
+                if !self.is_valid_cast(from, to) {
+                    self.errors.add(ValidationErrorKind::InvalidCast { from, to }, node.span());
+                }
+
+                to
+            }
+            ParserNodeKind::CommandLiteral(literal) | ParserNodeKind::CommandResult(literal) => {
+                self.check_registry_ids(literal, node.span().start + 1);
+                self.check_forbidden_commands(literal, node.span().start + 1);
+
+                match node.kind() {
+                    ParserNodeKind::CommandResult(_) => self.types.int(),
+                    _ => self.types.none(),
+                }
+            }
+            ParserNodeKind::StructDefinition { name, members, .. } => {
                 self.current_struct = self.types.get_type_key(name);
 
                 for member in members {
@@ -570,250 +1345,3412 @@ impl<'a> Validator<'a> {
         ty
     }
 
-    fn resolver(&self) -> Resolver {
-        Resolver::new(
-            &self.pack_name,
-            &self.global_functions,
-            &self.types,
-            &self.scope_stack,
-        )
+    // visits the body of a block that is compiled to its own function, such as an every(n) block or a state's on_enter
+    fn visit_generated_body(&mut self, body: &'a ParserNode) {
+        self.current_return_type = Some(self.types.none());
+        self.scope_stack.push();
+
+        self.visit_node(body);
+
+        self.current_return_type = None;
+        self.scope_stack.pop();
     }
 
-    fn check_node_returns(&self, node: &ParserNode) -> bool {
-        match node.kind() {
-            ParserNodeKind::Return(_) => true,
-            ParserNodeKind::If {
-                body,
-                else_ifs,
-                else_body,
-                ..
-            } => {
-                self.check_node_returns(&body)
-                    && else_ifs
-                        .iter()
-                        .all(|(_, body)| self.check_node_returns(body))
-                    && else_body
-                        .as_ref()
-                        .map_or(false, |body| self.check_node_returns(&body))
-            }
-            ParserNodeKind::For { .. } => false,
-            ParserNodeKind::Block(nodes) => nodes.iter().any(|node| self.check_node_returns(node)),
+    fn visit_function_call(
+        &mut self,
+        node: &'a ParserNode,
+        expr: &'a ParserNode,
+        arg_nodes: &'a [ParserNode],
+        mut callee: Resolution,
+    ) -> TypeKey {
+        // methods that aren't static take the value they're called on as their first parameter, "self"
+        let has_receiver = match callee.last() {
+            ResolvedPart::Method(ty, name) => !ty
+                .from(&self.types)
+                .as_struct_def()
+                .function(name)
+                .unwrap()
+                .is_static(),
             _ => false,
-        }
-    }
+        };
 
-    // should only be passed the contents of the root Program node
-    fn scan_struct_defs(&mut self, nodes: &[ParserNode]) {
-        let struct_defs = nodes
-            .iter()
-            .filter_map(|node| match node.kind() {
-                ParserNodeKind::StructDefinition { name, members } => {
-                    Some((name, members.as_slice()))
+        if has_receiver {
+            let receiver = expr.unwrap_expression().as_member_receiver();
+            self.visit_node(receiver);
+
+            // a receiver that can be assigned to gets back the changes that the method makes to self
+            if matches!(receiver.kind(), ParserNodeKind::Identifier(_) | ParserNodeKind::MemberAccess { .. }) {
+                if let Ok(resolution) = self.resolver().resolve(receiver) {
+                    if resolution.find_assignable_type(&self.types).is_some() {
+                        self.tags.tag_resolution(receiver, resolution);
+                    }
                 }
-                _ => None,
-            })
-            .collect::<Vec<(&String, &[ParserNode])>>();
+            }
+        }
 
-        // first pass to register empty struct definitions
-        for ((name, members), node) in struct_defs.iter().zip(nodes) {
-            if self.types.has_type(name) {
-                self.errors.add(
-                    ValidationErrorKind::StructAlreadyDefined(name.to_string()),
-                    node.span(),
-                );
-                continue;
+        // calls are to the function's name in the pack, and calls to overloaded functions are resolved by the types of
+        // their arguments, so those are visited first
+        let mut arg_types = None;
+
+        if let ResolvedPart::GenericStruct(name) = callee.last() {
+            let name = name.clone();
+            let actual = arg_nodes.iter().map(|arg| self.visit_node(arg)).collect::<Vec<TypeKey>>();
+
+            match self.instantiate_struct(node, &name, &actual) {
+                Some(ty) => callee.replace_last(ResolvedPart::Constructor(ty)),
+                None => return self.types.unknown(),
             }
 
-            self.types.insert(
-                name.to_string(),
-                SculkType::Struct(StructDef::new_empty(name.to_string())),
-            );
-        }
+            arg_types = Some(actual);
+        } else if let ResolvedPart::GlobalFunction(name) = callee.last() {
+            // a generic function is called through the instance for the types of its arguments
+            if self.generic_functions.contains_key(name) {
+                let name = name.clone();
+                let actual = arg_nodes.iter().map(|arg| self.visit_node(arg)).collect::<Vec<TypeKey>>();
 
-        // second pass to fill in concrete struct types from the nodes
-        for (name, members) in &struct_defs {
-            for member in *members {
-                let struct_type = self.types.get_type_key(name).unwrap();
+                match self.instantiate(node, &name, &actual) {
+                    Some(instance) => callee.replace_last(ResolvedPart::GlobalFunction(instance)),
+                    None => return self.types.unknown(),
+                }
 
-                match member.kind() {
-                    ParserNodeKind::TypedIdentifier { name, ty } => {
-                        let field_type = self.types.get_type_key(ty);
+                arg_types = Some(actual);
+            } else if let [function] = self.find_overloads(name).as_slice() {
+                let name = function.name().to_string();
+                callee.replace_last(ResolvedPart::GlobalFunction(name));
+            } else {
+                let name = name.clone();
+                let actual = arg_nodes.iter().map(|arg| self.visit_node(arg)).collect::<Vec<TypeKey>>();
+
+                let overload = self.find_overloads(&name).into_iter().find(|overload| {
+                    overload.params().len() == actual.len()
+                        && overload.params().iter().zip(&actual).all(|(param, ty)| param.param_type() == *ty)
+                });
 
-                        match field_type {
-                            Some(ty) => {
-                                match struct_type
-                                    .from_mut(&mut self.types)
-                                    .as_struct_def_mut()
-                                    .add_field(FieldDef::new(name.to_string(), ty))
-                                {
-                                    Ok(_) => {}
-                                    Err(_) => self.errors.add(
-                                        ValidationErrorKind::StructFieldAlreadyDefined {
-                                            struct_name: name.to_string(),
-                                            field_name: name.to_string(),
-                                        },
-                                        member.span(),
-                                    ),
-                                }
-                            }
-                            None => self.errors.add(
-                                ValidationErrorKind::UnknownType(ty.to_string()),
-                                member.span(),
-                            ),
-                        }
+                match overload {
+                    Some(overload) => callee.replace_last(ResolvedPart::GlobalFunction(overload.name().to_string())),
+                    None => {
+                        self.errors.add(ValidationErrorKind::NoMatchingOverload { name, actual }, node.span());
+                        return self.types.unknown();
                     }
-                    ParserNodeKind::FunctionDeclaration {
-                        name,
-                        args,
-                        return_ty,
-                        body,
-                        is_static
-                    } => {
-                        let owner = match is_static {
-                            true => None,
-                            false => Some(struct_type),
-                        };
+                }
 
-                        let func_signature = self.create_func_def(owner, member);
+                arg_types = Some(actual);
+            }
+        }
 
-                        match struct_type
-                            .from_mut(&mut self.types)
-                            .as_struct_def_mut()
-                            .add_function(func_signature)
-                        {
-                            Ok(_) => {}
-                            Err(_) => self.errors.add(
-                                ValidationErrorKind::FunctionAlreadyDefined(name.clone()),
-                                member.span(),
-                            ),
-                        }
+        let (expected_types, ret_type, param_names) = match callee.last() {
+            // the parameters of a function type have no names, so they're named by their position
+            ResolvedPart::Reference(ty, _) => {
+                let function_def = ty.from(&self.types).as_function_def().unwrap();
+                let param_names = (1..=function_def.params().len()).map(|i| format!("argument {}", i)).collect();
+
+                (function_def.params().to_vec(), function_def.return_type(), param_names)
+            }
+            _ => {
+                let func_signature = match &callee.last() {
+                    ResolvedPart::GlobalFunction(name) => self
+                        .global_functions
+                        .get(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
+                        .unwrap(),
+                    ResolvedPart::Method(ty, name) => ty
+                        .from(&self.types)
+                        .as_struct_def()
+                        .function(&name)
+                        .unwrap(),
+                    ResolvedPart::Constructor(ty) => {
+                        ty.from(&self.types).as_struct_def().constructor()
                     }
                     _ => unreachable!(),
-                }
+                };
+
+                let params = &func_signature.params()[has_receiver as usize..];
+
+                let expected_types = params
+                    .iter()
+                    .map(|param| param.param_type())
+                    .collect::<Vec<TypeKey>>();
+
+                let ret_type = func_signature.return_type();
+
+                let param_names = params
+                    .iter()
+                    .map(|param| param.name().to_string())
+                    .collect::<Vec<String>>();
+
+                (expected_types, ret_type, param_names)
             }
-        }
+        };
 
-        // third pass to check for self-referencing structs
+        let param_count = expected_types.len();
+
+        if arg_nodes.len() < param_count {
+            let missing_count = param_count - arg_nodes.len();
+            let missing = &param_names[param_count - missing_count..];
+
+            self.errors.add(
+                ValidationErrorKind::NotEnoughArguments {
+                    callee_span: expr.span(),
+                    missing: missing.to_vec(),
+                },
+                node.span(),
+            );
+        }
+
+        for (i, (arg, expected_type)) in arg_nodes.iter().zip(expected_types).enumerate() {
+            let arg_type = match &arg_types {
+                Some(arg_types) => arg_types[i],
+                None => self.visit_node(arg),
+            };
+
+            if !self.accepts(expected_type, arg_type, arg) {
+                self.errors.add(
+                    ValidationErrorKind::FunctionCallArgTypeMismatch {
+                        name: param_names[i].to_string(),
+                        expected: expected_type,
+                        actual: arg_type,
+                    },
+                    arg.span(),
+                );
+            }
+        }
+
+        let callee_objective = match callee.last() {
+            ResolvedPart::GlobalFunction(name) => Some(name.clone()),
+            ResolvedPart::Method(ty, name) => Some(format!("{}.{}", ty.from(&self.types).as_struct_def().name(), name)),
+            _ => None,
+        };
+
+        if let (Some(caller), Some(callee)) = (&self.current_function, callee_objective) {
+            self.calls.push((caller.clone(), callee, node));
+        }
+
+        if let ResolvedPart::Reference(ty, _) = callee.last() {
+            self.reference_calls.push((self.current_function.clone(), *ty, node));
+        }
+
+        if let ResolvedPart::GlobalFunction(name) = callee.last() {
+            if let Some(declaration) = self.inline_functions.get(name) {
+                self.tags.tag_inline_call(node, declaration);
+            }
+        }
+
+        self.tags.tag_resolution(node, callee);
+
+        ret_type
+    }
+
+    // The rest of the function is run later by an await or a sleep(), so there has to be nothing left for the caller to
+    // wait for. Only the function's locals are used after it, which are kept until then, as there can't be any
+    // temporary values of an expression or a loop around it
+    fn check_await(&mut self, node: &'a ParserNode) {
+        if self.in_inline_function() {
+            self.errors.add(ValidationErrorKind::ControlFlowInInlineFunction, node.span());
+        } else if !self.in_function_body(node) {
+            self.errors.add(ValidationErrorKind::AwaitNotInFunctionBody, node.span());
+        } else if self.current_return_type != Some(self.types.none()) {
+            self.errors.add(ValidationErrorKind::AwaitInValueFunction, node.span());
+        }
+    }
+
+    // A loop that goes over the budget carries on in the next tick, and the statements after it run once it's over,
+    // so it has the same restrictions as an await
+    fn check_budgeted_loop(&mut self, node: &'a ParserNode) {
+        if self.in_inline_function() || !self.in_function_body(node) {
+            self.errors.add(ValidationErrorKind::BudgetedLoopNotInFunctionBody, node.span());
+        } else if self.current_return_type != Some(self.types.none()) {
+            self.errors.add(ValidationErrorKind::BudgetedLoopInValueFunction, node.span());
+        }
+    }
+
+    fn in_function_body(&self, node: &ParserNode) -> bool {
+        self.current_body
+            .is_some_and(|body| body.iter().any(|statement| std::ptr::eq(statement.unwrap_expression(), node)))
+    }
+
+    // The body of an inline function is put where it's called, so it can only return at its end, where the value it
+    // returns becomes the value of the call
+    fn in_inline_function(&self) -> bool {
+        self.current_struct.is_none()
+            && self.current_function.as_ref().is_some_and(|name| {
+                self.global_functions
+                    .get(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
+                    .is_some_and(|signature| signature.attributes().inline)
+            })
+    }
+
+    // A function that's named without being called is a reference to it, whose type is made from its signature.
+    // Overloads can't be told apart without the types of the arguments, so they can't be referenced
+    fn visit_function_ref(&mut self, node: &'a ParserNode, name: &str) -> TypeKey {
+        let location = match self.overloads[name].as_slice() {
+            [location] => location.clone(),
+            _ => {
+                self.errors.add(ValidationErrorKind::OverloadedFunctionReference(name.to_string()), node.span());
+                return self.types.unknown();
+            }
+        };
+
+        let signature = &self.global_functions[&location];
+        let params = signature.params().iter().map(ParamDef::param_type).collect();
+        let ty = self.types.function_of(params, signature.return_type());
+
+        let mut resolution = self.resolver().resolve(node).unwrap();
+        resolution.replace_last(ResolvedPart::GlobalFunction(location.path.clone()));
+
+        self.tags.tag_resolution(node, resolution);
+        self.tags.add_function_ref(location.path, ty);
+
+        ty
+    }
+
+    // The instance of a generic function for the types of the arguments it's called with, which is added to the global
+    // functions the first time it's called with them and named after the types of its parameters like an overload, e.g
+    // "max.int.int". Gives back its name in the pack
+    fn instantiate(&mut self, node: &'a ParserNode, name: &str, actual: &[TypeKey]) -> Option<String> {
+        let generic = self.generic_functions[name];
+
+        let (type_params, bounds, args) = match generic.kind() {
+            ParserNodeKind::FunctionDeclaration { type_params, bounds, args, .. } => (type_params, bounds, args),
+            _ => unreachable!(),
+        };
+
+        // the argument that had an error has been reported already
+        if actual.contains(&self.types.unknown()) {
+            return None;
+        }
+
+        let params = args.iter().map(|arg| arg.as_typed_identifier().1).collect::<Vec<&str>>();
+        let bindings = self.infer_type_args(node, name, type_params, &params, actual)?;
+
+        // the methods of the traits are called on the type the type parameter stands for, so it has to have them
+        for (type_param, trait_name) in bounds {
+            let (_, ty) = bindings.iter().find(|(bound, _)| bound == type_param).unwrap();
+            let ty = self.types.find_type(ty).unwrap();
+
+            if self.traits.contains_key(trait_name) && !self.implementations.contains(&(ty, trait_name.clone())) {
+                self.errors.add(
+                    ValidationErrorKind::TraitNotImplemented {
+                        ty,
+                        trait_name: trait_name.clone(),
+                    },
+                    node.span(),
+                );
+                return None;
+            }
+        }
+
+        let outer = std::mem::replace(&mut self.type_bindings, bindings.clone());
+        let signature = self.create_func_def(None, generic);
+        self.type_bindings = outer;
+
+        let mut pack_name = signature.overload_name(&self.types);
+
+        if let Some((module, _)) = self.modules.iter().find(|(_, span)| span.contains(&generic.span().start)) {
+            pack_name = format!("{}.{}", module, pack_name);
+        }
+
+        let location = ResourceLocation::new(self.pack_name.clone(), pack_name.clone());
+
+        if self.global_functions.contains_key(&location) {
+            return Some(pack_name);
+        }
+
+        // a call made from the body of an instance is an instance deeper
+        let depth = match self.type_bindings.is_empty() {
+            true => 0,
+            false => self.instance_depth + 1,
+        };
+
+        if depth > MAX_INSTANCE_DEPTH {
+            self.errors.add(ValidationErrorKind::InstantiationTooDeep(name.to_string()), node.span());
+            return None;
+        }
+
+        // every instance has its own copy of the declaration so that the nodes of its body can be tagged with their
+        // own types. They're needed for as long as the program is, which the validator only borrows
+        let instance: &'a ParserNode = Box::leak(Box::new(generic.clone()));
+
+        self.tags.tag_function_name(instance, pack_name.clone());
+        self.tags.add_instance(instance);
+        self.global_functions.insert(location, signature.renamed(pack_name.clone()));
+        self.pending_instances.push((instance, bindings, depth));
+
+        Some(pack_name)
+    }
+
+    // The instance of a generic struct whose constructor is called with arguments of the given types, e.g `Pair<int>`
+    // for `Pair(1, 2)`
+    fn instantiate_struct(&mut self, node: &'a ParserNode, name: &str, actual: &[TypeKey]) -> Option<TypeKey> {
+        if actual.contains(&self.types.unknown()) {
+            return None;
+        }
+
+        let (type_params, fields) = self.types.generic_struct(name).unwrap();
+        let fields = fields.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let bindings = self.infer_type_args(node, name, &type_params, &fields, actual)?;
+        let instance = format!("{}<{}>", name, bindings.iter().map(|(_, ty)| ty.as_str()).collect::<Vec<&str>>().join(", "));
+
+        match self.types.find_type(&instance) {
+            Some(ty) => Some(ty),
+            None => {
+                self.errors.add(ValidationErrorKind::UnknownType(instance), node.span());
+                None
+            }
+        }
+    }
+
+    // The types that the type parameters of a generic function or struct stand for, found by matching the types of its
+    // parameters against the types of the arguments it's given, in the order the type parameters were declared
+    fn infer_type_args(
+        &mut self,
+        node: &'a ParserNode,
+        name: &str,
+        type_params: &[String],
+        params: &[&str],
+        actual: &[TypeKey],
+    ) -> Option<TypeBindings> {
+        let mut bindings = Vec::new();
+
+        for (param, actual) in params.iter().zip(actual) {
+            infer_type_arg(param, &actual.from(&self.types).to_string(), type_params, &mut bindings);
+        }
+
+        let mut ordered = Vec::new();
+
+        for type_param in type_params {
+            match bindings.iter().find(|(bound, _)| bound == type_param) {
+                Some(binding) => ordered.push(binding.clone()),
+                None => {
+                    self.errors.add(
+                        ValidationErrorKind::CannotInferTypeParameter {
+                            name: name.to_string(),
+                            param: type_param.clone(),
+                        },
+                        node.span(),
+                    );
+                    return None;
+                }
+            }
+        }
+
+        Some(ordered)
+    }
+
+    // The instances of generic functions are validated once the rest of the program has been, with the types their type
+    // parameters stand for. Validating one can add more, which are validated too
+    fn visit_instances(&mut self) {
+        while let Some((instance, bindings, depth)) = self.pending_instances.pop() {
+            self.type_bindings = bindings;
+            self.instance_depth = depth;
+
+            // the instances of a function share its body, so an error that doesn't depend on the types is only reported once
+            let reported = self.errors.len();
+            self.visit_node(instance);
+            self.errors.remove_repeated(reported);
+        }
+
+        self.type_bindings.clear();
+        self.instance_depth = 0;
+    }
+
+    // Finds a type by its name, where the type parameters of the generic function being validated stand for the types
+    // they're bound to
+    fn find_type(&mut self, name: &str) -> Option<TypeKey> {
+        let name = TypePool::substitute(name, &self.type_bindings);
+        self.types.find_type(&name)
+    }
+
+    // A call through a function reference can call any of the functions referenced with its type, so it counts as a call
+    // to each of them when looking for recursive calls
+    fn link_reference_calls(&mut self) {
+        for (caller, ty, node) in std::mem::take(&mut self.reference_calls) {
+            let callees = self.tags.function_refs(ty);
+
+            if callees.is_empty() {
+                self.errors.add(ValidationErrorKind::NoFunctionReferences(ty), node.span());
+            }
+
+            if let Some(caller) = caller {
+                for callee in callees {
+                    self.calls.push((caller.clone(), callee.to_string(), node));
+                }
+            }
+        }
+    }
+
+    // A call is recursive if the function it calls can end up calling the caller again, directly or through other functions.
+    // Such calls have to save the caller's values before they're overwritten, see Instruction::PushFrame
+    fn tag_recursive_calls(&mut self) {
+        let mut callees: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for (caller, callee, _) in &self.calls {
+            callees.entry(caller).or_default().push(callee);
+        }
+
+        for (caller, callee, node) in &self.calls {
+            let mut visited = vec![callee.as_str()];
+            let mut pending = vec![callee.as_str()];
+
+            while let Some(function) = pending.pop() {
+                if function == caller {
+                    self.tags.tag_recursive_call(node);
+                    break;
+                }
+
+                for next in callees.get(function).into_iter().flatten() {
+                    if !visited.contains(next) {
+                        visited.push(next);
+                        pending.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    // Whether a value of the actual type can be used where a value of the expected type is. Where an optional is
+    // expected, a value of the type that it wraps or null can be used too, and the node is tagged with the optional so
+    // that its value is wrapped into one when it's compiled
+    fn accepts(&mut self, expected: TypeKey, actual: TypeKey, node: &'a ParserNode) -> bool {
+        if expected == actual {
+            return true;
+        }
+
+        match self.types.inner_of(expected) {
+            Some(inner) if actual == inner || actual == self.types.null() => {
+                self.tags.tag_optional(node, expected);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // The type of a conditional is the type that both of its values have. Otherwise one of them has to be accepted where
+    // the other is expected, e.g a plain value next to an optional, and null next to a plain value makes it optional
+    fn conditional_type(
+        &mut self,
+        node: &ParserNode,
+        (then, then_type): (&'a ParserNode, TypeKey),
+        (otherwise, otherwise_type): (&'a ParserNode, TypeKey),
+    ) -> TypeKey {
+        let unknown = self.types.unknown();
+
+        if then_type == unknown || otherwise_type == unknown {
+            return unknown;
+        }
+
+        if self.accepts(then_type, otherwise_type, otherwise) {
+            return then_type;
+        }
+
+        if self.accepts(otherwise_type, then_type, then) {
+            return otherwise_type;
+        }
+
+        let null = self.types.null();
+        let optional = match (then_type == null, otherwise_type == null) {
+            (true, _) => self.types.optional_of(otherwise_type),
+            (_, true) => self.types.optional_of(then_type),
+            _ => None,
+        };
+
+        match optional {
+            Some(optional) => {
+                self.accepts(optional, then_type, then);
+                self.accepts(optional, otherwise_type, otherwise);
+                optional
+            }
+            None => {
+                self.errors.add(
+                    ValidationErrorKind::ConditionalTypeMismatch {
+                        then: then_type,
+                        otherwise: otherwise_type,
+                    },
+                    node.span(),
+                );
+
+                unknown
+            }
+        }
+    }
+
+    // Casts only go between types that are kept in scores: bools and ints convert both ways, where any int other than 0
+    // is true, ints and bools widen to longs and longs wrap around into ints, and enums become the index of their variant.
+    // There's no fixed-point type to cast to or from yet, the trig builtins take and give ints scaled by FIXED_POINT_SCALE
+    fn is_valid_cast(&self, from: TypeKey, to: TypeKey) -> bool {
+        let (int, bool, long) = (self.types.int(), self.types.bool(), self.types.long());
+
+        from == to
+            || from == self.types.unknown()
+            || (from == int && to == bool)
+            || (from == bool && to == int)
+            || ((from == int || from == bool) && to == long)
+            || (from == long && to == int)
+            || (from.from(&self.types).as_enum_def().is_some() && to == int)
+    }
+
+    fn visit_long_conversion(&mut self, node: &'a ParserNode, arg_nodes: &'a [ParserNode]) -> TypeKey {
+        match arg_nodes {
+            [arg] => {
+                let arg_type = self.visit_node(arg);
+
+                if arg_type != self.types.int() {
+                    self.errors.add(
+                        ValidationErrorKind::FunctionCallArgTypeMismatch {
+                            name: "value".to_string(),
+                            expected: self.types.int(),
+                            actual: arg_type,
+                        },
+                        arg.span(),
+                    );
+                }
+            }
+            _ => self.errors.add(ValidationErrorKind::InvalidLongConversion, node.span()),
+        }
+
+        self.types.long()
+    }
+
+    fn visit_builtin_call(
+        &mut self,
+        builtin: Builtin,
+        node: &'a ParserNode,
+        arg_nodes: &'a [ParserNode],
+    ) -> TypeKey {
+        match builtin {
+            Builtin::Snapshot | Builtin::Rollback => {
+                let expected = "a snapshot name followed by the variables to save or restore";
+
+                let name = match arg_nodes.first().map(|arg| arg.unwrap_expression().kind()) {
+                    Some(ParserNodeKind::StringLiteral(name)) => name,
+                    _ => {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                            node.span(),
+                        );
+                        return self.types.none();
+                    }
+                };
+
+                if !builtin::is_valid_nbt_key(name) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidStorageKey(name.clone()),
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                if arg_nodes.len() < 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                }
+
+                for arg in &arg_nodes[1..] {
+                    self.visit_assignable_arg(arg);
+                }
+
+                self.types.none()
+            }
+            Builtin::Print if !self.has_print_target(arg_nodes) => {
+                if arg_nodes.is_empty() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "the text to show, optionally preceded by a target",
+                        },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                // the call is tagged with the target it's sent to instead
+                self.tags.tag_selector(node, "@a".to_string());
+
+                for arg in arg_nodes {
+                    self.visit_text_component(arg);
+                }
+
+                self.types.none()
+            }
+            Builtin::Print | Builtin::Title | Builtin::Subtitle | Builtin::Actionbar | Builtin::Bossbar => {
+                let (expected, expected_first) = match builtin {
+                    Builtin::Bossbar => ("a bossbar id followed by the text to show", "a lowercase bossbar id"),
+                    _ => ("a target followed by the text to show", "a selector or player name"),
+                };
+
+                let first = match builtin {
+                    Builtin::Bossbar => match arg_nodes.first().map(|arg| arg.unwrap_expression().kind()) {
+                        Some(ParserNodeKind::StringLiteral(first)) => Some(first.clone()),
+                        _ => None,
+                    },
+                    _ => arg_nodes.first().and_then(|arg| self.target_arg(arg)),
+                };
+
+                let first = match first {
+                    Some(first) if arg_nodes.len() >= 2 => first,
+                    _ => {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                            node.span(),
+                        );
+                        return self.types.none();
+                    }
+                };
+
+                let valid = match builtin {
+                    Builtin::Bossbar => builtin::is_valid_resource_path(&first),
+                    _ => builtin::is_valid_target(&first),
+                };
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinValue {
+                            value: first,
+                            expected: expected_first,
+                        },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                for arg in &arg_nodes[1..] {
+                    self.visit_text_component(arg);
+                }
+
+                self.types.none()
+            }
+            Builtin::Forceload | Builtin::Unforceload | Builtin::IsLoaded => {
+                let (params, required, expected): (&[&str], usize, _) = match builtin {
+                    Builtin::IsLoaded => (&["x", "y", "z"], 3, "the x, y and z coordinates of a block"),
+                    _ => (&["x", "z", "radius"], 2, "the x and z coordinates of a block, optionally followed by a radius"),
+                };
+
+                if arg_nodes.len() < required || arg_nodes.len() > params.len() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                for (arg, name) in arg_nodes.iter().zip(params) {
+                    // the radius decides how many chunks are loaded, which has to be known to stay within the game's limit
+                    if *name == "radius" {
+                        match arg.unwrap_expression().kind() {
+                            ParserNodeKind::NumberLiteral(radius) if (0..=builtin::MAX_FORCELOAD_RADIUS).contains(radius) => {}
+                            _ => self.errors.add(
+                                ValidationErrorKind::InvalidBuiltinArguments {
+                                    builtin,
+                                    expected: "a radius between 0 and 120 blocks",
+                                },
+                                arg.span(),
+                            ),
+                        }
+
+                        continue;
+                    }
+
+                    self.visit_int_arg(arg, name);
+                }
+
+                match builtin {
+                    Builtin::IsLoaded => self.types.bool(),
+                    _ => self.types.none(),
+                }
+            }
+            Builtin::PlaceStructure | Builtin::Fill | Builtin::Clone => {
+                let (params, string, expected): (&[&str], _, _) = match builtin {
+                    Builtin::PlaceStructure => (
+                        &["x", "y", "z"],
+                        Some("a structure id such as \"pack:arena\""),
+                        "a structure id followed by the coordinates to place it at",
+                    ),
+                    Builtin::Fill => (
+                        &["x1", "y1", "z1", "x2", "y2", "z2"],
+                        Some("a block such as \"minecraft:stone\""),
+                        "the coordinates of two corners followed by a block",
+                    ),
+                    _ => (
+                        &["x1", "y1", "z1", "x2", "y2", "z2", "x", "y", "z"],
+                        None,
+                        "the coordinates of two corners followed by the coordinates to copy the blocks to",
+                    ),
+                };
+
+                if arg_nodes.len() != params.len() + string.is_some() as usize {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                // the structure id comes first, the block to fill with last
+                let (coordinates, string_arg) = match builtin {
+                    Builtin::PlaceStructure => (&arg_nodes[1..], arg_nodes.first()),
+                    Builtin::Fill => (&arg_nodes[..6], arg_nodes.last()),
+                    _ => (arg_nodes, None),
+                };
+
+                if let (Some(expected_string), Some(string_arg)) = (string, string_arg) {
+                    let valid = match string_arg.unwrap_expression().kind() {
+                        ParserNodeKind::StringLiteral(value) => match builtin {
+                            Builtin::PlaceStructure => builtin::is_valid_resource_location(value),
+                            _ => builtin::is_valid_block(value),
+                        },
+                        _ => false,
+                    };
+
+                    if !valid {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: expected_string,
+                            },
+                            string_arg.span(),
+                        );
+                    }
+                }
+
+                for (arg, name) in coordinates.iter().zip(params) {
+                    self.visit_int_arg(arg, name);
+                }
+
+                // the game refuses to fill or clone too many blocks at once, which can be checked when the corners are constant
+                if let Builtin::Fill | Builtin::Clone = builtin {
+                    let corners = coordinates[..6]
+                        .iter()
+                        .map(|arg| match arg.unwrap_expression().kind() {
+                            ParserNodeKind::NumberLiteral(n) => Some(*n as i64),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<i64>>>();
+
+                    if let Some(corners) = corners {
+                        let volume = (0..3)
+                            .map(|i| (corners[i] - corners[i + 3]).abs() + 1)
+                            .product::<i64>();
+
+                        if volume > builtin::MAX_BLOCK_VOLUME {
+                            self.errors.add(ValidationErrorKind::VolumeTooLarge(volume), node.span());
+                        }
+                    }
+                }
+
+                self.types.none()
+            }
+            Builtin::SetTime | Builtin::SetWeather => {
+                let (expected, max_args) = match builtin {
+                    Builtin::SetTime => ("a time of day such as Day", 1),
+                    _ => ("a weather such as Rain, optionally followed by a duration in ticks", 2),
+                };
+
+                let name = match arg_nodes.first().map(|arg| arg.unwrap_expression().kind()) {
+                    Some(ParserNodeKind::Identifier(name)) if arg_nodes.len() <= max_args => name,
+                    _ => {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                            node.span(),
+                        );
+                        return self.types.none();
+                    }
+                };
+
+                // the variants are checked by name rather than resolved, so they can't clash with variables
+                let (valid, kind, variants) = match builtin {
+                    Builtin::SetTime => (builtin::TimeOfDay::from_name(name).is_some(), "time of day", &builtin::TimeOfDay::NAMES[..]),
+                    _ => (builtin::Weather::from_name(name).is_some(), "weather", &builtin::Weather::NAMES[..]),
+                };
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::UnknownVariant {
+                            kind,
+                            name: name.clone(),
+                            variants,
+                        },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                if let Some(duration) = arg_nodes.get(1) {
+                    match duration.unwrap_expression().kind() {
+                        ParserNodeKind::NumberLiteral(ticks) if *ticks < 1 => self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: "a duration of at least 1 tick",
+                            },
+                            duration.span(),
+                        ),
+                        _ => self.visit_int_arg(duration, "duration"),
+                    }
+                }
+
+                self.types.none()
+            }
+            Builtin::GetAttribute | Builtin::SetAttribute | Builtin::AddModifier | Builtin::RemoveModifier => {
+                let (arg_range, expected) = match builtin {
+                    Builtin::GetAttribute => (2..=3, "a target and an attribute, optionally followed by a scale"),
+                    Builtin::SetAttribute => (3..=3, "a target, an attribute and its new base value"),
+                    Builtin::AddModifier => (5..=5, "a target, an attribute, the name of the modifier, an amount and an operation"),
+                    _ => (3..=3, "a target, an attribute and the name of the modifier"),
+                };
+
+                if !arg_range.contains(&arg_nodes.len()) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                match self.target_arg(&arg_nodes[0]) {
+                    Some(target) if builtin::is_single_target(&target) => {}
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "a selector or player name that refers to a single entity",
+                        },
+                        arg_nodes[0].span(),
+                    ),
+                }
+
+                self.check_variant_arg(&arg_nodes[1], "attribute", &builtin::EntityAttribute::NAMES);
+
+                match builtin {
+                    Builtin::GetAttribute => {
+                        if let Some(scale) = arg_nodes.get(2) {
+                            match scale.unwrap_expression().kind() {
+                                ParserNodeKind::NumberLiteral(_) => {}
+                                ParserNodeKind::StringLiteral(scale) if builtin::is_valid_decimal(scale) => {}
+                                _ => self.errors.add(
+                                    ValidationErrorKind::InvalidBuiltinArguments {
+                                        builtin,
+                                        expected: "a constant scale such as 1000 or \"0.5\"",
+                                    },
+                                    scale.span(),
+                                ),
+                            }
+                        }
+
+                        return self.types.int();
+                    }
+                    Builtin::SetAttribute => self.visit_decimal_arg(&arg_nodes[2], "value"),
+                    _ => {
+                        match arg_nodes[2].unwrap_expression().kind() {
+                            ParserNodeKind::StringLiteral(name) if !name.is_empty() => {}
+                            _ => self.errors.add(
+                                ValidationErrorKind::InvalidBuiltinArguments {
+                                    builtin,
+                                    expected: "the name of the modifier",
+                                },
+                                arg_nodes[2].span(),
+                            ),
+                        }
+
+                        if let Builtin::AddModifier = builtin {
+                            self.visit_decimal_arg(&arg_nodes[3], "amount");
+                            self.check_variant_arg(&arg_nodes[4], "modifier operation", &builtin::ModifierOperation::NAMES);
+                        }
+                    }
+                }
+
+                self.types.none()
+            }
+            Builtin::GiveEffect | Builtin::ClearEffect => {
+                let (arg_range, expected) = match builtin {
+                    Builtin::GiveEffect => (
+                        2..=5,
+                        "a target and an effect, optionally followed by the seconds, amplifier and whether to hide the particles",
+                    ),
+                    _ => (1..=2, "a target, optionally followed by an effect"),
+                };
+
+                if !arg_range.contains(&arg_nodes.len()) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                match self.target_arg(&arg_nodes[0]) {
+                    Some(target) if builtin::is_valid_target(&target) => {}
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "a selector or player name",
+                        },
+                        arg_nodes[0].span(),
+                    ),
+                }
+
+                if let Some(effect) = arg_nodes.get(1) {
+                    self.check_variant_arg(effect, "effect", &builtin::EFFECT_NAMES);
+                }
+
+                // constant seconds and amplifiers are checked against the limits of the effect command
+                let limits = [
+                    ("seconds", 1, builtin::MAX_EFFECT_SECONDS),
+                    ("amplifier", 0, builtin::MAX_EFFECT_AMPLIFIER),
+                ];
+
+                for (arg, (name, min, max)) in arg_nodes.iter().skip(2).zip(limits) {
+                    match arg.unwrap_expression().kind() {
+                        ParserNodeKind::NumberLiteral(n) if !(min..=max).contains(n) => self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinValue {
+                                value: n.to_string(),
+                                expected: match name {
+                                    "seconds" => "between 1 and 1000000 seconds",
+                                    _ => "an amplifier between 0 and 255",
+                                },
+                            },
+                            arg.span(),
+                        ),
+                        _ => self.visit_int_arg(arg, name),
+                    }
+                }
+
+                // the game expects the literal words true or false, so hiding particles can't depend on a runtime value
+                if let Some(hide_particles) = arg_nodes.get(4) {
+                    if !matches!(hide_particles.unwrap_expression().kind(), ParserNodeKind::BoolLiteral(_)) {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: "true or false",
+                            },
+                            hide_particles.span(),
+                        );
+                    }
+                }
+
+                self.types.none()
+            }
+            Builtin::Damage | Builtin::Heal => {
+                let (arg_range, expected) = match builtin {
+                    Builtin::Damage => (2..=3, "a target and an amount, optionally followed by a damage type"),
+                    _ => (2..=2, "a target and an amount"),
+                };
+
+                if !arg_range.contains(&arg_nodes.len()) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                match self.target_arg(&arg_nodes[0]) {
+                    Some(target) if builtin::is_valid_target(&target) => {}
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "a selector or player name",
+                        },
+                        arg_nodes[0].span(),
+                    ),
+                }
+
+                match arg_nodes[1].unwrap_expression().kind() {
+                    ParserNodeKind::NumberLiteral(n) if *n <= 0 => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinValue {
+                            value: n.to_string(),
+                            expected: "an amount above 0",
+                        },
+                        arg_nodes[1].span(),
+                    ),
+                    _ => self.visit_int_arg(&arg_nodes[1], "amount"),
+                }
+
+                if let Some(damage_type) = arg_nodes.get(2) {
+                    self.check_variant_arg(damage_type, "damage type", &builtin::DAMAGE_TYPE_NAMES);
+                }
+
+                self.types.none()
+            }
+            Builtin::XpLevels | Builtin::XpPoints | Builtin::SetXp | Builtin::AddXp => {
+                let (arg_range, expected) = match builtin {
+                    Builtin::XpLevels | Builtin::XpPoints => (1..=1, "a player"),
+                    _ => (2..=3, "a target and an amount, optionally followed by Points or Levels"),
+                };
+
+                if !arg_range.contains(&arg_nodes.len()) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                // experience can only be queried for a single player
+                let (valid, expected_target) = match self.target_arg(&arg_nodes[0]) {
+                    Some(target) => match builtin {
+                        Builtin::XpLevels | Builtin::XpPoints => {
+                            (builtin::is_single_target(&target), "a selector or player name that refers to a single player")
+                        }
+                        _ => (builtin::is_valid_target(&target), "a selector or player name"),
+                    },
+                    _ => (false, "a selector or player name"),
+                };
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: expected_target,
+                        },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                match builtin {
+                    Builtin::XpLevels | Builtin::XpPoints => self.types.int(),
+                    _ => {
+                        self.visit_int_arg(&arg_nodes[1], "amount");
+
+                        if let Some(unit) = arg_nodes.get(2) {
+                            self.check_variant_arg(unit, "experience unit", &builtin::XP_UNIT_NAMES);
+                        }
+
+                        self.types.none()
+                    }
+                }
+            }
+            Builtin::Leaderboard => {
+                let expected = "a per-player variable or objective followed by the number of players to show";
+
+                if arg_nodes.len() != 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                let source = arg_nodes[0].unwrap_expression();
+
+                let valid = match source.kind() {
+                    ParserNodeKind::StringLiteral(objective) => builtin::is_valid_objective(objective),
+                    ParserNodeKind::Identifier(_) => match self.resolver().resolve(source) {
+                        Ok(resolution) => match resolution.last() {
+                            ResolvedPart::PlayerVariable(ty, _) => *ty == self.types.int(),
+                            _ => false,
+                        },
+                        Err(_) => false,
+                    },
+                    _ => false,
+                };
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "an int per-player variable or the name of an objective",
+                        },
+                        source.span(),
+                    );
+                }
+
+                match arg_nodes[1].unwrap_expression().kind() {
+                    ParserNodeKind::NumberLiteral(size) if (1..=builtin::MAX_LEADERBOARD_SIZE).contains(size) => {}
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "between 1 and 15 players",
+                        },
+                        arg_nodes[1].span(),
+                    ),
+                }
+
+                self.types.none()
+            }
+            Builtin::Sin | Builtin::Cos | Builtin::Atan2 => {
+                let (params, expected): (&[&str], _) = match builtin {
+                    Builtin::Atan2 => (&["y", "x"], "the y and x of a direction"),
+                    _ => (&["angle"], "a fixed-point angle in degrees"),
+                };
+
+                if arg_nodes.len() != params.len() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                for (arg, name) in arg_nodes.iter().zip(params) {
+                    self.visit_int_arg(arg, name);
+                }
+
+                self.types.int()
+            }
+            Builtin::Min | Builtin::Max | Builtin::Abs | Builtin::Clamp | Builtin::Pow | Builtin::Sqrt => {
+                let (params, expected): (&[&str], _) = match builtin {
+                    Builtin::Min | Builtin::Max => (&["a", "b"], "two ints"),
+                    Builtin::Clamp => (&["value", "low", "high"], "an int and the lowest and highest value it may have"),
+                    Builtin::Pow => (&["base", "exponent"], "a base and an exponent"),
+                    _ => (&["value"], "an int"),
+                };
+
+                if arg_nodes.len() != params.len() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                for (arg, name) in arg_nodes.iter().zip(params) {
+                    self.visit_int_arg(arg, name);
+                }
+
+                self.types.int()
+            }
+            Builtin::Assert => {
+                let expected = "a condition, optionally followed by a message";
+
+                if arg_nodes.is_empty() || arg_nodes.len() > 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                self.visit_typed_arg(&arg_nodes[0], "condition", self.types.bool());
+
+                if let Some(message) = arg_nodes.get(1) {
+                    if !matches!(message.unwrap_expression().kind(), ParserNodeKind::StringLiteral(_)) {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: "the message as a string literal",
+                            },
+                            message.span(),
+                        );
+                    }
+                }
+
+                self.types.none()
+            }
+            Builtin::Error => {
+                if !matches!(arg_nodes, [message] if matches!(message.unwrap_expression().kind(), ParserNodeKind::StringLiteral(_))) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments {
+                            builtin,
+                            expected: "the message as a string literal",
+                        },
+                        node.span(),
+                    );
+                }
+
+                // it returns from the function it's in, which would be the caller once inlined
+                if self.in_inline_function() {
+                    self.errors.add(ValidationErrorKind::ControlFlowInInlineFunction, node.span());
+                }
+
+                self.types.none()
+            }
+            Builtin::Sleep => {
+                let expected = "the number of ticks to sleep for, of at least 1";
+
+                match arg_nodes {
+                    [ticks] if matches!(ticks.unwrap_expression().kind(), ParserNodeKind::NumberLiteral(n) if *n >= 1) => {
+                        self.check_await(node)
+                    }
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    ),
+                }
+
+                self.types.none()
+            }
+            Builtin::Random => {
+                let expected = "the lowest and highest value it may return";
+
+                if arg_nodes.len() != 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                for (arg, name) in arg_nodes.iter().zip(["min", "max"]) {
+                    self.visit_int_arg(arg, name);
+                }
+
+                // the random command needs at least two values to choose from. A range that isn't known at compile
+                // time is assumed to have them
+                if let (ParserNodeKind::NumberLiteral(min), ParserNodeKind::NumberLiteral(max)) =
+                    (arg_nodes[0].unwrap_expression().kind(), arg_nodes[1].unwrap_expression().kind())
+                {
+                    if min >= max {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: "a lowest value that is below the highest value",
+                            },
+                            node.span(),
+                        );
+                    }
+                }
+
+                self.types.int()
+            }
+            Builtin::Hash | Builtin::Noise => {
+                let (params, required, expected): (&[&str], usize, _) = match builtin {
+                    Builtin::Hash => (&["a", "b", "c"], 1, "between 1 and 3 ints"),
+                    _ => (&["x", "z", "cell_size", "seed"], 3, "the x and z of a position and the size of a cell, optionally followed by a seed"),
+                };
+
+                if arg_nodes.len() < required || arg_nodes.len() > params.len() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                for (arg, name) in arg_nodes.iter().zip(params) {
+                    self.visit_int_arg(arg, name);
+                }
+
+                // a cell size that isn't known at compile time is assumed to be positive
+                if builtin == Builtin::Noise {
+                    if let ParserNodeKind::NumberLiteral(cell_size) = arg_nodes[2].unwrap_expression().kind() {
+                        if *cell_size < 1 {
+                            self.errors.add(
+                                ValidationErrorKind::InvalidBuiltinArguments {
+                                    builtin,
+                                    expected: "a cell size of at least 1 block",
+                                },
+                                arg_nodes[2].span(),
+                            );
+                        }
+                    }
+                }
+
+                self.types.int()
+            }
+            Builtin::EntityId => {
+                let expected = "a selector or player name that refers to a single entity";
+
+                if arg_nodes.len() != 1 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                let valid = self.target_arg(&arg_nodes[0]).is_some_and(|target| builtin::is_single_target(&target));
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                self.types.int()
+            }
+            Builtin::SettingsMenu => {
+                let expected = "a selector or player name";
+
+                if arg_nodes.len() != 1 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                let valid = self.target_arg(&arg_nodes[0]).is_some_and(|target| builtin::is_valid_target(&target));
+
+                if !valid {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                // the menu is only generated for packs that have something to configure
+                if self.configs.is_empty() {
+                    self.errors.add(ValidationErrorKind::NoConfigValues, node.span());
+                }
+
+                self.types.none()
+            }
+            Builtin::Teleport => {
+                let expected = "a target followed by the vec3 to teleport it to";
+
+                if arg_nodes.len() != 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                if !self.target_arg(&arg_nodes[0]).is_some_and(|target| builtin::is_valid_target(&target)) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                self.visit_typed_arg(&arg_nodes[1], "position", self.types.vec3());
+                self.types.none()
+            }
+            Builtin::Positioned => {
+                let expected = "a vec3 followed by a function";
+
+                if arg_nodes.len() != 2 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                self.visit_typed_arg(&arg_nodes[0], "position", self.types.vec3());
+                self.visit_callback_arg(builtin, &arg_nodes[1], expected);
+                self.types.none()
+            }
+            Builtin::Summon => {
+                let expected = "an entity type and a vec3, optionally followed by the entity's data";
+
+                if !(2..=3).contains(&arg_nodes.len()) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.selector();
+                }
+
+                match arg_nodes[0].unwrap_expression().kind() {
+                    ParserNodeKind::StringLiteral(entity) if builtin::is_valid_resource_location(entity) => {
+                        if let Some(registries) = self.registries {
+                            if !registries.contains(RegistryKind::EntityType, entity) {
+                                self.errors.add(
+                                    ValidationErrorKind::UnknownRegistryEntry {
+                                        registry: RegistryKind::EntityType,
+                                        id: registry::normalize_id(entity),
+                                    },
+                                    arg_nodes[0].span(),
+                                );
+                            }
+                        }
+                    }
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        arg_nodes[0].span(),
+                    ),
+                }
+
+                self.visit_typed_arg(&arg_nodes[1], "position", self.types.vec3());
+
+                if let Some(data) = arg_nodes.get(2) {
+                    let valid = match data.unwrap_expression().kind() {
+                        // the tag that the entity is found by goes in its Tags, which would be replaced by the data's
+                        ParserNodeKind::StringLiteral(data) => {
+                            data.starts_with('{') && data.ends_with('}') && !data.contains("Tags:")
+                        }
+                        _ => false,
+                    };
+
+                    if !valid {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidBuiltinArguments {
+                                builtin,
+                                expected: "the entity's data as a compound without Tags, e.g \"{NoAI:1b}\"",
+                            },
+                            data.span(),
+                        );
+                    }
+                }
+
+                let tag = format!("{}.summon{}", self.pack_name, self.tags.summon_tags.len());
+
+                self.tags.tag_selector(node, format!("@e[tag={},limit=1]", tag));
+                self.tags.tag_summon(node, tag);
+                self.types.selector()
+            }
+            Builtin::Emit => {
+                let expected = "a message, e.g Damage(5)";
+
+                if arg_nodes.len() != 1 {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return self.types.none();
+                }
+
+                let ty = self.visit_node(&arg_nodes[0]);
+
+                if ty != self.types.unknown() && !self.messages.contains(&ty) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        arg_nodes[0].span(),
+                    );
+                }
+
+                self.types.none()
+            }
+            Builtin::UnforceloadAll => {
+                if !arg_nodes.is_empty() {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected: "no arguments" },
+                        node.span(),
+                    );
+                }
+
+                self.types.none()
+            }
+            // text components only make sense as part of a builtin that shows text
+            _ => {
+                self.errors.add(
+                    ValidationErrorKind::UnexpectedTextComponent(builtin),
+                    node.span(),
+                );
+
+                self.types.none()
+            }
+        }
+    }
+
+    // Tables are indexed by name, while anything else that is indexed has to be an array
+    fn visit_index(&mut self, expr: &'a ParserNode, index: &'a ParserNode) -> TypeKey {
+        let index_type = self.visit_node(index);
+
+        if index_type != self.types.int() && index_type != self.types.unknown() {
+            self.errors.add(ValidationErrorKind::IndexTypeMismatch(index_type), index.span());
+        }
+
+        let name = match expr.unwrap_expression().kind() {
+            ParserNodeKind::Identifier(name) if self.tables.contains_key(name) => name,
+            _ => {
+                let ty = self.visit_node(expr);
+
+                return match self.types.element_of(ty) {
+                    Some(element) => element,
+                    None => {
+                        if ty != self.types.unknown() {
+                            self.errors.add(ValidationErrorKind::NotIndexable(ty), expr.span());
+                        }
+
+                        self.types.unknown()
+                    }
+                };
+            }
+        };
+
+        let len = self.tables[name].len();
+
+        if let ParserNodeKind::NumberLiteral(n) = index.unwrap_expression().kind() {
+            if *n < 0 || *n as usize >= len {
+                self.errors.add(
+                    ValidationErrorKind::TableIndexOutOfBounds {
+                        table: name.clone(),
+                        index: *n,
+                        len,
+                    },
+                    index.span(),
+                );
+            }
+        }
+
+        self.types.int()
+    }
+
+    // The type of an array literal comes from its first element, so an empty one has no type
+    fn visit_array_literal(&mut self, node: &'a ParserNode, elements: &'a [ParserNode]) -> TypeKey {
+        let element_types = elements.iter().map(|element| self.visit_node(element)).collect::<Vec<TypeKey>>();
+
+        let element_type = match element_types.first() {
+            Some(ty) if *ty == self.types.unknown() => return self.types.unknown(),
+            Some(ty) => *ty,
+            None => {
+                self.errors.add(ValidationErrorKind::EmptyArrayLiteral, node.span());
+                return self.types.unknown();
+            }
+        };
+
+        let array_type = match self.types.array_of(element_type) {
+            Some(array_type) => array_type,
+            None => {
+                self.errors.add(ValidationErrorKind::UnsupportedArrayElement(element_type), elements[0].span());
+                return self.types.unknown();
+            }
+        };
+
+        for (element, ty) in elements.iter().zip(element_types).skip(1) {
+            if ty != element_type && ty != self.types.unknown() {
+                self.errors.add(
+                    ValidationErrorKind::ArrayElementTypeMismatch {
+                        expected: element_type,
+                        actual: ty,
+                    },
+                    element.span(),
+                );
+            }
+        }
+
+        array_type
+    }
+
+    fn visit_tuple(&mut self, elements: &'a [ParserNode]) -> TypeKey {
+        let mut element_types = Vec::new();
+
+        for element in elements {
+            let ty = self.visit_node(element);
+
+            if ty == self.types.unknown() {
+                return ty;
+            }
+
+            if ty == self.types.none() {
+                self.errors.add(ValidationErrorKind::UnsupportedTupleElement(ty), element.span());
+                return self.types.unknown();
+            }
+
+            element_types.push(ty);
+        }
+
+        self.types.tuple_of(element_types)
+    }
+
+    // Declares a variable for each element of a tuple, e.g `let (a, b) = f();`
+    fn visit_tuple_declaration(&mut self, node: &'a ParserNode, names: &[String], expr: &'a ParserNode) {
+        let expr_type = self.visit_node(expr);
+
+        let element_types = match expr_type.from(&self.types) {
+            SculkType::Tuple(def) if def.elements().len() == names.len() => def.elements().to_vec(),
+            SculkType::Tuple(_) => {
+                self.errors.add(
+                    ValidationErrorKind::TupleLengthMismatch {
+                        expected: names.len(),
+                        actual: expr_type,
+                    },
+                    expr.span(),
+                );
+
+                vec![self.types.unknown(); names.len()]
+            }
+            SculkType::Unknown => vec![self.types.unknown(); names.len()],
+            _ => {
+                self.errors.add(ValidationErrorKind::NotATuple(expr_type), expr.span());
+                vec![self.types.unknown(); names.len()]
+            }
+        };
+
+        for (name, ty) in names.iter().zip(element_types) {
+            if name == "_" {
+                continue;
+            }
+
+            if self.scope_stack.variable_exists(name) {
+                self.errors.add(
+                    ValidationErrorKind::VariableAlreadyDefined(name.to_string()),
+                    node.span(),
+                );
+            }
+
+            self.scope_stack.register_variable(name.to_string(), ty);
+        }
+    }
+
+    // Checks an assignment to an element of an array, e.g `a[i] = 5;`, where the array itself has to be assignable
+    fn visit_element_assignment(
+        &mut self,
+        node: &'a ParserNode,
+        array: &'a ParserNode,
+        index: &'a ParserNode,
+        expr: &'a ParserNode,
+    ) {
+        let index_type = self.visit_node(index);
+
+        if index_type != self.types.int() && index_type != self.types.unknown() {
+            self.errors.add(ValidationErrorKind::IndexTypeMismatch(index_type), index.span());
+        }
+
+        let expr_type = self.visit_node(expr);
+
+        // tables are only ever written when the pack loads
+        if matches!(array.kind(), ParserNodeKind::Identifier(name) if self.tables.contains_key(name)) {
+            self.errors.add(ValidationErrorKind::NotAssignable, node.span());
+            return;
+        }
+
+        let resolution = match self.resolver().resolve(array) {
+            Ok(resolution) => resolution,
+            Err(err) => {
+                self.errors.add(ValidationErrorKind::CouldNotResolve(err), array.span());
+                return;
+            }
+        };
+
+        let array_type = match resolution.find_assignable_type(&self.types) {
+            Some(ty) => ty,
+            None => {
+                self.errors.add(ValidationErrorKind::NotAssignable, node.span());
+                return;
+            }
+        };
+
+        match self.types.element_of(array_type) {
+            Some(element) if element != expr_type => self.errors.add(
+                ValidationErrorKind::VariableAssignmentTypeMismatch {
+                    expected: element,
+                    actual: expr_type,
+                    expr_span: expr.span(),
+                },
+                node.span(),
+            ),
+            Some(_) => {}
+            None => self.errors.add(ValidationErrorKind::NotIndexable(array_type), array.span()),
+        }
+
+        self.tags.tag_resolution(array, resolution);
+    }
+
+    // The target of a builtin, which is either a string like "@a[tag=x]" or a player name, or a selector value
+    // The first argument of print() is its target if it's a selector, or a string that starts with @ even if it's not a
+    // valid selector. Any other string is only the target if more text follows it and it's a player name, so that
+    // print("Steve", ...) is still sent to Steve while print("hello") is sent to every player
+    fn has_print_target(&self, args: &[ParserNode]) -> bool {
+        match args.first().map(|first| first.unwrap_expression().kind()) {
+            Some(ParserNodeKind::StringLiteral(first)) => {
+                first.starts_with('@') || (args.len() >= 2 && builtin::is_valid_target(first))
+            }
+            Some(ParserNodeKind::SelectorLiteral(_)) => true,
+            Some(ParserNodeKind::Identifier(name)) => self.scope_stack.find_variable_type(name) == Some(self.types.selector()),
+            _ => false,
+        }
+    }
+
+    fn target_arg(&mut self, arg: &'a ParserNode) -> Option<String> {
+        let arg = arg.unwrap_expression();
+
+        match arg.kind() {
+            ParserNodeKind::StringLiteral(target) => Some(target.clone()),
+            ParserNodeKind::SelectorLiteral(_) => {
+                self.visit_node(arg);
+                self.tags.find_selector(arg).map(str::to_string)
+            }
+            ParserNodeKind::Identifier(name) if self.scope_stack.find_variable_type(name) == Some(self.types.selector()) => {
+                self.visit_node(arg);
+                self.tags.find_selector(arg).map(str::to_string)
+            }
+            _ => None,
+        }
+    }
+
+    // visits a part of the text given to a builtin like print(). Parts are either strings, text component builders
+    // such as color("red", ...), or int, bool and string expressions, which are shown through a score or nbt component.
+    // Selectors are shown as the names of the entities they select, and text values as the text they hold
+    fn visit_text_component(&mut self, arg: &'a ParserNode) {
+        let part = arg.unwrap_expression();
+
+        match part.kind() {
+            ParserNodeKind::StringLiteral(_) => return,
+            ParserNodeKind::FunctionCall { args, .. } => {
+                if let Ok(resolution) = self.resolver().resolve(part) {
+                    if let ResolvedPart::Builtin(builtin) = resolution.last() {
+                        if builtin.is_text_component() {
+                            let builtin = *builtin;
+
+                            self.tags.tag_resolution(part, resolution);
+                            self.visit_text_builder(builtin, part, args);
+                            return;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let ty = self.visit_node(arg);
+
+        // text variables that aren't known were already reported where they're named
+        if ty == self.types.text() {
+            if !self.tags.is_text(part) && !matches!(part.kind(), ParserNodeKind::Identifier(_)) {
+                self.errors.add(ValidationErrorKind::UnknownText, arg.span());
+            }
+        } else if ty != self.types.int()
+            && ty != self.types.bool()
+            && ty != self.types.string()
+            && ty != self.types.selector()
+            && ty != self.types.unknown()
+        {
+            self.errors.add(ValidationErrorKind::TextComponentTypeMismatch(ty), arg.span());
+        }
+    }
+
+    fn visit_text_builder(&mut self, builtin: Builtin, node: &'a ParserNode, arg_nodes: &'a [ParserNode]) {
+        if let Builtin::ClickTrigger | Builtin::ClickFunction = builtin {
+            self.visit_click_callback(builtin, node, arg_nodes);
+            return;
+        }
+
+        if let Builtin::Score = builtin {
+            match arg_nodes {
+                [value] => self.visit_int_arg(value, "value"),
+                _ => self.errors.add(
+                    ValidationErrorKind::InvalidBuiltinArguments { builtin, expected: "an int to show the score of" },
+                    node.span(),
+                ),
+            }
+
+            return;
+        }
+
+        // every builder takes an optional string followed by a number of text components
+        let (string, components, expected) = match builtin {
+            Builtin::Color => (Some("a color name or hex code"), 1, "a color followed by a text component"),
+            Builtin::Bold | Builtin::Italic | Builtin::Underlined => (None, 1, "a text component"),
+            Builtin::Hover => (None, 2, "the text to show on hover followed by a text component"),
+            Builtin::ClickRun | Builtin::ClickSuggest => (Some("a command"), 1, "a command followed by a text component"),
+            Builtin::ClickUrl => (Some("an http or https url"), 1, "a url followed by a text component"),
+            Builtin::Selector => (Some("a selector or player name"), 0, "a selector"),
+            _ => unreachable!(),
+        };
+
+        if arg_nodes.len() != string.is_some() as usize + components {
+            self.errors.add(
+                ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                node.span(),
+            );
+            return;
+        }
+
+        if let Some(expected_string) = string {
+            let value = match (builtin, arg_nodes[0].unwrap_expression().kind()) {
+                (Builtin::Selector, _) => self.target_arg(&arg_nodes[0]),
+                (_, ParserNodeKind::StringLiteral(value)) => Some(value.clone()),
+                _ => None,
+            };
+
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        node.span(),
+                    );
+                    return;
+                }
+            };
+
+            let valid = match builtin {
+                Builtin::Color => builtin::is_valid_color(&value),
+                Builtin::ClickRun | Builtin::ClickSuggest => !value.trim().is_empty(),
+                Builtin::ClickUrl => value.starts_with("http://") || value.starts_with("https://"),
+                Builtin::Selector => builtin::is_valid_target(&value),
+                _ => unreachable!(),
+            };
+
+            if !valid {
+                self.errors.add(
+                    ValidationErrorKind::InvalidBuiltinValue {
+                        value,
+                        expected: expected_string,
+                    },
+                    arg_nodes[0].span(),
+                );
+            }
+        }
+
+        for arg in &arg_nodes[string.is_some() as usize..] {
+            self.visit_text_component(arg);
+        }
+    }
+
+    // visits the arguments of click_trigger() and click_function(), the first of which names the function to run on click
+    fn visit_click_callback(&mut self, builtin: Builtin, node: &'a ParserNode, arg_nodes: &'a [ParserNode]) {
+        let expected = "a function followed by a text component";
+
+        let callback = match arg_nodes {
+            [callback, _] => callback,
+            _ => {
+                self.errors.add(
+                    ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                    node.span(),
+                );
+                return;
+            }
+        };
+
+        self.visit_callback_arg(builtin, callback, expected);
+        self.visit_text_component(&arg_nodes[1]);
+    }
+
+    // visits a builtin argument that names a function to run, which has to be one without parameters
+    fn visit_callback_arg(&mut self, builtin: Builtin, callback: &'a ParserNode, expected: &'static str) {
+        let callback = callback.unwrap_expression();
+
+        let name = match callback.kind() {
+            ParserNodeKind::Identifier(name) => name,
+            _ => {
+                self.errors.add(
+                    ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                    callback.span(),
+                );
+                return;
+            }
+        };
+
+        match self.resolver().resolve(callback) {
+            Ok(resolution) => match resolution.last() {
+                ResolvedPart::GlobalFunction(_) => {
+                    match self.find_parameterless_overload(name) {
+                        Some(signature) if signature.name() != name => {
+                            self.tags.tag_function_name(callback, signature.name().to_string());
+                        }
+                        _ => {}
+                    }
+
+                    if self.find_parameterless_overload(name).is_none() {
+                        self.errors.add(
+                            ValidationErrorKind::CallbackWithParameters { name: name.clone(), builtin },
+                            callback.span(),
+                        );
+                    }
+
+                    self.tags.tag_resolution(callback, resolution);
+                }
+                _ => {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinArguments { builtin, expected },
+                        callback.span(),
+                    );
+                }
+            },
+            Err(err) => {
+                self.errors
+                    .add(ValidationErrorKind::CouldNotResolve(err), callback.span());
+            }
+        }
+    }
+
+    // visits a builtin argument that must be an int, such as the coordinate of a block
+    fn visit_int_arg(&mut self, arg: &'a ParserNode, name: &str) {
+        self.visit_typed_arg(arg, name, self.types.int());
+    }
+
+    fn visit_typed_arg(&mut self, arg: &'a ParserNode, name: &str, expected: TypeKey) {
+        let ty = self.visit_node(arg);
+
+        if ty != expected && ty != self.types.unknown() {
+            self.errors.add(
+                ValidationErrorKind::FunctionCallArgTypeMismatch {
+                    name: name.to_string(),
+                    expected,
+                    actual: ty,
+                },
+                arg.span(),
+            );
+        }
+    }
+
+    // visits a builtin argument that is either an int or a decimal number in a string, e.g "0.15"
+    fn visit_decimal_arg(&mut self, arg: &'a ParserNode, name: &str) {
+        match arg.unwrap_expression().kind() {
+            ParserNodeKind::StringLiteral(value) => {
+                if !builtin::is_valid_decimal(value) {
+                    self.errors.add(
+                        ValidationErrorKind::InvalidBuiltinValue {
+                            value: value.clone(),
+                            expected: "a decimal number such as \"0.15\"",
+                        },
+                        arg.span(),
+                    );
+                }
+            }
+            _ => self.visit_int_arg(arg, name),
+        }
+    }
+
+    // checks that a builtin argument names a variant of one of the enums that builtins take, such as Weather.
+    // The variants are checked by name rather than resolved, so they can't clash with variables
+    fn check_variant_arg(&mut self, arg: &ParserNode, kind: &'static str, variants: &'static [&'static str]) {
+        match arg.unwrap_expression().kind() {
+            ParserNodeKind::Identifier(name) if variants.contains(&name.as_str()) => {}
+            ParserNodeKind::Identifier(name) => self.errors.add(
+                ValidationErrorKind::UnknownVariant {
+                    kind,
+                    name: name.clone(),
+                    variants,
+                },
+                arg.span(),
+            ),
+            _ => self.errors.add(
+                ValidationErrorKind::ExpectedVariant { kind, variants },
+                arg.span(),
+            ),
+        }
+    }
+
+    // visits a builtin argument that must refer to a variable or field, such as the variables given to snapshot()
+    fn visit_assignable_arg(&mut self, arg: &'a ParserNode) {
+        let path = arg.unwrap_expression();
+
+        let resolution = match path.kind() {
+            ParserNodeKind::Identifier(_) | ParserNodeKind::MemberAccess { .. } => {
+                match self.resolver().resolve(path) {
+                    Ok(resolution) => resolution,
+                    Err(err) => {
+                        self.errors
+                            .add(ValidationErrorKind::CouldNotResolve(err), path.span());
+                        return;
+                    }
+                }
+            }
+            _ => {
+                self.errors.add(ValidationErrorKind::NotAssignable, arg.span());
+                return;
+            }
+        };
+
+        if resolution.find_assignable_type(&self.types).is_none() {
+            self.errors.add(ValidationErrorKind::NotAssignable, arg.span());
+            return;
+        }
+
+        self.check_unrolled_variable(path);
+
+        self.tags.tag_resolution(path, resolution);
+    }
+
+    fn resolver(&self) -> Resolver {
+        Resolver::new(
+            &self.pack_name,
+            &self.global_functions,
+            &self.overloads,
+            &self.generic_functions,
+            &self.player_variables,
+            &self.configs,
+            &self.types,
+            &self.scope_stack,
+        )
+    }
+
+    // checks the ids used in a command literal against the loaded registries, if there are any
+    fn check_registry_ids(&mut self, command: &str, command_start: usize) {
+        let registries = match self.registries {
+            Some(registries) => registries,
+            None => return,
+        };
+
+        for (kind, id, offset) in registry::find_command_ids(command) {
+            if !registries.contains(kind, id) {
+                self.errors.add(
+                    ValidationErrorKind::UnknownRegistryEntry {
+                        registry: kind,
+                        id: registry::normalize_id(id),
+                    },
+                    command_start + offset..command_start + offset + id.len(),
+                );
+            }
+        }
+    }
+
+    // checks that a command literal doesn't run any of the commands forbidden with --forbid
+    fn check_forbidden_commands(&mut self, command: &str, command_start: usize) {
+        for (name, offset) in safety::find_commands(command) {
+            if self.forbidden_commands.iter().any(|forbidden| forbidden == name) {
+                self.errors.add(
+                    ValidationErrorKind::ForbiddenCommand(name.to_string()),
+                    command_start + offset..command_start + offset + name.len(),
+                );
+            }
+        }
+    }
+
+    fn check_node_returns(&self, node: &ParserNode) -> bool {
+        match node.kind() {
+            ParserNodeKind::Return(_) => true,
+            ParserNodeKind::If {
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                self.check_node_returns(&body)
+                    && else_ifs
+                        .iter()
+                        .all(|(_, body)| self.check_node_returns(body))
+                    && else_body
+                        .as_ref()
+                        .map_or(false, |body| self.check_node_returns(&body))
+            }
+            ParserNodeKind::IfLet { body, else_body, .. } => {
+                self.check_node_returns(body) && else_body.as_ref().is_some_and(|body| self.check_node_returns(body))
+            }
+            // only a match with an arm that matches every value is sure to run one of its arms.
+            // Matches on enums have to cover every variant, so they're sure to run one as well
+            ParserNodeKind::Match { arms, .. } => {
+                arms.iter().any(|(pattern, _)| {
+                    pattern.matches_everything() || matches!(pattern, ArmPattern::Variant { .. })
+                }) && arms.iter().all(|(_, body)| self.check_node_returns(body))
+            }
+            ParserNodeKind::For { .. } => false,
+            ParserNodeKind::Block(nodes) => nodes.iter().any(|node| self.check_node_returns(node)),
+            _ => false,
+        }
+    }
+
+    // Every arm of a match on an enum has to name one of its variants, unless it's a _ arm,
+    // and the arms have to cover every variant between them
+    fn check_enum_arms(&mut self, node: &ParserNode, enum_type: TypeKey, arms: &[(ArmPattern, ParserNode)]) {
+        let enum_def = enum_type.from(&self.types).as_enum_def().unwrap();
+        let mut covered = Vec::new();
+        let mut errors = Vec::new();
+        let mut catch_all = false;
+
+        for (pattern, body) in arms {
+            match pattern {
+                // nothing is left for an arm after a _ arm, or for a variant that an earlier arm already matches
+                _ if catch_all => errors.push((ValidationErrorKind::UnreachableMatchArm(pattern.to_string()), body.span())),
+                ArmPattern::Variant { enum_name, variant } if covered.contains(&variant.as_str()) && enum_name == enum_def.name() => {
+                    errors.push((ValidationErrorKind::UnreachableMatchArm(pattern.to_string()), body.span()))
+                }
+                ArmPattern::Variant { enum_name, variant }
+                    if enum_name == enum_def.name() && enum_def.variant_value(variant).is_some() =>
+                {
+                    covered.push(variant.as_str());
+                }
+                ArmPattern::Variant { enum_name, variant } => errors.push((
+                    ValidationErrorKind::UnexpectedArmPattern {
+                        expected: enum_type,
+                        pattern: format!("{}.{}", enum_name, variant),
+                    },
+                    body.span(),
+                )),
+                ArmPattern::Range(range) if range.matches_everything() => catch_all = true,
+                ArmPattern::Range(range) => errors.push((
+                    ValidationErrorKind::UnexpectedArmPattern {
+                        expected: enum_type,
+                        pattern: range.to_string(),
+                    },
+                    body.span(),
+                )),
+            }
+        }
+
+        let missing = enum_def
+            .variants()
+            .iter()
+            .filter(|variant| !covered.contains(&variant.as_str()))
+            .cloned()
+            .collect::<Vec<String>>();
+
+        if !missing.is_empty() && !catch_all {
+            errors.push((ValidationErrorKind::NonExhaustiveMatch { ty: enum_type, missing }, node.span()));
+        }
+
+        for (error, span) in errors {
+            self.errors.add(error, span);
+        }
+    }
+
+    // should only be passed the contents of the root Program node
+    fn scan_enum_defs(&mut self, nodes: &[ParserNode]) {
+        for node in nodes {
+            let (name, variants) = match node.kind() {
+                ParserNodeKind::EnumDefinition { name, variants } => (name, variants),
+                _ => continue,
+            };
+
+            if self.types.has_type(name) {
+                self.errors.add(ValidationErrorKind::TypeAlreadyDefined(name.clone()), node.span());
+                continue;
+            }
+
+            for (i, variant) in variants.iter().enumerate() {
+                if variants[..i].contains(variant) {
+                    self.errors.add(
+                        ValidationErrorKind::VariantAlreadyDefined {
+                            enum_name: name.clone(),
+                            variant: variant.clone(),
+                        },
+                        node.span(),
+                    );
+                }
+            }
+
+            self.types.insert(name.clone(), SculkType::Enum(EnumDef::new(name.clone(), variants.clone())));
+        }
+    }
+
+    // should only be passed the contents of the root Program node
+    fn scan_struct_defs(&mut self, nodes: &[ParserNode]) {
+        // generic structs are only types once they're given type arguments, so their fields are kept as they were
+        // written until then, see TypePool::find_type
+        for node in nodes {
+            let (name, type_params, members) = match node.kind() {
+                ParserNodeKind::StructDefinition { name, type_params, members, .. } if !type_params.is_empty() => {
+                    (name, type_params, members)
+                }
+                _ => continue,
+            };
+
+            if self.types.has_type(name) || self.types.is_generic_struct(name) {
+                self.errors.add(ValidationErrorKind::StructAlreadyDefined(name.to_string()), node.span());
+                continue;
+            }
+
+            let mut fields: Vec<(String, String)> = Vec::new();
+
+            for member in members {
+                let (field, ty) = member.as_typed_identifier();
+
+                if fields.iter().any(|(existing, _)| existing == field) {
+                    self.errors.add(
+                        ValidationErrorKind::StructFieldAlreadyDefined {
+                            struct_name: name.to_string(),
+                            field_name: field.to_string(),
+                        },
+                        member.span(),
+                    );
+                    continue;
+                }
+
+                fields.push((field.to_string(), ty.to_string()));
+            }
+
+            self.types.add_generic_struct(name.clone(), type_params.clone(), fields);
+        }
+
+        let struct_defs = nodes
+            .iter()
+            .filter_map(|node| match node.kind() {
+                ParserNodeKind::StructDefinition { name, type_params, members, .. } if type_params.is_empty() => {
+                    Some((name, members.as_slice()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<(&String, &[ParserNode])>>();
+
+        // first pass to register empty struct definitions
+        for ((name, members), node) in struct_defs.iter().zip(nodes) {
+            if self.types.has_type(name) || self.types.is_generic_struct(name) {
+                self.errors.add(
+                    ValidationErrorKind::StructAlreadyDefined(name.to_string()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            self.types.insert(
+                name.to_string(),
+                SculkType::Struct(StructDef::new_empty(name.to_string())),
+            );
+        }
+
+        // second pass to fill in concrete struct types from the nodes
+        for (name, members) in &struct_defs {
+            for member in *members {
+                let struct_type = self.types.get_type_key(name).unwrap();
+
+                match member.kind() {
+                    ParserNodeKind::TypedIdentifier { name, ty } => {
+                        let field_type = self.types.find_type(ty);
+
+                        match field_type {
+                            Some(ty) => {
+                                match struct_type
+                                    .from_mut(&mut self.types)
+                                    .as_struct_def_mut()
+                                    .add_field(FieldDef::new(name.to_string(), ty))
+                                {
+                                    Ok(_) => {}
+                                    Err(_) => self.errors.add(
+                                        ValidationErrorKind::StructFieldAlreadyDefined {
+                                            struct_name: name.to_string(),
+                                            field_name: name.to_string(),
+                                        },
+                                        member.span(),
+                                    ),
+                                }
+                            }
+                            None => self.errors.add(
+                                ValidationErrorKind::UnknownType(ty.to_string()),
+                                member.span(),
+                            ),
+                        }
+                    }
+                    ParserNodeKind::FunctionDeclaration {
+                        name,
+                        args,
+                        return_ty,
+                        body,
+                        is_static,
+                        ..
+                    } => {
+                        let owner = match is_static {
+                            true => None,
+                            false => Some(struct_type),
+                        };
+
+                        let func_signature = self.create_func_def(owner, member);
+
+                        // a method is named after its struct, which every call to it goes through, and its self is copied
+                        // back after the call, which inlining doesn't do. Methods are always part of their struct
+                        let attributes = func_signature.attributes();
+                        let cfg = member.has_attribute("cfg");
+
+                        for (applied, name) in [
+                            (attributes.no_mangle, "no_mangle"),
+                            (attributes.weak, "weak"),
+                            (attributes.inline, "inline"),
+                            (cfg, "cfg"),
+                        ] {
+                            if applied {
+                                self.errors.add(
+                                    ValidationErrorKind::AttributeNotAllowed {
+                                        name: name.to_string(),
+                                        on: "methods",
+                                    },
+                                    member.span(),
+                                );
+                            }
+                        }
+
+                        match struct_type
+                            .from_mut(&mut self.types)
+                            .as_struct_def_mut()
+                            .add_function(func_signature)
+                        {
+                            Ok(_) => {}
+                            Err(_) => self.errors.add(
+                                ValidationErrorKind::FunctionAlreadyDefined(name.clone()),
+                                member.span(),
+                            ),
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        // third pass to check for self-referencing structs
         for ((name, fields), nodes) in struct_defs.into_iter().zip(nodes) {
             let struct_type = self.types.get_type_key(name).unwrap();
             let struct_def = struct_type.from(&self.types).as_struct_def();
 
-            if struct_def.self_referencing(&self.types) {
+            if struct_def.self_referencing(&self.types) {
+                self.errors.add(
+                    ValidationErrorKind::StructSelfReferences(struct_def.name().to_string()),
+                    nodes.span(),
+                );
+            }
+        }
+
+        // Finalize the type pool (calculates field offsets and assigns constructors)
+        self.types.finalize();
+
+        // messages are sent through storage by field name, so every field has to fit in a single int
+        for node in nodes {
+            let name = match node.kind() {
+                ParserNodeKind::StructDefinition { name, is_message: true, .. } => name,
+                _ => continue,
+            };
+
+            let message_type = self.types.get_type_key(name).unwrap();
+
+            let unsupported_fields = message_type
+                .from(&self.types)
+                .as_struct_def()
+                .fields()
+                .filter(|field| field.field_type() != self.types.int() && field.field_type() != self.types.bool())
+                .map(|field| field.name().to_string())
+                .collect::<Vec<String>>();
+
+            for field in unsupported_fields {
+                self.errors.add(
+                    ValidationErrorKind::UnsupportedMessageField {
+                        message: name.clone(),
+                        field,
+                    },
+                    node.span(),
+                );
+            }
+
+            self.messages.push(message_type);
+        }
+
+        // the fields of an attached struct are scores on its entities, which are told apart by their tags and type
+        for node in nodes {
+            let (name, selector) = match node.kind() {
+                ParserNodeKind::StructDefinition { name, entity: Some(selector), .. } => (name, selector),
+                _ => continue,
+            };
+
+            if let Err(reason) = builtin::check_selector(selector) {
+                self.errors.add(
+                    ValidationErrorKind::InvalidSelector {
+                        selector: selector.clone(),
+                        reason,
+                    },
+                    node.span(),
+                );
+            } else if !selector.starts_with("@e")
+                || builtin::selector_arguments(selector).is_empty()
+                || builtin::selector_arguments(selector).iter().any(|(key, _)| !matches!(*key, "tag" | "type"))
+            {
+                self.errors.add(
+                    ValidationErrorKind::InvalidBuiltinValue {
+                        value: selector.clone(),
+                        expected: "a selector of entities by their tags and type, e.g @e[tag=enemy]",
+                    },
+                    node.span(),
+                );
+            }
+
+            let struct_type = self.types.get_type_key(name).unwrap();
+
+            let unsupported_fields = struct_type
+                .from(&self.types)
+                .as_struct_def()
+                .fields()
+                .filter(|field| field.field_type() != self.types.int() && field.field_type() != self.types.bool())
+                .map(|field| field.name().to_string())
+                .collect::<Vec<String>>();
+
+            for field in unsupported_fields {
+                self.errors.add(
+                    ValidationErrorKind::UnsupportedEntityStructField {
+                        struct_name: name.clone(),
+                        field,
+                    },
+                    node.span(),
+                );
+            }
+
+            struct_type.from_mut(&mut self.types).as_struct_def_mut().set_entity(selector.clone());
+            self.entity_structs.push(struct_type);
+        }
+    }
+
+    // Traits are checked once the functions of structs are known, since a struct implements a trait by having a method
+    // with the same signature as each of the trait's methods. Should only be passed the contents of the root Program node
+    fn scan_trait_defs(&mut self, nodes: &[ParserNode]) {
+        for node in nodes {
+            let (name, methods) = match node.kind() {
+                ParserNodeKind::TraitDefinition { name, methods } => (name, methods),
+                _ => continue,
+            };
+
+            if self.traits.contains_key(name) {
+                self.errors.add(ValidationErrorKind::TraitAlreadyDefined(name.clone()), node.span());
+                continue;
+            }
+
+            let mut signatures: Vec<FunctionSignature> = Vec::new();
+
+            for method in methods {
+                let (method_name, args, return_ty) = match method.kind() {
+                    ParserNodeKind::MethodSignature { name, args, return_ty } => (name, args, return_ty),
+                    _ => unreachable!(),
+                };
+
+                if signatures.iter().any(|signature| signature.name() == method_name) {
+                    self.errors.add(ValidationErrorKind::FunctionAlreadyDefined(method_name.clone()), method.span());
+                    continue;
+                }
+
+                let mut params = Vec::new();
+
+                for arg in args {
+                    let (arg_name, ty) = arg.as_typed_identifier();
+
+                    match self.types.find_type(ty) {
+                        Some(ty) => params.push(ParamDef::new(arg_name.to_string(), ty)),
+                        None => self.errors.add(ValidationErrorKind::UnknownType(ty.to_string()), arg.span()),
+                    }
+                }
+
+                let return_type = match return_ty {
+                    Some(ty) => self.types.find_type(ty).unwrap_or_else(|| {
+                        self.errors.add(ValidationErrorKind::UnknownType(ty.to_string()), method.span());
+                        self.types.unknown()
+                    }),
+                    None => self.types.none(),
+                };
+
+                signatures.push(FunctionSignature::new(
+                    method_name.clone(),
+                    params,
+                    return_type,
+                    false,
+                    FunctionAttributes::default(),
+                ));
+            }
+
+            self.traits.insert(name.clone(), signatures);
+        }
+
+        for node in nodes {
+            let (name, traits) = match node.kind() {
+                ParserNodeKind::StructDefinition { name, traits, .. } if !traits.is_empty() => (name, traits),
+                _ => continue,
+            };
+
+            let struct_type = match self.types.get_type_key(name) {
+                Some(ty) => ty,
+                None => continue,
+            };
+
+            for trait_name in traits {
+                let methods = match self.traits.get(trait_name) {
+                    Some(methods) => methods,
+                    None => {
+                        self.errors.add(ValidationErrorKind::UnknownTrait(trait_name.clone()), node.span());
+                        continue;
+                    }
+                };
+
+                let struct_def = struct_type.from(&self.types).as_struct_def();
+                let mut implemented = true;
+
+                for method in methods {
+                    // the struct's method also takes self, which the trait's doesn't name
+                    let kind = match struct_def.function(method.name()) {
+                        None => ValidationErrorKind::MissingTraitMethod {
+                            struct_name: name.clone(),
+                            trait_name: trait_name.clone(),
+                            method: method.name().to_string(),
+                        },
+                        Some(function)
+                            if function.is_static()
+                                || function.return_type() != method.return_type()
+                                || !function.params()[1..].iter().map(ParamDef::param_type).eq(method.params().iter().map(ParamDef::param_type)) =>
+                        {
+                            ValidationErrorKind::TraitMethodMismatch {
+                                struct_name: name.clone(),
+                                trait_name: trait_name.clone(),
+                                method: method.name().to_string(),
+                            }
+                        }
+                        Some(_) => continue,
+                    };
+
+                    self.errors.add(kind, node.span());
+                    implemented = false;
+                }
+
+                if implemented {
+                    self.implementations.push((struct_type, trait_name.clone()));
+                }
+            }
+        }
+    }
+
+    // should only be passed the contents of the root Program node
+    // A function whose #[cfg] condition is false is left out as if it wasn't declared, so that several versions of a
+    // function can be declared for different targets. The condition can only use the TARGET constants
+    fn scan_cfg_conditions(&mut self, nodes: &'a [ParserNode]) {
+        for node in nodes {
+            let attributes = match node.kind() {
+                ParserNodeKind::FunctionDeclaration { attributes, .. } => attributes,
+                _ => continue,
+            };
+
+            for attribute in attributes {
+                let args = match attribute.kind() {
+                    ParserNodeKind::Attribute { name, args } if name == "cfg" => args,
+                    _ => continue,
+                };
+
+                let cond = match args.as_slice() {
+                    [cond] => cond,
+                    _ => {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: "cfg".to_string(),
+                                expected: "a condition",
+                            },
+                            attribute.span(),
+                        );
+                        continue;
+                    }
+                };
+
+                match consteval::eval(cond, &self.target) {
+                    Ok(Value::Bool(true)) => {}
+                    Ok(Value::Bool(false)) => self.tags.leave_out(node),
+                    Ok(value) => self.errors.add(
+                        ValidationErrorKind::CouldNotEvaluate(ConstEvalErrorKind::TypeMismatch {
+                            expected: "bool",
+                            actual: value.type_name(),
+                        }),
+                        cond.span(),
+                    ),
+                    Err(err) => self.errors.add(ValidationErrorKind::CouldNotEvaluate(err.kind), err.span),
+                }
+            }
+        }
+    }
+
+    fn scan_func_defs(&mut self, nodes: &'a [ParserNode]) {
+        let func_defs = nodes
+            .iter()
+            .filter(|node| match node.kind() {
+                ParserNodeKind::FunctionDeclaration { type_params, .. } => type_params.is_empty() && !self.tags.is_left_out(node),
+                _ => false,
+            })
+            .collect::<Vec<&ParserNode>>();
+
+        // generic functions are only added to the global functions once they're called, see Validator::instantiate
+        for node in nodes.iter().filter(|node| !self.tags.is_left_out(node)) {
+            let name = match node.kind() {
+                ParserNodeKind::FunctionDeclaration { name, type_params, .. } if !type_params.is_empty() => name,
+                _ => continue,
+            };
+
+            if let ParserNodeKind::FunctionDeclaration { bounds, attributes, .. } = node.kind() {
+                for (_, trait_name) in bounds.iter().filter(|(_, trait_name)| !self.traits.contains_key(trait_name)) {
+                    self.errors.add(ValidationErrorKind::UnknownTrait(trait_name.clone()), node.span());
+                }
+
+                // every instance would be given the same name, and instances aren't part of the program to be inlined from
+                for attribute in attributes {
+                    if let ParserNodeKind::Attribute { name, .. } = attribute.kind() {
+                        if name == "no_mangle" || name == "weak" || name == "inline" {
+                            self.errors.add(
+                                ValidationErrorKind::AttributeNotAllowed {
+                                    name: name.clone(),
+                                    on: "generic functions",
+                                },
+                                attribute.span(),
+                            );
+                        }
+                    }
+                }
+            }
+
+            if self.generic_functions.contains_key(name) || func_defs.iter().any(|func| func.as_func_name() == name) {
+                self.errors.add(ValidationErrorKind::FunctionAlreadyDefined(name.clone()), node.span());
+                continue;
+            }
+
+            if self.types.has_type(name) || self.types.is_generic_struct(name) {
+                self.errors.add(ValidationErrorKind::FunctionStructNameClash(name.clone()), node.span());
+            }
+
+            self.generic_functions.insert(name.clone(), node);
+        }
+
+        // weak functions come last, so that the functions replacing them are known by the time they're seen
+        let mut func_defs = func_defs;
+        func_defs.sort_by_key(|node| node.has_attribute("weak"));
+
+        let mut declared = HashMap::new();
+
+        for node in func_defs.iter().filter(|node| !node.has_attribute("weak")) {
+            *declared.entry(node.as_func_name()).or_insert(0) += 1;
+        }
+
+        let mut declarations: HashMap<ResourceLocation, Range<usize>> = HashMap::new();
+
+        for node in func_defs {
+            match node.kind() {
+                ParserNodeKind::FunctionDeclaration { name, .. } => {
+                    let mut func_signature = self.create_func_def(None, node);
+                    let pinned = func_signature.attributes().no_mangle || func_signature.attributes().weak;
+
+                    // overloads are told apart in the pack by the types of their parameters
+                    let mut pack_name = match declared.get(name.as_str()) {
+                        _ if pinned => name.clone(),
+                        Some(1) => name.clone(),
+                        _ => func_signature.overload_name(&self.types),
+                    };
+
+                    // and the functions of imported files by the modules they're in
+                    if let Some((module, _)) = self.modules.iter().find(|(_, span)| span.contains(&node.span().start)) {
+                        if !pinned {
+                            pack_name = format!("{}.{}", module, pack_name);
+                        }
+                    }
+
+                    if pack_name != *name {
+                        self.tags.tag_function_name(node, pack_name.clone());
+                        func_signature = func_signature.renamed(pack_name);
+                    }
+
+                    let location = ResourceLocation::new(self.pack_name.clone(), func_signature.name().to_string());
+
+                    // a weak function is left out of the pack when another function has its name, which has to be
+                    // called the same way. Two weak functions of the same name are only allowed if one replaces both
+                    if let Some(existing) = self.global_functions.get(&location).filter(|_| func_signature.attributes().weak) {
+                        let matches = existing.return_type() == func_signature.return_type()
+                            && existing.params().iter().map(|param| param.param_type()).eq(func_signature.params().iter().map(|param| param.param_type()));
+
+                        if existing.attributes().weak {
+                            self.errors.add(ValidationErrorKind::AmbiguousWeakFunction(name.clone()), node.span());
+                        } else if !matches {
+                            self.errors.add(ValidationErrorKind::WeakOverrideMismatch(name.clone()), declarations[&location].clone());
+                        }
+
+                        if let Some(existing) = self.global_functions.get_mut(&location) {
+                            existing.attributes_mut().replaces_weak = true;
+                        }
+
+                        self.tags.leave_out(node);
+                        continue;
+                    }
+
+                    if self.global_functions.contains_key(&location) {
+                        let kind = match func_signature.params() {
+                            [] => ValidationErrorKind::FunctionAlreadyDefined(name.clone()),
+                            params => ValidationErrorKind::OverloadAlreadyDefined {
+                                name: name.clone(),
+                                params: params.iter().map(|param| param.param_type()).collect(),
+                            },
+                        };
+
+                        self.errors.add(kind, node.span());
+                        continue;
+                    }
+
+                    if self.types.has_type(name) || self.types.is_generic_struct(name) {
+                        self.errors.add(
+                            ValidationErrorKind::FunctionStructNameClash(name.clone()),
+                            node.span(),
+                        );
+                    }
+
+                    if func_signature.attributes().inline {
+                        self.inline_functions.insert(func_signature.name().to_string(), node);
+                    }
+
+                    self.overloads.entry(name.clone()).or_default().push(location.clone());
+                    declarations.insert(location.clone(), node.span());
+                    self.global_functions.insert(location, func_signature);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // The signatures of every function declared with the given name, in the order they were declared
+    fn find_overloads(&self, name: &str) -> Vec<&FunctionSignature> {
+        self.overloads
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|location| &self.global_functions[location])
+            .collect()
+    }
+
+    // The overload of a function that takes no parameters, which is the one that's run when it's used as a callback or
+    // a sub-command. Its name in the pack is the name it was declared with
+    fn find_parameterless_overload(&self, name: &str) -> Option<&FunctionSignature> {
+        self.find_overloads(name).into_iter().find(|signature| signature.params().is_empty())
+    }
+
+    fn scan_player_variables(&mut self, nodes: &'a [ParserNode]) {
+        for node in nodes {
+            let (name, ty, default) = match node.kind() {
+                ParserNodeKind::PlayerVariableDeclaration { name, ty, default, display } => {
+                    if display.as_deref().is_some_and(|display| builtin::display_name_component(display).is_none()) {
+                        self.errors.add(ValidationErrorKind::InvalidDisplayName, node.span());
+                    }
+
+                    (name, ty, default)
+                }
+                _ => continue,
+            };
+
+            if self.player_variables.contains_key(name) {
+                self.errors.add(
+                    ValidationErrorKind::VariableAlreadyDefined(name.clone()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            if self.types.has_type(name)
+                || self.overloads.contains_key(name)
+            {
+                self.errors.add(
+                    ValidationErrorKind::PlayerVariableNameClash(name.clone()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            let ty = match self.types.find_type(ty) {
+                Some(ty) => ty,
+                None => {
+                    self.errors.add(ValidationErrorKind::UnknownType(ty.clone()), node.span());
+                    continue;
+                }
+            };
+
+            // every player variable is stored in a single score, so only single-slot values can be held
+            if ty != self.types.int() && ty != self.types.bool() {
+                self.errors.add(
+                    ValidationErrorKind::UnsupportedPlayerVariableType(ty),
+                    node.span(),
+                );
+                continue;
+            }
+
+            if let Some(default) = default {
+                let default_type = self.visit_node(default);
+
+                if default_type != ty {
+                    self.errors.add(
+                        ValidationErrorKind::VariableAssignmentTypeMismatch {
+                            expected: ty,
+                            actual: default_type,
+                            expr_span: default.span(),
+                        },
+                        node.span(),
+                    );
+                }
+            }
+
+            self.player_variables.insert(name.clone(), ty);
+        }
+    }
+
+    // Config values can be changed once the pack is loaded, so an assertion about them is checked against their defaults
+    fn check_static_assert(&mut self, node: &ParserNode, expr: &ParserNode, message: &str) {
+        let mut env = self.config_defaults.clone();
+        env.extend(self.target.clone());
+
+        match consteval::eval(expr, &env) {
+            Ok(Value::Bool(true)) => {}
+            Ok(Value::Bool(false)) => {
+                self.errors.add(ValidationErrorKind::StaticAssertFailed(message.to_string()), node.span());
+            }
+            Ok(value) => self.errors.add(
+                ValidationErrorKind::CouldNotEvaluate(ConstEvalErrorKind::TypeMismatch {
+                    expected: "bool",
+                    actual: value.type_name(),
+                }),
+                expr.span(),
+            ),
+            Err(err) => self.errors.add(ValidationErrorKind::CouldNotEvaluate(err.kind), err.span),
+        }
+    }
+
+    fn scan_configs(&mut self, nodes: &'a [ParserNode]) {
+        for node in nodes {
+            let (name, ty, default) = match node.kind() {
+                ParserNodeKind::Config { name, ty, default } => (name, ty, default),
+                _ => continue,
+            };
+
+            if self.configs.contains_key(name) {
+                self.errors.add(
+                    ValidationErrorKind::VariableAlreadyDefined(name.clone()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            if self.types.has_type(name)
+                || self.player_variables.contains_key(name)
+                || self.overloads.contains_key(name)
+            {
+                self.errors.add(
+                    ValidationErrorKind::ConfigNameClash(name.clone()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            let ty = match self.types.find_type(ty) {
+                Some(ty) => ty,
+                None => {
+                    self.errors.add(ValidationErrorKind::UnknownType(ty.clone()), node.span());
+                    continue;
+                }
+            };
+
+            // config values are copied from storage into a single score each
+            if ty != self.types.int() && ty != self.types.bool() {
+                self.errors.add(
+                    ValidationErrorKind::UnsupportedConfigType(ty),
+                    node.span(),
+                );
+                continue;
+            }
+
+            let default_type = self.visit_node(default);
+
+            if default_type != ty {
+                self.errors.add(
+                    ValidationErrorKind::VariableAssignmentTypeMismatch {
+                        expected: ty,
+                        actual: default_type,
+                        expr_span: default.span(),
+                    },
+                    node.span(),
+                );
+            }
+
+            if let Ok(value) = consteval::eval(default, &HashMap::new()) {
+                self.config_defaults.insert(name.clone(), value);
+            }
+
+            self.configs.insert(name.clone(), ty);
+        }
+    }
+
+    fn scan_tables(&mut self, nodes: &[ParserNode]) {
+        for node in nodes {
+            let (name, contents) = match node.kind() {
+                ParserNodeKind::Table { name, contents } => (name, contents),
+                _ => continue,
+            };
+
+            if self.tables.contains_key(name) {
+                self.errors.add(ValidationErrorKind::TableAlreadyDefined(name.clone()), node.span());
+                continue;
+            }
+
+            let len = match contents {
+                TableContents::Generated { len, .. } => *len,
+                TableContents::Listed(values) => values.len() as i32,
+            };
+
+            if !(1..=MAX_TABLE_LEN).contains(&len) {
+                self.errors.add(ValidationErrorKind::InvalidTableLength(len), node.span());
+                continue;
+            }
+
+            let values = match contents {
+                TableContents::Generated { index, len, expr } => (0..*len)
+                    .map(|i| consteval::eval(expr, &HashMap::from([(index.clone(), Value::Int(i))])).map(Value::as_score))
+                    .collect(),
+                TableContents::Listed(values) => values
+                    .iter()
+                    .map(|value| consteval::eval(value, &HashMap::new()).map(Value::as_score))
+                    .collect(),
+            };
+
+            match values {
+                Ok(values) => {
+                    self.tables.insert(name.clone(), values);
+                }
+                Err(err) => {
+                    self.errors.add(ValidationErrorKind::CouldNotEvaluate(err.kind), err.span);
+                    // the table is still registered so that reading from it doesn't cause more errors
+                    self.tables.insert(name.clone(), vec![0; len as usize]);
+                }
+            }
+        }
+    }
+
+    fn scan_state_machines(&mut self, nodes: &[ParserNode]) {
+        for node in nodes {
+            let (name, states, transitions) = match node.kind() {
+                ParserNodeKind::StateMachine { name, states, transitions } => (name, states, transitions),
+                _ => continue,
+            };
+
+            if self.state_machines.contains_key(name) {
+                self.errors.add(
+                    ValidationErrorKind::StateMachineAlreadyDefined(name.clone()),
+                    node.span(),
+                );
+                continue;
+            }
+
+            let mut state_names = Vec::new();
+
+            for state in states {
+                let state_name = match state.kind() {
+                    ParserNodeKind::State { name, .. } => name,
+                    _ => unreachable!(),
+                };
+
+                if state_names.contains(state_name) {
+                    self.errors.add(
+                        ValidationErrorKind::StateAlreadyDefined(state_name.clone()),
+                        state.span(),
+                    );
+                } else {
+                    state_names.push(state_name.clone());
+                }
+            }
+
+            if state_names.is_empty() {
+                self.errors.add(
+                    ValidationErrorKind::StateMachineWithoutStates(name.clone()),
+                    node.span(),
+                );
+            }
+
+            for transition in transitions {
+                let (from, to) = match transition.kind() {
+                    ParserNodeKind::StateTransition { from, to } => (from, to),
+                    _ => unreachable!(),
+                };
+
+                for state in [from, to] {
+                    if !state_names.contains(state) {
+                        self.errors.add(
+                            ValidationErrorKind::UnknownState {
+                                machine: name.clone(),
+                                state: state.clone(),
+                            },
+                            transition.span(),
+                        );
+                    }
+                }
+            }
+
+            self.state_machines.insert(name.clone(), state_names);
+        }
+    }
+
+    fn create_func_def(&mut self, owner: Option<TypeKey>, func: &ParserNode) -> FunctionSignature {
+        let (name, args, return_ty_str, is_static, is_public, attributes) = match func.kind() {
+            ParserNodeKind::FunctionDeclaration {
+                name,
+                args,
+                return_ty,
+                is_static,
+                is_public,
+                attributes,
+                ..
+            } => (name, args, return_ty, is_static, is_public, attributes),
+            _ => unreachable!(),
+        };
+
+        let mut arg_types = Vec::new();
+
+        for arg in args {
+            let (arg_name, arg_type_str) = arg.as_typed_identifier();
+            let arg_type = self.find_type(arg_type_str);
+
+            match arg_type {
+                Some(ty) => arg_types.push(ty),
+                None => {
+                    arg_types.push(self.types.unknown());
+                    self.errors.add(
+                        ValidationErrorKind::UnknownType(arg_type_str.to_string()),
+                        arg.span(),
+                    );
+                }
+            }
+        }
+
+        let return_type = match return_ty_str {
+            Some(return_ty_str) => match self.find_type(return_ty_str) {
+                Some(ty) => ty,
+                None => {
+                    self.errors.add(
+                        ValidationErrorKind::UnknownType(return_ty_str.to_string()),
+                        func.span(),
+                    );
+                    self.types.none()
+                }
+            },
+            None => self.types.none(),
+        };
+
+        // If owner is not None, then the first parameter must be named "self" and of type owner
+        let mut params = args
+            .iter()
+            .map(|arg| arg.as_identifier())
+            .zip(arg_types)
+            .map(|(name, ty)| ParamDef::new(name.to_string(), ty))
+            .collect::<Vec<ParamDef>>();
+
+        if let Some(owner) = owner {
+            params.insert(0, ParamDef::new("self".to_string(), owner));
+        }
+
+        let mut attributes = self.create_func_attributes(attributes);
+        attributes.public = *is_public;
+
+        // the game runs item abilities without any arguments
+        if attributes.on_use.is_some() && !params.is_empty() {
+            self.errors.add(
+                ValidationErrorKind::AttributeRequiresNoParameters("on_use".to_string()),
+                func.span(),
+            );
+        }
+
+        // nor does it give the functions in the load and tick tags any, and it runs them as the server without a prefix
+        if let Some(hook) = attributes.hook {
+            if !params.is_empty() {
+                self.errors.add(
+                    ValidationErrorKind::AttributeRequiresNoParameters(hook.tag_name().to_string()),
+                    func.span(),
+                );
+            }
+
+            if attributes.execute.is_some() {
+                self.errors.add(
+                    ValidationErrorKind::ConflictingAttributes(hook.tag_name().to_string(), "execute".to_string()),
+                    func.span(),
+                );
+            }
+        }
+
+        // damage handlers may take the amount of damage as their only parameter
+        if (attributes.on_damage_taken || attributes.on_damage_dealt)
+            && (params.len() > 1 || params.iter().any(|param| param.param_type() != self.types.int()))
+        {
+            self.errors.add(
+                ValidationErrorKind::InvalidDamageHandlerParameters,
+                func.span(),
+            );
+        }
+
+        // subscribers receive the message they're subscribed to as their only parameter
+        if attributes.subscribe && !matches!(params.as_slice(), [param] if self.messages.contains(&param.param_type())) {
+            self.errors.add(
+                ValidationErrorKind::InvalidSubscriberParameters,
+                func.span(),
+            );
+        }
+
+        FunctionSignature::new(name.clone(), params, return_type, *is_static, attributes)
+    }
+
+    // The only attribute a loop takes is #[spread(n)], which runs n of its iterations per tick and carries on with
+    // the rest on the next one. Since nothing can wait for it to finish, it has to be the last thing its function does
+    fn check_loop_attributes(&mut self, node: &'a ParserNode, attributes: &'a [ParserNode]) {
+        let mut seen = Vec::new();
+
+        for attribute in attributes {
+            let (name, args) = match attribute.kind() {
+                ParserNodeKind::Attribute { name, args } => (name, args),
+                _ => unreachable!(),
+            };
+
+            if seen.contains(&name) {
+                self.errors.add(
+                    ValidationErrorKind::AttributeAlreadyApplied(name.clone()),
+                    attribute.span(),
+                );
+                continue;
+            }
+
+            seen.push(name);
+
+            match name.as_str() {
+                "spread" => {
+                    match args.as_slice() {
+                        [arg] if matches!(arg.kind(), ParserNodeKind::NumberLiteral(n) if *n > 0) => {}
+                        _ => self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "the number of iterations to run per tick",
+                            },
+                            attribute.span(),
+                        ),
+                    }
+
+                    if !self.current_body.and_then(|body| body.last()).is_some_and(|tail| std::ptr::eq(tail, node)) {
+                        self.errors.add(ValidationErrorKind::SpreadLoopNotLast, attribute.span());
+                    }
+                }
+                "unroll" => {
+                    match args.as_slice() {
+                        [] => {}
+                        [arg] if matches!(arg.kind(), ParserNodeKind::NumberLiteral(n) if *n > 0) => {}
+                        _ => self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "nothing, or the number of iterations to put in each function",
+                            },
+                            attribute.span(),
+                        ),
+                    }
+
+                    if let ParserNodeKind::For { init, cond, step, .. } = node.kind() {
+                        if consteval::trip_count(init, cond, step).is_none() {
+                            self.errors.add(ValidationErrorKind::CannotUnrollLoop, attribute.span());
+                        }
+                    }
+                }
+                _ => self.errors.add(
+                    ValidationErrorKind::UnknownAttribute(name.clone()),
+                    attribute.span(),
+                ),
+            }
+        }
+
+        if seen.iter().any(|name| *name == "spread") && seen.iter().any(|name| *name == "unroll") {
+            self.errors.add(
+                ValidationErrorKind::ConflictingAttributes("spread".to_string(), "unroll".to_string()),
+                node.span(),
+            );
+        }
+    }
+
+    // Assigning to the variable of an unrolled loop would change how many times it runs, which was already decided
+    fn check_unrolled_variable(&mut self, path: &ParserNode) {
+        if let ParserNodeKind::Identifier(name) = path.unwrap_expression().kind() {
+            if self.scope_stack.is_unrolled_variable(name) {
+                self.errors.add(ValidationErrorKind::UnrolledVariableAssigned(name.clone()), path.span());
+            }
+        }
+    }
+
+    fn create_func_attributes(&mut self, attributes: &[ParserNode]) -> FunctionAttributes {
+        let mut func_attributes = FunctionAttributes::default();
+        let mut seen = Vec::new();
+
+        for attribute in attributes {
+            let (name, args) = match attribute.kind() {
+                ParserNodeKind::Attribute { name, args } => (name, args),
+                _ => unreachable!(),
+            };
+
+            if seen.contains(&name) {
                 self.errors.add(
-                    ValidationErrorKind::StructSelfReferences(struct_def.name().to_string()),
-                    nodes.span(),
+                    ValidationErrorKind::AttributeAlreadyApplied(name.clone()),
+                    attribute.span(),
                 );
+                continue;
             }
-        }
 
-        // Finalize the type pool (calculates field offsets and assigns constructors)
-        self.types.finalize();
-    }
+            seen.push(name);
 
-    // should only be passed the contents of the root Program node
-    fn scan_func_defs(&mut self, nodes: &[ParserNode]) {
-        let func_defs = nodes
-            .iter()
-            .filter(|node| match node.kind() {
-                ParserNodeKind::FunctionDeclaration { .. } => true,
-                _ => false,
-            })
-            .collect::<Vec<&ParserNode>>();
+            match name.as_str() {
+                "execute" => match args.as_slice() {
+                    [arg] => match arg.kind() {
+                        ParserNodeKind::StringLiteral(prefix) if !prefix.trim().is_empty() => {
+                            // the first word is a subcommand, but a `run` in the prefix would get around --forbid
+                            for (command, _) in safety::find_commands(prefix).into_iter().skip(1) {
+                                if self.forbidden_commands.iter().any(|forbidden| forbidden == command) {
+                                    self.errors.add(ValidationErrorKind::ForbiddenCommand(command.to_string()), arg.span());
+                                }
+                            }
 
-        for node in func_defs {
-            match node.kind() {
-                ParserNodeKind::FunctionDeclaration {
-                    name,
-                    args,
-                    return_ty: return_ty_str,
-                    ..
-                } => {
-                    let func_signature = self.create_func_def(None, node);
-
-                    if self
-                        .global_functions
-                        .contains_key(&ResourceLocation::new(self.pack_name.clone(), name.clone()))
-                    {
+                            func_attributes.execute = Some(prefix.trim().to_string());
+                        }
+                        _ => self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "a non-empty string of execute subcommands",
+                            },
+                            arg.span(),
+                        ),
+                    },
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidAttributeArguments {
+                            name: name.clone(),
+                            expected: "a non-empty string of execute subcommands",
+                        },
+                        attribute.span(),
+                    ),
+                },
+                "on_use" => match args.as_slice() {
+                    [arg] => match arg.kind() {
+                        ParserNodeKind::NamedArgument { name: arg_name, value } if arg_name == "item" => {
+                            match value.kind() {
+                                ParserNodeKind::StringLiteral(item) if builtin::is_valid_resource_location(item) => {
+                                    if let Some(registries) = self.registries {
+                                        if !registries.contains(RegistryKind::Item, item) {
+                                            self.errors.add(
+                                                ValidationErrorKind::UnknownRegistryEntry {
+                                                    registry: RegistryKind::Item,
+                                                    id: registry::normalize_id(item),
+                                                },
+                                                value.span(),
+                                            );
+                                        }
+                                    }
+
+                                    func_attributes.on_use = Some(registry::normalize_id(item));
+                                }
+                                _ => self.errors.add(
+                                    ValidationErrorKind::InvalidAttributeArguments {
+                                        name: name.clone(),
+                                        expected: "an item id such as \"minecraft:carrot_on_a_stick\"",
+                                    },
+                                    value.span(),
+                                ),
+                            }
+                        }
+                        _ => self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "item = \"<item id>\"",
+                            },
+                            arg.span(),
+                        ),
+                    },
+                    _ => self.errors.add(
+                        ValidationErrorKind::InvalidAttributeArguments {
+                            name: name.clone(),
+                            expected: "item = \"<item id>\"",
+                        },
+                        attribute.span(),
+                    ),
+                },
+                "on_damage_taken" | "on_damage_dealt" | "subscribe" | "no_mangle" | "weak" | "inline" => {
+                    if !args.is_empty() {
                         self.errors.add(
-                            ValidationErrorKind::FunctionAlreadyDefined(name.clone()),
-                            node.span(),
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "no arguments",
+                            },
+                            attribute.span(),
+                        );
+                    }
+
+                    match name.as_str() {
+                        "on_damage_taken" => func_attributes.on_damage_taken = true,
+                        "on_damage_dealt" => func_attributes.on_damage_dealt = true,
+                        "no_mangle" => func_attributes.no_mangle = true,
+                        "weak" => func_attributes.weak = true,
+                        "inline" => func_attributes.inline = true,
+                        _ => func_attributes.subscribe = true,
+                    }
+                }
+                "load" | "tick" => {
+                    if !args.is_empty() {
+                        self.errors.add(
+                            ValidationErrorKind::InvalidAttributeArguments {
+                                name: name.clone(),
+                                expected: "no arguments",
+                            },
+                            attribute.span(),
                         );
                     }
 
-                    if self.types.has_type(&name) {
+                    // a function can only be in one of the tags, as the load tag runs before the first tick anyway
+                    if func_attributes.hook.is_some() {
                         self.errors.add(
-                            ValidationErrorKind::FunctionStructNameClash(name.clone()),
-                            node.span(),
+                            ValidationErrorKind::ConflictingAttributes("load".to_string(), "tick".to_string()),
+                            attribute.span(),
                         );
+                        continue;
                     }
 
-                    self.global_functions.insert(
-                        ResourceLocation::new(self.pack_name.clone(), name.clone()),
-                        func_signature,
-                    );
+                    func_attributes.hook = Some(match name.as_str() {
+                        "load" => FunctionHook::Load,
+                        _ => FunctionHook::Tick,
+                    });
                 }
-                _ => unreachable!(),
+                // the condition was checked before the functions were scanned, see Validator::scan_cfg_conditions
+                "cfg" => {}
+                "require_op" | "permission" => {
+                    let tag = match (name.as_str(), args.as_slice()) {
+                        ("require_op", []) => Some(format!("{}.admin", self.pack_name)),
+                        ("permission", [arg]) => match arg.kind() {
+                            ParserNodeKind::StringLiteral(tag) if builtin::is_valid_tag(tag) => Some(tag.clone()),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+
+                    let tag = match tag {
+                        Some(tag) => tag,
+                        None => {
+                            self.errors.add(
+                                ValidationErrorKind::InvalidAttributeArguments {
+                                    name: name.clone(),
+                                    expected: match name.as_str() {
+                                        "require_op" => "no arguments",
+                                        _ => "the tag that is required, e.g \"vip\"",
+                                    },
+                                },
+                                attribute.span(),
+                            );
+                            continue;
+                        }
+                    };
+
+                    // a function can only check for a single tag
+                    if func_attributes.permission.is_some() {
+                        self.errors.add(
+                            ValidationErrorKind::ConflictingAttributes("require_op".to_string(), "permission".to_string()),
+                            attribute.span(),
+                        );
+                        continue;
+                    }
+
+                    func_attributes.permission = Some(tag);
+                }
+                _ => self.errors.add(
+                    ValidationErrorKind::UnknownAttribute(name.clone()),
+                    attribute.span(),
+                ),
             }
         }
+
+        func_attributes
     }
+}
 
-    fn create_func_def(&mut self, owner: Option<TypeKey>, func: &ParserNode) -> FunctionSignature {
-        let (name, args, return_ty_str, is_static) = match func.kind() {
-            ParserNodeKind::FunctionDeclaration {
-                name,
-                args,
-                return_ty,
-                is_static,
-                ..
-            } => (name, args, return_ty, is_static),
-            _ => unreachable!(),
-        };
+// Binds the type parameters in the name of a parameter's type to the parts of the argument's type that they're in the
+// place of, e.g `T[]` given `int[]` binds T to int. A type parameter that's bound already keeps its type, so an argument
+// of another type is reported when the call is checked against the instance
+/// Whether --loop-budget limits a for loop. A #[spread] loop limits itself, and a loop that's unrolled completely is no
+/// longer a loop once it's compiled
+pub(super) fn is_budgeted_loop(node: &ParserNode) -> bool {
+    let ParserNodeKind::For { attributes, init, cond, step, .. } = node.kind() else {
+        return false;
+    };
 
-        let mut arg_types = Vec::new();
+    if node.has_attribute("spread") {
+        return false;
+    }
 
-        for arg in args {
-            let (arg_name, arg_type_str) = arg.as_typed_identifier();
-            let arg_type = self.types.get_type_key(arg_type_str);
+    let unroll = attributes.iter().find_map(|attribute| match attribute.kind() {
+        ParserNodeKind::Attribute { name, args } if name == "unroll" => Some(args.first().map(ParserNode::kind)),
+        _ => None,
+    });
 
-            match arg_type {
-                Some(ty) => arg_types.push(ty),
-                None => {
-                    arg_types.push(self.types.unknown());
-                    self.errors.add(
-                        ValidationErrorKind::UnknownType(arg_type_str.to_string()),
-                        arg.span(),
-                    );
-                }
-            }
+    match unroll {
+        None => true,
+        Some(Some(ParserNodeKind::NumberLiteral(per_block))) => {
+            consteval::trip_count(init, cond, step).is_some_and(|iterations| (*per_block as u32) < iterations)
         }
+        Some(_) => false,
+    }
+}
 
-        let return_type = match return_ty_str {
-            Some(return_ty_str) => match self.types.get_type_key(return_ty_str) {
-                Some(ty) => ty,
-                None => {
-                    self.errors.add(
-                        ValidationErrorKind::UnknownType(return_ty_str.to_string()),
-                        func.span(),
-                    );
-                    self.types.none()
-                }
-            },
-            None => self.types.none(),
-        };
+fn infer_type_arg(param: &str, actual: &str, type_params: &[String], bindings: &mut TypeBindings) {
+    if type_params.iter().any(|type_param| type_param == param) {
+        if !bindings.iter().any(|(bound, _)| bound == param) {
+            bindings.push((param.to_string(), actual.to_string()));
+        }
 
-        // If owner is not None, then the first parameter must be named "self" and of type owner
-        let mut params = args
-            .iter()
-            .map(|arg| arg.as_identifier())
-            .zip(arg_types)
-            .map(|(name, ty)| ParamDef::new(name.to_string(), ty))
-            .collect::<Vec<ParamDef>>();
+        return;
+    }
 
-        if let Some(owner) = owner {
-            params.insert(0, ParamDef::new("self".to_string(), owner));
+    // an optional parameter takes the type it wraps too, but null doesn't say what that is
+    if let Some(inner) = param.strip_suffix('?') {
+        if actual != "null" {
+            infer_type_arg(inner, actual.strip_suffix('?').unwrap_or(actual), type_params, bindings);
         }
 
-        FunctionSignature::new(name.clone(), params, return_type, *is_static)
+        return;
+    }
+
+    let (params, actuals) = match (param.strip_suffix("[]"), actual.strip_suffix("[]")) {
+        (Some(param), Some(actual)) => (vec![param], vec![actual]),
+        _ => match (param.split_once('<'), actual.split_once('<')) {
+            (Some((base, params)), Some((actual_base, actuals))) if base.trim() == actual_base => (
+                TypePool::split_list(params.strip_suffix('>').unwrap_or(params)),
+                TypePool::split_list(actuals.strip_suffix('>').unwrap_or(actuals)),
+            ),
+            _ => match (param.strip_prefix('(').and_then(|p| p.strip_suffix(')')), actual.strip_prefix('(').and_then(|a| a.strip_suffix(')'))) {
+                (Some(params), Some(actuals)) => (TypePool::split_list(params), TypePool::split_list(actuals)),
+                _ => return,
+            },
+        },
+    };
+
+    for (param, actual) in params.into_iter().zip(actuals) {
+        infer_type_arg(param, actual, type_params, bindings);
     }
 }
 
@@ -822,6 +4759,7 @@ pub struct ValidatorOutput<'a> {
     pub types: TypePool,
     pub errors: Vec<ValidationError>,
     pub tags: TagPool<'a>,
+    pub tables: IndexMap<String, Vec<i32>>,
 }
 
 #[derive(Clone, Debug)]
@@ -839,7 +4777,15 @@ impl ValidationError {
 #[derive(Clone, Debug)]
 pub enum ValidationErrorKind {
     CannotBreakOutsideLoop,
+    UnknownLoopLabel(String),
     ExpectedBoolInIf(TypeKey),
+    ExpectedBoolInConditional(TypeKey),
+    ConditionalTypeMismatch {
+        then: TypeKey,
+        otherwise: TypeKey,
+    },
+    VariableWithoutValue(String),
+    ExpectedIntInMatch(TypeKey),
     ExpectedBoolInForCondition(TypeKey),
     UnknownVariable(String),
     UnknownFunction(String),
@@ -875,6 +4821,7 @@ pub enum ValidationErrorKind {
         rhs: TypeKey,
         op: Operation,
     },
+    NotOperatorTypeMismatch(TypeKey),
     LogicalOperatorTypeMismatch {
         lhs: TypeKey,
         rhs: TypeKey,
@@ -884,12 +4831,46 @@ pub enum ValidationErrorKind {
         ty: TypeKey,
     },
     FunctionAlreadyDefined(String),
+    OverloadAlreadyDefined {
+        name: String,
+        params: Vec<TypeKey>,
+    },
+    NoMatchingOverload {
+        name: String,
+        actual: Vec<TypeKey>,
+    },
     FunctionStructNameClash(String),
     StructAlreadyDefined(String),
     StructFieldAlreadyDefined {
         struct_name: String,
         field_name: String,
     },
+    // a generic function or struct's type parameter that doesn't appear in the types of the arguments it's given
+    CannotInferTypeParameter {
+        name: String,
+        param: String,
+    },
+    InstantiationTooDeep(String),
+    TraitAlreadyDefined(String),
+    UnknownTrait(String),
+    // a struct that says it implements a trait without a method the trait has
+    MissingTraitMethod {
+        struct_name: String,
+        trait_name: String,
+        method: String,
+    },
+    // or with one whose parameters or return type aren't the ones of the trait's method
+    TraitMethodMismatch {
+        struct_name: String,
+        trait_name: String,
+        method: String,
+    },
+    // a generic function that's called with a type for a type parameter whose bounds the type doesn't implement
+    TraitNotImplemented {
+        ty: TypeKey,
+        trait_name: String,
+    },
+    GenericFunctionReference(String),
     AmbiguousCall(String),
     StructSelfReferences(String),
     UnknownType(String),
@@ -897,6 +4878,166 @@ pub enum ValidationErrorKind {
     CannotReferenceMethodAsValue,
     NotAssignable,
     StaticNotAllowed,
+    UnknownAttribute(String),
+    SpreadLoopNotLast,
+    AwaitNotInFunctionBody,
+    AwaitInValueFunction,
+    BudgetedLoopNotInFunctionBody,
+    BudgetedLoopInValueFunction,
+    AttributeAlreadyApplied(String),
+    ConflictingAttributes(String, String),
+    InvalidAttributeArguments {
+        name: String,
+        expected: &'static str,
+    },
+    UnknownRegistryEntry {
+        registry: RegistryKind,
+        id: String,
+    },
+    InvalidBuiltinArguments {
+        builtin: Builtin,
+        expected: &'static str,
+    },
+    InvalidStorageKey(String),
+    PlayerVariableNameClash(String),
+    UnsupportedPlayerVariableType(TypeKey),
+    InvalidDisplayName,
+    ConfigNameClash(String),
+    UnsupportedConfigType(TypeKey),
+    ConfigValueNotAssignable(String),
+    NoConfigValues,
+    InvalidTickInterval(i32),
+    StateMachineAlreadyDefined(String),
+    StateMachineWithoutStates(String),
+    StateAlreadyDefined(String),
+    UnknownStateMachine(String),
+    UnknownState {
+        machine: String,
+        state: String,
+    },
+    RegionAlreadyDefined(String),
+    InvalidBuiltinValue {
+        value: String,
+        expected: &'static str,
+    },
+    UnexpectedTextComponent(Builtin),
+    TextComponentTypeMismatch(TypeKey),
+    CallbackWithParameters {
+        name: String,
+        builtin: Builtin,
+    },
+    AttributeRequiresNoParameters(String),
+    AttributeNotAllowed {
+        name: String,
+        on: &'static str,
+    },
+    AmbiguousWeakFunction(String),
+    InvalidRunAs {
+        expected: &'static str,
+    },
+    WeakOverrideMismatch(String),
+    InvalidDamageHandlerParameters,
+    VolumeTooLarge(i64),
+    UnknownVariant {
+        kind: &'static str,
+        name: String,
+        variants: &'static [&'static str],
+    },
+    ExpectedVariant {
+        kind: &'static str,
+        variants: &'static [&'static str],
+    },
+    UnsupportedMessageField {
+        message: String,
+        field: String,
+    },
+    UnsupportedEntityStructField {
+        struct_name: String,
+        field: String,
+    },
+    ControlFlowInEntityLoop,
+    ControlFlowInInlineFunction,
+    CannotUnrollLoop,
+    BreakInUnrolledLoop,
+    UnrolledVariableAssigned(String),
+    OverloadedFunctionReference(String),
+    NoFunctionReferences(TypeKey),
+    EntityStructNotValue(String),
+    InvalidSubscriberParameters,
+    TableAlreadyDefined(String),
+    InvalidTableLength(i32),
+    CouldNotEvaluate(ConstEvalErrorKind),
+    NotIndexable(TypeKey),
+    IndexTypeMismatch(TypeKey),
+    EmptyArrayLiteral,
+    UnsupportedArrayElement(TypeKey),
+    UnsupportedTupleElement(TypeKey),
+    NotATuple(TypeKey),
+    TupleLengthMismatch {
+        expected: usize,
+        actual: TypeKey,
+    },
+    ArrayElementTypeMismatch {
+        expected: TypeKey,
+        actual: TypeKey,
+    },
+    TableIndexOutOfBounds {
+        table: String,
+        index: i32,
+        len: usize,
+    },
+    InvalidLongConversion,
+    InvalidCast {
+        from: TypeKey,
+        to: TypeKey,
+    },
+    ExpectedOptional(TypeKey),
+    CoalesceDefaultMismatch {
+        expected: TypeKey,
+        actual: TypeKey,
+    },
+    NullWithoutOptional,
+    InvalidSelector {
+        selector: String,
+        reason: String,
+    },
+    UnknownSelector(String),
+    SelectorNotAssignable,
+    UnknownText,
+    TextNotAssignable,
+    StaticAssertFailed(String),
+    CommandsAlreadyDefined,
+    SubcommandAlreadyDefined(String),
+    SubcommandWithParameters(String),
+    ForbiddenCommand(String),
+    TypeAlreadyDefined(String),
+    VariantAlreadyDefined {
+        enum_name: String,
+        variant: String,
+    },
+    UnexpectedArmPattern {
+        expected: TypeKey,
+        pattern: String,
+    },
+    NonExhaustiveMatch {
+        ty: TypeKey,
+        missing: Vec<String>,
+    },
+    UnreachableMatchArm(String),
+}
+
+// The tag and type arguments of a selector, with the namespaces of types filled in so that e.g type=zombie and
+// type=minecraft:zombie are the same
+fn selector_filters(selector: &str) -> Vec<(&str, String)> {
+    builtin::selector_arguments(selector)
+        .into_iter()
+        .filter(|(key, _)| matches!(*key, "tag" | "type"))
+        .map(|(key, value)| match (key, value.strip_prefix('!')) {
+            ("type", Some(id)) => (key, format!("!{}", registry::normalize_id(id))),
+            ("type", None) => (key, registry::normalize_id(value)),
+            _ => (key, value.to_string()),
+        })
+        .collect()
 }
 
 pub struct ScopeStack {
@@ -913,11 +5054,57 @@ impl ScopeStack {
     }
 
     fn push(&mut self) {
-        self.scopes.push(Scope::new(false));
+        self.scopes.push(Scope::new(false, None));
+    }
+
+    fn push_loop(&mut self, label: Option<String>) {
+        self.scopes.push(Scope::new(true, label));
+    }
+
+    // the body of a loop over entities is its own function, run as each of them, see ParserNodeKind::ForEntities
+    fn push_entity_loop(&mut self) {
+        let mut scope = Scope::new(false, None);
+        scope.is_entity_loop = true;
+        self.scopes.push(scope);
+    }
+
+    fn is_in_entity_loop(&self) -> bool {
+        self.scopes.iter().any(|scope| scope.is_entity_loop)
+    }
+
+    // whether the loop that a break leaves is outside of a loop over entities, which it can't leave
+    fn breaks_out_of_entity_loop(&self, label: Option<&str>) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if scope.is_entity_loop {
+                return true;
+            }
+
+            match label {
+                Some(label) if scope.label.as_deref() == Some(label) => return false,
+                None if scope.is_loop => return false,
+                _ => {}
+            }
+        }
+
+        false
     }
 
-    fn push_loop(&mut self) {
-        self.scopes.push(Scope::new(true));
+    // whether the loop that a break leaves is unrolled, which leaves no loop behind to break out of
+    fn breaks_out_of_unrolled_loop(&self, label: Option<&str>) -> bool {
+        let target = self.scopes.iter().rev().find(|scope| match label {
+            Some(label) => scope.label.as_deref() == Some(label),
+            None => scope.is_loop,
+        });
+
+        target.is_some_and(|scope| scope.is_unrolled)
+    }
+
+    fn is_unrolled_variable(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.get_variable(name).is_some())
+            .is_some_and(|scope| scope.is_unrolled)
     }
 
     fn pop(&mut self) {
@@ -932,6 +5119,10 @@ impl ScopeStack {
         self.scopes.iter().rev().any(|scope| scope.is_loop)
     }
 
+    fn is_in_loop_labeled(&self, label: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.label.as_deref() == Some(label))
+    }
+
     fn register_variable(&mut self, name: String, ty: TypeKey) {
         self.last_mut().add_variable(name, ty);
     }
@@ -946,6 +5137,29 @@ impl ScopeStack {
         None
     }
 
+    fn register_selector(&mut self, name: String, selector: String) {
+        self.last_mut().selectors.insert(name, selector);
+    }
+
+    fn register_text(&mut self, name: String) {
+        self.last_mut().texts.insert(name);
+    }
+
+    // whether a text variable was declared with text that is known at compile time, see TagPool::is_text
+    fn is_known_text(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.get_variable(name).is_some())
+            .is_some_and(|scope| scope.texts.contains(name))
+    }
+
+    // the selector that a selector variable holds, from the scope that declares the variable
+    fn find_selector(&self, name: &str) -> Option<&str> {
+        let scope = self.scopes.iter().rev().find(|scope| scope.get_variable(name).is_some())?;
+        scope.selectors.get(name).map(String::as_str)
+    }
+
     fn variable_exists(&self, name: &str) -> bool {
         for scope in self.scopes.iter().rev() {
             if scope.get_variable(name).is_some() {
@@ -959,14 +5173,27 @@ impl ScopeStack {
 
 pub struct Scope {
     variables: HashMap<String, TypeKey>,
+    // the selectors that the scope's selector variables hold
+    selectors: HashMap<String, String>,
+    // the text variables of the scope whose text is known
+    texts: HashSet<String>,
     is_loop: bool,
+    is_entity_loop: bool,
+    // whether the scope is the loop scope of a #[unroll] loop, which declares its variable
+    is_unrolled: bool,
+    label: Option<String>,
 }
 
 impl Scope {
-    fn new(is_loop: bool) -> Self {
+    fn new(is_loop: bool, label: Option<String>) -> Self {
         Self {
             variables: HashMap::new(),
+            selectors: HashMap::new(),
+            texts: HashSet::new(),
             is_loop,
+            is_entity_loop: false,
+            is_unrolled: false,
+            label,
         }
     }
 
@@ -993,6 +5220,21 @@ impl ValidationErrorList {
         self.0.push(ValidationError::new(kind, span));
     }
 
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    // Removes the errors from the given index on that are the same as one before it
+    fn remove_repeated(&mut self, from: usize) {
+        for error in self.0.split_off(from) {
+            let debug = format!("{:?}", error);
+
+            if !self.0.iter().any(|reported| format!("{:?}", reported) == debug) {
+                self.0.push(error);
+            }
+        }
+    }
+
     fn dissolve(self) -> Vec<ValidationError> {
         self.0
     }
@@ -1001,6 +5243,28 @@ impl ValidationErrorList {
 pub struct TagPool<'a> {
     types: HashMap<ByAddress<&'a ParserNode>, TypeKey>,
     resolutions: HashMap<ByAddress<&'a ParserNode>, Resolution>,
+    recursive_calls: HashSet<ByAddress<&'a ParserNode>>,
+    // the names in the pack of functions that aren't named what they were declared as, by the nodes that declare or
+    // name them
+    function_names: HashMap<ByAddress<&'a ParserNode>, String>,
+    // the optionals that the values of nodes are wrapped into, see Validator::accepts
+    optionals: HashMap<ByAddress<&'a ParserNode>, TypeKey>,
+    // the selectors that selector values are, which are known at compile time
+    selectors: HashMap<ByAddress<&'a ParserNode>, String>,
+    // the nodes whose value is text that is known at compile time, which are text literals and the variables that hold
+    // them. The IR turns them into JSON text where they're declared
+    texts: HashSet<ByAddress<&'a ParserNode>>,
+    // the tags that summon() calls give the entities they summon
+    summon_tags: HashMap<ByAddress<&'a ParserNode>, String>,
+    // the functions that are referenced and the types they're referenced with, in the order they were first referenced
+    function_refs: Vec<(String, TypeKey)>,
+    // the declarations of the instances of generic functions, which aren't part of the program, see Validator::instantiate
+    instances: Vec<&'a ParserNode>,
+    // the declarations of functions that are left out of the pack, which are weak functions that another function
+    // replaces and functions whose #[cfg] condition is false
+    left_out: HashSet<ByAddress<&'a ParserNode>>,
+    // the declarations of the #[inline] functions that calls are to, by the calls
+    inline_calls: HashMap<ByAddress<&'a ParserNode>, &'a ParserNode>,
 }
 
 impl<'a> TagPool<'a> {
@@ -1008,9 +5272,87 @@ impl<'a> TagPool<'a> {
         Self {
             types: HashMap::new(),
             resolutions: HashMap::new(),
+            recursive_calls: HashSet::new(),
+            function_names: HashMap::new(),
+            optionals: HashMap::new(),
+            selectors: HashMap::new(),
+            texts: HashSet::new(),
+            summon_tags: HashMap::new(),
+            function_refs: Vec::new(),
+            instances: Vec::new(),
+            left_out: HashSet::new(),
+            inline_calls: HashMap::new(),
+        }
+    }
+
+    pub fn tag_function_name(&mut self, node: &'a ParserNode, name: String) {
+        self.function_names.insert(ByAddress(node), name);
+    }
+
+    /// The name in the pack of the function that the node declares or names, if it's not the name it was declared with,
+    /// such as an overload or a function of an imported file
+    pub fn find_function_name(&self, node: &'a ParserNode) -> Option<&str> {
+        self.function_names.get(&ByAddress(node)).map(String::as_str)
+    }
+
+    pub fn add_function_ref(&mut self, name: String, ty: TypeKey) {
+        if !self.function_refs.iter().any(|(function, _)| *function == name) {
+            self.function_refs.push((name, ty));
         }
     }
 
+    /// The functions that are referenced with the given type, in the order they were first referenced
+    pub fn function_refs(&self, ty: TypeKey) -> Vec<&str> {
+        self.function_refs
+            .iter()
+            .filter(|(_, function_ty)| *function_ty == ty)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The number that a reference to the function holds, which is its position among the functions referenced with the
+    /// same type, counting from 1 so that a reference that was never set doesn't call anything
+    pub fn function_ref_id(&self, name: &str) -> i32 {
+        let ty = self.function_refs.iter().find(|(function, _)| function == name).unwrap().1;
+        self.function_refs(ty).iter().position(|function| *function == name).unwrap() as i32 + 1
+    }
+
+    pub fn add_instance(&mut self, node: &'a ParserNode) {
+        self.instances.push(node);
+    }
+
+    /// The declarations of the instances of generic functions, which are compiled like the functions of the program
+    pub fn instances(&self) -> impl Iterator<Item = &'a ParserNode> + '_ {
+        self.instances.iter().copied()
+    }
+
+    pub fn leave_out(&mut self, node: &'a ParserNode) {
+        self.left_out.insert(ByAddress(node));
+    }
+
+    /// Whether the declaration is of a function that's left out of the pack, either because it's a weak function that
+    /// another function replaces or because its #[cfg] condition is false.
+    pub fn is_left_out(&self, node: &'a ParserNode) -> bool {
+        self.left_out.contains(&ByAddress(node))
+    }
+
+    pub fn tag_inline_call(&mut self, node: &'a ParserNode, declaration: &'a ParserNode) {
+        self.inline_calls.insert(ByAddress(node), declaration);
+    }
+
+    /// The declaration of the #[inline] function that the call is to, whose body is put in place of the call.
+    pub fn find_inline_call(&self, node: &'a ParserNode) -> Option<&'a ParserNode> {
+        self.inline_calls.get(&ByAddress(node)).copied()
+    }
+
+    pub fn tag_recursive_call(&mut self, node: &'a ParserNode) {
+        self.recursive_calls.insert(ByAddress(node));
+    }
+
+    pub fn is_recursive_call(&self, node: &'a ParserNode) -> bool {
+        self.recursive_calls.contains(&ByAddress(node))
+    }
+
     pub fn tag_type(&mut self, node: &'a ParserNode, ty: TypeKey) {
         self.types.insert(ByAddress(node), ty);
     }
@@ -1019,6 +5361,47 @@ impl<'a> TagPool<'a> {
         self.resolutions.insert(ByAddress(node), resolution);
     }
 
+    pub fn tag_optional(&mut self, node: &'a ParserNode, optional: TypeKey) {
+        self.optionals.insert(ByAddress(node), optional);
+    }
+
+    /// The optional that the node's value is wrapped into where it's used, if it's a plain value or null that's used
+    /// where an optional is expected
+    pub fn find_optional(&self, node: &'a ParserNode) -> Option<TypeKey> {
+        self.optionals.get(&ByAddress(node)).copied()
+    }
+
+    pub fn tag_selector(&mut self, node: &'a ParserNode, selector: String) {
+        self.selectors.insert(ByAddress(node), selector);
+    }
+
+    /// The selector that the node's value is, if it's a selector literal or a variable that holds one
+    pub fn find_selector(&self, node: &'a ParserNode) -> Option<&str> {
+        self.selectors.get(&ByAddress(node)).map(String::as_str)
+    }
+
+    pub fn tag_summon(&mut self, node: &'a ParserNode, tag: String) {
+        self.summon_tags.insert(ByAddress(node), tag);
+    }
+
+    pub fn get_summon_tag(&self, node: &'a ParserNode) -> &str {
+        &self.summon_tags[&ByAddress(node)]
+    }
+
+    pub fn tag_text(&mut self, node: &'a ParserNode) {
+        self.texts.insert(ByAddress(node));
+    }
+
+    /// Whether the node's value is text that is known at compile time, see ValidationErrorKind::UnknownText
+    pub fn is_text(&self, node: &'a ParserNode) -> bool {
+        self.texts.contains(&ByAddress(node))
+    }
+
+    /// The type of the node's value where it's used, which is the optional it's wrapped into if there is one
+    pub fn get_value_type(&self, node: &'a ParserNode) -> TypeKey {
+        self.find_optional(node).unwrap_or_else(|| self.get_type(node))
+    }
+
     pub fn get_type(&self, node: &'a ParserNode) -> TypeKey {
         *self.types.get(&ByAddress(node)).unwrap()
     }
@@ -1026,4 +5409,12 @@ impl<'a> TagPool<'a> {
     pub fn get_resolution(&self, node: &'a ParserNode) -> &Resolution {
         self.resolutions.get(&ByAddress(node)).unwrap()
     }
+
+    pub fn find_resolution(&self, node: &'a ParserNode) -> Option<&Resolution> {
+        self.resolutions.get(&ByAddress(node))
+    }
+
+    pub fn find_type(&self, node: &'a ParserNode) -> Option<TypeKey> {
+        self.types.get(&ByAddress(node)).copied()
+    }
 }
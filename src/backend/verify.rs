@@ -0,0 +1,261 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use ariadne::{Color, Fmt};
+
+use crate::data::{Objective, ResourceLocation, ScoreboardSlot};
+
+use super::ir::{storage_path, Instruction, IrFunction, ValueLocation};
+
+/// A mistake in the IR that the compiler made itself, rather than one in the code being compiled.
+/// These are reported as internal compiler errors, with the function and instruction they were found at.
+pub struct InternalError {
+    function: Objective,
+    instruction: Option<String>,
+    message: String,
+}
+
+impl Display for InternalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} in function {}: {}", "internal compiler error".fg(Color::Red), self.function, self.message)?;
+
+        if let Some(instruction) = &self.instruction {
+            writeln!(f, "  at: {}", instruction.trim())?;
+        }
+
+        write!(f, "  this is a bug in sculk, please report it along with the code that caused it")
+    }
+}
+
+/// Checks the IR of a pack for invariants that codegen relies on:
+/// - values of a function, other than its parameters, are written somewhere before they can be read
+/// - blocks are created in the function that runs them before they are run, and only once
+/// - breaks are inside the loop they break out of, since nothing else clears the flag they set
+/// - every call frame that is pushed is popped again in the same sequence of instructions
+/// - calls to functions of the pack refer to functions that exist, and every name is a valid resource location
+pub fn verify(pack_name: &str, funcs: &[IrFunction]) -> Vec<InternalError> {
+    let functions = funcs
+        .iter()
+        .map(|func| ResourceLocation::new(pack_name.to_string(), func.objective().to_string()))
+        .collect::<HashSet<ResourceLocation>>();
+
+    let mut errors = Vec::new();
+
+    for func in funcs {
+        let mut verifier = FunctionVerifier {
+            pack_name,
+            func,
+            functions: &functions,
+            created: HashSet::new(),
+            written: HashSet::new(),
+            reads: Vec::new(),
+            literals: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        if !is_valid_path(&func.objective().0) {
+            verifier.error(None, format!("'{}' is not a valid function name", func.objective()));
+        }
+
+        verifier.verify_sequence(func.body(), 0);
+        verifier.verify_reads();
+        errors.extend(verifier.errors);
+    }
+
+    errors
+}
+
+/// Checks the objectives of a pack's generated commands, given the objectives whose scores they use along with the
+/// first function that used each, and the objectives that they create along with the function that created them and
+/// whether it's run when the pack is loaded. Every objective that is used has to be created when the pack is loaded,
+/// and no objective can be created more than once then, since creating it again fails.
+pub fn verify_objectives(
+    used: &BTreeMap<String, ResourceLocation>,
+    created: &[(String, ResourceLocation, bool)],
+) -> Vec<InternalError> {
+    let mut errors = Vec::new();
+    let mut on_load: HashMap<&str, &ResourceLocation> = HashMap::new();
+
+    for (objective, function, _) in created.iter().filter(|(.., on_load)| *on_load) {
+        if let Some(first) = on_load.insert(objective, function) {
+            errors.push(InternalError {
+                function: Objective(function.path.clone()),
+                instruction: None,
+                message: format!("objective {} is created again, after {} created it", objective, first),
+            });
+        }
+    }
+
+    for (objective, function) in used {
+        if !on_load.contains_key(objective.as_str()) {
+            errors.push(InternalError {
+                function: Objective(function.path.clone()),
+                instruction: None,
+                message: format!("objective {} is used but never created when the pack is loaded", objective),
+            });
+        }
+    }
+
+    errors
+}
+
+// Case is left alone, since the functions of struct methods keep the case of the struct's name
+fn is_valid_path(path: &str) -> bool {
+    !path.is_empty() && path.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+}
+
+struct FunctionVerifier<'a> {
+    pack_name: &'a str,
+    func: &'a IrFunction,
+    functions: &'a HashSet<ResourceLocation>,
+    created: HashSet<usize>,
+    // the slots and offsets of the function's own values that are written anywhere in it
+    written: HashSet<(usize, usize)>,
+    reads: Vec<(ValueLocation, &'a Instruction)>,
+    literals: Vec<&'a str>,
+    errors: Vec<InternalError>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn error(&mut self, instruction: Option<&Instruction>, message: String) {
+        self.errors.push(InternalError {
+            function: self.func.objective().clone(),
+            instruction: instruction.map(|instruction| instruction.to_string()),
+            message,
+        });
+    }
+
+    fn read(&mut self, location: &ValueLocation, instruction: &'a Instruction) {
+        self.reads.push((location.clone(), instruction));
+    }
+
+    fn write(&mut self, location: &ValueLocation) {
+        if location.objective == *self.func.objective() {
+            self.written.insert((location.slot, location.offset));
+        }
+    }
+
+    fn check_block(&mut self, id: usize, instruction: &Instruction) {
+        if !self.created.contains(&id) {
+            self.error(Some(instruction), format!("block {} is run before it is created", id));
+        }
+    }
+
+    // loops is how many loop blocks the sequence is nested in
+    fn verify_sequence(&mut self, body: &'a [Instruction], loops: usize) {
+        let mut frames = 0;
+
+        for instruction in body {
+            match instruction {
+                Instruction::SetValueToValue { source, target } => {
+                    self.read(source, instruction);
+                    self.write(target);
+                }
+                Instruction::ValueBinaryOperation { source, target, .. } => {
+                    self.read(source, instruction);
+                    self.read(target, instruction);
+                    self.write(target);
+                }
+                Instruction::LongBinaryOperation { source, target, .. } => {
+                    for half in 0..2 {
+                        self.read(&source.offset(half), instruction);
+                        self.read(&target.offset(half), instruction);
+                        self.write(&target.offset(half));
+                    }
+                }
+                Instruction::ToggleValue { target } | Instruction::ModifyValue { target, .. } => {
+                    self.read(target, instruction);
+                    self.write(target);
+                }
+                Instruction::SetValueToConstant { target, .. }
+                | Instruction::LoadValueFromStorage { target, .. }
+                | Instruction::SetValueToCommandSuccess { target, .. }
+                | Instruction::SetValueToCommandResult { target, .. }
+                | Instruction::SetValueToConditionSuccess { target, .. } => self.write(target),
+                Instruction::SetValueToMatch { source, target, .. } => {
+                    self.read(source, instruction);
+                    self.write(target);
+                }
+                Instruction::SaveValueToStorage { source, .. } => self.read(source, instruction),
+                Instruction::Return { source, size } => {
+                    if let Some(source) = source {
+                        for offset in 0..*size {
+                            self.read(&source.offset(offset), instruction);
+                        }
+                    }
+                }
+                Instruction::Break { depth } => {
+                    if *depth >= loops {
+                        self.error(Some(instruction), "break out of a loop block that it isn't in".to_string());
+                    }
+                }
+                Instruction::Call { function, .. } => {
+                    if function.namespace == self.pack_name && !self.functions.contains(function) {
+                        self.error(Some(instruction), format!("call to function {} which does not exist", function));
+                    } else if !is_valid_path(&function.path) {
+                        self.error(Some(instruction), format!("'{}' is not a valid function name", function));
+                    }
+                }
+                Instruction::PushFrame => frames += 1,
+                Instruction::PopFrame => {
+                    if frames == 0 {
+                        self.error(Some(instruction), "call frame popped without being pushed".to_string());
+                    } else {
+                        frames -= 1;
+                    }
+                }
+                Instruction::CreateBlock { id, is_loop, body, .. } => {
+                    if !self.created.insert(*id) {
+                        self.error(None, format!("block {} is created more than once", id));
+                    }
+
+                    self.verify_sequence(body, loops + *is_loop as usize);
+                }
+                Instruction::EnterBlock { id }
+                | Instruction::ScheduleBlock { id, .. }
+                | Instruction::RunBlockAs { id, .. } => self.check_block(*id, instruction),
+                Instruction::IfValueMatchesRunBlock { source, block, .. }
+                | Instruction::IfValueInRangeRunBlock { source, block, .. } => {
+                    self.read(source, instruction);
+                    self.check_block(*block, instruction);
+                }
+                Instruction::PlaceCommandLiteral(literal) => self.literals.push(literal),
+            }
+        }
+
+        if frames != 0 {
+            self.error(None, format!("{} call frame(s) pushed without being popped", frames));
+        }
+    }
+
+    // Reads are only checked against the function's own values, since parameters and return values are written by
+    // other functions. Command literals may write values too, so a value that one of them mentions counts as written,
+    // whether that's its score or the storage that strings and arrays are kept in
+    fn verify_reads(&mut self) {
+        let params = self.func.signature().params().len();
+        let mut reported = HashSet::new();
+
+        for (location, instruction) in std::mem::take(&mut self.reads) {
+            if location.player
+                || location.objective != *self.func.objective()
+                || location.slot < params
+                || self.written.contains(&(location.slot, location.offset))
+            {
+                continue;
+            }
+
+            let slot = ScoreboardSlot::from(&location).to_string();
+            let path = storage_path(&location);
+
+            if self.literals.iter().any(|literal| literal.contains(&slot) || literal.contains(&path))
+                || !reported.insert(slot.clone())
+            {
+                continue;
+            }
+
+            self.error(Some(instruction), format!("{} is read but never written", slot));
+        }
+    }
+}
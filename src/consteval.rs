@@ -0,0 +1,304 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    backend::builtin::{self, FIXED_POINT_SCALE},
+    parser::{Operation, ParserNode, ParserNodeKind},
+};
+
+/// The value of an expression computed at compile time, which has the type that the validator gives the expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Int(i32),
+    Bool(bool),
+}
+
+impl Value {
+    /// The score that the value is stored in at runtime, where bools are 1 or 0.
+    pub fn as_score(self) -> i32 {
+        match self {
+            Value::Int(n) => n,
+            Value::Bool(b) => b as i32,
+        }
+    }
+
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Bool(_) => "bool",
+        }
+    }
+}
+
+/// Evaluates an expression at compile time, such as the expression that computes the elements of a table, with the
+/// same semantics as the code it compiles to. The names in `env` are the only variables that can be referenced.
+/// Operands must have the types that the validator requires of them: arithmetic and comparisons take ints, and `&&`
+/// and `||` take bools. Division and modulo round towards negative infinity to match the scoreboard operations they'd
+/// compile to.
+///
+/// Besides arithmetic, a few math functions are available, which are computed with floats and rounded:
+/// - `sin(angle)`, `cos(angle)` and `atan2(y, x)`, which use fixed-point numbers like the builtins of the same name
+/// - `sqrt(x)`, `pow(base, exponent)`, `abs(x)`, `min(a, b)` and `max(a, b)`
+pub fn eval(node: &ParserNode, env: &HashMap<String, Value>) -> Result<Value, ConstEvalError> {
+    let error = |kind| Err(ConstEvalError::new(kind, node.span()));
+
+    match node.kind() {
+        ParserNodeKind::NumberLiteral(n) => Ok(Value::Int(*n)),
+        ParserNodeKind::BoolLiteral(b) => Ok(Value::Bool(*b)),
+        ParserNodeKind::Expression(expr) => eval(expr, env),
+        ParserNodeKind::Identifier(name) => match env.get(name) {
+            Some(value) => Ok(*value),
+            None => error(ConstEvalErrorKind::UnknownName(name.clone())),
+        },
+        // a name with a member, such as TARGET.pack_format, is looked up by its whole path
+        ParserNodeKind::MemberAccess { expr, member } => match (expr.unwrap_expression().kind(), member.kind()) {
+            (ParserNodeKind::Identifier(name), ParserNodeKind::Identifier(member)) => {
+                let path = format!("{}.{}", name, member);
+
+                match env.get(&path) {
+                    Some(value) => Ok(*value),
+                    None => error(ConstEvalErrorKind::UnknownName(path)),
+                }
+            }
+            _ => error(ConstEvalErrorKind::NotConstant),
+        },
+        ParserNodeKind::Unary(expr, op) => match (op, eval(expr, env)?) {
+            (Operation::Negate, value) => {
+                let value = expect_int(expr, value)?;
+                value.checked_neg().map_or_else(|| error(ConstEvalErrorKind::Overflow), |n| Ok(Value::Int(n)))
+            }
+            (Operation::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (Operation::Not, Value::Int(n)) => Ok(Value::Int((n == 0) as i32)),
+            _ => unreachable!(),
+        },
+        // only the chosen value is evaluated, like in the compiled code
+        ParserNodeKind::Conditional { cond, then, otherwise } => match expect_bool(cond, eval(cond, env)?)? {
+            true => eval(then, env),
+            false => eval(otherwise, env),
+        },
+        ParserNodeKind::Cast { expr, ty } => match (eval(expr, env)?, ty.as_str()) {
+            (Value::Int(n), "bool") => Ok(Value::Bool(n != 0)),
+            (Value::Bool(b), "int") => Ok(Value::Int(b as i32)),
+            (value, ty) if value.type_name() == ty => Ok(value),
+            _ => error(ConstEvalErrorKind::NotConstant),
+        },
+        ParserNodeKind::Operation(lhs_node, rhs_node, op) => {
+            let (lhs, rhs) = (eval(lhs_node, env)?, eval(rhs_node, env)?);
+
+            if let Operation::And | Operation::Or = op {
+                let (lhs, rhs) = (expect_bool(lhs_node, lhs)?, expect_bool(rhs_node, rhs)?);
+
+                return Ok(Value::Bool(match op {
+                    Operation::And => lhs && rhs,
+                    _ => lhs || rhs,
+                }));
+            }
+
+            let (lhs, rhs) = (expect_int(lhs_node, lhs)?, expect_int(rhs_node, rhs)?);
+
+            let result = match op {
+                Operation::Add => lhs.checked_add(rhs),
+                Operation::Subtract => lhs.checked_sub(rhs),
+                Operation::Multiply => lhs.checked_mul(rhs),
+                Operation::Divide | Operation::Modulo if rhs == 0 => return error(ConstEvalErrorKind::DivisionByZero),
+                Operation::Divide => floor_div(lhs, rhs),
+                Operation::Modulo => floor_div(lhs, rhs).map(|quotient| lhs - quotient * rhs),
+                Operation::GreaterThan => return Ok(Value::Bool(lhs > rhs)),
+                Operation::LessThan => return Ok(Value::Bool(lhs < rhs)),
+                Operation::GreaterThanOrEquals => return Ok(Value::Bool(lhs >= rhs)),
+                Operation::LessThanOrEquals => return Ok(Value::Bool(lhs <= rhs)),
+                Operation::CheckEquals => return Ok(Value::Bool(lhs == rhs)),
+                Operation::NotEquals => return Ok(Value::Bool(lhs != rhs)),
+                Operation::BitAnd => Some(lhs & rhs),
+                Operation::BitOr => Some(lhs | rhs),
+                Operation::BitXor => Some(lhs ^ rhs),
+                // shift amounts are masked to 0..31 like the compiled shifts
+                Operation::ShiftLeft => Some(lhs.wrapping_shl(rhs as u32)),
+                Operation::ShiftRight => Some(lhs.wrapping_shr(rhs as u32)),
+                Operation::And | Operation::Or | Operation::Not | Operation::Negate => unreachable!(),
+            };
+
+            result.map_or_else(|| error(ConstEvalErrorKind::Overflow), |n| Ok(Value::Int(n)))
+        }
+        ParserNodeKind::FunctionCall { expr, args } => {
+            let name = match expr.kind() {
+                ParserNodeKind::Identifier(name) => name.as_str(),
+                _ => return error(ConstEvalErrorKind::NotConstant),
+            };
+
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, env).and_then(|value| expect_int(arg, value)))
+                .collect::<Result<Vec<i32>, ConstEvalError>>()?;
+
+            let expected = match name {
+                "atan2" | "pow" | "min" | "max" => 2,
+                "sin" | "cos" | "sqrt" | "abs" => 1,
+                _ => return error(ConstEvalErrorKind::UnknownFunction(name.to_string())),
+            };
+
+            if args.len() != expected {
+                return error(ConstEvalErrorKind::WrongArgumentCount {
+                    function: name.to_string(),
+                    expected,
+                });
+            }
+
+            let result = match name {
+                "sin" => round(from_fixed(args[0]).to_radians().sin() * FIXED_POINT_SCALE as f64),
+                "cos" => round(from_fixed(args[0]).to_radians().cos() * FIXED_POINT_SCALE as f64),
+                "atan2" => round((args[0] as f64).atan2(args[1] as f64).to_degrees() * FIXED_POINT_SCALE as f64),
+                "sqrt" if args[0] < 0 => return error(ConstEvalErrorKind::NegativeSqrt(args[0])),
+                "sqrt" => Some((args[0] as f64).sqrt() as i32),
+                "pow" => u32::try_from(args[1]).ok().and_then(|exponent| args[0].checked_pow(exponent)),
+                "abs" => args[0].checked_abs(),
+                "min" => Some(args[0].min(args[1])),
+                "max" => Some(args[0].max(args[1])),
+                _ => unreachable!(),
+            };
+
+            result.map_or_else(|| error(ConstEvalErrorKind::Overflow), |n| Ok(Value::Int(n)))
+        }
+        _ => error(ConstEvalErrorKind::NotConstant),
+    }
+}
+
+/// The constants that describe the version that a pack is compiled for, which `#[cfg]` conditions and static assertions
+/// can use to tell versions apart:
+/// - `TARGET.pack_format`, the pack format given with `--pack-format`, or the newest one
+/// - `TARGET.has_macros`, `TARGET.has_random` and `TARGET.has_damage`, whether the version has function macros and the
+///   random and damage commands
+/// - `TARGET.release`, whether the pack is compiled with `--release`
+pub fn target_constants(pack_format: Option<u32>, release: bool) -> HashMap<String, Value> {
+    let pack_format = pack_format.unwrap_or(builtin::LATEST_PACK_FORMAT);
+
+    [
+        ("pack_format", Value::Int(pack_format as i32)),
+        ("has_macros", Value::Bool(pack_format >= builtin::MACRO_PACK_FORMAT)),
+        ("has_random", Value::Bool(pack_format >= builtin::RANDOM_PACK_FORMAT)),
+        ("has_damage", Value::Bool(pack_format >= builtin::DAMAGE_PACK_FORMAT)),
+        ("release", Value::Bool(release)),
+    ]
+    .into_iter()
+    .map(|(name, value)| (format!("TARGET.{}", name), value))
+    .collect()
+}
+
+/// The most iterations that a loop can be unrolled into, see [`trip_count`].
+pub const MAX_UNROLLED_ITERATIONS: u32 = 256;
+
+/// The number of times that a `for` loop's body runs, if its variable starts at a constant, its condition only
+/// depends on the variable and its step only changes the variable, such as `for let i = 0; i < 8; i++`. Loops that
+/// would run more than [`MAX_UNROLLED_ITERATIONS`] times give `None`, as do loops that never finish.
+pub fn trip_count(init: &ParserNode, cond: &ParserNode, step: &ParserNode) -> Option<u32> {
+    let (name, start) = match init.kind() {
+        ParserNodeKind::VariableDeclaration { name, expr, .. } => (name.as_identifier(), expr),
+        _ => return None,
+    };
+
+    // the step is turned into the expression that computes the variable's next value
+    let next = match step.kind() {
+        ParserNodeKind::OpEquals { path, expr, op } if is_identifier(path, name) => ParserNode::new(
+            ParserNodeKind::Operation(path.clone(), expr.clone(), *op),
+            step.span(),
+        ),
+        ParserNodeKind::VariableAssignment { path, expr } if is_identifier(path, name) => expr.as_ref().clone(),
+        _ => return None,
+    };
+
+    let mut env = HashMap::from([(name.to_string(), eval(start, &HashMap::new()).ok()?)]);
+
+    for count in 0..=MAX_UNROLLED_ITERATIONS {
+        match eval(cond, &env).ok()? {
+            Value::Bool(true) => {}
+            Value::Bool(false) => return Some(count),
+            Value::Int(_) => return None,
+        }
+
+        let value = eval(&next, &env).ok()?;
+        env.insert(name.to_string(), value);
+    }
+
+    None
+}
+
+fn is_identifier(node: &ParserNode, name: &str) -> bool {
+    matches!(node.unwrap_expression().kind(), ParserNodeKind::Identifier(ident) if ident == name)
+}
+
+fn expect_int(node: &ParserNode, value: Value) -> Result<i32, ConstEvalError> {
+    match value {
+        Value::Int(n) => Ok(n),
+        value => Err(ConstEvalError::new(
+            ConstEvalErrorKind::TypeMismatch {
+                expected: "int",
+                actual: value.type_name(),
+            },
+            node.span(),
+        )),
+    }
+}
+
+fn expect_bool(node: &ParserNode, value: Value) -> Result<bool, ConstEvalError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        value => Err(ConstEvalError::new(
+            ConstEvalErrorKind::TypeMismatch {
+                expected: "bool",
+                actual: value.type_name(),
+            },
+            node.span(),
+        )),
+    }
+}
+
+fn floor_div(lhs: i32, rhs: i32) -> Option<i32> {
+    let quotient = lhs.checked_div(rhs)?;
+
+    match lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+        true => Some(quotient - 1),
+        false => Some(quotient),
+    }
+}
+
+fn from_fixed(value: i32) -> f64 {
+    value as f64 / FIXED_POINT_SCALE as f64
+}
+
+fn round(value: f64) -> Option<i32> {
+    let value = value.round();
+
+    match value >= i32::MIN as f64 && value <= i32::MAX as f64 {
+        true => Some(value as i32),
+        false => None,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConstEvalError {
+    pub kind: ConstEvalErrorKind,
+    pub span: Range<usize>,
+}
+
+impl ConstEvalError {
+    pub fn new(kind: ConstEvalErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ConstEvalErrorKind {
+    NotConstant,
+    UnknownName(String),
+    UnknownFunction(String),
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+    },
+    DivisionByZero,
+    NegativeSqrt(i32),
+    Overflow,
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+}
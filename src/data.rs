@@ -1,4 +1,11 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ResourceLocation {
@@ -36,14 +43,84 @@ impl Display for Objective {
     }
 }
 
+thread_local! {
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// An interned string. Each distinct string is only allocated once however many symbols refer to it,
+/// so cloning a symbol doesn't allocate and two symbols are equal exactly when they point to the same string.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(text: &str) -> Self {
+        SYMBOLS.with(|symbols| {
+            let mut symbols = symbols.borrow_mut();
+
+            match symbols.get(text) {
+                Some(symbol) => Self(symbol.clone()),
+                None => {
+                    let symbol: Rc<str> = Rc::from(text);
+                    symbols.insert(symbol.clone());
+                    Self(symbol)
+                }
+            }
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const u8).hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Generated packs can have millions of actions, and most of them refer to a handful of objectives and entries,
+// so both are interned rather than each slot owning copies of them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ScoreboardSlot {
-    pub objective: Objective,
-    pub entry: String,
+    pub objective: Symbol,
+    pub entry: Symbol,
 }
 
 impl ScoreboardSlot {
     pub fn new(objective: Objective, entry: String) -> Self {
-        Self { objective, entry }
+        Self {
+            objective: Symbol::intern(&objective.0),
+            entry: Symbol::intern(&entry),
+        }
     }
 }
 
@@ -53,13 +130,15 @@ impl Display for ScoreboardSlot {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ScoreboardOperationType {
     Add,
     Subtract,
     Multiply,
     Divide,
     Modulo,
-    Set, // add the others when i feel like it
+    Set,
+    Max, // add the others when i feel like it
 }
 
 impl Display for ScoreboardOperationType {
@@ -71,6 +150,7 @@ impl Display for ScoreboardOperationType {
             ScoreboardOperationType::Divide => write!(f, "/="),
             ScoreboardOperationType::Modulo => write!(f, "%="),
             ScoreboardOperationType::Set => write!(f, "="),
+            ScoreboardOperationType::Max => write!(f, ">"),
         }
     }
 }
\ No newline at end of file
@@ -1,15 +1,18 @@
-use std::{collections::HashMap, io};
+use std::{collections::HashMap, io::{self, IsTerminal}, ops::Range, str::FromStr};
 
 use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
 
 use crate::{
     backend::{resolve::ResolvedPart,
+        builtin::Builtin,
         function::FunctionSignature,
         resolve::{Resolution, ResolutionError},
-        type_pool::TypePool,
+        type_pool::{TypeKey, TypePool},
         validate::{ValidationError, ValidationErrorKind},
     },
+    consteval::{self, ConstEvalErrorKind},
     data::ResourceLocation,
+    modules::Sources,
     parser::ParseError,
 };
 
@@ -30,6 +33,88 @@ impl From<ValidationError> for CompileError {
     }
 }
 
+impl CompileError {
+    fn span(&self) -> &Range<usize> {
+        match self {
+            CompileError::Parse(error) => &error.span,
+            CompileError::Validate(error) => &error.span,
+        }
+    }
+
+    // Moves the spans of the error from the source of the whole program to the source of the file that it's in
+    fn relocate(&mut self, offset: usize) {
+        let relocate = |span: &mut Range<usize>| *span = span.start - offset..span.end - offset;
+
+        match self {
+            CompileError::Parse(error) => relocate(&mut error.span),
+            CompileError::Validate(error) => {
+                relocate(&mut error.span);
+
+                match &mut error.kind {
+                    ValidationErrorKind::VariableAssignmentTypeMismatch { expr_span, .. }
+                    | ValidationErrorKind::ReturnTypeMismatch { expr_span, .. } => relocate(expr_span),
+                    ValidationErrorKind::NotEnoughArguments { callee_span, .. } => relocate(callee_span),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// When errors and other diagnostics are shown in color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    /// when printing to a terminal, unless the NO_COLOR environment variable is set
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice '{}', expected one of: auto, always, never", s)),
+        }
+    }
+}
+
+/// Turns color on or off for everything printed afterwards. Diagnostics are printed to stdout, so that's the stream
+/// that has to be a terminal for colors to be shown automatically. An empty NO_COLOR doesn't count as set, see no-color.org
+pub fn set_color_choice(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").map_or(true, |value| value.is_empty()) && io::stdout().is_terminal()
+        }
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+
+    if enabled {
+        yansi::Paint::enable();
+    } else {
+        yansi::Paint::disable();
+    }
+}
+
+/// Prints the errors of a program that may be made up of several files, each in the file that it's in.
+pub fn print_reports(
+    sources: &Sources,
+    errors: Vec<CompileError>,
+    types: &TypePool,
+    signatures: &HashMap<ResourceLocation, FunctionSignature>,
+) {
+    for mut error in errors {
+        let file = sources.file_at(error.span().start);
+        error.relocate(file.span.start);
+        print_report(&file.path, sources.text(file), &error, types, signatures);
+    }
+}
+
 pub fn print_report(
     file_name: &str,
     file_content: &str,
@@ -57,6 +142,39 @@ pub fn print_report(
                         .with_message("cannot break outside a for, while, or foreach loop".to_string())
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::White))
                 }
+                ValidationErrorKind::UnknownLoopLabel(label) => {
+                    report
+                        .with_message(format!("there is no loop labeled {} to break out of", format!("'{}", label).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::White))
+                }
+                ValidationErrorKind::ExpectedBoolInConditional(ty) => {
+                    report
+                        .with_message(format!("the condition before ? must be of type '{}'", types.bool().from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", ty.from(types).fg(Color::Cyan))))
+                }
+                ValidationErrorKind::ConditionalTypeMismatch { then, otherwise } => {
+                    report
+                        .with_message(format!(
+                            "the values of a conditional must have the same type, but they are '{}' and '{}'",
+                            then.from(types).fg(Color::Cyan),
+                            otherwise.from(types).fg(Color::Cyan)
+                        ))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::VariableWithoutValue(name) => {
+                    report
+                        .with_message(format!("'{}' cannot be given the result of an expression that has no value", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::ExpectedIntInMatch(ty) => {
+                    report
+                        .with_message(format!("the value of a match statement must be of type '{}' or an enum", types.int().from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", ty.from(types).fg(Color::Cyan))))
+                }
                 ValidationErrorKind::ExpectedBoolInIf(ty) => {
                     report
                         .with_message(format!("an if statement's condition must be of type '{}'", types.bool().from(types).fg(Color::Cyan)))
@@ -76,9 +194,9 @@ pub fn print_report(
                         .with_message("unknown variable")
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                 }
-                ValidationErrorKind::UnknownFunction(_) => {
+                ValidationErrorKind::UnknownFunction(name) => {
                     report
-                        .with_message("unknown function")
+                        .with_message(format!("unknown function '{}'", name.fg(Color::Green)))
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                 }
                 ValidationErrorKind::VariableAlreadyDefined(_) => {
@@ -148,6 +266,28 @@ pub fn print_report(
                         .with_message(format!("a function with the name '{}' already exists", name.fg(Color::Green)))
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                 }
+                ValidationErrorKind::OverloadAlreadyDefined { name, params } => {
+                    report
+                        .with_message(format!("an overload of '{}' that takes ({}) already exists", name.fg(Color::Green), type_list(params, types)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::NoMatchingOverload { name, actual } => {
+                    let mut overloads = signatures
+                        .values()
+                        .filter(|signature| signature.declared_name() == name)
+                        .map(|signature| {
+                            let params = signature.params().iter().map(|param| param.param_type()).collect::<Vec<_>>();
+                            format!("{}({})", name, type_list(&params, types))
+                        })
+                        .collect::<Vec<String>>();
+
+                    overloads.sort();
+
+                    report
+                        .with_message(format!("no overload of '{}' takes arguments of types ({})", name.fg(Color::Green), type_list(actual, types)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!("the overloads are: {}", overloads.join(", ")))
+                }
                 ValidationErrorKind::FunctionStructNameClash(name) => {
                     report
                         .with_message(format!("clash between a struct and function which share the name '{}'", name.fg(Color::Green)))
@@ -181,6 +321,14 @@ pub fn print_report(
                         .with_message("unknown type")
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                 }
+                ValidationErrorKind::NotOperatorTypeMismatch(ty) => {
+                    report
+                        .with_message(format!("the {} operator can only be applied to a value of type '{}'", "!".fg(Color::Yellow), types.bool().from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", ty.from(types).fg(Color::Cyan))))
+                        .with_note("to check if a number is zero, compare it instead, e.g 'x == 0'")
+                }
                 ValidationErrorKind::LogicalOperatorTypeMismatch { lhs, rhs, op } => {
                     report
                         .with_message(format!("logical operators such as {} can only be applied to operands of type '{}'", op.fg(Color::Yellow), types.bool().from(types).fg(Color::Cyan)))
@@ -198,7 +346,13 @@ pub fn print_report(
                                     ResolvedPart::Type(ty) => format!("- type '{}'", name.fg(Color::Green)),
                                     ResolvedPart::Field(ty, name) => format!("- field '{}' of type '{}'", name.fg(Color::Green), ty.from(&types).as_struct_def().name().fg(Color::Cyan)),
                                     ResolvedPart::Method(ty, name) => format!("- method '{}' of type '{}'", name.fg(Color::Green), ty.from(&types).as_struct_def().name().fg(Color::Cyan)),
+                                    ResolvedPart::Variant(ty, _) => format!("- variant of enum '{}'", ty.from(types).fg(Color::Cyan)),
                                     ResolvedPart::Constructor(ty) => format!("- constructor of type '{}'", name.fg(Color::Cyan)),
+                                    ResolvedPart::PlayerVariable(_, name) => format!("- player variable '{}'", name.fg(Color::Green)),
+                                    ResolvedPart::ConfigValue(_, name) => format!("- config value '{}'", name.fg(Color::Green)),
+                                    ResolvedPart::Builtin(builtin) => format!("- builtin '{}'", builtin.fg(Color::Green)),
+                                    ResolvedPart::Reference(_, name) => format!("- function reference '{}'", name.fg(Color::Green)),
+                                    ResolvedPart::GenericStruct(name) => format!("- generic struct '{}'", name.fg(Color::Cyan)),
                                 }).collect::<Vec<String>>().join("\n"))))
                     }
                     ResolutionError::UnresolvedIdentifier(name) => {
@@ -208,7 +362,7 @@ pub fn print_report(
                     }
                     ResolutionError::MemberDoesNotExist(ty, name) => {
                         report
-                            .with_message(format!("type '{}' does not have a member named '{}'", ty.from(&types).as_struct_def().name().fg(Color::Cyan), name.fg(Color::Green)))
+                            .with_message(format!("type '{}' does not have a member named '{}'", ty.from(&types).fg(Color::Cyan), name.fg(Color::Green)))
                             .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                     }
                     ResolutionError::CannotCallExpression => {
@@ -236,6 +390,11 @@ pub fn print_report(
                             .with_message(format!("type '{}' does not have a static method named '{}'", ty.from(&types).as_struct_def().name().fg(Color::Cyan), name.fg(Color::Green)))
                             .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                     }
+                    ResolutionError::TableElementNotAssignable => {
+                        report
+                            .with_message("the elements of a table can only be read")
+                            .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                    }
                 }
                 ValidationErrorKind::CannotReferenceMethodAsValue => {
                     report
@@ -252,6 +411,560 @@ pub fn print_report(
                         .with_message("static functions can only exist inside struct definitions")
                         .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
                 }
+                ValidationErrorKind::UnknownAttribute(name) => {
+                    report
+                        .with_message(format!("unknown attribute '{}'", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::SpreadLoopNotLast => {
+                    report
+                        .with_message("a loop with #[spread] must be the last statement of its function")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the rest of the loop runs on later ticks, so nothing after it could wait for it to finish")
+                }
+                ValidationErrorKind::AwaitNotInFunctionBody => {
+                    report
+                        .with_message("await and sleep() can only be used directly in the body of a function")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("waiting inside a loop, an if or a nested block is not supported")
+                }
+                ValidationErrorKind::AwaitInValueFunction => {
+                    report
+                        .with_message("functions that return a value cannot await or sleep")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the caller carries on before the rest of the function runs, so it could never get the value")
+                }
+                ValidationErrorKind::BudgetedLoopNotInFunctionBody => {
+                    report
+                        .with_message("with a loop budget, loops can only be used directly in the body of a function")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the loop may carry on in a later tick, so only the rest of its function can wait for it")
+                }
+                ValidationErrorKind::BudgetedLoopInValueFunction => {
+                    report
+                        .with_message("with a loop budget, functions that return a value cannot loop")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the loop may carry on in a later tick, after the caller has carried on without the value")
+                }
+                ValidationErrorKind::AttributeAlreadyApplied(name) => {
+                    report
+                        .with_message(format!("attribute '{}' is applied more than once", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::ConflictingAttributes(first, second) => {
+                    report
+                        .with_message(format!("attributes '{}' and '{}' cannot be applied together", first.fg(Color::Green), second.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidAttributeArguments { name, expected } => {
+                    report
+                        .with_message(format!("invalid arguments for attribute '{}'", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected {}", expected)))
+                }
+                ValidationErrorKind::UnknownRegistryEntry { registry, id } => {
+                    report
+                        .with_message(format!("unknown {} '{}'", registry, id.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("this id does not exist in the registries of the targeted version")
+                }
+                ValidationErrorKind::InvalidBuiltinArguments { builtin, expected } => {
+                    report
+                        .with_message(format!("invalid arguments for builtin '{}'", builtin.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected {}", expected)))
+                }
+                ValidationErrorKind::PlayerVariableNameClash(name) => {
+                    report
+                        .with_message(format!("the player variable '{}' shares its name with a function or struct", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnsupportedPlayerVariableType(ty) => {
+                    report
+                        .with_message(format!("player variables cannot be of type '{}'", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("each player variable is stored in a single score, so only 'int' and 'bool' are supported")
+                }
+                ValidationErrorKind::InvalidDisplayName => {
+                    report
+                        .with_message("the display name of a player variable isn't a valid text component")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("a display name that starts with { or [ has to be JSON, anything else is used as plain text")
+                }
+                ValidationErrorKind::ConfigNameClash(name) => {
+                    report
+                        .with_message(format!("the config value '{}' shares its name with a function, struct or player variable", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnsupportedConfigType(ty) => {
+                    report
+                        .with_message(format!("config values cannot be of type '{}'", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("each config value is read into a single score, so only 'int' and 'bool' are supported")
+                }
+                ValidationErrorKind::ConfigValueNotAssignable(name) => {
+                    report
+                        .with_message(format!("the config value '{}' cannot be assigned to", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("config values are changed by editing the pack's config storage and reloading")
+                }
+                ValidationErrorKind::NoConfigValues => {
+                    report
+                        .with_message("the settings menu cannot be shown because no config values are declared")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidTickInterval(ticks) => {
+                    report
+                        .with_message(format!("cannot run a block every {} ticks", ticks.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the interval must be at least 1 tick")
+                }
+                ValidationErrorKind::StateMachineAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a state machine with the name '{}' already exists", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::StateMachineWithoutStates(name) => {
+                    report
+                        .with_message(format!("the state machine '{}' has no states", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the first state declared is the one the machine starts in")
+                }
+                ValidationErrorKind::StateAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a state with the name '{}' already exists", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnknownStateMachine(name) => {
+                    report
+                        .with_message(format!("unknown state machine '{}'", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnknownState { machine, state } => {
+                    report
+                        .with_message(format!("the state machine '{}' has no state named '{}'", machine.fg(Color::Green), state.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::RegionAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a region with the name '{}' already exists", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidBuiltinValue { value, expected } => {
+                    report
+                        .with_message(format!("'{}' is not valid here", value.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected {}", expected)))
+                }
+                ValidationErrorKind::InvalidRunAs { expected } => {
+                    report
+                        .with_message(format!("invalid use of '{}'", "run_as".fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected {}", expected)))
+                }
+                ValidationErrorKind::UnexpectedTextComponent(builtin) => {
+                    report
+                        .with_message(format!("'{}' builds a text component, which can only be shown by builtins like print", builtin.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::TextComponentTypeMismatch(ty) => {
+                    report
+                        .with_message(format!("a value of type '{}' cannot be shown as text", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("only strings, text components, ints and bools can be shown")
+                }
+                ValidationErrorKind::CallbackWithParameters { name, builtin } => {
+                    let when = match builtin {
+                        Builtin::Positioned => "be run by positioned",
+                        _ => "run on click",
+                    };
+
+                    report
+                        .with_message(format!("'{}' cannot {} because it takes parameters", name.fg(Color::Green), when))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::AttributeRequiresNoParameters(name) => {
+                    report
+                        .with_message(format!("attribute '{}' can only be applied to functions without parameters", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::AttributeNotAllowed { name, on } => {
+                    report
+                        .with_message(format!("attribute '{}' cannot be applied to {}", name.fg(Color::Green), on))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::AmbiguousWeakFunction(name) => {
+                    report
+                        .with_message(format!("weak function '{}' is declared more than once", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!("declare '{}' without #[weak] to choose what it does", name.fg(Color::Green)))
+                }
+                ValidationErrorKind::WeakOverrideMismatch(name) => {
+                    report
+                        .with_message(format!("'{}' replaces a weak function, but doesn't take the same parameters and return the same type", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidDamageHandlerParameters => {
+                    report
+                        .with_message("damage handlers may only take the amount of damage as a parameter")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the amount is an int, in tenths of a health point")
+                }
+                ValidationErrorKind::UnsupportedMessageField { message, field } => {
+                    report
+                        .with_message(format!("field '{}' of message '{}' must be an int or a bool", field.fg(Color::Green), message.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnsupportedEntityStructField { struct_name, field } => {
+                    report
+                        .with_message(format!("field '{}' of attached struct '{}' must be an int or a bool", field.fg(Color::Green), struct_name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("each field is kept in a score on the entity")
+                }
+                ValidationErrorKind::ControlFlowInEntityLoop => {
+                    report
+                        .with_message("cannot break or return from inside a loop over entities or an as/at block")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the body is run as or at each entity by its own function")
+                }
+                ValidationErrorKind::ControlFlowInInlineFunction => {
+                    report
+                        .with_message("an inline function can only return at the end of its body, and cannot await")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the body of the function is put where it's called, so returning would leave the caller")
+                }
+                ValidationErrorKind::CannotUnrollLoop => {
+                    report
+                        .with_message("cannot unroll a loop whose iterations aren't known at compile time")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!(
+                            "the loop's variable has to start at a constant and only be changed by its step, and it can run at most {} times",
+                            consteval::MAX_UNROLLED_ITERATIONS
+                        ))
+                }
+                ValidationErrorKind::BreakInUnrolledLoop => {
+                    report
+                        .with_message("cannot break out of an unrolled loop")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the iterations of the loop are put one after another, so there is no loop to leave")
+                }
+                ValidationErrorKind::UnrolledVariableAssigned(name) => {
+                    report
+                        .with_message(format!("cannot assign to '{}', the variable of an unrolled loop", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the number of times the loop runs is worked out from its step alone")
+                }
+                ValidationErrorKind::OverloadedFunctionReference(name) => {
+                    report
+                        .with_message(format!("cannot take a reference to '{}', as it is overloaded", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("a reference can only be taken to a function that has a single signature")
+                }
+                ValidationErrorKind::CannotInferTypeParameter { name, param } => {
+                    report
+                        .with_message(format!("cannot infer the type of '{}' for '{}'", param.fg(Color::Cyan), name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("every type parameter has to be used in the type of a parameter, so it's known from the arguments")
+                }
+                ValidationErrorKind::InstantiationTooDeep(name) => {
+                    report
+                        .with_message(format!("'{}' is instantiated too deeply", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("a generic function that calls itself with a bigger type every time would be compiled forever")
+                }
+                ValidationErrorKind::GenericFunctionReference(name) => {
+                    report
+                        .with_message(format!("cannot take a reference to '{}', as it is generic", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("a generic function is only compiled for the types it's called with")
+                }
+                ValidationErrorKind::TraitAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a trait with the name '{}' already exists", name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnknownTrait(name) => {
+                    report
+                        .with_message(format!("unknown trait '{}'", name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::MissingTraitMethod { struct_name, trait_name, method } => {
+                    report
+                        .with_message(format!("struct '{}' implements '{}' but has no method named '{}'", struct_name.fg(Color::Cyan), trait_name.fg(Color::Cyan), method.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::TraitMethodMismatch { struct_name, trait_name, method } => {
+                    report
+                        .with_message(format!("method '{}' of struct '{}' doesn't match the one of trait '{}'", method.fg(Color::Green), struct_name.fg(Color::Cyan), trait_name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("it has to take the same parameters and return the same type, and can't be static")
+                }
+                ValidationErrorKind::TraitNotImplemented { ty, trait_name } => {
+                    report
+                        .with_message(format!("type '{}' does not implement trait '{}'", ty.from(types).fg(Color::Cyan), trait_name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::NoFunctionReferences(ty) => {
+                    report
+                        .with_message(format!("no function is ever referenced as '{}', so there is nothing for this call to call", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::EntityStructNotValue(name) => {
+                    report
+                        .with_message(format!("'{}' is an entity, so only its fields can be used", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the fields of an attached struct are scores on the entity rather than a value")
+                }
+                ValidationErrorKind::InvalidSubscriberParameters => {
+                    report
+                        .with_message("a subscriber must take a single message as its parameter")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::VolumeTooLarge(volume) => {
+                    report
+                        .with_message(format!("this area contains {} blocks, which is too many to change at once", volume.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!("at most {} blocks can be filled or cloned by a single command", crate::backend::builtin::MAX_BLOCK_VOLUME))
+                }
+                ValidationErrorKind::UnknownVariant { kind, name, variants } => {
+                    report
+                        .with_message(format!("'{}' is not a valid {}", name.fg(Color::Green), kind))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected one of {}", variants.join(", "))))
+                }
+                ValidationErrorKind::ExpectedVariant { kind, variants } => {
+                    report
+                        .with_message(format!("expected a {}", kind))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("expected one of {}", variants.join(", "))))
+                }
+                ValidationErrorKind::TableAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a table with the name '{}' already exists", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidTableLength(len) => {
+                    report
+                        .with_message(format!("a table cannot have {} elements", len.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!("a table must have between 1 and {} elements", crate::backend::validate::MAX_TABLE_LEN))
+                }
+                ValidationErrorKind::StaticAssertFailed(message) => {
+                    report
+                        .with_message(format!("static assertion failed: {}", message))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::CouldNotEvaluate(kind) => {
+                    let message = match kind {
+                        ConstEvalErrorKind::NotConstant => "this expression cannot be computed at compile time".to_string(),
+                        ConstEvalErrorKind::UnknownName(name) => format!("unknown name '{}' in a compile-time expression", name.fg(Color::Green)),
+                        ConstEvalErrorKind::UnknownFunction(name) => format!("'{}' cannot be called at compile time", name.fg(Color::Green)),
+                        ConstEvalErrorKind::WrongArgumentCount { function, expected } => {
+                            format!("'{}' takes {} arguments", function.fg(Color::Green), expected.fg(Color::Cyan))
+                        }
+                        ConstEvalErrorKind::DivisionByZero => "division by zero in a compile-time expression".to_string(),
+                        ConstEvalErrorKind::NegativeSqrt(n) => format!("cannot take the square root of {}", n.fg(Color::Cyan)),
+                        ConstEvalErrorKind::Overflow => "this compile-time expression overflows an int".to_string(),
+                        ConstEvalErrorKind::TypeMismatch { expected, actual } => {
+                            format!("expected '{}' in a compile-time expression, found '{}'", expected.fg(Color::Cyan), actual.fg(Color::Cyan))
+                        }
+                    };
+
+                    report
+                        .with_message(message)
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("compile-time expressions may use ints, bools, operators and sin, cos, atan2, sqrt, pow, abs, min and max")
+                }
+                ValidationErrorKind::NotIndexable(ty) => {
+                    report
+                        .with_message(format!("a value of type '{}' cannot be indexed", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("only tables and arrays can be indexed")
+                }
+                ValidationErrorKind::EmptyArrayLiteral => {
+                    report
+                        .with_message("the type of an empty array cannot be inferred")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("give the variable an array type, e.g 'let values: int[] = [];'")
+                }
+                ValidationErrorKind::UnsupportedArrayElement(ty) => {
+                    report
+                        .with_message(format!("values of type '{}' cannot be put in an array", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("arrays can only hold ints and bools")
+                }
+                ValidationErrorKind::UnsupportedTupleElement(ty) => {
+                    report
+                        .with_message(format!("values of type '{}' cannot be put in a tuple", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::NotATuple(ty) => {
+                    report
+                        .with_message("only tuples can be split into variables")
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("this is of type '{}'", ty.from(types).fg(Color::Cyan))))
+                }
+                ValidationErrorKind::TupleLengthMismatch { expected, actual } => {
+                    report
+                        .with_message(format!("expected a tuple of {} values", expected.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", actual.from(types).fg(Color::Cyan))))
+                        .with_note("name an element _ to skip it")
+                }
+                ValidationErrorKind::ArrayElementTypeMismatch { expected, actual } => {
+                    report
+                        .with_message(format!("expected an element of type '{}'", expected.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", actual.from(types).fg(Color::Cyan))))
+                        .with_note("every element of an array must have the same type as the first one")
+                }
+                ValidationErrorKind::ForbiddenCommand(name) => {
+                    report
+                        .with_message(format!("the '{}' command is not allowed", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("this command was forbidden with --forbid")
+                }
+                ValidationErrorKind::CommandsAlreadyDefined => {
+                    report
+                        .with_message("a pack can only have one commands block")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::SubcommandAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a sub-command with the name '{}' already exists", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::SubcommandWithParameters(name) => {
+                    report
+                        .with_message(format!("'{}' cannot be run as a sub-command because it takes parameters", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidLongConversion => {
+                    report
+                        .with_message(format!("'{}' takes a single int to convert", "long".fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::InvalidCast { from, to } => {
+                    report
+                        .with_message(format!(
+                            "cannot cast '{}' to '{}'",
+                            from.from(types).fg(Color::Cyan),
+                            to.from(types).fg(Color::Cyan)
+                        ))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("casts convert between ints and bools, from ints and bools to longs, from longs to ints and from enums to ints")
+                }
+                ValidationErrorKind::ExpectedOptional(ty) => {
+                    report
+                        .with_message("?? and if let can only be used with optional values, such as 'int?'")
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", ty.from(types).fg(Color::Cyan))))
+                }
+                ValidationErrorKind::CoalesceDefaultMismatch { expected, actual } => {
+                    report
+                        .with_message(format!(
+                            "the default after ?? must be of type '{}'",
+                            expected.from(types).fg(Color::Cyan)
+                        ))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", actual.from(types).fg(Color::Cyan))))
+                }
+                ValidationErrorKind::InvalidSelector { selector, reason } => {
+                    report
+                        .with_message(format!("'{}' is not a valid selector", selector.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(reason))
+                }
+                ValidationErrorKind::UnknownSelector(name) => {
+                    report
+                        .with_message(format!("the selector that '{}' holds is not known at compile time", name.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("selectors are put into the commands that use them, so only variables declared with a selector can be used")
+                }
+                ValidationErrorKind::SelectorNotAssignable => {
+                    report
+                        .with_message("a selector variable cannot be assigned to, as its selector is put into the commands that use it")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("declare a new variable with the other selector instead")
+                }
+                ValidationErrorKind::UnknownText => {
+                    report
+                        .with_message("this text is not known at compile time")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("text is put into the commands that show it, so only text literals and the variables declared with them can be shown")
+                }
+                ValidationErrorKind::TextNotAssignable => {
+                    report
+                        .with_message("a text variable cannot be assigned to, as its text is put into the commands that show it")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("declare a new variable with the other text instead")
+                }
+                ValidationErrorKind::NullWithoutOptional => {
+                    report
+                        .with_message("null can only be used where an optional value is expected")
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("give the variable an optional type, e.g 'let x: int? = null;'")
+                }
+                ValidationErrorKind::TypeAlreadyDefined(name) => {
+                    report
+                        .with_message(format!("a type with the name '{}' already exists", name.fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::VariantAlreadyDefined { enum_name, variant } => {
+                    report
+                        .with_message(format!("enum '{}' already has a variant named '{}'", enum_name.fg(Color::Cyan), variant.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::UnexpectedArmPattern { expected, pattern } => {
+                    report
+                        .with_message(format!("'{}' cannot be matched against a value of type '{}'", pattern.fg(Color::Green), expected.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                }
+                ValidationErrorKind::NonExhaustiveMatch { ty, missing } => {
+                    report
+                        .with_message(format!("the match doesn't cover every variant of enum '{}'", ty.from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("missing {}", missing.join(", "))))
+                        .with_note("add an arm for each missing variant, or a _ arm")
+                }
+                ValidationErrorKind::UnreachableMatchArm(pattern) => {
+                    report
+                        .with_message(format!("the match arm {} is unreachable", pattern.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("the arms before it already match every value it matches")
+                }
+                ValidationErrorKind::IndexTypeMismatch(ty) => {
+                    report
+                        .with_message(format!("an index must be of type '{}'", types.int().from(types).fg(Color::Cyan)))
+                        .with_label(Label::new((file_name, error.span.clone()))
+                            .with_color(Color::Red)
+                            .with_message(format!("... but '{}' was given instead", ty.from(types).fg(Color::Cyan))))
+                }
+                ValidationErrorKind::TableIndexOutOfBounds { table, index, len } => {
+                    report
+                        .with_message(format!("index {} is out of bounds for table '{}'", index.fg(Color::Cyan), table.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note(format!("the table has {} elements", len))
+                }
+                ValidationErrorKind::InvalidStorageKey(key) => {
+                    report
+                        .with_message(format!("'{}' cannot be used as a storage key", key.fg(Color::Green)))
+                        .with_label(Label::new((file_name, error.span.clone())).with_color(Color::Red))
+                        .with_note("storage keys may only contain letters, digits, underscores and hyphens")
+                }
             }
         }
     }
@@ -260,3 +973,11 @@ pub fn print_report(
         .finish()
         .print((file_name, Source::from(file_content)));
 }
+
+// Lists types like the parameters of a function, e.g "int, bool"
+fn type_list(list: &[TypeKey], types: &TypePool) -> String {
+    list.iter()
+        .map(|ty| ty.from(types).fg(Color::Cyan).to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
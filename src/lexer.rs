@@ -1,8 +1,11 @@
-use std::ops::Range;
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use logos::{Lexer, Logos};
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct LexerExtras {
     line: usize,
     last_line_idx: usize,
@@ -56,6 +59,27 @@ pub enum Token<'a> {
     #[token("!")]
     Not,
 
+    #[token("&")]
+    Ampersand,
+
+    #[token("|")]
+    Pipe,
+
+    #[token("^")]
+    Caret,
+
+    #[token("<<")]
+    ShiftLeft,
+
+    #[token(">>")]
+    ShiftRight,
+
+    #[token("++")]
+    Increment,
+
+    #[token("--")]
+    Decrement,
+
     #[token("+=")]
     AddEquals,
 
@@ -80,6 +104,12 @@ pub enum Token<'a> {
     #[token("{")]
     LeftBrace,
 
+    #[token("[")]
+    LeftBracket,
+
+    #[token("]")]
+    RightBracket,
+
     #[token("}")]
     RightBrace,
 
@@ -131,11 +161,35 @@ pub enum Token<'a> {
     #[token("->")]
     Arrow,
 
+    #[token("=>")]
+    FatArrow,
+
+    #[token("..")]
+    DotDot,
+
+    #[token("..=")]
+    DotDotEquals,
+
+    #[token("#")]
+    Pound,
+
+    #[token("?")]
+    Question,
+
+    #[token("??")]
+    QuestionQuestion,
+
+    #[regex(r#""([^"\\]|\\.)*""#, |tok| { let slice = tok.slice(); &slice[1..slice.len() - 1] })]
+    String(&'a str),
+
     #[regex(r"[a-zA-Z_]+[a-zA-Z0-9_]*", |tok| tok.slice())]
     Identifier(&'a str),
 
-    #[regex(r"@[p|r|a|e|s]", |tok| tok.slice().chars().nth(1).unwrap())]
-    Selector(char),
+    #[regex(r"'[a-zA-Z_]+[a-zA-Z0-9_]*", |tok| &tok.slice()[1..])]
+    Label(&'a str),
+
+    #[regex(r"@[prase]", selector)]
+    Selector(&'a str),
 
     #[token("\n", |lex| {
         lex.extras.line += 1;
@@ -149,6 +203,40 @@ pub enum Token<'a> {
     Error,
 }
 
+// A selector's arguments can have brackets of their own, e.g `@e[nbt={Tags:["a"]}]`, so the bracket that closes them
+// is found by counting the brackets and braces outside of strings. A selector whose arguments aren't closed is an error
+fn selector<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Option<&'a str> {
+    let remainder = lex.remainder();
+
+    if !remainder.starts_with('[') {
+        return Some(lex.slice());
+    }
+
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in remainder.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '{' if !in_string => depth += 1,
+            ']' | '}' if !in_string => {
+                depth -= 1;
+
+                if depth == 0 {
+                    lex.bump(i + 1);
+                    return Some(lex.slice());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
 pub struct TokenStream<'a> {
     lexer: Lexer<'a, Token<'a>>,
     current: Option<Token<'a>>,
@@ -157,22 +245,29 @@ pub struct TokenStream<'a> {
     current_span: Range<usize>,
     next_span: Range<usize>,
     src_len: usize,
+    // added to every span, for sources that are part of a larger one, see modules::Sources
+    offset: usize,
+    // how long the tokens took to lex, which --timings reports apart from the rest of parsing
+    lexing: Duration,
 }
 
 impl<'a> TokenStream<'a> {
-    pub fn new(src: &'a str) -> Self {
+    pub fn new(src: &'a str, offset: usize) -> Self {
+        let start = Instant::now();
         let mut lexer = Token::lexer(src);
 
         let next = lexer.next();
 
         Self {
-            next_span: lexer.span(),
-            current_span: 0..0,
+            next_span: lexer.span().start + offset..lexer.span().end + offset,
+            current_span: offset..offset,
             lexer,
             current: None,
             next,
             col: 0,
             src_len: src.len(),
+            offset,
+            lexing: start.elapsed(),
         }
     }
 
@@ -180,8 +275,8 @@ impl<'a> TokenStream<'a> {
         self.current = self.next.take();
         self.current_span = self.next_span.clone();
 
-        self.next = self.lexer.next();
-        self.next_span = self.lexer.span();
+        self.next = self.lex();
+        self.next_span = self.lexer.span().start + self.offset..self.lexer.span().end + self.offset;
 
         self.col = self.lexer.span().start - self.lexer.extras.last_line_idx;
 
@@ -192,6 +287,11 @@ impl<'a> TokenStream<'a> {
         self.next.as_ref()
     }
 
+    /// The token after the one that `peek` gives, which is lexed without consuming anything.
+    pub fn peek_second(&self) -> Option<Token<'a>> {
+        self.lexer.clone().next()
+    }
+
     pub fn current(&mut self) -> Option<&Token> {
         self.current.as_ref()
     }
@@ -216,10 +316,31 @@ impl<'a> TokenStream<'a> {
         self.lexer.remainder()
     }
 
+    /// The source of a span of tokens, which has to be in the source that's being lexed.
+    pub fn slice(&self, span: Range<usize>) -> &'a str {
+        &self.lexer.source()[span.start - self.offset..span.end - self.offset]
+    }
+
     pub fn src_len(&self) -> usize {
         self.src_len
     }
 
+    /// How long lexing the tokens that have been read so far took.
+    pub fn lexing(&self) -> Duration {
+        self.lexing
+    }
+
+    fn lex(&mut self) -> Option<Token<'a>> {
+        let start = Instant::now();
+        let token = self.lexer.next();
+        self.lexing += start.elapsed();
+        token
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     pub fn bump(&mut self, n: usize) {
         for c in self.lexer.remainder()[..n].chars() {
             if c == '\n' {
@@ -228,7 +349,13 @@ impl<'a> TokenStream<'a> {
             }
         }
 
+        // the skipped text becomes the current token along with the one before it, e.g a command literal and its /
+        let end = self.lexer.span().end + n + self.offset;
+        self.current = self.next.take();
+        self.current_span = self.next_span.start..end;
+
         self.lexer.bump(n);
-        self.next = self.lexer.next();
+        self.next = self.lex();
+        self.next_span = self.lexer.span().start + self.offset..self.lexer.span().end + self.offset;
     }
 }
@@ -0,0 +1,640 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use ariadne::{Color, Fmt};
+use backend::{
+    codegen::{CompiledFunction, NamingScheme},
+    dpc_backend::DPCBackend,
+    function::FunctionSignature,
+    ir::{IrCompiler, IrFunction},
+    type_pool::TypePool,
+    types::SculkType,
+    validate::{TagPool, Validator, ValidatorOutput},
+    verify, Backend, DefaultBackend,
+};
+use data::ResourceLocation;
+use error::{ColorChoice, CompileError};
+use itertools::Itertools;
+use modules::Sources;
+use parser::{ParserNode, ParserNodeKind};
+use registry::Registries;
+use scoreboards::ScoreboardReport;
+use timings::Timings;
+
+mod backend;
+pub mod consteval;
+mod data;
+mod error;
+mod lexer;
+mod modules;
+pub mod parser;
+mod registry;
+mod safety;
+mod scoreboards;
+mod simulator;
+pub mod testing;
+mod timings;
+
+pub use backend::codegen::{CheckReport, CheckStats, CodeGen};
+
+#[derive(argh::FromArgs)]
+/// Configuration for the compiler.
+pub struct Config {
+    /// a list of paths to sculk files that should be compiled
+    #[argh(positional)]
+    files: Vec<String>,
+
+    /// the name of the datapack to generate
+    #[argh(option, short = 'n', default = "String::from(\"pack\")")]
+    pack: String,
+
+    /// the backend to use for compilation
+    #[argh(option, short = 'b', default = "String::from(\"default\")")]
+    backend: String,
+
+    #[argh(switch, short = 'd')]
+    /// dumps sculk's ir to a file for debugging purposes
+    dump_ir: bool,
+
+    /// path to a registries.json report from the data generator, used to validate ids for the targeted version
+    #[argh(option)]
+    registries: Option<String>,
+
+    /// the naming scheme for generated anonymous functions: numbered, hashed, or line
+    #[argh(option, default = "NamingScheme::Numbered")]
+    naming: NamingScheme,
+
+    /// the number of loop iterations that may run in a tick before the remaining ones are deferred to the next tick.
+    /// The rest of the function waits for a deferred loop, so loops can then only be used where an await could be.
+    /// Loops are not limited by default
+    #[argh(option)]
+    loop_budget: Option<u32>,
+
+    /// a command that command literals may not run, e.g --forbid op. Can be given more than once, and
+    /// --forbid dangerous forbids every command that controls the server, such as op, stop and whitelist
+    #[argh(option)]
+    forbid: Vec<String>,
+
+    /// a function to run whenever the pack is loaded, once everything the pack sets up is in place, e.g --entry main.
+    /// Can be given more than once to run several in order. By default no function runs on load, as for libraries
+    #[argh(option)]
+    entry: Vec<String>,
+
+    #[argh(switch)]
+    /// prints how long each phase of compilation took for every file, and which functions took longest to generate
+    timings: bool,
+
+    #[argh(switch)]
+    /// writes the code of each function as soon as it is generated instead of holding the code of every function in
+    /// memory until the end, which keeps memory usage down for very large packs. Only affects the default backend
+    streaming: bool,
+
+    /// writes a JSON report of every scoreboard objective the pack uses, and of the fake players whose scores it uses in
+    /// each, to the given path. Only supported by the default backend without --streaming
+    #[argh(option)]
+    scoreboard_report: Option<String>,
+
+    /// the path to the scoreboard report of another pack, see --scoreboard-report, which fails compilation if both packs
+    /// use an objective, since they'd share its scores on the same server. Can be given more than once
+    #[argh(option)]
+    collisions_with: Vec<String>,
+
+    #[argh(switch)]
+    /// compiles every file twice and fails if the outputs are not byte-for-byte identical
+    verify_reproducible: bool,
+
+    #[argh(switch)]
+    /// checks the generated IR for mistakes made by the compiler itself and reports them as internal compiler errors.
+    /// This is always done by debug builds
+    verify_ir: bool,
+
+    /// a directory of .sculk fixtures to compile and check against the files expected of each, which are kept in a
+    /// directory named after the fixture. Any files given to compile are ignored
+    #[argh(option)]
+    golden: Option<String>,
+
+    #[argh(switch)]
+    /// with --golden, replaces the expected files of every fixture with the files it generates now
+    bless: bool,
+
+    #[argh(switch)]
+    /// instead of writing the pack, checks the code generated for every expression that can be evaluated at compile
+    /// time against the value the compiler computes for it, by running that code in a simulator
+    differential: bool,
+
+    #[argh(switch)]
+    /// instead of writing the pack, parses and validates every file and prints its errors along with a summary of it,
+    /// exiting with a failure if any file has errors. Meant for editors and pre-commit hooks
+    check: bool,
+
+    #[argh(switch)]
+    /// with --check, also generates the code of every file without writing it, so that mistakes made by the compiler
+    /// itself are caught too
+    check_codegen: bool,
+
+    #[argh(switch)]
+    /// when validation finds errors only inside functions, still generates every other function, so that editors can
+    /// preview the output of a file while it's being edited. Functions that call one with errors are left out too. The
+    /// errors are reported as usual
+    partial: bool,
+
+    /// the pack format of the Minecraft version the pack targets, e.g 15 for 1.20.1. Builtins only use commands that the
+    /// version has, such as random() which falls back to a scoreboard generator before 18. Defaults to the newest version
+    #[argh(option)]
+    pack_format: Option<u32>,
+
+    #[argh(switch)]
+    /// leaves out the checks that are only meant for development, such as assert(), whose conditions aren't evaluated
+    release: bool,
+
+    /// when to show errors in color: auto, always, or never. auto uses color when printing to a terminal, unless the
+    /// NO_COLOR environment variable is set
+    #[argh(option, default = "ColorChoice::Auto")]
+    color: ColorChoice,
+}
+
+/// Compiles the files given on the command line, or checks or tests them as the flags say, which is what the sculk
+/// binary does. Exits with 1 if checking or testing found a problem.
+pub fn run(config: Config) {
+    error::set_color_choice(config.color);
+
+    if config.files.is_empty() && config.golden.is_none() {
+        println!("no files to compile");
+        return;
+    }
+
+    if config.loop_budget == Some(0) {
+        println!("the loop budget must allow at least one iteration per tick");
+        return;
+    }
+
+    let registries = match &config.registries {
+        Some(path) => match Registries::load(path) {
+            Ok(registries) => Some(registries),
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if let Some(dir) = &config.golden {
+        if !testing::check_fixtures(&config, registries.as_ref(), Path::new(dir), config.bless) {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if config.check {
+        let checked = config.files.iter().map(|file| check_file(&config, registries.as_ref(), file)).collect::<Vec<bool>>();
+
+        if checked.contains(&false) {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if config.differential {
+        let agreed = config.files.iter().map(|file| testing::differential(&config, registries.as_ref(), file)).collect::<Vec<bool>>();
+
+        if agreed.contains(&false) {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let mut errors = Vec::new();
+
+    for file in &config.files {
+        let (info, result) = compile_file(&config, registries.as_ref(), file);
+
+        if let Err(errs) = result {
+            errors.push((errs, info));
+        }
+    }
+
+    for (errs, info) in errors {
+        let info = info.expect("info should be present if there are errors");
+        error::print_reports(&info.sources, errs, &info.types, &info.signatures);
+    }
+}
+
+fn compile_file(
+    config: &Config,
+    registries: Option<&Registries>,
+    path: &str,
+) -> (Option<Info>, Result<(), Vec<CompileError>>) {
+    let file_content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("failed to read file: {}", err);
+            return (None, Err(Vec::new()));
+        }
+    };
+
+    let mut timings = Timings::default();
+
+    let (info, funcs, tolerated) = match compile_to_ir(config, registries, path, &file_content, &mut timings) {
+        Ok(compiled) => compiled,
+        Err((info, errors)) => return (Some(info), Err(errors)),
+    };
+
+    if let Err(message) = check_entry_points(config, &info.signatures) {
+        println!("{}", message);
+        return (Some(info), Err(Vec::new()));
+    }
+
+    if config.dump_ir {
+        dump_ir(&config, &info.types, &info.signatures, &funcs);
+    }
+
+    if cfg!(debug_assertions) || config.verify_ir {
+        let start = Instant::now();
+        let internal_errors = verify::verify(&config.pack, &funcs);
+        timings.record("verification", start);
+
+        if !internal_errors.is_empty() {
+            for err in internal_errors {
+                println!("{}", err);
+            }
+
+            return (Some(info), Err(Vec::new()));
+        }
+    }
+
+    if (config.scoreboard_report.is_some() || !config.collisions_with.is_empty())
+        && (config.backend != "default" || config.streaming)
+    {
+        println!("--scoreboard-report and --collisions-with are only supported by the default backend without --streaming");
+        return (Some(info), Err(Vec::new()));
+    }
+
+    if config.verify_reproducible && !verify_reproducible(config, registries, path, &file_content, &funcs) {
+        return (Some(info), Err(Vec::new()));
+    }
+
+    match config.backend.as_str() {
+        // generating and writing are interleaved, so they're timed together
+        "default" if config.streaming => {
+            let start = Instant::now();
+            let streamed = DefaultBackend::stream(config, &funcs, &mut timings);
+            timings.record("streaming", start);
+
+            if let Err(internal_errors) = streamed {
+                for err in internal_errors {
+                    println!("{}", err);
+                }
+
+                return (Some(info), Err(Vec::new()));
+            }
+        }
+        "default" => {
+            let start = Instant::now();
+            let generated = DefaultBackend::generate(config, &funcs, &mut timings);
+            timings.record("codegen", start);
+
+            let files = match generated {
+                Ok(files) => files,
+                Err(internal_errors) => {
+                    for err in internal_errors {
+                        println!("{}", err);
+                    }
+
+                    return (Some(info), Err(Vec::new()));
+                }
+            };
+
+            if let Err(message) = check_scoreboards(config, &files) {
+                println!("{}", message);
+                return (Some(info), Err(Vec::new()));
+            }
+
+            let start = Instant::now();
+            DefaultBackend::write(files);
+            timings.record("output", start);
+        }
+        "dpc" => {
+            // the dpc backend generates and writes its output in one go
+            let start = Instant::now();
+            DPCBackend::compile(config, &funcs, &info.types);
+            timings.record("dpc", start);
+        }
+        _ => {
+            println!("unknown backend: {}", config.backend);
+            return (None, Err(Vec::new()));
+        }
+    }
+
+    if config.timings {
+        timings.print(path);
+    }
+
+    if !tolerated.is_empty() {
+        return (Some(info), Err(tolerated));
+    }
+
+    (Some(info), Ok(()))
+}
+
+// Checks a file without writing anything and prints a summary of it, see CodeGen::check_src. Returns whether it had no
+// errors
+fn check_file(config: &Config, registries: Option<&Registries>, path: &str) -> bool {
+    let src = match std::fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(err) => {
+            println!("failed to read file: {}", err);
+            return false;
+        }
+    };
+
+    let report = CodeGen::check_src(config, registries, path, &src, config.check_codegen);
+    let stats = &report.stats;
+    let ok = report.errors.is_empty() && report.internal_errors.is_empty();
+
+    let mut summary = format!(
+        "{}: {}, {} lines, {} functions, {} structs",
+        path,
+        match report.errors.len() + report.internal_errors.len() {
+            0 => "ok".fg(Color::Green).to_string(),
+            1 => "1 error".fg(Color::Red).to_string(),
+            n => format!("{} errors", n).fg(Color::Red).to_string(),
+        },
+        stats.lines,
+        stats.functions,
+        stats.structs
+    );
+
+    if let Some(commands) = stats.commands {
+        summary.push_str(&format!(", {} commands", commands));
+    }
+
+    for err in &report.internal_errors {
+        println!("{}", err);
+    }
+
+    if !report.errors.is_empty() {
+        let info = report.info;
+        error::print_reports(&info.sources, report.errors, &info.types, &info.signatures);
+    }
+
+    println!("{} in {:.1?}", summary, stats.duration);
+
+    ok
+}
+
+// Entry points are run by the load tag, which can't give them any arguments, so they have to be functions of the pack
+// that take none. Functions of modules and overloads are named like their objectives, e.g util.setup or reset.int
+fn check_entry_points(config: &Config, signatures: &HashMap<ResourceLocation, FunctionSignature>) -> Result<(), String> {
+    for name in &config.entry {
+        match signatures.get(&ResourceLocation::new(config.pack.clone(), name.clone())) {
+            None => return Err(format!("entry point '{}' is not a function of the pack", name)),
+            Some(signature) if !signature.params().is_empty() => {
+                return Err(format!("entry point '{}' cannot be run on load because it takes parameters", name))
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Writes the scoreboard report of the generated files if one was asked for, and checks them against the reports of
+// other packs. Every collision is reported before failing, so they can all be dealt with at once
+fn check_scoreboards(config: &Config, files: &[(PathBuf, String)]) -> Result<(), String> {
+    if config.scoreboard_report.is_none() && config.collisions_with.is_empty() {
+        return Ok(());
+    }
+
+    let report = ScoreboardReport::new(&config.pack, files);
+
+    if let Some(path) = &config.scoreboard_report {
+        std::fs::write(path, report.to_json()).map_err(|err| format!("failed to write scoreboard report: {}", err))?;
+    }
+
+    let mut messages = Vec::new();
+
+    for path in &config.collisions_with {
+        let other = ScoreboardReport::load(path)?;
+
+        for collision in report.collisions(&other) {
+            messages.push(match collision.fake_players.is_empty() {
+                true => format!("objective {} is also used by pack {}", collision.objective, other.pack()),
+                false => format!(
+                    "objective {} is also used by pack {}, which uses the same fake players: {}",
+                    collision.objective,
+                    other.pack(),
+                    collision.fake_players.join(", ")
+                ),
+            });
+        }
+    }
+
+    match messages.is_empty() {
+        true => Ok(()),
+        false => Err(format!("scoreboard collisions found:\n  {}", messages.join("\n  "))),
+    }
+}
+
+// The IR of a program, along with the validation errors of the functions that were left out of it
+type PartialIr = (Info, Vec<IrFunction>, Vec<CompileError>);
+
+// Runs every phase up to and including IR generation. With --partial, the validation errors that only affect some
+// functions are given back alongside the IR of the rest
+fn compile_to_ir(
+    config: &Config,
+    registries: Option<&Registries>,
+    path: &str,
+    src: &str,
+    timings: &mut Timings,
+) -> Result<PartialIr, (Info, Vec<CompileError>)> {
+    let mut errors = Vec::new();
+
+    // the parser lexes tokens as it needs them, so the time spent lexing is taken out of the time parsing took
+    let start = Instant::now();
+    let (parser_output, sources) = modules::parse_program(path, src.to_string());
+    let parsing = start.elapsed();
+    timings.record_duration("lexing", parser_output.lexing);
+    timings.record_duration("parsing", parsing.saturating_sub(parser_output.lexing));
+
+    errors.extend(parser_output.errors.into_iter().map(CompileError::Parse));
+    let parsed = errors.is_empty();
+
+    let start = Instant::now();
+    let mut validator = Validator::new(config.pack.clone(), registries, safety::expand_forbidden(&config.forbid));
+    validator.set_modules(sources.modules());
+    validator.set_target(config.pack_format, config.release);
+    validator.set_loop_budget(config.loop_budget.is_some());
+    let validator_output = validator.validate_program(&parser_output.ast);
+    timings.record("validation", start);
+
+    errors.extend(
+        validator_output
+            .errors
+            .into_iter()
+            .map(CompileError::Validate),
+    );
+
+    // an AST with parse errors may be missing parts of any function, so only validation errors can be tolerated
+    let skipped = match errors.is_empty() {
+        true => None,
+        false if config.partial && parsed => affected_functions(parser_output.ast.as_program(), &validator_output.tags, &errors),
+        false => None,
+    };
+
+    if !errors.is_empty() && skipped.is_none() {
+        return Err((
+            Info {
+                types: validator_output.types,
+                signatures: validator_output.global_functions,
+                sources,
+            },
+            errors,
+        ));
+    }
+
+    let mut ir_compiler = IrCompiler::new(
+        config.pack.clone(),
+        sources.src(),
+        validator_output.types,
+        validator_output.global_functions,
+        validator_output.tags,
+        validator_output.tables,
+        config.loop_budget,
+    );
+
+    if let Some(skipped) = skipped {
+        ir_compiler.skip_functions(skipped);
+    }
+
+    ir_compiler.set_entry_points(config.entry.clone());
+    ir_compiler.set_pack_format(config.pack_format);
+    ir_compiler.set_release(config.release);
+
+    let start = Instant::now();
+    ir_compiler.visit_program(parser_output.ast.as_program());
+    timings.record("ir", start);
+
+    let (signatures, types, _, funcs) = ir_compiler.dissolve();
+
+    Ok((Info { types, signatures, sources }, funcs, errors))
+}
+
+// Finds the functions and methods that contain errors, named like their objectives. Gives back None if any error is
+// outside of a function, since it could affect the whole program, e.g a struct with a field of an unknown type
+fn affected_functions<'a>(
+    program: &'a [ParserNode],
+    tags: &TagPool<'a>,
+    errors: &[CompileError],
+) -> Option<HashSet<String>> {
+    let functions = program
+        .iter()
+        .flat_map(|node| match node.kind() {
+            // every instance of a generic function shares its body, so an error in one can't be left out on its own
+            ParserNodeKind::FunctionDeclaration { type_params, .. } if !type_params.is_empty() => Vec::new(),
+            ParserNodeKind::FunctionDeclaration { name, .. } => {
+                vec![(tags.find_function_name(node).unwrap_or(name).to_string(), node.span())]
+            }
+            ParserNodeKind::StructDefinition { name, members, .. } => members
+                .iter()
+                .filter(|member| member.is_func_declaration())
+                .map(|method| (format!("{}.{}", name, method.as_func_name()), method.span()))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut affected = HashSet::new();
+
+    for error in errors {
+        let span = match error {
+            CompileError::Validate(error) => &error.span,
+            CompileError::Parse(_) => return None,
+        };
+
+        let (name, _) = functions
+            .iter()
+            .find(|(_, function)| function.start <= span.start && span.end <= function.end)?;
+
+        affected.insert(name.clone());
+    }
+
+    Some(affected)
+}
+
+// Compiles the source a second time from scratch and checks that the output is byte-for-byte identical
+fn verify_reproducible(
+    config: &Config,
+    registries: Option<&Registries>,
+    path: &str,
+    src: &str,
+    funcs: &[IrFunction],
+) -> bool {
+    if config.backend != "default" {
+        println!("--verify-reproducible is only supported by the default backend");
+        return false;
+    }
+
+    let second_funcs = match compile_to_ir(config, registries, path, src, &mut Timings::default()) {
+        Ok((_, funcs, _)) => funcs,
+        Err(_) => {
+            println!("output is not reproducible: the second compilation failed");
+            return false;
+        }
+    };
+
+    // mistakes in the generated code are reported when the pack is generated for real
+    let first = DefaultBackend::generate(config, funcs, &mut Timings::default()).unwrap_or_default();
+    let second = DefaultBackend::generate(config, &second_funcs, &mut Timings::default()).unwrap_or_default();
+
+    if first == second {
+        return true;
+    }
+
+    println!("output is not reproducible, the following files differ between two compilations:");
+
+    for path in first
+        .iter()
+        .chain(second.iter())
+        .filter(|file| !first.contains(file) || !second.contains(file))
+        .map(|(path, _)| path)
+        .unique()
+    {
+        println!("  {}", path.display());
+    }
+
+    false
+}
+
+fn dump_ir(
+    config: &Config,
+    types: &TypePool,
+    signatures: &HashMap<ResourceLocation, FunctionSignature>,
+    funcs: &[IrFunction],
+) {
+    let mut s = String::new();
+
+    for func in funcs {
+        s.push_str(format!("fn {}", func.objective(),).as_str());
+
+        for instr in func.body() {
+            s.push_str(format!("\n{}", instr).as_str());
+        }
+    }
+
+    if let Err(err) = std::fs::write("ir_dump.txt", s) {
+        println!("failed to dump ir: {}", err);
+    }
+}
+
+pub struct Info {
+    types: TypePool,
+    signatures: HashMap<ResourceLocation, FunctionSignature>,
+    sources: Sources,
+}
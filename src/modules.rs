@@ -0,0 +1,170 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::parser::{ParseError, Parser, ParserNode, ParserNodeKind, ParserOutput};
+
+/// The extension of sculk files, which the path of an import may leave out.
+const EXTENSION: &str = "sculk";
+
+/// The sources of every file that makes up a program: the file being compiled, followed by the files that it imports,
+/// directly or not. Each file is parsed on its own, but the spans of their nodes are offsets into the source of the
+/// whole program, which is the sources of the files one after another, so the rest of the compiler can treat the
+/// files as one.
+pub struct Sources {
+    src: String,
+    files: Vec<SourceFile>,
+}
+
+/// A file of a program, see `Sources`.
+pub struct SourceFile {
+    pub path: String,
+    /// Where the file's source is in the source of the whole program.
+    pub span: Range<usize>,
+    /// The module that the file's functions are namespaced by, e.g "util.math" for util/math.sculk, which is None for
+    /// the file being compiled.
+    pub module: Option<String>,
+}
+
+impl Sources {
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn text(&self, file: &SourceFile) -> &str {
+        &self.src[file.span.clone()]
+    }
+
+    /// The file that an offset into the source of the whole program is in.
+    pub fn file_at(&self, offset: usize) -> &SourceFile {
+        self.files
+            .iter()
+            .rev()
+            .find(|file| file.span.start <= offset)
+            .unwrap_or(&self.files[0])
+    }
+
+    /// The modules of the files that were imported and the spans of their sources, see `Validator::set_modules`.
+    pub fn modules(&self) -> Vec<(String, Range<usize>)> {
+        self.files
+            .iter()
+            .filter_map(|file| Some((file.module.clone()?, file.span.clone())))
+            .collect()
+    }
+}
+
+/// Parses the file at the given path, whose source has already been read, along with every file that it imports.
+/// Imports are relative to the file they're in, and a file is only included once however many times it's imported.
+/// Files that can't be imported are reported as parse errors at their imports.
+pub fn parse_program(path: &str, src: String) -> (ParserOutput, Sources) {
+    let root_dir = directory_of(Path::new(path)).canonicalize();
+
+    let mut sources = Sources {
+        src: String::new(),
+        files: Vec::new(),
+    };
+
+    let mut nodes = Vec::new();
+    let mut errors = Vec::new();
+    let mut lexing = Duration::ZERO;
+    let mut loaded = HashSet::new();
+    let mut pending = VecDeque::from([(PathBuf::from(path), None, src)]);
+
+    if let Ok(root) = Path::new(path).canonicalize() {
+        loaded.insert(root);
+    }
+
+    while let Some((file, module, src)) = pending.pop_front() {
+        let offset = sources.src.len();
+
+        sources.src.push_str(&src);
+        // keeps the last token of a file from running into the first token of the next
+        sources.src.push('\n');
+
+        sources.files.push(SourceFile {
+            path: file.display().to_string(),
+            span: offset..offset + src.len(),
+            module,
+        });
+
+        let output = Parser::new_at(&src, offset).parse();
+        errors.extend(output.errors);
+        lexing += output.lexing;
+
+        for node in output.ast.as_program() {
+            if let ParserNodeKind::Import(import) = node.kind() {
+                let imported = root_dir
+                    .as_ref()
+                    .map_err(|_| "the directory of the file being compiled cannot be read".to_string())
+                    .and_then(|root_dir| resolve_import(root_dir, &file, import));
+
+                match imported {
+                    Ok((path, canonical, module)) if loaded.insert(canonical.clone()) => match std::fs::read_to_string(&path) {
+                        Ok(src) => pending.push_back((path, Some(module), src)),
+                        Err(err) => errors.push(ParseError::new(format!("failed to read file: {}", err), node.span())),
+                    },
+                    Ok(_) => {}
+                    Err(message) => errors.push(ParseError::new(message, node.span())),
+                }
+            }
+
+            nodes.push(node.clone());
+        }
+    }
+
+    let ast = ParserNode::new(ParserNodeKind::Program(nodes), 0..sources.src.len());
+
+    (ParserOutput { ast, errors, lexing }, sources)
+}
+
+// Finds the file that an import refers to, giving back its path as it's shown in errors, its canonical path, which
+// tells files apart however they're imported, and the name of its module, which is its path from the directory of the
+// file being compiled
+fn resolve_import(root_dir: &Path, importer: &Path, import: &str) -> Result<(PathBuf, PathBuf, String), String> {
+    let mut path = match importer.parent() {
+        Some(dir) => dir.join(import),
+        None => PathBuf::from(import),
+    };
+
+    if path.extension().is_none() {
+        path.set_extension(EXTENSION);
+    }
+
+    let canonical = path
+        .canonicalize()
+        .map_err(|_| format!("cannot find the file '{}'", path.display()))?;
+
+    let relative = canonical
+        .strip_prefix(root_dir)
+        .map_err(|_| "only files in the directory of the file being compiled, or below it, can be imported".to_string())?;
+
+    let segments = relative
+        .with_extension("")
+        .iter()
+        .map(|segment| segment.to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+
+    for segment in &segments {
+        if !segment
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "'{}' cannot be the name of a module, as they may only contain lowercase letters, digits, underscores and hyphens",
+                segment
+            ));
+        }
+    }
+
+    Ok((path, canonical, segments.join(".")))
+}
+
+fn directory_of(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
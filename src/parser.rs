@@ -1,6 +1,9 @@
-use std::{collections::HashMap, fmt::Display, ops::Range, process::Output};
+use std::{collections::HashMap, fmt::Display, ops::Range, process::Output, time::Duration};
 
-use crate::lexer::{Token, TokenStream};
+use crate::{
+    consteval::{self, ConstEvalErrorKind, Value},
+    lexer::{Token, TokenStream},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Operation {
@@ -19,6 +22,11 @@ pub enum Operation {
     Negate,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl Display for Operation {
@@ -39,12 +47,35 @@ impl Display for Operation {
             Operation::Negate => "-",
             Operation::And => "&&",
             Operation::Or => "||",
+            Operation::BitAnd => "&",
+            Operation::BitOr => "|",
+            Operation::BitXor => "^",
+            Operation::ShiftLeft => "<<",
+            Operation::ShiftRight => ">>",
         };
 
         write!(f, "{}", op)
     }
 }
 
+/// Where the data of an NBT path is, which is written the way commands like `data get` take it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataSource {
+    // the resource location of the storage, e.g `pack:config`
+    Storage(String),
+    // the selector of the entity, e.g `@s`
+    Entity(String),
+}
+
+impl Display for DataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataSource::Storage(storage) => write!(f, "storage {}", storage),
+            DataSource::Entity(selector) => write!(f, "entity {}", selector),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ParserNode {
     kind: ParserNodeKind,
@@ -71,6 +102,7 @@ pub enum ParserNodeKind {
     Block(Vec<ParserNode>),
     NumberLiteral(i32),
     BoolLiteral(bool),
+    StringLiteral(String),
     Identifier(String),
     TypedIdentifier {
         name: String,
@@ -81,22 +113,143 @@ pub enum ParserNodeKind {
         expr: Box<ParserNode>,
         ty: Option<String>,
     },
+    // a top-level variable that every player has their own copy of, e.g `let deaths: int per player;`. Its objective,
+    // which is prefixed with the pack's name like `pack.deaths`, can be given a display name, which is either text or a
+    // JSON text component, e.g `... = 0 named "Deaths";`
+    PlayerVariableDeclaration {
+        name: String,
+        ty: String,
+        default: Option<Box<ParserNode>>,
+        display: Option<String>,
+    },
+    // a top-level value that server admins can change in storage without recompiling, e.g `config max_homes: int = 3;`
+    Config {
+        name: String,
+        ty: String,
+        default: Box<ParserNode>,
+    },
+    // a condition that has to hold for the program to compile, which is checked at compile time, e.g
+    // `static_assert(max_players > 0, "there has to be room for a player");`. It can be top-level or a statement
+    StaticAssert {
+        expr: Box<ParserNode>,
+        message: String,
+    },
+    // a top-level import of another file, e.g `import "util/math";`, which is resolved before validation
+    Import(String),
+    // a top-level block that runs every n ticks, e.g `every(20) { ... }`
+    Every {
+        ticks: i32,
+        body: Box<ParserNode>,
+    },
+    // a top-level group of states, only one of which is active at a time
+    StateMachine {
+        name: String,
+        states: Vec<ParserNode>,
+        transitions: Vec<ParserNode>,
+    },
+    State {
+        name: String,
+        on_enter: Option<Box<ParserNode>>,
+        on_exit: Option<Box<ParserNode>>,
+    },
+    // a named box between two corners that runs handlers when players walk into or out of it
+    Region {
+        name: String,
+        from: (i32, i32, i32),
+        to: (i32, i32, i32),
+        on_enter: Option<Box<ParserNode>>,
+        on_leave: Option<Box<ParserNode>>,
+    },
+    // a top-level list of ints that is computed at compile time and stored when the pack loads,
+    // e.g `table SQUARES[i; 16] = i * i;` or `table PRIMES = [2, 3, 5, 7];`
+    Table {
+        name: String,
+        contents: TableContents,
+    },
+    // a top-level list of sub-commands that players run with `/trigger <pack> set <n>`, and that can be run with
+    // `/function <pack>:zz_commands/run {command:"<name>"}`, e.g `commands { heal => heal_player "restores your health"; }`
+    Commands(Vec<ParserNode>),
+    // maps a sub-command to the function it runs, optionally with a description for the help message
+    Subcommand {
+        name: String,
+        function: String,
+        description: Option<String>,
+    },
+    // reads an element of a table or an array, e.g `SQUARES[n]`
+    Index {
+        expr: Box<ParserNode>,
+        index: Box<ParserNode>,
+    },
+    // e.g `[1, 2, x]`, whose elements all have the same type
+    ArrayLiteral(Vec<ParserNode>),
+    // e.g `(1, true)`, which has at least two elements of any type
+    Tuple(Vec<ParserNode>),
+    // declares that a state machine may go from one state to another, e.g `idle -> fighting;`
+    StateTransition {
+        from: String,
+        to: String,
+    },
+    // moves a state machine to another state, e.g `boss -> fighting;`
+    ChangeState {
+        machine: String,
+        state: String,
+    },
+    // formatted text, e.g `text!{"Hello ", score(x), bold}`, whose parts are shown one after another and whose styles
+    // apply to all of them
+    TextLiteral {
+        parts: Vec<ParserNode>,
+        styles: Vec<String>,
+    },
+    // an int in the data of a storage or an entity, e.g `data storage pack:config max.health`, which can be read like a
+    // variable and assigned to
+    DataPath {
+        source: DataSource,
+        path: String,
+    },
     VariableAssignment {
         path: Box<ParserNode>,
         expr: Box<ParserNode>,
     },
+    // declares a variable for each element of a tuple, e.g `let (a, b) = f();`, where an element named _ is skipped
+    TupleDeclaration {
+        names: Vec<String>,
+        expr: Box<ParserNode>,
+    },
     FunctionDeclaration {
         name: String,
+        // the names of the types a generic function is declared over, e.g `fn max<T>(a: T, b: T) -> T`, which it's
+        // compiled once for each combination of types it's called with
+        type_params: Vec<String>,
+        // the traits that the types of a generic function's type parameters have to implement, as the type parameter
+        // and the trait, e.g `fn tick_all<T: Tickable>(a: T, b: T)`
+        bounds: Vec<(String, String)>,
         args: Vec<ParserNode>,
         return_ty: Option<String>,
         body: Box<ParserNode>,
         is_static: bool,
+        // declared with `pub`, e.g `pub fn reset()`, see FunctionAttributes::public
+        is_public: bool,
+        attributes: Vec<ParserNode>,
+    },
+    Attribute {
+        name: String,
+        args: Vec<ParserNode>,
+    },
+    // an attribute argument given by name, e.g `item = "minecraft:stick"`
+    NamedArgument {
+        name: String,
+        value: Box<ParserNode>,
     },
     Return(Option<Box<ParserNode>>),
     FunctionCall {
         expr: Box<ParserNode>,
         args: Vec<ParserNode>,
     },
+    // a single call that runs as the entities a selector selects, e.g `run_as(@a) reset()`
+    RunAs {
+        selector: Box<ParserNode>,
+        call: Box<ParserNode>,
+    },
     Expression(Box<ParserNode>),
     Operation(Box<ParserNode>, Box<ParserNode>, Operation),
     OpEquals {
@@ -105,28 +258,179 @@ pub enum ParserNodeKind {
         op: Operation,
     },
     Unary(Box<ParserNode>, Operation),
+    // converts a value to another type, e.g `flag as int` or `n as long`
+    Cast {
+        expr: Box<ParserNode>,
+        ty: String,
+    },
     If {
         cond: Box<ParserNode>,
         body: Box<ParserNode>,
         else_ifs: Vec<(ParserNode, ParserNode)>,
         else_body: Option<Box<ParserNode>>,
     },
+    // runs the body with the value of an optional bound to a name if it's present, e.g `if let n = maybe { ... }`.
+    // An else if after it is kept as an if inside the else body
+    IfLet {
+        name: String,
+        expr: Box<ParserNode>,
+        body: Box<ParserNode>,
+        else_body: Option<Box<ParserNode>>,
+    },
+    // one of two values depending on a condition, where only the chosen one is evaluated, e.g `a > b ? a : b`
+    Conditional {
+        cond: Box<ParserNode>,
+        then: Box<ParserNode>,
+        otherwise: Box<ParserNode>,
+    },
+    // the value of an optional, or the default if it's missing, e.g `maybe ?? 0`
+    Coalesce {
+        expr: Box<ParserNode>,
+        default: Box<ParserNode>,
+    },
+    // a selector like `@e[type=zombie, distance=..5]`, whose value is known at compile time
+    SelectorLiteral(String),
+    // the missing value of an optional
+    Null,
+    // runs the first arm whose pattern matches the value of an int or enum expression,
+    // e.g `match x { 0 => ..., 1..5 => ..., 5..=9 => ..., _ => ... }` or `match c { Color.Red => ..., _ => ... }`
+    Match {
+        expr: Box<ParserNode>,
+        arms: Vec<(ArmPattern, ParserNode)>,
+    },
     For {
+        // e.g `#[spread(100)]`, which spreads the loop's iterations over several ticks
+        attributes: Vec<ParserNode>,
+        // e.g `'outer` in `'outer: for ...`, which a break can name to leave this loop from inside a nested one
+        label: Option<String>,
         init: Box<ParserNode>,
         cond: Box<ParserNode>,
         step: Box<ParserNode>,
         body: Box<ParserNode>,
     },
+    // e.g `as @a at @s { ... }`, which runs the body behind the execute subcommands, each of which is as or at and a
+    // selector, in the order they're written
+    ContextBlock {
+        subcommands: Vec<(String, String)>,
+        body: Box<ParserNode>,
+    },
+    // e.g `for e in @e[tag=enemy] { ... }`, which runs the body as and at each entity
+    ForEntities {
+        name: String,
+        selector: String,
+        body: Box<ParserNode>,
+    },
     StructDefinition {
         name: String,
+        // the names of the types a generic struct is declared over, e.g `struct Pair<T> { a: T b: T }`
+        type_params: Vec<String>,
+        // the traits the struct implements, e.g `struct Timer: Tickable { ... }`
+        traits: Vec<String>,
         members: Vec<ParserNode>,
+        // set for messages, which are structs that can be sent to other packs, e.g `message Damage { amount: int }`
+        is_message: bool,
+        // the selector of the entities an attached struct's fields are scores on, e.g `struct Enemy on @e[tag=enemy] { hp: int }`
+        entity: Option<String>,
+    },
+    // the methods that the structs that implement a trait have, e.g `trait Tickable { fn tick(); }`, whose calls are
+    // resolved to the method of a struct when a generic function is instantiated for it
+    TraitDefinition {
+        name: String,
+        methods: Vec<ParserNode>,
+    },
+    // a method of a trait, which is declared like a method of a struct without a body
+    MethodSignature {
+        name: String,
+        args: Vec<ParserNode>,
+        return_ty: Option<String>,
+    },
+    // e.g `enum Color { Red, Green, Blue }`, whose variants are stored as the ints 0, 1 and 2
+    EnumDefinition {
+        name: String,
+        variants: Vec<String>,
     },
     MemberAccess {
         expr: Box<ParserNode>,
         member: Box<ParserNode>,
     },
-    Break,
+    // the label of the loop to break out of, or None for the innermost one
+    Break(Option<String>),
+    // pauses the rest of the function for a number of ticks, e.g `await ticks(20);` or `await tick();`
+    Await(i32),
     CommandLiteral(String),
+    // a command literal used as a value, which is the command's result, e.g `let pigs = /execute if entity @e[type=pig];`.
+    // The command runs until the ; so it can only be the whole right side of a let or an assignment
+    CommandResult(String),
+}
+
+#[derive(Clone, Debug)]
+pub enum TableContents {
+    // every element is computed from its index, e.g `[i; 16] = i * i`
+    Generated {
+        index: String,
+        len: i32,
+        expr: Box<ParserNode>,
+    },
+    Listed(Vec<ParserNode>),
+}
+
+/// The pattern of a match arm, which is an inclusive range like the ones of `execute if score ... matches`.
+/// A single number has the same min and max, while an open end is unbounded, so `_` and `..` match every value.
+/// The end of a `a..b` pattern is left out of it, so its max is `b - 1`, while `a..=b` has a max of `b`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MatchPattern {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl MatchPattern {
+    pub fn matches_everything(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+}
+
+/// What a match arm is compared against, which is either a range of ints or a variant of an enum, e.g `Color.Red`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArmPattern {
+    Range(MatchPattern),
+    Variant { enum_name: String, variant: String },
+}
+
+impl ArmPattern {
+    pub fn matches_everything(&self) -> bool {
+        match self {
+            ArmPattern::Range(range) => range.matches_everything(),
+            ArmPattern::Variant { .. } => false,
+        }
+    }
+}
+
+impl Display for MatchPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            (min, max) => write!(
+                f,
+                "{}..{}",
+                min.map_or(String::new(), |min| min.to_string()),
+                max.map_or(String::new(), |max| max.to_string())
+            ),
+        }
+    }
+}
+
+// The pattern as it's written in the source, e.g `_`, `3..=7` or `Color.Red`
+impl Display for ArmPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArmPattern::Range(range) if range.matches_everything() => write!(f, "_"),
+            ArmPattern::Range(MatchPattern { min, max: Some(max) }) if *min != Some(*max) => {
+                write!(f, "{}..={}", min.map_or(String::new(), |min| min.to_string()), max)
+            }
+            ArmPattern::Range(range) => write!(f, "{}", range),
+            ArmPattern::Variant { enum_name, variant } => write!(f, "{}.{}", enum_name, variant),
+        }
+    }
 }
 
 impl ParserNodeKind {
@@ -175,6 +479,14 @@ impl ParserNode {
         }
     }
 
+    // strips away the expression nodes wrapping a value, e.g Expression(Expression(Identifier)) -> Identifier
+    pub fn unwrap_expression(&self) -> &ParserNode {
+        match &self.kind {
+            ParserNodeKind::Expression(expr) => expr.unwrap_expression(),
+            _ => self,
+        }
+    }
+
     pub fn as_identifier(&self) -> &str {
         match &self.kind {
             ParserNodeKind::Identifier(name) => &name,
@@ -211,6 +523,14 @@ impl ParserNode {
         }
     }
 
+    /// The expression that a member is accessed on, e.g "p" in "p.x"
+    pub fn as_member_receiver(&self) -> &ParserNode {
+        match &self.kind {
+            ParserNodeKind::MemberAccess { expr, .. } => expr,
+            _ => panic!("tried to get member receiver from non-member-access node"),
+        }
+    }
+
     pub fn as_func_name(&self) -> &str {
         match &self.kind {
             ParserNodeKind::FunctionDeclaration { name, .. } => &name,
@@ -224,6 +544,16 @@ impl ParserNode {
             _ => panic!("tried to get function body from non-function node"),
         }
     }
+
+    /// Whether the function was declared with the attribute of the given name, e.g `weak` for `#[weak]`.
+    pub fn has_attribute(&self, name: &str) -> bool {
+        match &self.kind {
+            ParserNodeKind::FunctionDeclaration { attributes, .. } | ParserNodeKind::For { attributes, .. } => attributes
+                .iter()
+                .any(|attribute| matches!(&attribute.kind, ParserNodeKind::Attribute { name: applied, .. } if applied == name)),
+            _ => panic!("tried to get attributes from a node that can't have them"),
+        }
+    }
 }
 
 macro_rules! expect_tok {
@@ -235,18 +565,35 @@ macro_rules! expect_tok {
     };
 }
 
+/// How many nodes may be nested inside each other, e.g through parentheses or blocks. Parsing is recursive,
+/// so without a limit a long enough run of `(` would overflow the stack.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+/// The style flags that text literals take, which are the names of the keys that JSON text sets them with.
+const TEXT_STYLES: [&str; 5] = ["bold", "italic", "underlined", "strikethrough", "obfuscated"];
+
 pub struct Parser<'a> {
     tokens: TokenStream<'a>,
     errors: Vec<ParseError>,
     current_node_starts: Vec<usize>,
+    // set when the >> that closes nested type arguments, e.g `Pair<Box<int>>`, has been consumed by the inner ones, so
+    // the outer ones are already closed
+    closed_type_args: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(src: &'a str) -> Self {
+        Self::new_at(src, 0)
+    }
+
+    /// Creates a parser for a source that starts at the given offset in the source of the whole program, which the
+    /// spans of its nodes and errors are relative to.
+    pub fn new_at(src: &'a str, offset: usize) -> Self {
         Self {
-            tokens: TokenStream::new(src),
+            tokens: TokenStream::new(src, offset),
             errors: Vec::new(),
             current_node_starts: Vec::new(),
+            closed_type_args: false,
         }
     }
 
@@ -257,7 +604,11 @@ impl<'a> Parser<'a> {
         self.current_node_starts
             .push(self.tokens.peeked_span().start);
 
-        let result = parser(self);
+        let result = if self.current_node_starts.len() > MAX_NESTING_DEPTH {
+            self.error("too deeply nested")
+        } else {
+            parser(self)
+        };
 
         let node_span = self.current_node_starts.pop().unwrap()..self.tokens.current_span().end;
 
@@ -272,7 +623,7 @@ impl<'a> Parser<'a> {
 
         while self.tokens.peek().is_some() {
             match self.tokens.peek().unwrap() {
-                Token::Fn | Token::Static => match self.call(Self::parse_func_declaration) {
+                Token::Fn | Token::Static | Token::Pound | Token::Identifier("pub") => match self.call(Self::parse_func_declaration) {
                     Ok(stmt) => nodes.push(stmt),
                     Err(_) => continue, // error already logged, continue parsing
                 },
@@ -280,6 +631,54 @@ impl<'a> Parser<'a> {
                     Ok(stmt) => nodes.push(stmt),
                     Err(_) => continue, // error already logged, continue parsing
                 },
+                Token::Let => match self.call(Self::parse_player_var_declaration) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("config") => match self.call(Self::parse_config) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("every") => match self.call(Self::parse_every) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("statemachine") => match self.call(Self::parse_state_machine) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("region") => match self.call(Self::parse_region) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("table") => match self.call(Self::parse_table) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("message") => match self.call(Self::parse_message) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("enum") => match self.call(Self::parse_enum_definition) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("trait") => match self.call(Self::parse_trait_definition) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("commands") => match self.call(Self::parse_commands) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("static_assert") => match self.call(Self::parse_static_assert) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
+                Token::Identifier("import") => match self.call(Self::parse_import) {
+                    Ok(stmt) => nodes.push(stmt),
+                    Err(_) => continue, // error already logged, continue parsing
+                },
                 _ => {
                     self.error_at("unexpected token or symbol", self.tokens.peeked_span());
                     continue;
@@ -288,11 +687,28 @@ impl<'a> Parser<'a> {
         }
 
         ParserOutput::new(
-            ParserNode::new(ParserNodeKind::Program(nodes), 0..self.tokens.src_len()),
+            ParserNode::new(
+                ParserNodeKind::Program(nodes),
+                self.tokens.offset()..self.tokens.offset() + self.tokens.src_len(),
+            ),
             self.errors,
+            self.tokens.lexing(),
         )
     }
 
+    /// Parses the program like `parse`, but only returns the AST if there were no errors, since the AST of a program
+    /// with errors is missing the parts that failed to parse. This never panics, however malformed the source is,
+    /// so it's safe to run on source code submitted by users.
+    pub fn try_parse(self) -> Result<ParserNode, Vec<ParseError>> {
+        let output = self.parse();
+
+        if output.errors.is_empty() {
+            Ok(output.ast)
+        } else {
+            Err(output.errors)
+        }
+    }
+
     fn parse_block(&mut self) -> ParserKindResult {
         expect_tok!(self, Token::LeftBrace, "expected {");
 
@@ -308,26 +724,90 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_path(&mut self) -> ParserKindResult {
+        // "data" is only a keyword when it's followed by where the data is, so it can still be the name of a variable
+        if self.tokens.peek() == Some(&Token::Identifier("data"))
+            && matches!(self.tokens.peek_second(), Some(Token::Identifier("storage" | "entity")))
+        {
+            return self.parse_data_path();
+        }
+
         let mut identifier = self.call(Self::parse_identifier)?;
 
         while self.tokens.peek().is_some() {
             match self.tokens.peek().unwrap() {
                 Token::LeftParens => identifier = self.call(|s| s.parse_func_call(identifier))?,
                 Token::Dot => identifier = self.call(|s| s.parse_member_access(identifier))?,
+                Token::LeftBracket => identifier = self.call(|s| s.parse_index(identifier))?,
                 _ => break,
             }
         }
 
+        // `run_as(@a) reset()` is told apart from a call to a function named run_as by the call that follows it
+        if let ParserNodeKind::FunctionCall { expr, args } = &mut identifier.kind {
+            if matches!(expr.kind(), ParserNodeKind::Identifier(name) if name == "run_as")
+                && matches!(self.tokens.peek(), Some(Token::Identifier(_)))
+            {
+                if args.len() != 1 {
+                    return self.error("expected a single selector in run_as(...)");
+                }
+
+                let selector = args.pop().unwrap();
+                let call = self.call(Self::parse_path)?;
+
+                if !call.is_call() {
+                    return self.error("expected a function call after run_as(...)");
+                }
+
+                return Ok(ParserNodeKind::RunAs {
+                    selector: Box::new(selector),
+                    call: Box::new(call),
+                });
+            }
+        }
+
         Ok(identifier.kind)
     }
 
     fn parse_statement_inner(&mut self) -> ParserKindResult {
+        // as and at only start a block when a selector follows them, so they can still be the names of variables
+        if matches!(self.tokens.peek(), Some(Token::Identifier("as" | "at")))
+            && matches!(self.tokens.peek_second(), Some(Token::Selector(_)))
+        {
+            return self.parse_context_block();
+        }
+
+        // match, await and static_assert are lexed as identifiers and are only keywords when the token after them can't
+        // go on a statement that starts with a variable, so they can still be the names of variables like as and at
+        let second = self.tokens.peek_second();
+        let continues_path = matches!(
+            second,
+            Some(
+                Token::Equals
+                    | Token::AddEquals
+                    | Token::SubtractEquals
+                    | Token::MultiplyEquals
+                    | Token::DivideEquals
+                    | Token::ModuloEquals
+                    | Token::Increment
+                    | Token::Decrement
+                    | Token::Arrow
+                    | Token::Dot
+                    | Token::LeftBracket
+                    | Token::Semicolon
+            )
+        );
+
         match self.tokens.peek() {
             Some(Token::Let) => self.parse_var_declaration(),
             Some(Token::Fn) => self.parse_func_declaration(),
+            Some(Token::Pound) => self.parse_attributed_statement(),
             Some(Token::Struct) => self.parse_struct_definition(),
             Some(Token::If) => self.parse_if(),
-            Some(Token::For) => self.parse_for(),
+            Some(Token::Identifier("match")) if !continues_path => self.parse_match(),
+            // await is always followed by tick() or ticks(n)
+            Some(Token::Identifier("await")) if matches!(second, Some(Token::Identifier(_))) => self.parse_await(),
+            Some(Token::Identifier("static_assert")) if second == Some(Token::LeftParens) => self.parse_static_assert(),
+            Some(Token::For) | Some(Token::Label(_)) => self.parse_for(Vec::new()),
             Some(Token::LeftBrace) => self.parse_block(),
             Some(Token::Return) => self.parse_return_statement(),
             Some(Token::Break) => self.parse_break_statement(),
@@ -335,23 +815,26 @@ impl<'a> Parser<'a> {
                 let path = self.call(Self::parse_path)?;
 
                 match self.tokens.peek() {
-                    Some(Token::Semicolon) => {
-                        if !path.is_call() {
+                    // a match arm's statement ends with , or } instead, while other statements still have to end with ;
+                    Some(Token::Semicolon) | Some(Token::Comma) | Some(Token::RightBrace) => {
+                        if !path.is_call() && !matches!(path.kind(), ParserNodeKind::RunAs { .. }) {
                             self.error("expected a function call before ;")
                         } else {
                             Ok(path.kind)
                         }
                     }
                     Some(Token::Equals) => self.parse_var_assignment(path),
+                    Some(Token::Arrow) => self.parse_state_change(path),
                     Some(Token::AddEquals)
                     | Some(Token::SubtractEquals)
                     | Some(Token::MultiplyEquals)
                     | Some(Token::DivideEquals)
                     | Some(Token::ModuloEquals) => self.parse_op_equals(path),
+                    Some(Token::Increment) | Some(Token::Decrement) => self.parse_increment(path),
                     _ => self.error("expected statement"),
                 }
             }
-            Some(Token::Slash) => self.parse_command_literal(),
+            Some(Token::Slash) => self.parse_command_literal(false),
             _ => self.error("expected statement"),
         }
     }
@@ -361,10 +844,15 @@ impl<'a> Parser<'a> {
 
         match stmt {
             ParserNodeKind::For { .. }
+            | ParserNodeKind::ForEntities { .. }
+            | ParserNodeKind::ContextBlock { .. }
             | ParserNodeKind::If { .. }
+            | ParserNodeKind::IfLet { .. }
+            | ParserNodeKind::Match { .. }
             | ParserNodeKind::FunctionDeclaration { .. }
             | ParserNodeKind::StructDefinition { .. }
             | ParserNodeKind::CommandLiteral(_) // command literals are a special case and handle the semicolon themselves
+            | ParserNodeKind::StaticAssert { .. } // as do static asserts, which can be top-level
             | ParserNodeKind::Block(_) => Ok(stmt),
             _ => {
                 expect_tok!(self, Token::Semicolon, "expected ;");
@@ -376,173 +864,914 @@ impl<'a> Parser<'a> {
     fn parse_var_declaration(&mut self) -> ParserKindResult {
         expect_tok!(self, Token::Let, "expected let");
 
+        if self.tokens.peek() == Some(&Token::LeftParens) {
+            return self.parse_tuple_declaration();
+        }
+
         let identifier = self.call(|parser| parser.parse_typed_identifier(true))?;
 
         expect_tok!(self, Token::Equals, "expected =");
 
-        let expr = self.call(Self::parse_expression)?;
+        let expr = self.call(Self::parse_assigned_value)?;
+
+        let ty = match identifier.kind() {
+            ParserNodeKind::TypedIdentifier { ty, .. } => Some(ty.clone()),
+            _ => None,
+        };
 
         Ok(ParserNodeKind::VariableDeclaration {
             name: Box::new(identifier),
             expr: Box::new(expr),
-            ty: None,
+            ty,
         })
     }
 
-    fn parse_var_assignment(&mut self, path: ParserNode) -> ParserKindResult {
+    fn parse_tuple_declaration(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut names = Vec::new();
+
+        loop {
+            match self.tokens.next() {
+                Some(Token::Identifier(name)) => names.push(name.to_string()),
+                _ => return self.error("expected the name of a variable"),
+            }
+
+            match self.tokens.next() {
+                Some(Token::Comma) => {}
+                Some(Token::RightParens) => break,
+                _ => return self.error("expected , or )"),
+            }
+        }
+
         expect_tok!(self, Token::Equals, "expected =");
 
         let expr = self.call(Self::parse_expression)?;
 
-        Ok(ParserNodeKind::VariableAssignment {
-            path: Box::new(path),
+        Ok(ParserNodeKind::TupleDeclaration {
+            names,
             expr: Box::new(expr),
         })
     }
 
-    fn parse_func_declaration(&mut self) -> ParserKindResult {
-        let is_static = match self.tokens.peek() {
-            Some(Token::Static) => {
-                self.tokens.next(); // consume the static
-                true
-            }
-            _ => false,
-        };
-
-        expect_tok!(self, Token::Fn, "expected fn");
-
-        let name = self.call(Self::parse_identifier)?;
+    fn parse_player_var_declaration(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Let, "expected let");
 
-        expect_tok!(self, Token::LeftParens, "expected (");
+        let (name, ty) = match self.parse_typed_identifier(false)? {
+            ParserNodeKind::TypedIdentifier { name, ty } => (name, ty),
+            _ => unreachable!(),
+        };
 
-        let mut args = Vec::new();
+        // "per" and "player" are only keywords in this position, so they're matched as identifiers
+        expect_tok!(self, Token::Identifier("per"), "expected 'per player' after the type of a top-level variable");
+        expect_tok!(self, Token::Identifier("player"), "expected 'player'");
 
-        if self.tokens.peek() != Some(&Token::RightParens) {
-            let arg = self.call(|parser| parser.parse_typed_identifier(false))?;
-            args.push(arg);
+        let default = match self.tokens.peek() {
+            Some(Token::Equals) => {
+                self.tokens.next();
 
-            while self.tokens.peek() == Some(&Token::Comma) {
-                self.tokens.next(); // consume the comma
-                let arg = self.call(|parser| parser.parse_typed_identifier(false))?;
-                args.push(arg);
+                Some(Box::new(self.call(|parser| match parser.tokens.peek() {
+                    Some(Token::Number(_)) => parser.parse_number(),
+                    Some(Token::Bool(_)) => parser.parse_bool(),
+                    _ => parser.error("expected a number or bool as the default value"),
+                })?))
             }
-        }
+            _ => None,
+        };
 
-        expect_tok!(self, Token::RightParens, "expected )");
+        let display = match self.tokens.peek() {
+            Some(Token::Identifier("named")) => {
+                self.tokens.next();
 
-        let return_ty = match self.tokens.peek() {
-            Some(Token::Arrow) => {
-                self.tokens.next(); // consume the arrow
-                let return_ty = self.call(Self::parse_identifier)?;
-                Some(return_ty.as_identifier().to_string())
+                match self.tokens.next() {
+                    Some(Token::String(display)) => Some(unescape_string(display)),
+                    _ => return self.error("expected the display name of the variable's objective"),
+                }
             }
             _ => None,
         };
 
-        let body = self.call(Self::parse_block)?;
-
-        let name = name.as_identifier().to_string();
+        expect_tok!(self, Token::Semicolon, "expected ;");
 
-        Ok(ParserNodeKind::FunctionDeclaration {
-            name: name.clone(),
-            args,
-            return_ty,
-            body: Box::new(body),
-            is_static
-        })
+        Ok(ParserNodeKind::PlayerVariableDeclaration { name, ty, default, display })
     }
 
-    fn parse_return_statement(&mut self) -> ParserKindResult {
-        expect_tok!(self, Token::Return, "expected return");
+    fn parse_config(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("config"), "expected config");
 
-        let expr = match self.tokens.peek() {
-            Some(Token::Semicolon) => None,
-            _ => Some(self.call(Self::parse_expression)?),
+        let (name, ty) = match self.parse_typed_identifier(false)? {
+            ParserNodeKind::TypedIdentifier { name, ty } => (name, ty),
+            _ => unreachable!(),
         };
 
-        Ok(ParserNodeKind::Return(expr.map(|expr| Box::new(expr))))
-    }
+        expect_tok!(self, Token::Equals, "expected a default value for the config value");
 
-    fn parse_break_statement(&mut self) -> ParserKindResult {
-        expect_tok!(self, Token::Break, "expected break");
+        let default = Box::new(self.call(|parser| match parser.tokens.peek() {
+            Some(Token::Number(_)) => parser.parse_number(),
+            Some(Token::Bool(_)) => parser.parse_bool(),
+            _ => parser.error("expected a number or bool as the default value"),
+        })?);
+
+        expect_tok!(self, Token::Semicolon, "expected ;");
 
-        Ok(ParserNodeKind::Break)
+        Ok(ParserNodeKind::Config { name, ty, default })
     }
 
-    fn parse_func_call(&mut self, callee: ParserNode) -> ParserKindResult {
+    fn parse_every(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("every"), "expected every");
         expect_tok!(self, Token::LeftParens, "expected (");
 
-        let mut args = Vec::new();
+        let ticks = match self.tokens.next() {
+            Some(Token::Number(n)) => *n,
+            _ => return self.error("expected the number of ticks between runs"),
+        };
 
-        if self.tokens.peek() != Some(&Token::RightParens) {
-            let arg = self.call(Self::parse_expression)?;
-            args.push(arg);
+        expect_tok!(self, Token::RightParens, "expected )");
 
-            while self.tokens.peek() == Some(&Token::Comma) {
-                self.tokens.next(); // consume the comma
-                let arg = self.call(Self::parse_expression)?;
-                args.push(arg);
-            }
-        }
+        let body = self.call(Self::parse_block)?;
+
+        Ok(ParserNodeKind::Every {
+            ticks,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_static_assert(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("static_assert"), "expected static_assert");
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let expr = self.call(Self::parse_expression)?;
+
+        expect_tok!(self, Token::Comma, "expected , followed by the message of the assertion");
+
+        let message = match self.tokens.next() {
+            Some(Token::String(message)) => unescape_string(message),
+            _ => return self.error("expected the message to give when the assertion fails"),
+        };
+
+        expect_tok!(self, Token::RightParens, "expected )");
+        expect_tok!(self, Token::Semicolon, "expected ;");
+
+        Ok(ParserNodeKind::StaticAssert {
+            expr: Box::new(expr),
+            message,
+        })
+    }
+
+    fn parse_import(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("import"), "expected import");
+
+        let path = match self.tokens.next() {
+            Some(Token::String(path)) => unescape_string(path),
+            _ => return self.error("expected the path of the file to import"),
+        };
+
+        expect_tok!(self, Token::Semicolon, "expected ;");
+
+        Ok(ParserNodeKind::Import(path))
+    }
+
+    fn parse_state_machine(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("statemachine"), "expected statemachine");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut states = Vec::new();
+        let mut transitions = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            match self.tokens.peek() {
+                Some(Token::Identifier("state")) => states.push(self.call(Self::parse_state)?),
+                Some(Token::Identifier(_)) => transitions.push(self.call(Self::parse_state_transition)?),
+                _ => return self.error("expected state, transition, or }"),
+            }
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::StateMachine {
+            name,
+            states,
+            transitions,
+        })
+    }
+
+    fn parse_state(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("state"), "expected state");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        let mut hooks = self.parse_hook_blocks(&["on_enter", "on_exit"])?;
+
+        Ok(ParserNodeKind::State {
+            name,
+            on_exit: hooks.pop().unwrap(),
+            on_enter: hooks.pop().unwrap(),
+        })
+    }
+
+    fn parse_region(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("region"), "expected region");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut coords = [0; 6];
+
+        for (i, coord) in coords.iter_mut().enumerate() {
+            if i > 0 {
+                expect_tok!(self, Token::Comma, "expected ,");
+            }
+
+            *coord = match self.tokens.next() {
+                Some(Token::Number(n)) => *n,
+                _ => return self.error("expected the coordinates of two corners, e.g (x1, y1, z1, x2, y2, z2)"),
+            };
+        }
+
+        expect_tok!(self, Token::RightParens, "expected )");
+
+        let mut hooks = self.parse_hook_blocks(&["on_enter", "on_leave"])?;
+
+        Ok(ParserNodeKind::Region {
+            name,
+            from: (coords[0], coords[1], coords[2]),
+            to: (coords[3], coords[4], coords[5]),
+            on_leave: hooks.pop().unwrap(),
+            on_enter: hooks.pop().unwrap(),
+        })
+    }
+
+    fn parse_table(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("table"), "expected table");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        let contents = match self.tokens.peek() {
+            Some(Token::LeftBracket) => {
+                self.tokens.next();
+
+                let index = self.parse_identifier()?.as_identifier().to_string();
+
+                expect_tok!(self, Token::Semicolon, "expected ;");
+
+                let len = match self.tokens.next() {
+                    Some(Token::Number(n)) => *n,
+                    _ => return self.error("expected the length of the table"),
+                };
+
+                expect_tok!(self, Token::RightBracket, "expected ]");
+                expect_tok!(self, Token::Equals, "expected =");
+
+                TableContents::Generated {
+                    index,
+                    len,
+                    expr: Box::new(self.call(Self::parse_expression)?),
+                }
+            }
+            Some(Token::Equals) => {
+                self.tokens.next();
+
+                expect_tok!(self, Token::LeftBracket, "expected [");
+
+                let mut values = Vec::new();
+
+                while self.tokens.peek() != Some(&Token::RightBracket) {
+                    values.push(self.call(Self::parse_expression)?);
+
+                    match self.tokens.peek() {
+                        Some(Token::Comma) => {
+                            self.tokens.next();
+                        }
+                        Some(Token::RightBracket) => {}
+                        _ => return self.error_at("expected , or ]", self.tokens.peeked_span()),
+                    }
+                }
+
+                self.tokens.next();
+
+                TableContents::Listed(values)
+            }
+            _ => return self.error_at("expected [index; length] or = after the name of a table", self.tokens.peeked_span()),
+        };
+
+        expect_tok!(self, Token::Semicolon, "expected ;");
+
+        Ok(ParserNodeKind::Table { name, contents })
+    }
+
+    fn parse_commands(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("commands"), "expected commands");
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut subcommands = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            match self.tokens.peek() {
+                Some(Token::Identifier(_)) => subcommands.push(self.call(Self::parse_subcommand)?),
+                _ => return self.error_at("expected a sub-command or }", self.tokens.peeked_span()),
+            }
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::Commands(subcommands))
+    }
+
+    fn parse_subcommand(&mut self) -> ParserKindResult {
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::FatArrow, "expected =>");
+
+        let function = self.parse_identifier()?.as_identifier().to_string();
+
+        let description = match self.tokens.peek() {
+            Some(Token::String(_)) => match self.parse_string()? {
+                ParserNodeKind::StringLiteral(description) => Some(description),
+                _ => unreachable!(),
+            },
+            _ => None,
+        };
+
+        expect_tok!(self, Token::Semicolon, "expected ;");
+
+        Ok(ParserNodeKind::Subcommand { name, function, description })
+    }
+
+    // parses a set of optional named blocks such as `{ on_enter { ... } on_exit { ... } }`, returned in the order of the given names
+    // errors are mapped from ParserKindResult since they never hold a value
+    fn parse_hook_blocks(&mut self, names: &[&str]) -> Result<Vec<Option<Box<ParserNode>>>, ()> {
+        if self.tokens.peek() != Some(&Token::LeftBrace) {
+            return self.error_at("expected {", self.tokens.peeked_span()).map(|_| Vec::new());
+        }
+
+        self.tokens.next();
+
+        let mut hooks = vec![None; names.len()];
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            let index = match self.tokens.next() {
+                Some(Token::Identifier(name)) => names.iter().position(|hook| hook == name),
+                _ => None,
+            };
+
+            let index = match index {
+                Some(index) => index,
+                None => return self.error(format!("expected {}, or }}", names.join(", "))).map(|_| Vec::new()),
+            };
+
+            if hooks[index].is_some() {
+                return self.error(format!("{} has already been declared", names[index])).map(|_| Vec::new());
+            }
+
+            hooks[index] = Some(Box::new(self.call(Self::parse_block)?));
+        }
+
+        self.tokens.next();
+
+        Ok(hooks)
+    }
+
+    fn parse_state_transition(&mut self) -> ParserKindResult {
+        let from = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::Arrow, "expected ->");
+
+        let to = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::Semicolon, "expected ;");
+
+        Ok(ParserNodeKind::StateTransition { from, to })
+    }
+
+    fn parse_state_change(&mut self, path: ParserNode) -> ParserKindResult {
+        let machine = match path.kind() {
+            ParserNodeKind::Identifier(name) => name.clone(),
+            _ => return self.error("expected the name of a state machine before ->"),
+        };
+
+        expect_tok!(self, Token::Arrow, "expected ->");
+
+        let state = self.parse_identifier()?.as_identifier().to_string();
+
+        Ok(ParserNodeKind::ChangeState { machine, state })
+    }
+
+    fn parse_var_assignment(&mut self, path: ParserNode) -> ParserKindResult {
+        expect_tok!(self, Token::Equals, "expected =");
+
+        let expr = self.call(Self::parse_assigned_value)?;
+
+        Ok(ParserNodeKind::VariableAssignment {
+            path: Box::new(path),
+            expr: Box::new(expr),
+        })
+    }
+
+    fn parse_func_declaration(&mut self) -> ParserKindResult {
+        let attributes = self.parse_attributes()?;
+        self.parse_func_declaration_with(attributes)
+    }
+
+    // Inside a function, attributes can go on a for loop as well as on a nested function
+    fn parse_attributed_statement(&mut self) -> ParserKindResult {
+        let attributes = self.parse_attributes()?;
+
+        match self.tokens.peek() {
+            Some(Token::For) | Some(Token::Label(_)) => self.parse_for(attributes),
+            _ => self.parse_func_declaration_with(attributes),
+        }
+    }
+
+    fn parse_attributes(&mut self) -> Result<Vec<ParserNode>, ()> {
+        let mut attributes = Vec::new();
+
+        while self.tokens.peek() == Some(&Token::Pound) {
+            attributes.push(self.call(Self::parse_attribute)?);
+        }
+
+        Ok(attributes)
+    }
+
+    fn parse_func_declaration_with(&mut self, attributes: Vec<ParserNode>) -> ParserKindResult {
+        let is_public = match self.tokens.peek() {
+            Some(Token::Identifier("pub")) => {
+                self.tokens.next(); // consume the pub
+                true
+            }
+            _ => false,
+        };
+
+        let is_static = match self.tokens.peek() {
+            Some(Token::Static) => {
+                self.tokens.next(); // consume the static
+                true
+            }
+            _ => false,
+        };
+
+        expect_tok!(self, Token::Fn, "expected fn");
+
+        let name = self.call(Self::parse_identifier)?;
+
+        let (type_params, bounds) = self.parse_type_params()?;
+
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut args = Vec::new();
+
+        if self.tokens.peek() != Some(&Token::RightParens) {
+            let arg = self.call(|parser| parser.parse_typed_identifier(false))?;
+            args.push(arg);
+
+            while self.tokens.peek() == Some(&Token::Comma) {
+                self.tokens.next(); // consume the comma
+                let arg = self.call(|parser| parser.parse_typed_identifier(false))?;
+                args.push(arg);
+            }
+        }
+
+        expect_tok!(self, Token::RightParens, "expected )");
+
+        let return_ty = match self.tokens.peek() {
+            Some(Token::Arrow) => {
+                self.tokens.next(); // consume the arrow
+                Some(self.parse_type_name()?)
+            }
+            _ => None,
+        };
+
+        let body = self.call(Self::parse_block)?;
+
+        let name = name.as_identifier().to_string();
+
+        Ok(ParserNodeKind::FunctionDeclaration {
+            name: name.clone(),
+            type_params,
+            bounds,
+            args,
+            return_ty,
+            body: Box::new(body),
+            is_static,
+            is_public,
+            attributes,
+        })
+    }
+
+    // parses an attribute such as #[execute("as @a")] which may precede a declaration
+    fn parse_attribute(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Pound, "expected #");
+        expect_tok!(self, Token::LeftBracket, "expected [");
+
+        let name = self.call(Self::parse_identifier)?;
+        let mut args = Vec::new();
+
+        if self.tokens.peek() == Some(&Token::LeftParens) {
+            self.tokens.next(); // consume the (
+
+            // the condition of #[cfg] is an expression, which is evaluated at compile time
+            if name.as_identifier() == "cfg" {
+                args.push(self.call(Self::parse_expression)?);
+            } else if self.tokens.peek() != Some(&Token::RightParens) {
+                args.push(self.call(Self::parse_attribute_arg)?);
+
+                while self.tokens.peek() == Some(&Token::Comma) {
+                    self.tokens.next(); // consume the comma
+                    args.push(self.call(Self::parse_attribute_arg)?);
+                }
+            }
+
+            expect_tok!(self, Token::RightParens, "expected )");
+        }
+
+        expect_tok!(self, Token::RightBracket, "expected ]");
+
+        Ok(ParserNodeKind::Attribute {
+            name: name.as_identifier().to_string(),
+            args,
+        })
+    }
+
+    fn parse_attribute_arg(&mut self) -> ParserKindResult {
+        match self.tokens.peek() {
+            Some(Token::Number(_)) => self.parse_number(),
+            Some(Token::Bool(_)) => self.parse_bool(),
+            Some(Token::String(_)) => self.parse_string(),
+            Some(Token::Identifier(_)) => {
+                let name = self.call(Self::parse_identifier)?;
+
+                if self.tokens.peek() != Some(&Token::Equals) {
+                    return Ok(name.kind);
+                }
+
+                self.tokens.next(); // consume the =
+
+                let value = match self.tokens.peek() {
+                    Some(Token::Number(_)) => self.call(Self::parse_number)?,
+                    Some(Token::Bool(_)) => self.call(Self::parse_bool)?,
+                    Some(Token::String(_)) => self.call(Self::parse_string)?,
+                    _ => return self.error("expected a literal as the value of a named attribute argument"),
+                };
+
+                Ok(ParserNodeKind::NamedArgument {
+                    name: name.as_identifier().to_string(),
+                    value: Box::new(value),
+                })
+            }
+            _ => self.error("expected a literal or identifier as an attribute argument"),
+        }
+    }
+
+    fn parse_return_statement(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Return, "expected return");
+
+        let expr = match self.tokens.peek() {
+            Some(Token::Semicolon) => None,
+            _ => Some(self.call(Self::parse_expression)?),
+        };
+
+        Ok(ParserNodeKind::Return(expr.map(|expr| Box::new(expr))))
+    }
+
+    fn parse_break_statement(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Break, "expected break");
+
+        let label = match self.tokens.peek() {
+            Some(Token::Label(label)) => {
+                let label = label.to_string();
+                self.tokens.next();
+                Some(label)
+            }
+            _ => None,
+        };
+
+        Ok(ParserNodeKind::Break(label))
+    }
+
+    fn parse_func_call(&mut self, callee: ParserNode) -> ParserKindResult {
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut args = Vec::new();
+
+        if self.tokens.peek() != Some(&Token::RightParens) {
+            let arg = self.call(Self::parse_expression)?;
+            args.push(arg);
+
+            while self.tokens.peek() == Some(&Token::Comma) {
+                self.tokens.next(); // consume the comma
+                let arg = self.call(Self::parse_expression)?;
+                args.push(arg);
+            }
+        }
+
+        expect_tok!(self, Token::RightParens, "expected )");
+
+        Ok(ParserNodeKind::FunctionCall {
+            expr: Box::new(callee),
+            args,
+        })
+    }
+
+    fn parse_member_access(&mut self, expr: ParserNode) -> ParserKindResult {
+        self.tokens.next(); // consume the .
+        let member = self.call(Self::parse_identifier)?;
+        Ok(ParserNodeKind::MemberAccess {
+            expr: Box::new(expr),
+            member: Box::new(member),
+        })
+    }
+
+    fn parse_index(&mut self, expr: ParserNode) -> ParserKindResult {
+        expect_tok!(self, Token::LeftBracket, "expected [");
+
+        let index = self.call(Self::parse_expression)?;
+
+        expect_tok!(self, Token::RightBracket, "expected ]");
+
+        Ok(ParserNodeKind::Index {
+            expr: Box::new(expr),
+            index: Box::new(index),
+        })
+    }
+
+    fn parse_if(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::If, "expected if");
+
+        if self.tokens.peek() == Some(&Token::Let) {
+            return self.parse_if_let();
+        }
+
+        let cond = self.call(Self::parse_expression)?;
+        let body = self.call(Self::parse_block)?;
+
+        let mut else_ifs = Vec::new();
+        let mut else_body = None;
+
+        while self.tokens.peek() == Some(&Token::Else) {
+            self.tokens.next(); // consume the else
+
+            if self.tokens.peek() == Some(&Token::If) {
+                self.tokens.next(); // consume the if
+                let cond = self.call(Self::parse_expression)?;
+                let body = self.call(Self::parse_block)?;
+
+                else_ifs.push((cond, body));
+            } else {
+                else_body = Some(self.call(Self::parse_block)?);
+                break;
+            }
+        }
+
+        Ok(ParserNodeKind::If {
+            cond: Box::new(cond),
+            body: Box::new(body),
+            else_ifs,
+            else_body: else_body.map(Box::new),
+        })
+    }
+
+    // the rest of an if let, after the if
+    fn parse_if_let(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Let, "expected let");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::Equals, "expected =");
+
+        let expr = self.call(Self::parse_expression)?;
+        let body = self.call(Self::parse_block)?;
+
+        let else_body = match self.tokens.peek() {
+            Some(Token::Else) => {
+                self.tokens.next(); // consume the else
+
+                match self.tokens.peek() {
+                    Some(Token::If) => {
+                        let else_if = self.call(Self::parse_if)?;
+                        let span = else_if.span();
+                        Some(ParserNode::new(ParserNodeKind::Block(vec![else_if]), span))
+                    }
+                    _ => Some(self.call(Self::parse_block)?),
+                }
+            }
+            _ => None,
+        };
+
+        Ok(ParserNodeKind::IfLet {
+            name,
+            expr: Box::new(expr),
+            body: Box::new(body),
+            else_body: else_body.map(Box::new),
+        })
+    }
+
+    fn parse_context_block(&mut self) -> ParserKindResult {
+        let mut subcommands = Vec::new();
+
+        while let Some(Token::Identifier(subcommand @ ("as" | "at"))) = self.tokens.peek() {
+            let subcommand = subcommand.to_string();
+            self.tokens.next();
+
+            let selector = match self.tokens.next() {
+                Some(Token::Selector(selector)) => selector.to_string(),
+                _ => return self.error(format!("expected a selector after {}", subcommand)),
+            };
+
+            subcommands.push((subcommand, selector));
+        }
+
+        let body = self.call(Self::parse_block)?;
+
+        Ok(ParserNodeKind::ContextBlock {
+            subcommands,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_await(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("await"), "expected await");
+
+        let counted = match self.tokens.next() {
+            Some(Token::Identifier("tick")) => false,
+            Some(Token::Identifier("ticks")) => true,
+            _ => return self.error("expected tick() or ticks(n) after await"),
+        };
+
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let ticks = match counted {
+            true => match self.tokens.next() {
+                Some(Token::Number(n)) if *n > 0 => *n,
+                _ => return self.error("expected a positive number of ticks"),
+            },
+            false => 1,
+        };
 
         expect_tok!(self, Token::RightParens, "expected )");
 
-        Ok(ParserNodeKind::FunctionCall {
-            expr: Box::new(callee),
-            args,
-        })
+        Ok(ParserNodeKind::Await(ticks))
     }
 
-    fn parse_member_access(&mut self, expr: ParserNode) -> ParserKindResult {
-        self.tokens.next(); // consume the .
-        let member = self.call(Self::parse_identifier)?;
-        Ok(ParserNodeKind::MemberAccess {
+    fn parse_match(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("match"), "expected match");
+
+        let expr = self.call(Self::parse_expression)?;
+
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut arms = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            let pattern = self.parse_arm_pattern()?;
+
+            expect_tok!(self, Token::FatArrow, "expected =>");
+
+            // an arm is either a block or a single statement, which is followed by a comma unless it's the last arm
+            let is_block = self.tokens.peek() == Some(&Token::LeftBrace);
+            let body = match is_block {
+                true => self.call(Self::parse_block)?,
+                false => self.call(|parser| {
+                    let stmt = parser.call(Self::parse_statement_inner)?;
+                    Ok(ParserNodeKind::Block(vec![stmt]))
+                })?,
+            };
+
+            match self.tokens.peek() {
+                Some(Token::Comma) => {
+                    self.tokens.next();
+                }
+                Some(Token::RightBrace) => {}
+                _ if !is_block => return self.error("expected , after the match arm"),
+                _ => {}
+            }
+
+            arms.push((pattern, body));
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::Match {
             expr: Box::new(expr),
-            member: Box::new(member),
+            arms,
         })
     }
 
-    fn parse_if(&mut self) -> ParserKindResult {
-        expect_tok!(self, Token::If, "expected if");
+    fn parse_arm_pattern(&mut self) -> Result<ArmPattern, ()> {
+        match self.tokens.peek() {
+            Some(Token::Identifier(name)) if *name != "_" => {
+                let enum_name = name.to_string();
+                self.tokens.next();
 
-        let cond = self.call(Self::parse_expression)?;
-        let body = self.call(Self::parse_block)?;
+                if self.tokens.peek() != Some(&Token::Dot) {
+                    return self
+                        .error("expected a number, a range, _ or a variant of an enum as the pattern of a match arm")
+                        .map(|_| ArmPattern::Range(MatchPattern { min: None, max: None }));
+                }
 
-        let mut else_ifs = Vec::new();
-        let mut else_body = None;
+                self.tokens.next(); // consume the .
 
-        while self.tokens.peek() == Some(&Token::Else) {
-            self.tokens.next(); // consume the else
+                let variant = self.parse_identifier()?.as_identifier().to_string();
 
-            if self.tokens.peek() == Some(&Token::If) {
-                self.tokens.next(); // consume the if
-                let cond = self.call(Self::parse_expression)?;
-                let body = self.call(Self::parse_block)?;
+                Ok(ArmPattern::Variant { enum_name, variant })
+            }
+            _ => self.parse_match_pattern().map(ArmPattern::Range),
+        }
+    }
 
-                else_ifs.push((cond, body));
-            } else {
-                else_body = Some(self.call(Self::parse_block)?);
-                break;
+    fn parse_match_pattern(&mut self) -> Result<MatchPattern, ()> {
+        let bound = |parser: &mut Self| match parser.tokens.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                parser.tokens.next();
+                Some(n)
             }
+            _ => None,
+        };
+
+        if self.tokens.peek() == Some(&Token::Identifier("_")) {
+            self.tokens.next();
+            return Ok(MatchPattern { min: None, max: None });
         }
 
-        Ok(ParserNodeKind::If {
-            cond: Box::new(cond),
-            body: Box::new(body),
-            else_ifs,
-            else_body: else_body.map(Box::new),
-        })
+        let min = bound(self);
+
+        // like the ranges of for loops, `..` leaves out the end and `..=` includes it
+        let inclusive = match self.tokens.peek() {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEquals) => true,
+            _ => {
+                return match min {
+                    Some(n) => Ok(MatchPattern { min: Some(n), max: Some(n) }),
+                    None => self
+                        .error("expected a number, a range or _ as the pattern of a match arm")
+                        .map(|_| MatchPattern { min: None, max: None }),
+                }
+            }
+        };
+
+        self.tokens.next(); // consume the .. or ..=
+
+        let end = bound(self);
+
+        if inclusive && end.is_none() {
+            return self
+                .error("expected the end of the range after ..=")
+                .map(|_| MatchPattern { min: None, max: None });
+        }
+
+        // the patterns are lowered to the inclusive ranges that commands take
+        let max = match (end, inclusive) {
+            (Some(end), false) => end.checked_sub(1),
+            (end, _) => end,
+        };
+
+        let empty = match (min, max) {
+            (Some(min), Some(max)) => min > max,
+            (_, None) => end.is_some(),
+            _ => false,
+        };
+
+        match empty {
+            true => self
+                .error(format!(
+                    "the range {}{}{} is empty, its start must be less than its end",
+                    min.map_or(String::new(), |min| min.to_string()),
+                    if inclusive { "..=" } else { ".." },
+                    end.map_or(String::new(), |end| end.to_string())
+                ))
+                .map(|_| MatchPattern { min: None, max: None }),
+            false => Ok(MatchPattern { min, max }),
+        }
     }
 
-    fn parse_for(&mut self) -> ParserKindResult {
+    fn parse_for(&mut self, attributes: Vec<ParserNode>) -> ParserKindResult {
+        let label = match self.tokens.peek() {
+            Some(Token::Label(label)) => {
+                let label = label.to_string();
+                self.tokens.next();
+                expect_tok!(self, Token::Colon, "expected : after the label of a loop");
+                Some(label)
+            }
+            _ => None,
+        };
+
         expect_tok!(self, Token::For, "expected for");
 
+        if matches!(self.tokens.peek(), Some(Token::Identifier(_))) && self.tokens.peek_second() == Some(Token::Identifier("in")) {
+            return self.parse_for_in(attributes, label);
+        }
+
         let init = self.call(Self::parse_statement)?;
         let cond = self.call(Self::parse_expression_statement)?;
         let step = self.call(Self::parse_statement_inner)?;
         let body = self.call(Self::parse_block)?;
 
         Ok(ParserNodeKind::For {
+            attributes,
+            label,
             init: Box::new(init),
             cond: Box::new(cond),
             step: Box::new(step),
@@ -550,6 +1779,110 @@ impl<'a> Parser<'a> {
         })
     }
 
+    // `for i in a..b step s { ... }` is sugar for `for let (i, i.end) = (a, b); i < i.end; i += s { ... }`, so the end is
+    // only evaluated once. `..=` includes the end, and a negative step counts down while i is above the end. The step
+    // has to be a number so that the direction is known. An end that is a number can't change, so it isn't kept.
+    // `for e in @e[...] { ... }` loops over entities instead, which is done by the game rather than with scores
+    fn parse_for_in(&mut self, attributes: Vec<ParserNode>, label: Option<String>) -> ParserKindResult {
+        let var = self.call(Self::parse_identifier)?;
+        let name = var.as_identifier().to_string();
+
+        expect_tok!(self, Token::Identifier("in"), "expected in");
+
+        if let Some(Token::Selector(selector)) = self.tokens.peek() {
+            let selector = selector.to_string();
+            self.tokens.next();
+
+            if !attributes.is_empty() || label.is_some() {
+                return self.error("a loop over entities can't have attributes or a label");
+            }
+
+            let body = self.call(Self::parse_block)?;
+
+            return Ok(ParserNodeKind::ForEntities {
+                name,
+                selector,
+                body: Box::new(body),
+            });
+        }
+
+        let start = self.call(Self::parse_expression)?;
+
+        let inclusive = match self.tokens.next() {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEquals) => true,
+            _ => return self.error("expected .. or ..= after the start of the range"),
+        };
+
+        let end = self.call(Self::parse_expression)?;
+
+        let step = match self.tokens.peek() {
+            Some(Token::Identifier("step")) => {
+                self.tokens.next();
+
+                match self.tokens.next() {
+                    Some(Token::Number(0)) => return self.error("the step of a range cannot be 0"),
+                    Some(Token::Number(n)) => ParserNode::new(ParserNodeKind::NumberLiteral(*n), self.tokens.current_span()),
+                    _ => return self.error("expected the step of the range, which must be a number"),
+                }
+            }
+            _ => ParserNode::new(ParserNodeKind::NumberLiteral(1), var.span()),
+        };
+
+        let body = self.call(Self::parse_block)?;
+
+        let op = match (step.as_num() > 0, inclusive) {
+            (true, false) => Operation::LessThan,
+            (true, true) => Operation::LessThanOrEquals,
+            (false, false) => Operation::GreaterThan,
+            (false, true) => Operation::GreaterThanOrEquals,
+        };
+
+        let span = start.span().start..end.span().end;
+
+        let (init, bound) = match end.unwrap_expression().kind() {
+            ParserNodeKind::NumberLiteral(_) => (
+                ParserNodeKind::VariableDeclaration {
+                    name: Box::new(var.clone()),
+                    expr: Box::new(start),
+                    ty: None,
+                },
+                end,
+            ),
+            _ => {
+                let end_name = format!("{}.end", name);
+                let end_span = end.span();
+
+                (
+                    ParserNodeKind::TupleDeclaration {
+                        names: vec![name.clone(), end_name.clone()],
+                        expr: Box::new(ParserNode::new(ParserNodeKind::Tuple(vec![start, end]), span.clone())),
+                    },
+                    ParserNode::new(ParserNodeKind::Identifier(end_name), end_span),
+                )
+            }
+        };
+
+        let cond = ParserNode::new(ParserNodeKind::Operation(Box::new(var.clone()), Box::new(bound), op), span.clone());
+        let step_span = step.span();
+
+        Ok(ParserNodeKind::For {
+            attributes,
+            label,
+            init: Box::new(ParserNode::new(init, span)),
+            cond: Box::new(cond),
+            step: Box::new(ParserNode::new(
+                ParserNodeKind::OpEquals {
+                    path: Box::new(var),
+                    expr: Box::new(step),
+                    op: Operation::Add,
+                },
+                step_span,
+            )),
+            body: Box::new(body),
+        })
+    }
+
     fn parse_expression_statement(&mut self) -> ParserKindResult {
         let expr = self.parse_expression()?;
         expect_tok!(self, Token::Semicolon, "expected ;");
@@ -557,7 +1890,57 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self) -> ParserKindResult {
-        self.parse_or()
+        self.parse_conditional()
+    }
+
+    // The right side of a let or an assignment. A command literal runs until the ; so this is the only place it can be
+    // a value, as nothing else could come after it
+    fn parse_assigned_value(&mut self) -> ParserKindResult {
+        match self.tokens.peek() {
+            Some(Token::Slash) => self.parse_command_literal(true),
+            _ => self.parse_expression(),
+        }
+    }
+
+    // `cond ? a : b` binds looser than ?? and groups to the right, so `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+    fn parse_conditional(&mut self) -> ParserKindResult {
+        let cond = self.call(Self::parse_coalesce)?;
+
+        if self.tokens.peek() != Some(&Token::Question) {
+            return Ok(cond.kind);
+        }
+
+        self.tokens.next();
+
+        let then = self.call(Self::parse_conditional)?;
+
+        expect_tok!(self, Token::Colon, "expected : followed by the value to use when the condition is false");
+
+        let otherwise = self.call(Self::parse_conditional)?;
+
+        Ok(ParserNodeKind::Conditional {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        })
+    }
+
+    // ?? binds looser than anything else and groups to the right, so `a ?? b ?? 0` falls back to b, then to 0
+    fn parse_coalesce(&mut self) -> ParserKindResult {
+        let expr = self.call(Self::parse_or)?;
+
+        if self.tokens.peek() != Some(&Token::QuestionQuestion) {
+            return Ok(expr.kind);
+        }
+
+        self.tokens.next();
+
+        let default = self.call(Self::parse_coalesce)?;
+
+        Ok(ParserNodeKind::Coalesce {
+            expr: Box::new(expr),
+            default: Box::new(default),
+        })
     }
 
     fn parse_number(&mut self) -> ParserKindResult {
@@ -569,41 +1952,344 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_bool(&mut self) -> ParserKindResult {
-        let tok = self.tokens.next();
+    fn parse_bool(&mut self) -> ParserKindResult {
+        let tok = self.tokens.next();
+
+        match tok {
+            Some(Token::Bool(b)) => Ok(ParserNodeKind::BoolLiteral(*b)),
+            _ => self.error("expected bool"),
+        }
+    }
+
+    fn parse_array_literal(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::LeftBracket, "expected [");
+
+        let mut elements = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBracket) {
+            elements.push(self.call(Self::parse_expression)?);
+
+            match self.tokens.peek() {
+                Some(Token::Comma) => {
+                    self.tokens.next();
+                }
+                Some(Token::RightBracket) => {}
+                _ => return self.error_at("expected , or ]", self.tokens.peeked_span()),
+            }
+        }
+
+        expect_tok!(self, Token::RightBracket, "expected ]");
+
+        Ok(ParserNodeKind::ArrayLiteral(elements))
+    }
+
+    fn parse_string(&mut self) -> ParserKindResult {
+        let tok = self.tokens.next();
+
+        match tok {
+            Some(Token::String(s)) => Ok(ParserNodeKind::StringLiteral(unescape_string(s))),
+            _ => self.error("expected string"),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> ParserKindResult {
+        let tok = self.tokens.next();
+
+        match tok {
+            Some(Token::Identifier(identifier)) => {
+                Ok(ParserNodeKind::Identifier(identifier.to_string()))
+            }
+            _ => self.error("expected identifier"),
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParserKindResult {
+        // "text" is only a keyword when it's followed by !, as it's also the name of the type
+        if self.tokens.peek() == Some(&Token::Identifier("text")) && self.tokens.peek_second() == Some(Token::Not) {
+            return self.parse_text_literal();
+        }
+
+        if self.tokens.peek() == Some(&Token::Identifier("format")) && self.tokens.peek_second() == Some(Token::Not) {
+            return self.parse_format();
+        }
+
+        match self.tokens.peek() {
+            Some(Token::Number(_)) => self.parse_number(),
+            Some(Token::Bool(_)) => self.parse_bool(),
+            Some(Token::String(_)) => self.parse_string(),
+            Some(Token::Selector(_)) => self.parse_selector(),
+            Some(Token::Identifier("null")) => {
+                self.tokens.next();
+                Ok(ParserNodeKind::Null)
+            }
+            Some(Token::Identifier(_)) => self.parse_path(),
+            Some(Token::LeftBracket) => self.parse_array_literal(),
+            Some(Token::LeftParens) => self.parse_parenthesized(),
+            Some(Token::Slash) => {
+                let span = self.tokens.peeked_span();
+                self.error_at("a command can only be a value when it's the whole right side of a let or an assignment", span)
+            }
+            _ => self.error("expected value or expression"),
+        }
+    }
+
+    // Either an expression in parentheses, e.g `(a + b)`, or a tuple when there's more than one, e.g `(a, b)`
+    fn parse_parenthesized(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut elements = vec![self.call(Self::parse_expression)?];
+
+        while self.tokens.peek() == Some(&Token::Comma) {
+            self.tokens.next();
+            elements.push(self.call(Self::parse_expression)?);
+        }
+
+        expect_tok!(self, Token::RightParens, "expected ) after expression");
+
+        match elements.len() {
+            1 => Ok(elements.pop().unwrap().kind),
+            _ => Ok(ParserNodeKind::Tuple(elements)),
+        }
+    }
+
+    // Parts are any text components that builtins like print() take, while the names of styles are style flags, e.g
+    // `text!{color("red", "Warning: "), message, bold}`
+    fn parse_text_literal(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("text"), "expected text");
+        expect_tok!(self, Token::Not, "expected !");
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut parts = Vec::new();
+        let mut styles = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            let style = match self.tokens.peek() {
+                Some(Token::Identifier(style)) if TEXT_STYLES.contains(style) => Some(style.to_string()),
+                _ => None,
+            };
+
+            match style {
+                Some(style) if matches!(self.tokens.peek_second(), Some(Token::Comma | Token::RightBrace)) => {
+                    styles.push(style);
+                    self.tokens.next();
+                }
+                _ => parts.push(self.call(Self::parse_expression)?),
+            }
+
+            match self.tokens.peek() {
+                Some(Token::Comma) => {
+                    self.tokens.next();
+                }
+                Some(Token::RightBrace) => {}
+                _ => return self.error_at("expected , or }", self.tokens.peeked_span()),
+            }
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        if parts.is_empty() {
+            return self.error("expected at least one part of the text");
+        }
+
+        Ok(ParserNodeKind::TextLiteral { parts, styles })
+    }
+
+    // `format!("{} of {}", 3, 4)` is a string that's put together while compiling, so it can go anywhere a string literal
+    // can and its arguments have to be known at compile time, like numbers and strings that are written out.
+    // `{{` and `}}` are a brace of their own
+    fn parse_format(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("format"), "expected format");
+        expect_tok!(self, Token::Not, "expected !");
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let template = match self.tokens.next() {
+            Some(Token::String(template)) => unescape_string(template),
+            _ => return self.error("expected the string to format"),
+        };
+
+        let mut args = Vec::new();
+
+        while self.tokens.peek() == Some(&Token::Comma) {
+            self.tokens.next();
+            args.push(self.call(Self::parse_expression)?);
+        }
+
+        expect_tok!(self, Token::RightParens, "expected , or ) after the arguments of format!");
+
+        let mut args = args.iter();
+        let mut formatted = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    formatted.push(c);
+                }
+                ('{', Some('}')) => {
+                    chars.next();
+
+                    let Some(arg) = args.next() else {
+                        return self.error("there are more {} in the string of format! than arguments");
+                    };
+
+                    let value = match arg.unwrap_expression().kind() {
+                        ParserNodeKind::StringLiteral(text) => text.clone(),
+                        _ => match consteval::eval(arg, &HashMap::new()) {
+                            Ok(Value::Int(n)) => n.to_string(),
+                            Ok(Value::Bool(b)) => b.to_string(),
+                            Err(err) => {
+                                let message = match err.kind {
+                                    ConstEvalErrorKind::NotConstant
+                                    | ConstEvalErrorKind::UnknownName(_)
+                                    | ConstEvalErrorKind::UnknownFunction(_) => {
+                                        "the arguments of format! have to be known at compile time"
+                                    }
+                                    _ => "this argument of format! cannot be evaluated",
+                                };
+
+                                return self.error_at(message, err.span);
+                            }
+                        },
+                    };
+
+                    formatted.push_str(&value);
+                }
+                ('{' | '}', _) => return self.error("a brace in the string of format! has to be {}, {{ or }}"),
+                _ => formatted.push(c),
+            }
+        }
+
+        if let Some(arg) = args.next() {
+            return self.error_at("there are more arguments to format! than {} in its string", arg.span());
+        }
+
+        Ok(ParserNodeKind::StringLiteral(formatted))
+    }
+
+    // Replaces every format! in the text of a command literal with the string it makes, e.g
+    // `/give @s diamond format!("{}", 2 * 32);`. start is where the text is in the source, which the spans of the
+    // format! arguments are relative to
+    fn expand_formats(&mut self, text: &'a str, start: usize) -> Result<String, ()> {
+        let mut expanded = String::new();
+        let mut in_str = false;
+        let mut escaped = false;
+        let mut copied = 0;
+
+        for (i, c) in text.char_indices() {
+            match c {
+                _ if i < copied => continue,
+                _ if escaped => escaped = false,
+                '\\' if in_str => escaped = true,
+                '"' => in_str = !in_str,
+                'f' if !in_str && text[i..].starts_with("format!(") => {
+                    let Some(end) = format_end(&text[i..]) else {
+                        return self.error_at("expected ) to close the format!", start + i..start + text.len()).map(|_| String::new());
+                    };
+
+                    let mut parser = Parser::new_at(&text[i..i + end], start + i);
+                    let formatted = parser.call(Self::parse_format);
+                    self.errors.append(&mut parser.errors);
+
+                    let Ok(formatted) = formatted else {
+                        self.recover();
+                        return Err(());
+                    };
+
+                    match formatted.kind {
+                        ParserNodeKind::StringLiteral(formatted) => {
+                            expanded.push_str(&text[copied..i]);
+                            expanded.push_str(&formatted);
+                            copied = i + end;
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        expanded.push_str(&text[copied..]);
+        Ok(expanded)
+    }
+
+    // The location of a storage and the NBT path are kept as they're written, since they aren't made of tokens that
+    // sculk has, e.g `data storage pack:stats/kills list[{id:"zombie"}].count`. They end at the first space that isn't
+    // inside brackets or braces
+    fn parse_data_path(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("data"), "expected data");
+
+        let source = match self.tokens.next() {
+            Some(Token::Identifier("storage")) => {
+                let start = self.tokens.peeked_span().start;
+
+                expect_tok!(self, Token::Identifier(_), "expected the resource location of a storage");
+
+                while self.tokens.peeked_span().start == self.tokens.current_span().end
+                    && matches!(
+                        self.tokens.peek(),
+                        Some(Token::Identifier(_) | Token::Number(_) | Token::Colon | Token::Slash | Token::Dot | Token::Hyphen)
+                    )
+                {
+                    self.tokens.next();
+                }
 
-        match tok {
-            Some(Token::Bool(b)) => Ok(ParserNodeKind::BoolLiteral(*b)),
-            _ => self.error("expected bool"),
+                DataSource::Storage(self.tokens.slice(start..self.tokens.current_span().end).to_string())
+            }
+            Some(Token::Identifier("entity")) => match self.tokens.next() {
+                Some(Token::Selector(selector)) => DataSource::Entity(selector.to_string()),
+                _ => return self.error("expected the selector of an entity"),
+            },
+            _ => return self.error("expected storage or entity"),
+        };
+
+        if !matches!(
+            self.tokens.peek(),
+            Some(Token::Identifier(_) | Token::String(_) | Token::LeftBrace)
+        ) {
+            return self.error_at("expected an NBT path", self.tokens.peeked_span());
         }
-    }
 
-    fn parse_identifier(&mut self) -> ParserKindResult {
-        let tok = self.tokens.next();
+        let start = self.tokens.peeked_span().start;
+        let mut depth = 0;
 
-        match tok {
-            Some(Token::Identifier(identifier)) => {
-                Ok(ParserNodeKind::Identifier(identifier.to_string()))
+        loop {
+            match self.tokens.next() {
+                Some(Token::LeftBracket | Token::LeftBrace) => depth += 1,
+                Some(Token::RightBracket | Token::RightBrace) => depth -= 1,
+                // NBT has no ; outside of strings, so one that comes first means a bracket wasn't closed
+                Some(Token::Semicolon) | None => return self.error("expected ] or } to close the NBT path"),
+                Some(_) => {}
+            }
+
+            let continues = depth > 0
+                || (self.tokens.peeked_span().start == self.tokens.current_span().end
+                    && matches!(
+                        self.tokens.peek(),
+                        Some(
+                            Token::Identifier(_)
+                                | Token::String(_)
+                                | Token::Dot
+                                | Token::DotDot
+                                | Token::LeftBracket
+                                | Token::LeftBrace
+                        )
+                    ));
+
+            if !continues {
+                break;
             }
-            _ => self.error("expected identifier"),
         }
-    }
 
-    fn parse_primary(&mut self) -> ParserKindResult {
-        match self.tokens.peek() {
-            Some(Token::Number(_)) => self.parse_number(),
-            Some(Token::Bool(_)) => self.parse_bool(),
-            Some(Token::Identifier(_)) => self.parse_path(),
-            Some(Token::LeftParens) => {
-                self.tokens.next();
-                let expr = self.parse_statement()?;
+        let path = self.tokens.slice(start..self.tokens.current_span().end).to_string();
 
-                match self.tokens.next() {
-                    Some(Token::RightParens) => Ok(expr),
-                    _ => self.error("expected ) after expression"),
-                }
-            }
-            _ => self.error("expected value or expression"),
+        Ok(ParserNodeKind::DataPath { source, path })
+    }
+
+    fn parse_selector(&mut self) -> ParserKindResult {
+        match self.tokens.next() {
+            Some(Token::Selector(selector)) => Ok(ParserNodeKind::SelectorLiteral(selector.to_string())),
+            _ => self.error("expected selector"),
         }
     }
 
@@ -621,6 +2307,27 @@ impl<'a> Parser<'a> {
         Ok(ParserNodeKind::Unary(Box::new(expr), op))
     }
 
+    // `as` binds tighter than arithmetic but looser than unary operators, so `-x as long` negates before converting
+    fn parse_cast(&mut self) -> ParserKindResult {
+        let mut expr = self.call(Self::parse_unary)?;
+
+        while self.tokens.peek() == Some(&Token::Identifier("as")) {
+            self.tokens.next();
+
+            let ty = self.parse_type_name_with(false)?;
+            let span = expr.span().start..self.tokens.current_span().end;
+            expr = ParserNode::new(
+                ParserNodeKind::Cast {
+                    expr: Box::new(expr),
+                    ty,
+                },
+                span,
+            );
+        }
+
+        Ok(ParserNodeKind::Expression(Box::new(expr)))
+    }
+
     fn parse_or(&mut self) -> ParserKindResult {
         let mut expr = self.call(Self::parse_and)?;
 
@@ -689,10 +2396,10 @@ impl<'a> Parser<'a> {
         Ok(ParserNodeKind::Expression(Box::new(expr)))
     }
 
-    // does not necessarily parse a comparison, but rather a comparison or a term
+    // does not necessarily parse a comparison, but rather a comparison or a bitwise operation
     // TODO: stop multiple inequalities in the same expression
     fn parse_comparison(&mut self) -> ParserKindResult {
-        let mut expr = self.call(Self::parse_term)?;
+        let mut expr = self.call(Self::parse_bit_or)?;
 
         while self.tokens.peek().is_some() {
             let op = match self.tokens.peek().unwrap() {
@@ -705,7 +2412,54 @@ impl<'a> Parser<'a> {
 
             self.tokens.next();
 
-            let term = self.call(Self::parse_term)?;
+            let term = self.call(Self::parse_bit_or)?;
+            let span = expr.span().start..term.span().end;
+            expr = ParserNode::new(
+                ParserNodeKind::Operation(Box::new(expr), Box::new(term), op),
+                span,
+            );
+        }
+
+        Ok(ParserNodeKind::Expression(Box::new(expr)))
+    }
+
+    // bitwise operations bind tighter than comparisons and looser than arithmetic, from | up to shifts like in Rust
+    fn parse_bit_or(&mut self) -> ParserKindResult {
+        self.parse_binary_level(&[(Token::Pipe, Operation::BitOr)], Self::parse_bit_xor)
+    }
+
+    fn parse_bit_xor(&mut self) -> ParserKindResult {
+        self.parse_binary_level(&[(Token::Caret, Operation::BitXor)], Self::parse_bit_and)
+    }
+
+    fn parse_bit_and(&mut self) -> ParserKindResult {
+        self.parse_binary_level(&[(Token::Ampersand, Operation::BitAnd)], Self::parse_shift)
+    }
+
+    fn parse_shift(&mut self) -> ParserKindResult {
+        self.parse_binary_level(
+            &[(Token::ShiftLeft, Operation::ShiftLeft), (Token::ShiftRight, Operation::ShiftRight)],
+            Self::parse_term,
+        )
+    }
+
+    // parses a left-associative chain of the given operators, with operands parsed by the next level up
+    fn parse_binary_level(
+        &mut self,
+        ops: &[(Token<'static>, Operation)],
+        operand: fn(&mut Self) -> ParserKindResult,
+    ) -> ParserKindResult {
+        let mut expr = self.call(operand)?;
+
+        while let Some(op) = self
+            .tokens
+            .peek()
+            .and_then(|tok| ops.iter().find(|(op_tok, _)| op_tok == tok))
+            .map(|(_, op)| *op)
+        {
+            self.tokens.next();
+
+            let term = self.call(operand)?;
             let span = expr.span().start..term.span().end;
             expr = ParserNode::new(
                 ParserNodeKind::Operation(Box::new(expr), Box::new(term), op),
@@ -740,7 +2494,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_factor(&mut self) -> ParserKindResult {
-        let mut expr = self.call(Self::parse_unary)?;
+        let mut expr = self.call(Self::parse_cast)?;
 
         while self.tokens.peek().is_some() {
             let op = match self.tokens.peek().unwrap() {
@@ -752,7 +2506,7 @@ impl<'a> Parser<'a> {
 
             self.tokens.next();
 
-            let term = self.call(Self::parse_unary)?;
+            let term = self.call(Self::parse_cast)?;
             let span = expr.span().start..term.span().end;
             expr = ParserNode::new(
                 ParserNodeKind::Operation(Box::new(expr), Box::new(term), op),
@@ -782,7 +2536,23 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_command_literal(&mut self) -> ParserKindResult {
+    // x++ and x-- are the same as x += 1 and x -= 1, so they're parsed as those
+    fn parse_increment(&mut self, path: ParserNode) -> ParserKindResult {
+        let op = match self.tokens.next() {
+            Some(Token::Increment) => Operation::Add,
+            Some(Token::Decrement) => Operation::Subtract,
+            _ => unreachable!(),
+        };
+
+        Ok(ParserNodeKind::OpEquals {
+            path: Box::new(path),
+            expr: Box::new(ParserNode::new(ParserNodeKind::NumberLiteral(1), self.tokens.current_span())),
+            op,
+        })
+    }
+
+    // A command literal that's a value leaves the ; for the statement it's in to end on
+    fn parse_command_literal(&mut self, is_value: bool) -> ParserKindResult {
         let remainder = self.tokens.remainder();
         let mut end = 0;
         let mut in_str = false;
@@ -819,10 +2589,19 @@ impl<'a> Parser<'a> {
             return self.error("expected ; after command literal");
         }
 
-        let literal = remainder[..end].to_string();
-        self.tokens.bump(end + 1);
+        let start = self.tokens.peeked_span().end;
 
-        Ok(ParserNodeKind::CommandLiteral(literal))
+        match is_value {
+            true => self.tokens.bump(end),
+            false => self.tokens.bump(end + 1),
+        }
+
+        let literal = self.expand_formats(&remainder[..end], start)?;
+
+        match is_value {
+            true => Ok(ParserNodeKind::CommandResult(literal)),
+            false => Ok(ParserNodeKind::CommandLiteral(literal)),
+        }
     }
 
     fn parse_typed_identifier(&mut self, ty_optional: bool) -> ParserKindResult {
@@ -837,27 +2616,219 @@ impl<'a> Parser<'a> {
 
         expect_tok!(self, Token::Colon, "expected :");
 
-        let ty = match self.tokens.next() {
-            Some(Token::Identifier(name)) => name.to_string(),
-            _ => return self.error("expected valid type"),
-        };
+        let ty = self.parse_type_name()?;
 
         Ok(ParserNodeKind::TypedIdentifier { name, ty })
     }
 
+    // Parses the name of a type, where a trailing [] makes it an array of that type, e.g `int[]`, a trailing ? makes
+    // it optional, e.g `int?`, and types in parentheses make a tuple of them, e.g `(int, bool)`. A function type is
+    // written like the signature of a function, e.g `fn(int, int) -> int`, and everything after its -> is its return type
+    fn parse_type_name(&mut self) -> Result<String, ()> {
+        self.parse_type_name_with(true)
+    }
+
+    // the type of a cast is parsed without type arguments, so `x as int < y` still compares, which is fine because only
+    // numbers can be cast
+    fn parse_type_name_with(&mut self, type_args: bool) -> Result<String, ()> {
+        let name = match self.tokens.next() {
+            Some(Token::Identifier(name)) => {
+                let name = name.to_string();
+
+                if !type_args || self.tokens.peek() != Some(&Token::LessThan) {
+                    return self.parse_type_suffix(name);
+                }
+
+                self.tokens.next(); // consume the <
+
+                let mut args = vec![self.parse_type_name()?];
+
+                while !self.closed_type_args && self.tokens.peek() == Some(&Token::Comma) {
+                    self.tokens.next();
+                    args.push(self.parse_type_name()?);
+                }
+
+                let name = format!("{}<{}>", name, args.join(", "));
+
+                if self.closed_type_args {
+                    self.closed_type_args = false;
+                } else {
+                    match self.tokens.next() {
+                        Some(Token::GreaterThan) => {}
+                        Some(Token::ShiftRight) => {
+                            // the second > closes the type arguments this type is one of
+                            self.closed_type_args = true;
+                            return Ok(name);
+                        }
+                        _ => return self.error("expected , or > after a type argument").map(|_| String::new()),
+                    }
+                }
+
+                name
+            }
+            Some(Token::Fn) => {
+                if self.tokens.next() != Some(&Token::LeftParens) {
+                    return self.error("expected ( after fn in a function type").map(|_| String::new());
+                }
+
+                let mut params = Vec::new();
+
+                while self.tokens.peek() != Some(&Token::RightParens) {
+                    params.push(self.parse_type_name()?);
+
+                    match self.tokens.peek() {
+                        Some(Token::Comma) => {
+                            self.tokens.next();
+                        }
+                        Some(Token::RightParens) => {}
+                        _ => return self.error("expected , or ) after a parameter type").map(|_| String::new()),
+                    }
+                }
+
+                self.tokens.next(); // consume the )
+
+                if self.tokens.peek() != Some(&Token::Arrow) {
+                    return Ok(format!("fn({})", params.join(", ")));
+                }
+
+                self.tokens.next(); // consume the ->
+
+                return Ok(format!("fn({}) -> {}", params.join(", "), self.parse_type_name()?));
+            }
+            Some(Token::LeftParens) => {
+                let mut elements = vec![self.parse_type_name()?];
+
+                while self.tokens.peek() == Some(&Token::Comma) {
+                    self.tokens.next();
+                    elements.push(self.parse_type_name()?);
+                }
+
+                if elements.len() < 2 {
+                    return self.error("expected , after the first type of a tuple").map(|_| String::new());
+                }
+
+                match self.tokens.next() {
+                    Some(Token::RightParens) => format!("({})", elements.join(", ")),
+                    _ => return self.error("expected ) after the types of a tuple").map(|_| String::new()),
+                }
+            }
+            _ => return self.error("expected valid type").map(|_| String::new()),
+        };
+
+        self.parse_type_suffix(name)
+    }
+
+    // parses the ? or [] that may follow the name of a type
+    fn parse_type_suffix(&mut self, name: String) -> Result<String, ()> {
+        if self.tokens.peek() == Some(&Token::Question) {
+            self.tokens.next();
+            return Ok(format!("{}?", name));
+        }
+
+        if self.tokens.peek() != Some(&Token::LeftBracket) {
+            return Ok(name);
+        }
+
+        self.tokens.next(); // consume the [
+
+        match self.tokens.next() {
+            Some(Token::RightBracket) => Ok(format!("{}[]", name)),
+            _ => self.error("expected ] after [ in array type").map(|_| String::new()),
+        }
+    }
+
+    // the type parameters of a generic declaration, e.g `<T, U>`, which there are none of when there's no <
+    // A type parameter may be followed by the traits its type has to implement, e.g `<T: Tickable + Named>`, which are
+    // given back as the type parameter and the trait
+    fn parse_type_params(&mut self) -> Result<TypeParams, ()> {
+        let mut params = Vec::new();
+        let mut bounds = Vec::new();
+
+        if self.tokens.peek() != Some(&Token::LessThan) {
+            return Ok((params, bounds));
+        }
+
+        self.tokens.next(); // consume the <
+
+        loop {
+            let param = self.parse_identifier()?.as_identifier().to_string();
+
+            if self.tokens.peek() == Some(&Token::Colon) {
+                self.tokens.next();
+                bounds.push((param.clone(), self.parse_identifier()?.as_identifier().to_string()));
+
+                while self.tokens.peek() == Some(&Token::Plus) {
+                    self.tokens.next();
+                    bounds.push((param.clone(), self.parse_identifier()?.as_identifier().to_string()));
+                }
+            }
+
+            params.push(param);
+
+            match self.tokens.next() {
+                Some(Token::Comma) => {}
+                Some(Token::GreaterThan) => return Ok((params, bounds)),
+                _ => return self.error("expected , or > after a type parameter").map(|_| (Vec::new(), Vec::new())),
+            }
+        }
+    }
+
     fn parse_struct_definition(&mut self) -> ParserKindResult {
         expect_tok!(self, Token::Struct, "expected struct");
 
         let name = self.parse_identifier()?;
 
+        let (type_params, bounds) = self.parse_type_params()?;
+
+        if !bounds.is_empty() {
+            return self.error("the type parameters of a generic struct can't have bounds");
+        }
+
+        let mut traits = Vec::new();
+
+        if self.tokens.peek() == Some(&Token::Colon) {
+            self.tokens.next();
+            traits.push(self.parse_identifier()?.as_identifier().to_string());
+
+            while self.tokens.peek() == Some(&Token::Comma) {
+                self.tokens.next();
+                traits.push(self.parse_identifier()?.as_identifier().to_string());
+            }
+        }
+
+        let entity = match self.tokens.peek() {
+            Some(Token::Identifier("on")) if !type_params.is_empty() => {
+                return self.error("a generic struct can't be attached to entities");
+            }
+            Some(Token::Identifier("on")) => {
+                self.tokens.next();
+
+                match self.tokens.next() {
+                    Some(Token::Selector(selector)) => Some(selector.to_string()),
+                    _ => return self.error("expected the selector of the entities the struct is attached to"),
+                }
+            }
+            _ => None,
+        };
+
         expect_tok!(self, Token::LeftBrace, "expected {");
 
         let mut members = Vec::new();
 
         while self.tokens.peek() != Some(&Token::RightBrace) {
             let member = match self.tokens.peek() {
+                // the fields of an attached struct live on the entity, so there's no struct value for methods to take
+                Some(Token::Identifier(_)) if entity.is_some() => self.call(|parser| parser.parse_typed_identifier(false))?,
+                _ if entity.is_some() => return self.error("expected field or }"),
+                // the methods of a generic struct would need to be generic too, which only functions can be
+                Some(Token::Identifier(_)) if !type_params.is_empty() => {
+                    self.call(|parser| parser.parse_typed_identifier(false))?
+                }
+                _ if !type_params.is_empty() => return self.error("expected field or }, a generic struct can only have fields"),
+                Some(Token::Static) | Some(Token::Fn) | Some(Token::Pound) | Some(Token::Identifier("pub")) => {
+                    self.call(|parser| parser.parse_func_declaration())?
+                }
                 Some(Token::Identifier(_)) => self.call(|parser| parser.parse_typed_identifier(false))?,
-                Some(Token::Static) | Some(Token::Fn) => self.call(|parser| parser.parse_func_declaration())?,
                 _ => return self.error("expected field, function declaration, or }")
             };
 
@@ -868,15 +2839,133 @@ impl<'a> Parser<'a> {
 
         Ok(ParserNodeKind::StructDefinition {
             name: name.as_identifier().to_string(),
+            type_params,
+            traits,
+            members,
+            is_message: false,
+            entity,
+        })
+    }
+
+    // messages are declared like structs, but only have fields
+    fn parse_message(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("message"), "expected message");
+
+        let name = self.parse_identifier()?;
+
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut members = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            members.push(match self.tokens.peek() {
+                Some(Token::Identifier(_)) => self.call(|parser| parser.parse_typed_identifier(false))?,
+                _ => return self.error("expected field or }"),
+            });
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::StructDefinition {
+            name: name.as_identifier().to_string(),
+            type_params: Vec::new(),
+            traits: Vec::new(),
             members,
+            is_message: true,
+            entity: None,
+        })
+    }
+
+    fn parse_trait_definition(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("trait"), "expected trait");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut methods = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            methods.push(self.call(Self::parse_method_signature)?);
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::TraitDefinition { name, methods })
+    }
+
+    // e.g `fn add(amount: int) -> int;`
+    fn parse_method_signature(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Fn, "expected fn or }");
+
+        let name = self.parse_identifier()?.as_identifier().to_string();
+
+        expect_tok!(self, Token::LeftParens, "expected (");
+
+        let mut args = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightParens) {
+            args.push(self.call(|parser| parser.parse_typed_identifier(false))?);
+
+            match self.tokens.peek() {
+                Some(Token::Comma) => {
+                    self.tokens.next();
+                }
+                Some(Token::RightParens) => {}
+                _ => return self.error("expected , or ) after a parameter"),
+            }
+        }
+
+        expect_tok!(self, Token::RightParens, "expected )");
+
+        let return_ty = match self.tokens.peek() {
+            Some(Token::Arrow) => {
+                self.tokens.next(); // consume the arrow
+                Some(self.parse_type_name()?)
+            }
+            _ => None,
+        };
+
+        expect_tok!(self, Token::Semicolon, "expected ; after a method of a trait");
+
+        Ok(ParserNodeKind::MethodSignature { name, args, return_ty })
+    }
+
+    // variants are separated by commas, and the last one may be followed by one too
+    fn parse_enum_definition(&mut self) -> ParserKindResult {
+        expect_tok!(self, Token::Identifier("enum"), "expected enum");
+
+        let name = self.parse_identifier()?;
+
+        expect_tok!(self, Token::LeftBrace, "expected {");
+
+        let mut variants = Vec::new();
+
+        while self.tokens.peek() != Some(&Token::RightBrace) {
+            variants.push(self.parse_identifier()?.as_identifier().to_string());
+
+            match self.tokens.peek() {
+                Some(Token::Comma) => {
+                    self.tokens.next();
+                }
+                Some(Token::RightBrace) => {}
+                _ => return self.error("expected , or } after the variant"),
+            }
+        }
+
+        expect_tok!(self, Token::RightBrace, "expected }");
+
+        Ok(ParserNodeKind::EnumDefinition {
+            name: name.as_identifier().to_string(),
+            variants,
         })
     }
 
     fn error(&mut self, error: impl Into<String>) -> ParserKindResult {
-        let error = ParseError::new(
-            error,
-            *self.current_node_starts.last().unwrap()..self.tokens.current_span().end,
-        );
+        // the node may have started at a token that was peeked but never read, which is after the current one
+        let end = self.tokens.current_span().end;
+        let start = (*self.current_node_starts.last().unwrap()).min(end);
+        let error = ParseError::new(error, start..end);
         self.errors.push(error);
         self.recover();
         Err(())
@@ -900,20 +2989,49 @@ impl<'a> Parser<'a> {
                 Token::Let => return,
                 Token::Struct => return,
                 Token::Fn => return,
+                Token::Pound => return,
                 _ => self.tokens.next(),
             };
         }
     }
 }
 
+// The length of a format! at the start of some text, up to and including the ) that closes it
+fn format_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_str => escaped = true,
+            '"' => in_str = !in_str,
+            '(' if !in_str => depth += 1,
+            ')' if !in_str => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
 pub struct ParserOutput {
     pub ast: ParserNode,
     pub errors: Vec<ParseError>,
+    // the part of the time parsing took that was spent lexing
+    pub lexing: Duration,
 }
 
 impl ParserOutput {
-    fn new(ast: ParserNode, errors: Vec<ParseError>) -> Self {
-        Self { ast, errors }
+    fn new(ast: ParserNode, errors: Vec<ParseError>, lexing: Duration) -> Self {
+        Self { ast, errors, lexing }
     }
 }
 
@@ -928,8 +3046,29 @@ impl FunctionDefinition {
     }
 }
 
+// resolves the escape sequences of a string literal, e.g \" -> "
+fn unescape_string(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => {}
+            },
+            (c, false) => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
 type ParseResult = Result<ParserNode, ()>;
 type ParserKindResult = Result<ParserNodeKind, ()>;
+// the type parameters of a generic declaration and the traits they are bounded by, see Parser::parse_type_params
+type TypeParams = (Vec<String>, Vec<(String, String)>);
 
 #[derive(Clone, Debug)]
 pub struct ParseError {
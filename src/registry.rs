@@ -0,0 +1,143 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use serde_json::Value;
+
+/// The vanilla registries that Sculk knows how to validate against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegistryKind {
+    Item,
+    EntityType,
+    Particle,
+    SoundEvent,
+}
+
+impl RegistryKind {
+    pub const ALL: [RegistryKind; 4] = [
+        RegistryKind::Item,
+        RegistryKind::EntityType,
+        RegistryKind::Particle,
+        RegistryKind::SoundEvent,
+    ];
+
+    /// The key of the registry inside the `registries.json` report.
+    pub fn report_key(&self) -> &'static str {
+        match self {
+            RegistryKind::Item => "minecraft:item",
+            RegistryKind::EntityType => "minecraft:entity_type",
+            RegistryKind::Particle => "minecraft:particle_type",
+            RegistryKind::SoundEvent => "minecraft:sound_event",
+        }
+    }
+}
+
+impl Display for RegistryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryKind::Item => write!(f, "item"),
+            RegistryKind::EntityType => write!(f, "entity type"),
+            RegistryKind::Particle => write!(f, "particle"),
+            RegistryKind::SoundEvent => write!(f, "sound"),
+        }
+    }
+}
+
+/// A set of registry dumps for a specific Minecraft version.
+/// These are read from the `reports/registries.json` file produced by the vanilla data generator
+/// (`java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`), so the ids used
+/// in a program can be checked against the exact version the pack targets.
+#[derive(Debug, Default)]
+pub struct Registries {
+    entries: HashMap<RegistryKind, HashSet<String>>,
+}
+
+impl Registries {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read registries file: {}", err))?;
+
+        Self::from_report(&content)
+    }
+
+    pub fn from_report(report: &str) -> Result<Self, String> {
+        let report: Value = serde_json::from_str(report)
+            .map_err(|err| format!("failed to parse registries file: {}", err))?;
+
+        let mut registries = Self::default();
+
+        for kind in RegistryKind::ALL {
+            let entries = report
+                .get(kind.report_key())
+                .and_then(|registry| registry.get("entries"))
+                .and_then(|entries| entries.as_object())
+                .ok_or_else(|| format!("registries file is missing '{}'", kind.report_key()))?;
+
+            registries
+                .entries
+                .insert(kind, entries.keys().cloned().collect());
+        }
+
+        Ok(registries)
+    }
+
+    /// Checks if an id exists in a registry. Ids without a namespace are assumed to be in the `minecraft` namespace.
+    pub fn contains(&self, kind: RegistryKind, id: &str) -> bool {
+        let id = normalize_id(id);
+
+        self.entries
+            .get(&kind)
+            .is_some_and(|entries| entries.contains(&id))
+    }
+}
+
+pub fn normalize_id(id: &str) -> String {
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+/// Finds the registry ids referenced by a command, alongside their byte offsets in the command.
+/// Only commands whose arguments are known to be registry ids are inspected; `execute ... run` chains are followed.
+pub fn find_command_ids(command: &str) -> Vec<(RegistryKind, &str, usize)> {
+    let (command, base) = match command.rfind(" run ") {
+        Some(idx) => (&command[idx + 5..], idx + 5),
+        None => (command, 0),
+    };
+
+    let words = command
+        .split(' ')
+        .scan(0, |offset, word| {
+            let start = *offset;
+            *offset += word.len() + 1;
+            Some((word, base + start))
+        })
+        .filter(|(word, _)| !word.is_empty())
+        .collect::<Vec<(&str, usize)>>();
+
+    let (kind, arg_idx) = match words.first().map(|(word, _)| *word) {
+        Some("summon") => (RegistryKind::EntityType, 1),
+        Some("particle") => (RegistryKind::Particle, 1),
+        Some("playsound") => (RegistryKind::SoundEvent, 1),
+        Some("stopsound") => (RegistryKind::SoundEvent, 3),
+        Some("give") | Some("clear") => (RegistryKind::Item, 2),
+        _ => return Vec::new(),
+    };
+
+    match words.get(arg_idx) {
+        Some((word, offset)) => {
+            // items can be followed by components or nbt, e.g minecraft:stone{...}
+            let id = word.split(['{', '[']).next().unwrap();
+
+            if id.is_empty() || id == "*" || id.starts_with('#') {
+                Vec::new()
+            } else {
+                vec![(kind, id, *offset)]
+            }
+        }
+        None => Vec::new(),
+    }
+}
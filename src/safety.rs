@@ -0,0 +1,70 @@
+/// Commands that let a datapack take over or shut down the server it runs on.
+/// Hosts that compile programs they don't trust can forbid all of them at once with `--forbid dangerous`.
+pub const DANGEROUS_COMMANDS: [&str; 16] = [
+    "op",
+    "deop",
+    "stop",
+    "whitelist",
+    "ban",
+    "ban-ip",
+    "pardon",
+    "pardon-ip",
+    "kick",
+    "save-off",
+    "publish",
+    "debug",
+    "jfr",
+    "perf",
+    "setidletimeout",
+    "transfer",
+];
+
+/// Expands the names given to `--forbid` into the commands that they forbid, where `dangerous` stands for every command
+/// in DANGEROUS_COMMANDS.
+pub fn expand_forbidden(names: &[String]) -> Vec<String> {
+    let mut commands = Vec::new();
+
+    for name in names {
+        let expanded = match name.as_str() {
+            "dangerous" => DANGEROUS_COMMANDS.iter().map(|command| command.to_string()).collect(),
+            _ => vec![name.trim_start_matches('/').to_string()],
+        };
+
+        for command in expanded {
+            if !commands.contains(&command) {
+                commands.push(command);
+            }
+        }
+    }
+
+    commands
+}
+
+/// Finds the commands that a command runs, alongside their byte offsets in the command. These are the command itself
+/// and the one after every `run`, so `execute ... run` and `return run` chains are followed.
+/// A `run` inside quoted text is treated the same way, which errs on the side of finding too many commands.
+pub fn find_commands(command: &str) -> Vec<(&str, usize)> {
+    let words = command
+        .split(' ')
+        .scan(0, |offset, word| {
+            let start = *offset;
+            *offset += word.len() + 1;
+            Some((word, start))
+        })
+        .filter(|(word, _)| !word.is_empty());
+
+    let mut commands = Vec::new();
+    let mut expects_command = true;
+
+    for (word, offset) in words {
+        if expects_command {
+            // macro lines start with $, and commands may be written with their leading slash
+            let name = word.trim_start_matches(['$', '/']);
+            commands.push((name, offset + word.len() - name.len()));
+        }
+
+        expects_command = word == "run";
+    }
+
+    commands
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use serde_json::{json, Value};
+
+/// The scoreboard objectives that a pack's commands use, along with the fake players whose scores they use in each.
+/// Objectives are shared by every pack on a server, so two packs that use the same one read and write each other's
+/// scores, which `collisions` finds from the reports of both.
+pub struct ScoreboardReport {
+    pack: String,
+    // fake players are score holders that aren't entities, e.g `v0` or `flag`, rather than `@s`
+    objectives: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// An objective that two packs both use, along with the fake players that both use scores of in it.
+pub struct Collision {
+    pub objective: String,
+    pub fake_players: Vec<String>,
+}
+
+impl ScoreboardReport {
+    /// Creates the report of the files generated by the default backend for the given pack.
+    pub fn new(pack_name: &str, files: &[(PathBuf, String)]) -> Self {
+        let mut report = Self {
+            pack: pack_name.to_string(),
+            objectives: BTreeMap::new(),
+        };
+
+        for (path, contents) in files {
+            if path.extension().is_some_and(|extension| extension == "mcfunction") {
+                for command in contents.lines() {
+                    report.add_command(command.trim());
+                }
+            }
+        }
+
+        report
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("failed to read scoreboard report: {}", err))?;
+
+        Self::from_json(&content)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let report: Value = serde_json::from_str(json).map_err(|err| format!("failed to parse scoreboard report: {}", err))?;
+        let invalid = || "scoreboard report is not a report made with --scoreboard-report".to_string();
+
+        let pack = report["pack"].as_str().ok_or_else(invalid)?.to_string();
+        let mut objectives = BTreeMap::new();
+
+        for (objective, fake_players) in report["objectives"].as_object().ok_or_else(invalid)? {
+            let fake_players = fake_players
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|fake_player| fake_player.as_str().map(str::to_string).ok_or_else(invalid))
+                .collect::<Result<BTreeSet<String>, String>>()?;
+
+            objectives.insert(objective.clone(), fake_players);
+        }
+
+        Ok(Self { pack, objectives })
+    }
+
+    pub fn to_json(&self) -> String {
+        let report = json!({
+            "pack": self.pack,
+            "objectives": self.objectives,
+        });
+
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+
+    pub fn pack(&self) -> &str {
+        &self.pack
+    }
+
+    /// The objectives that both reports use. Even when the packs use scores of different fake players in them,
+    /// creating the objective fails for the second pack, and removing it removes the scores of both.
+    pub fn collisions(&self, other: &ScoreboardReport) -> Vec<Collision> {
+        self.objectives
+            .iter()
+            .filter_map(|(objective, fake_players)| {
+                let other_fake_players = other.objectives.get(objective)?;
+
+                Some(Collision {
+                    objective: objective.clone(),
+                    fake_players: fake_players.intersection(other_fake_players).cloned().collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn add_command(&mut self, command: &str) {
+        if command.is_empty() || command.starts_with('#') {
+            return;
+        }
+
+        let args = command.split_whitespace().collect::<Vec<&str>>();
+
+        // execute chains are followed by looking for scores anywhere in the command
+        for idx in 0..args.len() {
+            match &args[idx..] {
+                ["scoreboard", "objectives", "add", objective, ..] => self.add_score(None, objective),
+                ["scoreboard", "players", "operation", target, target_objective, _, source, source_objective, ..] => {
+                    self.add_score(Some(target), target_objective);
+                    self.add_score(Some(source), source_objective);
+                }
+                ["scoreboard", "players", "set" | "add" | "remove" | "reset" | "get" | "enable", holder, objective, ..] => {
+                    self.add_score(Some(holder), objective)
+                }
+                ["if" | "unless", "score", holder, objective, "<" | "<=" | "=" | ">=" | ">", other, other_objective, ..] => {
+                    self.add_score(Some(holder), objective);
+                    self.add_score(Some(other), other_objective);
+                }
+                ["if" | "unless", "score", holder, objective, ..] | ["store", "result" | "success", "score", holder, objective, ..] => {
+                    self.add_score(Some(holder), objective)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_score(&mut self, holder: Option<&str>, objective: &str) {
+        let fake_players = self.objectives.entry(objective.to_string()).or_default();
+
+        // entities, every holder at once with *, and macros all stand for holders that aren't known until it's run
+        if let Some(holder) = holder.filter(|holder| !holder.starts_with(['@', '*', '$'])) {
+            fake_players.insert(holder.to_string());
+        }
+    }
+}
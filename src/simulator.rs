@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    path::PathBuf,
+};
+
+/// How many commands a simulation may run before it's assumed to never finish.
+const MAX_COMMANDS: usize = 1_000_000;
+
+/// A small interpreter for the commands that sculk generates, which runs the functions of a pack that only work with
+/// scores. Anything else, such as storage, entities or the world, isn't simulated, and running it is an error.
+///
+/// Scores follow the rules of the game: arithmetic wraps around like Java's ints, division and modulo round towards
+/// negative infinity and leave the score alone when dividing by zero, and reading a score that was never set fails.
+pub struct Simulator {
+    functions: HashMap<String, Vec<String>>,
+    load: Vec<String>,
+    scores: HashMap<(String, String), i32>,
+    commands_run: usize,
+}
+
+#[derive(Debug)]
+pub enum SimulationError {
+    UnknownFunction(String),
+    Unsupported(String),
+    TooManyCommands,
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::UnknownFunction(name) => write!(f, "function {} does not exist", name),
+            SimulationError::Unsupported(command) if command.chars().count() > 80 => {
+                write!(f, "'{}...' cannot be simulated", command.chars().take(80).collect::<String>())
+            }
+            SimulationError::Unsupported(command) => write!(f, "'{}' cannot be simulated", command),
+            SimulationError::TooManyCommands => write!(f, "more than {} commands were run", MAX_COMMANDS),
+        }
+    }
+}
+
+// What running a command did: either it produced a result, which is None if it failed, or it returned from the
+// function it was run in
+enum Flow {
+    Continue(Option<i32>),
+    Return(i32),
+}
+
+impl Simulator {
+    /// Creates a simulator for the files generated by the default backend for the given pack.
+    pub fn new(pack_name: &str, files: &[(PathBuf, String)]) -> Self {
+        let mut functions = HashMap::new();
+        let mut load = Vec::new();
+
+        for (path, contents) in files {
+            let path = path.to_string_lossy().replace('\\', "/");
+
+            if let Some(name) = path.strip_prefix(&format!("{}/", pack_name)).and_then(|name| name.strip_suffix(".mcfunction")) {
+                functions.insert(
+                    format!("{}:{}", pack_name, name),
+                    contents.lines().map(|line| line.trim().to_string()).collect(),
+                );
+            } else if path == "minecraft/tags/functions/load.json" {
+                let tag = serde_json::from_str::<serde_json::Value>(contents).unwrap_or_default();
+
+                load.extend(
+                    tag["values"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|value| value.as_str().map(str::to_string)),
+                );
+            }
+        }
+
+        Self {
+            functions,
+            load,
+            scores: HashMap::new(),
+            commands_run: 0,
+        }
+    }
+
+    /// Runs the functions of the pack that run when it's loaded.
+    pub fn load(&mut self) -> Result<(), SimulationError> {
+        for function in self.load.clone() {
+            self.run_function(&function)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn score(&self, holder: &str, objective: &str) -> Option<i32> {
+        self.scores.get(&(holder.to_string(), objective.to_string())).copied()
+    }
+
+    /// Runs a function, e.g `pack:main`, and gives back the value it returned, if it returned one.
+    pub fn run_function(&mut self, name: &str) -> Result<Option<i32>, SimulationError> {
+        let commands = match self.functions.get(name) {
+            Some(commands) => commands.clone(),
+            None => return Err(SimulationError::UnknownFunction(name.to_string())),
+        };
+
+        for command in commands.iter().filter(|command| !command.is_empty() && !command.starts_with('#')) {
+            if let Flow::Return(value) = self.run_command(command)? {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn run_command(&mut self, command: &str) -> Result<Flow, SimulationError> {
+        self.commands_run += 1;
+
+        if self.commands_run > MAX_COMMANDS {
+            return Err(SimulationError::TooManyCommands);
+        }
+
+        let args = command.split_whitespace().collect::<Vec<&str>>();
+        let unsupported = || Err(SimulationError::Unsupported(command.to_string()));
+
+        match args.as_slice() {
+            ["scoreboard", "objectives", "add", ..] => Ok(Flow::Continue(Some(0))),
+            ["scoreboard", "players", rest @ ..] => match self.run_players_command(rest) {
+                Some(result) => Ok(Flow::Continue(result)),
+                None => unsupported(),
+            },
+            ["execute", rest @ ..] => self.run_execute(command, rest),
+            ["function", name] => Ok(Flow::Continue(self.run_function(name)?)),
+            ["return", "fail"] => Ok(Flow::Return(0)),
+            ["return", "run", rest @ ..] => match self.run_command(&rest.join(" "))? {
+                Flow::Continue(result) => Ok(Flow::Return(result.unwrap_or(0))),
+                flow => Ok(flow),
+            },
+            ["return", value] => match value.parse() {
+                Ok(value) => Ok(Flow::Return(value)),
+                Err(_) => unsupported(),
+            },
+            _ => unsupported(),
+        }
+    }
+
+    // Runs a `scoreboard players` command, giving back its result, which is None if it failed, or None altogether if
+    // it isn't one that can be simulated
+    fn run_players_command(&mut self, args: &[&str]) -> Option<Option<i32>> {
+        let result = match args {
+            ["set", holder, objective, value] => {
+                let value = value.parse().ok()?;
+                self.set_score(holder, objective, value);
+                Some(value)
+            }
+            [op @ ("add" | "remove"), holder, objective, value] => {
+                let value = value.parse::<i32>().ok()?;
+                let current = self.score(holder, objective).unwrap_or(0);
+
+                let value = match *op {
+                    "add" => current.wrapping_add(value),
+                    _ => current.wrapping_sub(value),
+                };
+
+                self.set_score(holder, objective, value);
+                Some(value)
+            }
+            ["reset", holder, objective] => {
+                self.scores.remove(&(holder.to_string(), objective.to_string()));
+                Some(0)
+            }
+            ["get", holder, objective] => self.score(holder, objective),
+            ["operation", target, target_objective, op, source, source_objective] => {
+                let b = match self.score(source, source_objective) {
+                    Some(b) => b,
+                    None => return Some(None),
+                };
+
+                let a = self.score(target, target_objective).unwrap_or(0);
+
+                let result = match *op {
+                    "=" => b,
+                    "+=" => a.wrapping_add(b),
+                    "-=" => a.wrapping_sub(b),
+                    "*=" => a.wrapping_mul(b),
+                    "/=" if b == 0 => a,
+                    "/=" => floor_div(a, b),
+                    "%=" if b == 0 => a,
+                    "%=" => a.wrapping_sub(floor_div(a, b).wrapping_mul(b)),
+                    "<" => a.min(b),
+                    ">" => a.max(b),
+                    "><" => {
+                        self.set_score(source, source_objective, a);
+                        b
+                    }
+                    _ => return None,
+                };
+
+                self.set_score(target, target_objective, result);
+                Some(result)
+            }
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    fn run_execute(&mut self, command: &str, mut args: &[&str]) -> Result<Flow, SimulationError> {
+        let unsupported = || Err(SimulationError::Unsupported(command.to_string()));
+
+        // the scores that the result or success of the command is stored in once it has run
+        let mut stores = Vec::new();
+
+        let result = loop {
+            match args {
+                ["store", kind @ ("result" | "success"), "score", holder, objective, rest @ ..] => {
+                    stores.push((*kind == "success", holder.to_string(), objective.to_string()));
+                    args = rest;
+                }
+                [kind @ ("if" | "unless"), "score", holder, objective, rest @ ..] => {
+                    let score = self.score(holder, objective);
+
+                    let (passed, rest) = match rest {
+                        ["matches", range, rest @ ..] => match parse_range(range) {
+                            Some((min, max)) => (score.is_some_and(|score| min <= score && score <= max), rest),
+                            None => return unsupported(),
+                        },
+                        [op, other, other_objective, rest @ ..] => {
+                            let other = self.score(other, other_objective);
+
+                            let passed = match (score, other, *op) {
+                                (Some(a), Some(b), "<") => a < b,
+                                (Some(a), Some(b), "<=") => a <= b,
+                                (Some(a), Some(b), "=") => a == b,
+                                (Some(a), Some(b), ">=") => a >= b,
+                                (Some(a), Some(b), ">") => a > b,
+                                (_, _, "<" | "<=" | "=" | ">=" | ">") => false,
+                                _ => return unsupported(),
+                            };
+
+                            (passed, rest)
+                        }
+                        _ => return unsupported(),
+                    };
+
+                    args = rest;
+
+                    // a condition that fails stops the command, which only counts as a result when it's the last part
+                    if passed != (*kind == "if") {
+                        if !args.is_empty() {
+                            return Ok(Flow::Continue(None));
+                        }
+
+                        break None;
+                    }
+
+                    if args.is_empty() {
+                        break Some(1);
+                    }
+                }
+                ["run", rest @ ..] => match self.run_command(&rest.join(" "))? {
+                    Flow::Continue(result) => break result,
+                    flow => return Ok(flow),
+                },
+                _ => return unsupported(),
+            }
+        };
+
+        for (success, holder, objective) in stores {
+            let value = match (success, result) {
+                (true, result) => result.is_some() as i32,
+                (false, result) => result.unwrap_or(0),
+            };
+
+            self.set_score(&holder, &objective, value);
+        }
+
+        Ok(Flow::Continue(result))
+    }
+
+    fn set_score(&mut self, holder: &str, objective: &str, value: i32) {
+        self.scores.insert((holder.to_string(), objective.to_string()), value);
+    }
+}
+
+// Rounds towards negative infinity like Java's Math.floorDiv, where the smallest int divided by -1 wraps around
+fn floor_div(a: i32, b: i32) -> i32 {
+    let (a, b) = (a as i64, b as i64);
+    let quotient = a / b - ((a % b != 0 && (a < 0) != (b < 0)) as i64);
+    quotient as i32
+}
+
+// Parses the range of `matches`, e.g `5`, `1..`, `..3` or `1..5`, into its inclusive bounds
+fn parse_range(range: &str) -> Option<(i32, i32)> {
+    let bound = |bound: &str, default: i32| match bound {
+        "" => Some(default),
+        _ => bound.parse().ok(),
+    };
+
+    match range.split_once("..") {
+        Some((min, max)) => Some((bound(min, i32::MIN)?, bound(max, i32::MAX)?)),
+        None => range.parse().ok().map(|value| (value, value)),
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::DefaultBackend,
+    consteval::{self, Value},
+    compile_to_ir, error,
+    parser::{Parser, ParserNode, ParserNodeKind},
+    registry::Registries,
+    simulator::Simulator,
+    timings::Timings,
+    Config,
+};
+
+/// How many unchanged lines are shown around each change in a diff.
+const CONTEXT_LINES: usize = 2;
+
+/// Golden-file testing, which locks down the code that sculk generates.
+/// Every `name.sculk` fixture in the directory is compiled with the default backend, and the files it generates are
+/// compared with the expected files checked in under `name/` next to it. Any file that is missing, unexpected or
+/// different is reported with a diff. When blessing, the expected files are replaced with the generated ones instead.
+///
+/// Returns whether every fixture matched its expected files.
+pub fn check_fixtures(config: &Config, registries: Option<&Registries>, dir: &Path, bless: bool) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("failed to read fixture directory: {}", err);
+            return false;
+        }
+    };
+
+    let mut fixtures = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sculk"))
+        .collect::<Vec<PathBuf>>();
+    fixtures.sort();
+
+    let mut failed = 0;
+
+    for fixture in &fixtures {
+        let passed = match generate(config, registries, fixture) {
+            Some(generated) if bless => bless_fixture(fixture, generated),
+            Some(generated) => compare_fixture(fixture, generated),
+            None => false,
+        };
+
+        if !passed {
+            failed += 1;
+        }
+    }
+
+    match (bless, failed) {
+        (true, _) => println!("updated the expected files of {} fixture(s)", fixtures.len() - failed),
+        (false, 0) => println!("all {} fixture(s) match their expected files", fixtures.len()),
+        (false, _) => println!("{} of {} fixture(s) do not match their expected files", failed, fixtures.len()),
+    }
+
+    failed == 0
+}
+
+// The directory that the expected files of a fixture are kept in, named after the fixture without its extension
+fn expected_dir(fixture: &Path) -> PathBuf {
+    fixture.with_extension("")
+}
+
+fn generate(config: &Config, registries: Option<&Registries>, fixture: &Path) -> Option<BTreeMap<PathBuf, String>> {
+    let name = fixture.display().to_string();
+
+    let src = match std::fs::read_to_string(fixture) {
+        Ok(src) => src,
+        Err(err) => {
+            println!("failed to read fixture {}: {}", name, err);
+            return None;
+        }
+    };
+
+    match compile_to_ir(config, registries, &name, &src, &mut Timings::default()) {
+        Ok((_, funcs, _)) => match DefaultBackend::generate(config, &funcs, &mut Timings::default()) {
+            Ok(files) => Some(files.into_iter().collect()),
+            Err(internal_errors) => {
+                println!("fixture {} failed to generate:", name);
+
+                for err in internal_errors {
+                    println!("{}", err);
+                }
+
+                None
+            }
+        },
+        Err((info, errors)) => {
+            println!("fixture {} failed to compile:", name);
+            error::print_reports(&info.sources, errors, &info.types, &info.signatures);
+            None
+        }
+    }
+}
+
+fn bless_fixture(fixture: &Path, generated: BTreeMap<PathBuf, String>) -> bool {
+    let dir = expected_dir(fixture);
+
+    if dir.exists() {
+        if let Err(err) = std::fs::remove_dir_all(&dir) {
+            println!("failed to remove the old expected files of {}: {}", fixture.display(), err);
+            return false;
+        }
+    }
+
+    for (path, contents) in generated {
+        let path = dir.join(path);
+
+        if let Err(err) = std::fs::create_dir_all(path.parent().unwrap()) {
+            println!("failed to create directory for expected file: {}", err);
+            return false;
+        }
+
+        if let Err(err) = std::fs::write(&path, contents) {
+            println!("failed to write expected file: {}", err);
+            return false;
+        }
+    }
+
+    true
+}
+
+fn compare_fixture(fixture: &Path, generated: BTreeMap<PathBuf, String>) -> bool {
+    let dir = expected_dir(fixture);
+    let mut expected = BTreeMap::new();
+
+    if !dir.exists() {
+        println!("fixture {} has no expected files yet, --bless creates them from its output", fixture.display());
+        return false;
+    }
+
+    if let Err(err) = read_tree(&dir, Path::new(""), &mut expected) {
+        println!("failed to read the expected files of {}: {}", fixture.display(), err);
+        return false;
+    }
+
+    if expected == generated {
+        return true;
+    }
+
+    println!("fixture {} does not match its expected files:", fixture.display());
+
+    for (path, contents) in &expected {
+        match generated.get(path) {
+            Some(generated) if generated != contents => {
+                println!("  {} differs (- expected, + generated):", path.display());
+                print_diff(contents, generated);
+            }
+            Some(_) => {}
+            None => println!("  {} is expected but was not generated", path.display()),
+        }
+    }
+
+    for path in generated.keys().filter(|path| !expected.contains_key(*path)) {
+        println!("  {} was generated but is not expected", path.display());
+    }
+
+    false
+}
+
+// Reads every file under a directory, keyed by its path relative to the directory
+fn read_tree(root: &Path, relative: &Path, files: &mut BTreeMap<PathBuf, String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(root.join(relative))? {
+        let path = relative.join(entry?.file_name());
+
+        if root.join(&path).is_dir() {
+            read_tree(root, &path, files)?;
+        } else {
+            files.insert(path.clone(), std::fs::read_to_string(root.join(&path))?);
+        }
+    }
+
+    Ok(())
+}
+
+// Prints the changes between two files line by line, found through their longest common subsequence of lines.
+// Unchanged lines are only shown near a change, with the ones in between collapsed into a ...
+fn print_diff(expected: &str, generated: &str) {
+    let old = expected.lines().collect::<Vec<&str>>();
+    let new = generated.lines().collect::<Vec<&str>>();
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    let near_change = |index: usize| {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(lines.len());
+        lines[start..end].iter().any(|(marker, _)| *marker != ' ')
+    };
+
+    let mut collapsed = false;
+
+    for (index, (marker, line)) in lines.iter().enumerate() {
+        if near_change(index) {
+            println!("    {} {}", marker, line);
+            collapsed = false;
+        } else if !collapsed {
+            println!("    ...");
+            collapsed = true;
+        }
+    }
+}
+
+/// Differential testing, which checks the code that sculk generates against what the compiler itself computes.
+/// Every expression in the file that can be evaluated at compile time, e.g `3 * (4 + 5)`, is compiled on its own into
+/// a function that returns it, which is run by the simulator. Any expression whose generated code computes something
+/// else than the compile-time evaluator is reported along with both values. Expressions that can't be compiled on
+/// their own, or whose code can't be simulated, are skipped.
+///
+/// Returns whether the generated code of every expression that was checked computes the same value.
+pub fn differential(config: &Config, registries: Option<&Registries>, path: &str) -> bool {
+    let src = match std::fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(err) => {
+            println!("failed to read file: {}", err);
+            return false;
+        }
+    };
+
+    let Ok(ast) = Parser::new(&src).try_parse() else {
+        println!("{} could not be parsed, compile it to see why", path);
+        return false;
+    };
+
+    let mut expressions = Vec::new();
+    constant_expressions(&ast, &mut expressions);
+
+    let mut diverged = 0;
+    let mut skipped = 0;
+
+    for (node, expected) in &expressions {
+        let text = &src[node.span()];
+        let line = src[..node.span().start].lines().count().max(1);
+
+        match simulate_expression(config, registries, *expected, text) {
+            Ok(actual) if actual == Some(expected.as_score()) => {}
+            Ok(actual) => {
+                diverged += 1;
+
+                let actual = actual.map_or("nothing".to_string(), |actual| actual.to_string());
+                println!(
+                    "{}:{}: `{}` is {} at compile time, but its generated code computes {}",
+                    path, line, text, expected.as_score(), actual
+                );
+            }
+            Err(reason) => {
+                skipped += 1;
+                println!("{}:{}: skipped `{}`, {}", path, line, text, reason);
+            }
+        }
+    }
+
+    println!(
+        "checked {} constant expression(s) in {}, {} diverged and {} were skipped",
+        expressions.len() - skipped,
+        path,
+        diverged,
+        skipped
+    );
+
+    diverged == 0
+}
+
+// Finds the outermost expressions that the compile-time evaluator can compute, along with the values it computes
+fn constant_expressions<'a>(node: &'a ParserNode, expressions: &mut Vec<(&'a ParserNode, Value)>) {
+    let node = node.unwrap_expression();
+
+    if let ParserNodeKind::Operation(..)
+    | ParserNodeKind::Unary(..)
+    | ParserNodeKind::Cast { .. }
+    | ParserNodeKind::Conditional { .. } = node.kind()
+    {
+        if let Ok(value) = consteval::eval(node, &HashMap::new()) {
+            expressions.push((node, value));
+            return;
+        }
+    }
+
+    let children: Vec<&ParserNode> = match node.kind() {
+        ParserNodeKind::Program(nodes)
+        | ParserNodeKind::Block(nodes)
+        | ParserNodeKind::Tuple(nodes)
+        | ParserNodeKind::ArrayLiteral(nodes)
+        | ParserNodeKind::StructDefinition { members: nodes, .. } => nodes.iter().collect(),
+        ParserNodeKind::VariableDeclaration { expr, .. }
+        | ParserNodeKind::VariableAssignment { expr, .. }
+        | ParserNodeKind::TupleDeclaration { expr, .. }
+        | ParserNodeKind::OpEquals { expr, .. }
+        | ParserNodeKind::Unary(expr, _)
+        | ParserNodeKind::Cast { expr, .. }
+        | ParserNodeKind::Return(Some(expr)) => vec![expr],
+        ParserNodeKind::FunctionDeclaration { body, .. } | ParserNodeKind::Every { body, .. } => vec![body],
+        ParserNodeKind::FunctionCall { args, .. } => args.iter().collect(),
+        ParserNodeKind::RunAs { call, .. } => vec![call],
+        ParserNodeKind::Operation(lhs, rhs, _) => vec![lhs, rhs],
+        ParserNodeKind::If {
+            cond,
+            body,
+            else_ifs,
+            else_body,
+        } => [cond.as_ref(), body.as_ref()]
+            .into_iter()
+            .chain(else_ifs.iter().flat_map(|(cond, body)| [cond, body]))
+            .chain(else_body.as_deref())
+            .collect(),
+        ParserNodeKind::IfLet {
+            expr, body, else_body, ..
+        } => [expr.as_ref(), body.as_ref()].into_iter().chain(else_body.as_deref()).collect(),
+        ParserNodeKind::Coalesce { expr, default } => vec![expr, default],
+        ParserNodeKind::Conditional { cond, then, otherwise } => vec![cond, then, otherwise],
+        ParserNodeKind::Match { expr, arms } => std::iter::once(expr.as_ref()).chain(arms.iter().map(|(_, arm)| arm)).collect(),
+        ParserNodeKind::For {
+            init, cond, step, body, ..
+        } => vec![init, cond, step, body],
+        _ => Vec::new(),
+    };
+
+    for child in children {
+        constant_expressions(child, expressions);
+    }
+}
+
+// Compiles an expression on its own into a function that returns it, runs that function and gives back what it
+// returned, or why the expression was skipped
+fn simulate_expression(
+    config: &Config,
+    registries: Option<&Registries>,
+    value: Value,
+    text: &str,
+) -> Result<Option<i32>, String> {
+    let src = format!(
+        "fn zz_probe() -> {} {{\n    return {};\n}}\n",
+        value.type_name(),
+        text
+    );
+
+    // the probe doesn't import anything, so it doesn't need to be next to any other files
+    let funcs = match compile_to_ir(config, registries, "zz_probe.sculk", &src, &mut Timings::default()) {
+        Ok((_, funcs, _)) => funcs,
+        Err(_) => return Err("it can't be compiled on its own".to_string()),
+    };
+
+    let files = DefaultBackend::generate(config, &funcs, &mut Timings::default())
+        .map_err(|_| "its generated code has internal errors".to_string())?;
+    let mut simulator = Simulator::new(&config.pack, &files);
+
+    simulator
+        .load()
+        .and_then(|_| simulator.run_function(&format!("{}:zz_probe", config.pack)))
+        .map_err(|err| err.to_string())?;
+
+    Ok(simulator.score("v0", "zz_probe.return"))
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+/// How many of the functions that took longest to generate are listed by --timings.
+const SLOWEST_FUNCTIONS: usize = 10;
+
+/// How long each phase of compiling a file took, and how long the code of each function took to generate.
+/// Collected for every file, but only printed when --timings is given.
+#[derive(Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    functions: Vec<(String, Duration)>,
+}
+
+impl Timings {
+    /// Records a phase that started at the given instant and has just finished.
+    pub fn record(&mut self, phase: &'static str, start: Instant) {
+        self.phases.push((phase, start.elapsed()));
+    }
+
+    /// Records a phase that was timed as part of another one, such as lexing, which happens while parsing.
+    pub fn record_duration(&mut self, phase: &'static str, duration: Duration) {
+        self.phases.push((phase, duration));
+    }
+
+    pub fn record_function(&mut self, name: String, duration: Duration) {
+        self.functions.push((name, duration));
+    }
+
+    pub fn print(&self, file: &str) {
+        println!("timings for {}:", file);
+
+        for (phase, duration) in &self.phases {
+            println!("  {:<12} {:>10.3}ms", phase, millis(*duration));
+        }
+
+        let total = self.phases.iter().map(|(_, duration)| *duration).sum();
+        println!("  {:<12} {:>10.3}ms", "total", millis(total));
+
+        if self.functions.is_empty() {
+            return;
+        }
+
+        let mut functions = self.functions.iter().collect::<Vec<_>>();
+        functions.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+
+        println!("  slowest functions to generate:");
+
+        for (name, duration) in functions.into_iter().take(SLOWEST_FUNCTIONS) {
+            println!("    {:<30} {:>10.3}ms", name, millis(*duration));
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use sculk::{
+    consteval::{self, Value},
+    parser::{Parser, ParserNode, ParserNodeKind},
+};
+
+// The expression that the first statement of the program's only function declares a variable with
+fn declared_expression(program: &ParserNode) -> &ParserNode {
+    let ParserNodeKind::FunctionDeclaration { body, .. } = program.as_program()[0].kind() else {
+        panic!("expected a function declaration");
+    };
+    let ParserNodeKind::Block(statements) = body.kind() else {
+        panic!("expected the body of the function");
+    };
+    let ParserNodeKind::VariableDeclaration { expr, .. } = statements[0].unwrap_expression().kind() else {
+        panic!("expected a variable declaration");
+    };
+
+    expr
+}
+
+#[test]
+fn evaluates_expressions_parsed_outside_of_the_compiler() {
+    let output = Parser::new("fn main() { let x = -7 / n + 1 > 0 || false; }").parse();
+    assert!(output.errors.is_empty());
+
+    let expr = declared_expression(&output.ast);
+    let env = HashMap::from([("n".to_string(), Value::Int(2))]);
+
+    // division rounds towards negative infinity like the scoreboard operation it compiles to
+    assert_eq!(consteval::eval(expr, &env).ok(), Some(Value::Bool(false)));
+    assert!(consteval::eval(expr, &HashMap::new()).is_err());
+}
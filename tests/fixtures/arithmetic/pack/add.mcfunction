@@ -0,0 +1,8 @@
+scoreboard players operation v3 add = v0 add
+scoreboard players operation v4 add = v1 add
+scoreboard players operation v3 add += v4 add
+scoreboard players operation v5 add = v2 add
+scoreboard players operation v3 add += v5 add
+scoreboard players operation v0 add.return = v3 add
+scoreboard players set flag add.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,12 @@
+scoreboard players operation v3 determinant = v1 determinant
+scoreboard players operation v4 determinant = v1 determinant
+scoreboard players operation v3 determinant *= v4 determinant
+scoreboard players set v5 determinant 4
+scoreboard players operation v6 determinant = v0 determinant
+scoreboard players operation v5 determinant *= v6 determinant
+scoreboard players operation v7 determinant = v2 determinant
+scoreboard players operation v5 determinant *= v7 determinant
+scoreboard players operation v3 determinant -= v5 determinant
+scoreboard players operation v0 determinant.return = v3 determinant
+scoreboard players set flag determinant.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,22 @@
+scoreboard players set v0 main 1
+scoreboard players set v1 main 2
+scoreboard players set v2 main 3
+scoreboard players operation v0 add = v0 main
+scoreboard players operation v1 add = v1 main
+scoreboard players operation v2 add = v2 main
+function pack:add
+scoreboard players operation v3 main = v0 add.return
+scoreboard players operation v4 main = v3 main
+scoreboard players set v5 main 1
+scoreboard players operation v6 main = v4 main
+scoreboard players set v7 main 2
+scoreboard players operation v0 determinant = v5 main
+scoreboard players operation v1 determinant = v6 main
+scoreboard players operation v2 determinant = v7 main
+function pack:determinant
+scoreboard players operation v8 main = v0 determinant.return
+scoreboard players set v9 main 7
+scoreboard players operation v8 main %= v9 main
+scoreboard players operation v10 main = v8 main
+scoreboard players operation v11 main = v10 main
+tellraw @a ["",{"text":"d is "},{"score":{"name":"v11","objective":"main"}}]
\ No newline at end of file
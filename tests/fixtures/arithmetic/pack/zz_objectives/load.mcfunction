@@ -0,0 +1,5 @@
+scoreboard objectives add add dummy
+scoreboard objectives add add.return dummy
+scoreboard objectives add determinant dummy
+scoreboard objectives add determinant.return dummy
+scoreboard objectives add main dummy
\ No newline at end of file
@@ -0,0 +1,5 @@
+data modify storage pack:values "first".v1_0 set from storage pack:values "first".v0_0
+execute store result score v2 first run data get storage pack:values "first".v1_0[0]
+scoreboard players operation v0 first.return = v2 first
+scoreboard players set flag first.return 1
+return 0
\ No newline at end of file
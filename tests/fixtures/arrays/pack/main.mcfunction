@@ -0,0 +1,37 @@
+scoreboard players set v0 main 2
+scoreboard players operation v1 main = v0 main
+data modify storage pack:values "main".v2_0 set value [1,0,3]
+scoreboard players operation v3 main = v1 main
+execute store result storage pack:values "main".v2_0[1] int 1 run scoreboard players get v3 main
+data modify storage pack:values "main".v4_0 set from storage pack:values "main".v2_0
+data modify storage pack:values "main".v5_0 set value [1,0]
+data modify storage pack:values "main".v6_0 set from storage pack:values "main".v5_0
+scoreboard players set v7 main 7
+execute store result storage pack:values element int 1 run scoreboard players get v7 main
+data modify storage pack:values "main".v4_0[1] set from storage pack:values element
+data modify storage pack:values "main".v8_0 set from storage pack:values "main".v4_0
+execute store result score v9 main run data get storage pack:values "main".v8_0[0]
+scoreboard players operation v10 main = v1 main
+execute store result storage pack:args tables_get.index int 1 run scoreboard players get v10 main
+data modify storage pack:args tables_get.path set value "SQ"
+execute store result score v11 main run function pack:zz_tables/get with storage pack:args tables_get
+scoreboard players operation v9 main += v11 main
+execute store result storage pack:values element int 1 run scoreboard players get v9 main
+scoreboard players operation v12 main = v1 main
+execute store result storage pack:args arrays_set.index int 1 run scoreboard players get v12 main
+data modify storage pack:args arrays_set.path set value "\"main\".v4_0"
+function pack:zz_arrays/set with storage pack:args arrays_set
+data modify storage pack:values "main".v13_0 set from storage pack:values "main".v4_0
+data modify storage pack:values "main".v14_0 set from storage pack:values "main".v13_0
+data modify storage pack:values "main".v15_0 set from storage pack:values "main".v14_0
+scoreboard players set v16 main 9
+execute store result storage pack:values element int 1 run scoreboard players get v16 main
+data modify storage pack:values "main".v15_0[0] set from storage pack:values element
+data modify storage pack:values "main".v17_0 set from storage pack:values "main".v15_0
+data modify storage pack:values "main".v18_0 set from storage pack:values "main".v17_0
+data modify storage pack:values "first".v0_0 set from storage pack:values "main".v18_0
+function pack:first
+scoreboard players operation v19 main = v0 first.return
+data modify storage pack:values "main".v20_0 set from storage pack:values "main".v6_0
+execute store result score v21 main run data get storage pack:values "main".v20_0[1]
+tellraw @a ["",{"text":"first is "},{"score":{"name":"v19","objective":"main"}},{"text":" and flag is "},{"score":{"name":"v21","objective":"main"}}]
\ No newline at end of file
@@ -0,0 +1 @@
+$return run data modify storage pack:values $(path)[$(index)] set from storage pack:values element
\ No newline at end of file
@@ -0,0 +1,3 @@
+scoreboard objectives add first dummy
+scoreboard objectives add first.return dummy
+scoreboard objectives add main dummy
\ No newline at end of file
@@ -0,0 +1 @@
+$return run data get storage pack:tables $(path)[$(index)]
\ No newline at end of file
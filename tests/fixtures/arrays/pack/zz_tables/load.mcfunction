@@ -0,0 +1 @@
+data modify storage pack:tables SQ set value [I;0,1,4,9]
\ No newline at end of file
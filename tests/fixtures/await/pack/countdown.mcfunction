@@ -0,0 +1,7 @@
+scoreboard players operation v1 countdown = v0 countdown
+scoreboard players operation v2 countdown = v1 countdown
+
+data modify storage pack:awaits "countdown".b1 append value {}
+execute store result storage pack:awaits "countdown".b1[-1].v0_0 int 1 run scoreboard players get v0 countdown
+execute store result storage pack:awaits "countdown".b1[-1].v2_0 int 1 run scoreboard players get v2 countdown
+schedule function pack:countdown/zz_anon_0 20t append
\ No newline at end of file
@@ -0,0 +1,10 @@
+execute if data storage pack:awaits "countdown".b1[0].v0_0 store result score v0 countdown run data get storage pack:awaits "countdown".b1[0].v0_0
+execute if data storage pack:awaits "countdown".b1[0].v2_0 store result score v2 countdown run data get storage pack:awaits "countdown".b1[0].v2_0
+data remove storage pack:awaits "countdown".b1[0]
+scoreboard players remove v2 countdown 1
+scoreboard players operation v3 countdown = v2 countdown
+tellraw @a ["",{"text":"then "},{"score":{"name":"v3","objective":"countdown"}}]
+
+data modify storage pack:awaits "countdown".b2 append value {}
+execute store result storage pack:awaits "countdown".b2[-1].v0_0 int 1 run scoreboard players get v0 countdown
+schedule function pack:countdown/zz_anon_1 40t append
\ No newline at end of file
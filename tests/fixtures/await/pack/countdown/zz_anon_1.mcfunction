@@ -0,0 +1,4 @@
+execute if data storage pack:awaits "countdown".b2[0].v0_0 store result score v0 countdown run data get storage pack:awaits "countdown".b2[0].v0_0
+data remove storage pack:awaits "countdown".b2[0]
+scoreboard players operation v4 countdown = v0 countdown
+tellraw @a ["",{"text":"done after "},{"score":{"name":"v4","objective":"countdown"}}]
\ No newline at end of file
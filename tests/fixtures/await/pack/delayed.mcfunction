@@ -0,0 +1,8 @@
+scoreboard players operation v1 delayed = v0 delayed
+scoreboard players set v2 delayed 1
+scoreboard players operation v1 delayed += v2 delayed
+scoreboard players operation v3 delayed = v1 delayed
+
+data modify storage pack:awaits "delayed".b0 append value {}
+execute store result storage pack:awaits "delayed".b0[-1].v3_0 int 1 run scoreboard players get v3 delayed
+schedule function pack:delayed/zz_anon_0 5t append
\ No newline at end of file
@@ -0,0 +1,4 @@
+execute if data storage pack:awaits "delayed".b0[0].v3_0 store result score v3 delayed run data get storage pack:awaits "delayed".b0[0].v3_0
+data remove storage pack:awaits "delayed".b0[0]
+scoreboard players operation v4 delayed = v3 delayed
+tellraw @a ["",{"text":"y is "},{"score":{"name":"v4","objective":"delayed"}}]
\ No newline at end of file
@@ -0,0 +1,12 @@
+scoreboard players set v0 main 1
+scoreboard players operation v0 delayed = v0 main
+function pack:delayed
+scoreboard players set v1 main 2
+scoreboard players operation v0 delayed = v1 main
+function pack:delayed
+scoreboard players set v2 main 3
+scoreboard players operation v0 countdown = v2 main
+function pack:countdown
+scoreboard players set v3 main 5
+scoreboard players operation v0 countdown = v3 main
+function pack:countdown
\ No newline at end of file
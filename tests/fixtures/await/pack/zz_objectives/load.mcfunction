@@ -0,0 +1,3 @@
+scoreboard objectives add countdown dummy
+scoreboard objectives add delayed dummy
+scoreboard objectives add main dummy
\ No newline at end of file
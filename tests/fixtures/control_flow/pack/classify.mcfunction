@@ -0,0 +1,19 @@
+scoreboard players set flag classify.return 0
+
+scoreboard players operation v2 classify = v0 classify
+scoreboard players set v3 classify 0
+execute store success score v2 classify if score v2 classify < v3 classify
+execute if score v2 classify matches 1 run function pack:classify/zz_anon_0
+execute if score flag classify.return matches 1 run return 0
+scoreboard players operation v4 classify = v0 classify
+
+execute if score v4 classify matches 0 run function pack:classify/zz_anon_1
+execute if score flag classify.return matches 1 run return 0
+
+execute if score v4 classify matches 1..9 run function pack:classify/zz_anon_2
+execute if score flag classify.return matches 1 run return 0
+
+execute if score v4 classify matches ..-1 run function pack:classify/zz_anon_3
+execute if score flag classify.return matches 1 run return 0
+execute if score v4 classify matches 10.. run function pack:classify/zz_anon_3
+execute if score flag classify.return matches 1 run return 0
\ No newline at end of file
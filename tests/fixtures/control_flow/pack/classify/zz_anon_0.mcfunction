@@ -0,0 +1,4 @@
+scoreboard players set v1 classify 0
+scoreboard players operation v0 classify.return = v1 classify
+scoreboard players set flag classify.return 1
+return 0
\ No newline at end of file
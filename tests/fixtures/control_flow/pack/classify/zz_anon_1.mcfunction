@@ -0,0 +1,4 @@
+scoreboard players set v5 classify 1
+scoreboard players operation v0 classify.return = v5 classify
+scoreboard players set flag classify.return 1
+return 0
\ No newline at end of file
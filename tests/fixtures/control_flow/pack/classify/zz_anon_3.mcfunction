@@ -0,0 +1,4 @@
+scoreboard players set v7 classify 3
+scoreboard players operation v0 classify.return = v7 classify
+scoreboard players set flag classify.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,14 @@
+scoreboard players set v0 count 0
+scoreboard players operation v1 count = v0 count
+scoreboard players set v2 count 0
+scoreboard players operation v3 count = v2 count
+
+scoreboard players operation v9 count = v3 count
+scoreboard players set v10 count 10
+execute store success score v9 count if score v9 count < v10 count
+execute if score v9 count matches 1 run function pack:count/zz_anon_0
+execute if score flag count.break matches 1 run scoreboard players set flag count.break 0
+scoreboard players operation v11 count = v1 count
+scoreboard players operation v0 count.return = v11 count
+scoreboard players set flag count.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,14 @@
+
+scoreboard players operation v4 count = v3 count
+scoreboard players set v5 count 5
+execute store success score v4 count if score v4 count = v5 count
+execute if score v4 count matches 1 run function pack:count/zz_anon_1
+execute if score flag count.break matches 1.. run return 0
+scoreboard players operation v6 count = v3 count
+scoreboard players operation v1 count += v6 count
+scoreboard players add v3 count 1
+scoreboard players operation v7 count = v3 count
+scoreboard players set v8 count 10
+execute store success score v7 count if score v7 count < v8 count
+execute if score v7 count matches 1 run function pack:count/zz_anon_0
+execute if score flag count.break matches 1.. run return 0
\ No newline at end of file
@@ -0,0 +1,2 @@
+scoreboard players set flag count.break 1
+return 0
\ No newline at end of file
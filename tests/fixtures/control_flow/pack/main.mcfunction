@@ -0,0 +1,8 @@
+function pack:count
+scoreboard players operation v0 main = v0 count.return
+scoreboard players operation v0 classify = v0 main
+function pack:classify
+scoreboard players operation v1 main = v0 classify.return
+scoreboard players operation v2 main = v1 main
+scoreboard players operation v3 main = v2 main
+tellraw @a ["",{"text":"c is "},{"score":{"name":"v3","objective":"main"}}]
\ No newline at end of file
@@ -0,0 +1,6 @@
+scoreboard objectives add classify dummy
+scoreboard objectives add classify.return dummy
+scoreboard objectives add count dummy
+scoreboard objectives add count.break dummy
+scoreboard objectives add count.return dummy
+scoreboard objectives add main dummy
\ No newline at end of file
@@ -0,0 +1,69 @@
+scoreboard players set v0 main 2000000000
+scoreboard players set v2 main 1000000000
+scoreboard players operation v1 main = v0 main
+scoreboard players operation v1 main /= v2 main
+scoreboard players operation v1.1 main = v0 main
+scoreboard players operation v1.1 main %= v2 main
+scoreboard players operation v3 main = v1 main
+scoreboard players operation v3.1 main = v1.1 main
+scoreboard players set v4 main 1500000000
+scoreboard players set v6 main 1000000000
+scoreboard players operation v5 main = v4 main
+scoreboard players operation v5 main /= v6 main
+scoreboard players operation v5.1 main = v4 main
+scoreboard players operation v5.1 main %= v6 main
+scoreboard players operation v7 main = v5 main
+scoreboard players operation v7.1 main = v5.1 main
+scoreboard players operation v8 main = v7 main
+scoreboard players operation v8.1 main = v7.1 main
+scoreboard players operation v3 main += v8 main
+scoreboard players operation v3.1 main += v8.1 main
+execute if score v3.1 main matches 1000000000.. run scoreboard players add v3 main 1
+execute if score v3.1 main matches 1000000000.. run scoreboard players remove v3.1 main 1000000000
+scoreboard players operation v9 main = v3 main
+scoreboard players operation v9.1 main = v3.1 main
+scoreboard players set v10 main 700000000
+scoreboard players set v12 main 1000000000
+scoreboard players operation v11 main = v10 main
+scoreboard players operation v11 main /= v12 main
+scoreboard players operation v11.1 main = v10 main
+scoreboard players operation v11.1 main %= v12 main
+scoreboard players operation v9 main -= v11 main
+scoreboard players operation v9.1 main -= v11.1 main
+execute if score v9.1 main matches ..-1 run scoreboard players remove v9 main 1
+execute if score v9.1 main matches ..-1 run scoreboard players add v9.1 main 1000000000
+scoreboard players operation v13 main = v9 main
+scoreboard players operation v13.1 main = v9.1 main
+scoreboard players set v14 main -5
+scoreboard players set v16 main 1000000000
+scoreboard players operation v15 main = v14 main
+scoreboard players operation v15 main /= v16 main
+scoreboard players operation v15.1 main = v14 main
+scoreboard players operation v15.1 main %= v16 main
+scoreboard players operation v3 main -= v15 main
+scoreboard players operation v3.1 main -= v15.1 main
+execute if score v3.1 main matches ..-1 run scoreboard players remove v3 main 1
+execute if score v3.1 main matches ..-1 run scoreboard players add v3.1 main 1000000000
+scoreboard players operation v17 main = v13 main
+scoreboard players operation v17.1 main = v13.1 main
+scoreboard players set v18 main 1000000000
+scoreboard players operation v17 main *= v18 main
+scoreboard players operation v17 main += v17.1 main
+scoreboard players operation v19 main = v17 main
+scoreboard players operation v20 main = v19 main
+scoreboard players operation v21 main = v3 main
+scoreboard players operation v21.1 main = v3.1 main
+scoreboard players operation v22 main = v13 main
+scoreboard players operation v22.1 main = v13.1 main
+scoreboard players set long main 0
+execute if score v21 main > v22 main run scoreboard players set long main 1
+execute if score v21 main = v22 main if score v21.1 main > v22.1 main run scoreboard players set long main 1
+scoreboard players operation v21 main = long main
+scoreboard players operation v23 main = v3 main
+scoreboard players operation v23.1 main = v3.1 main
+scoreboard players operation v24 main = v7 main
+scoreboard players operation v24.1 main = v7.1 main
+scoreboard players set long main 0
+execute if score v23 main = v24 main if score v23.1 main = v24.1 main run scoreboard players set long main 1
+scoreboard players operation v23 main = long main
+tellraw @a ["",{"text":"c is "},{"score":{"name":"v20","objective":"main"}},{"text":" and a > c is "},{"score":{"name":"v21","objective":"main"}},{"text":" and a == b is "},{"score":{"name":"v23","objective":"main"}}]
\ No newline at end of file
@@ -0,0 +1 @@
+scoreboard objectives add main dummy
\ No newline at end of file
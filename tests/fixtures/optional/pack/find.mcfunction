@@ -0,0 +1,13 @@
+scoreboard players set flag find.return 0
+
+scoreboard players operation v4 find = v0 find
+scoreboard players set v5 find 3
+execute store success score v4 find if score v4 find > v5 find
+execute if score v4 find matches 1 run function pack:find/zz_anon_0
+execute if score flag find.return matches 1 run return 0
+scoreboard players set v6 find 0
+scoreboard players set v6.1 find 0
+scoreboard players operation v0 find.return = v6 find
+scoreboard players operation v0.1 find.return = v6.1 find
+scoreboard players set flag find.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,9 @@
+scoreboard players operation v1 find = v0 find
+scoreboard players set v2 find 2
+scoreboard players operation v1 find *= v2 find
+scoreboard players operation v3 find = v1 find
+scoreboard players set v3.1 find 1
+scoreboard players operation v0 find.return = v3 find
+scoreboard players operation v0.1 find.return = v3.1 find
+scoreboard players set flag find.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,42 @@
+scoreboard players set v0 main 0
+scoreboard players set v0.1 main 0
+scoreboard players operation v1 main = v0 main
+scoreboard players operation v1.1 main = v0.1 main
+scoreboard players operation v2 main = v1 main
+scoreboard players operation v2.1 main = v1.1 main
+scoreboard players operation v3 main = v2 main
+
+execute if score v2.1 main matches 0 run function pack:main/zz_anon_0
+scoreboard players operation v5 main = v3 main
+scoreboard players set v6 main 1
+scoreboard players operation v0 find = v6 main
+function pack:find
+scoreboard players operation v7 main = v0 find.return
+scoreboard players operation v7.1 main = v0.1 find.return
+scoreboard players operation v8 main = v7 main
+
+execute if score v7.1 main matches 0 run function pack:main/zz_anon_1
+scoreboard players operation v13 main = v8 main
+scoreboard players set v14 main 5
+scoreboard players operation v0 find = v14 main
+function pack:find
+scoreboard players operation v15 main = v0 find.return
+scoreboard players operation v15.1 main = v0.1 find.return
+
+execute if score v15.1 main matches 1 run function pack:main/zz_anon_3
+scoreboard players operation v18 main = v5 main
+scoreboard players operation v19 main = v13 main
+scoreboard players set v20 main 1
+scoreboard players operation v21 main = v20 main
+scoreboard players set v21.1 main 1
+scoreboard players operation v0 pick = v21 main
+scoreboard players operation v0.1 pick = v21.1 main
+function pack:pick
+scoreboard players operation v22 main = v0 pick.return
+scoreboard players set v23 main 0
+scoreboard players set v23.1 main 0
+scoreboard players operation v0 pick = v23 main
+scoreboard players operation v0.1 pick = v23.1 main
+function pack:pick
+scoreboard players operation v24 main = v0 pick.return
+tellraw @a ["",{"text":"x is "},{"score":{"name":"v18","objective":"main"}},{"text":" and z is "},{"score":{"name":"v19","objective":"main"}},{"text":" and pick is "},{"score":{"name":"v22","objective":"main"}},{"text":" or "},{"score":{"name":"v24","objective":"main"}}]
\ No newline at end of file
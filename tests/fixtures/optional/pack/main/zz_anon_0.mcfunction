@@ -0,0 +1,2 @@
+scoreboard players set v4 main 7
+scoreboard players operation v3 main = v4 main
\ No newline at end of file
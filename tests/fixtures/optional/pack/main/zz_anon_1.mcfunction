@@ -0,0 +1,9 @@
+scoreboard players set v9 main 2
+scoreboard players operation v0 find = v9 main
+function pack:find
+scoreboard players operation v10 main = v0 find.return
+scoreboard players operation v10.1 main = v0.1 find.return
+scoreboard players operation v11 main = v10 main
+
+execute if score v10.1 main matches 0 run function pack:main/zz_anon_2
+scoreboard players operation v8 main = v11 main
\ No newline at end of file
@@ -0,0 +1,2 @@
+scoreboard players set v12 main 0
+scoreboard players operation v11 main = v12 main
\ No newline at end of file
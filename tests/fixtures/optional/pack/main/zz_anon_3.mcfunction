@@ -0,0 +1,3 @@
+scoreboard players operation v16 main = v15 main
+scoreboard players operation v17 main = v16 main
+tellraw @a ["",{"text":"found "},{"score":{"name":"v17","objective":"main"}}]
\ No newline at end of file
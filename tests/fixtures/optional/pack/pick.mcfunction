@@ -0,0 +1,9 @@
+scoreboard players set flag pick.return 0
+scoreboard players operation v1 pick = v0 pick
+scoreboard players operation v1.1 pick = v0.1 pick
+
+
+execute if score v1.1 pick matches 1 run function pack:pick/zz_anon_0
+execute if score flag pick.return matches 1 run return 0
+execute if score v1.1 pick matches 0 run function pack:pick/zz_anon_1
+execute if score flag pick.return matches 1 run return 0
\ No newline at end of file
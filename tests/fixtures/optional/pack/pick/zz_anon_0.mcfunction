@@ -0,0 +1,5 @@
+scoreboard players operation v2 pick = v1 pick
+scoreboard players operation v3 pick = v2 pick
+scoreboard players operation v0 pick.return = v3 pick
+scoreboard players set flag pick.return 1
+return 0
\ No newline at end of file
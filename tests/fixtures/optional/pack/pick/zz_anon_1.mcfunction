@@ -0,0 +1,4 @@
+scoreboard players set v4 pick -1
+scoreboard players operation v0 pick.return = v4 pick
+scoreboard players set flag pick.return 1
+return 0
\ No newline at end of file
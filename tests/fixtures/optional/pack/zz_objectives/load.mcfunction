@@ -0,0 +1,5 @@
+scoreboard objectives add find dummy
+scoreboard objectives add find.return dummy
+scoreboard objectives add main dummy
+scoreboard objectives add pick dummy
+scoreboard objectives add pick.return dummy
\ No newline at end of file
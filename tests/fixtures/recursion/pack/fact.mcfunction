@@ -0,0 +1,40 @@
+scoreboard players set flag fact.return 0
+
+scoreboard players operation v2 fact = v0 fact
+scoreboard players set v3 fact 1
+execute store success score v2 fact if score v2 fact <= v3 fact
+execute if score v2 fact matches 1 run function pack:fact/zz_anon_0
+execute if score flag fact.return matches 1 run return 0
+scoreboard players operation v4 fact = v0 fact
+scoreboard players operation v5 fact = v0 fact
+scoreboard players set v6 fact 1
+scoreboard players operation v5 fact -= v6 fact
+data modify storage pack:stack frames prepend value {}
+execute store result storage pack:stack frames[0].v0_0 int 1 run scoreboard players get v0 fact
+execute store result storage pack:stack frames[0].v1_0 int 1 run scoreboard players get v1 fact
+execute store result storage pack:stack frames[0].v2_0 int 1 run scoreboard players get v2 fact
+execute store result storage pack:stack frames[0].v3_0 int 1 run scoreboard players get v3 fact
+execute store result storage pack:stack frames[0].v4_0 int 1 run scoreboard players get v4 fact
+execute store result storage pack:stack frames[0].v5_0 int 1 run scoreboard players get v5 fact
+execute store result storage pack:stack frames[0].v6_0 int 1 run scoreboard players get v6 fact
+execute store result storage pack:stack frames[0].v7_0 int 1 run scoreboard players get v7 fact
+execute store result storage pack:stack frames[0].return_flag int 1 run scoreboard players get flag fact.return
+data modify storage pack:stack frames[0].values set from storage pack:values "fact"
+scoreboard players operation v0 fact = v5 fact
+function pack:fact
+execute store result score v0 fact run data get storage pack:stack frames[0].v0_0
+execute store result score v1 fact run data get storage pack:stack frames[0].v1_0
+execute store result score v2 fact run data get storage pack:stack frames[0].v2_0
+execute store result score v3 fact run data get storage pack:stack frames[0].v3_0
+execute store result score v4 fact run data get storage pack:stack frames[0].v4_0
+execute store result score v5 fact run data get storage pack:stack frames[0].v5_0
+execute store result score v6 fact run data get storage pack:stack frames[0].v6_0
+execute store result score v7 fact run data get storage pack:stack frames[0].v7_0
+execute store result score flag fact.return run data get storage pack:stack frames[0].return_flag
+data modify storage pack:values "fact" set from storage pack:stack frames[0].values
+data remove storage pack:stack frames[0]
+scoreboard players operation v7 fact = v0 fact.return
+scoreboard players operation v4 fact *= v7 fact
+scoreboard players operation v0 fact.return = v4 fact
+scoreboard players set flag fact.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,4 @@
+scoreboard players set v1 fact 1
+scoreboard players operation v0 fact.return = v1 fact
+scoreboard players set flag fact.return 1
+return 0
\ No newline at end of file
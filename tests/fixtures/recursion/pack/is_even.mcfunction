@@ -0,0 +1,36 @@
+scoreboard players set flag is_even.return 0
+
+scoreboard players operation v2 is_even = v0 is_even
+scoreboard players set v3 is_even 0
+execute store success score v2 is_even if score v2 is_even = v3 is_even
+execute if score v2 is_even matches 1 run function pack:is_even/zz_anon_0
+execute if score flag is_even.return matches 1 run return 0
+scoreboard players operation v4 is_even = v0 is_even
+scoreboard players set v5 is_even 1
+scoreboard players operation v4 is_even -= v5 is_even
+data modify storage pack:stack frames prepend value {}
+execute store result storage pack:stack frames[0].v0_0 int 1 run scoreboard players get v0 is_even
+execute store result storage pack:stack frames[0].v1_0 int 1 run scoreboard players get v1 is_even
+execute store result storage pack:stack frames[0].v2_0 int 1 run scoreboard players get v2 is_even
+execute store result storage pack:stack frames[0].v3_0 int 1 run scoreboard players get v3 is_even
+execute store result storage pack:stack frames[0].v4_0 int 1 run scoreboard players get v4 is_even
+execute store result storage pack:stack frames[0].v5_0 int 1 run scoreboard players get v5 is_even
+execute store result storage pack:stack frames[0].v6_0 int 1 run scoreboard players get v6 is_even
+execute store result storage pack:stack frames[0].return_flag int 1 run scoreboard players get flag is_even.return
+data modify storage pack:stack frames[0].values set from storage pack:values "is_even"
+scoreboard players operation v0 is_odd = v4 is_even
+function pack:is_odd
+execute store result score v0 is_even run data get storage pack:stack frames[0].v0_0
+execute store result score v1 is_even run data get storage pack:stack frames[0].v1_0
+execute store result score v2 is_even run data get storage pack:stack frames[0].v2_0
+execute store result score v3 is_even run data get storage pack:stack frames[0].v3_0
+execute store result score v4 is_even run data get storage pack:stack frames[0].v4_0
+execute store result score v5 is_even run data get storage pack:stack frames[0].v5_0
+execute store result score v6 is_even run data get storage pack:stack frames[0].v6_0
+execute store result score flag is_even.return run data get storage pack:stack frames[0].return_flag
+data modify storage pack:values "is_even" set from storage pack:stack frames[0].values
+data remove storage pack:stack frames[0]
+scoreboard players operation v6 is_even = v0 is_odd.return
+scoreboard players operation v0 is_even.return = v6 is_even
+scoreboard players set flag is_even.return 1
+return 0
\ No newline at end of file
@@ -0,0 +1,4 @@
+scoreboard players set v1 is_even 1
+scoreboard players operation v0 is_even.return = v1 is_even
+scoreboard players set flag is_even.return 1
+return 0
\ No newline at end of file
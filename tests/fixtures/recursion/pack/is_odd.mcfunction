@@ -0,0 +1,36 @@
+scoreboard players set flag is_odd.return 0
+
+scoreboard players operation v2 is_odd = v0 is_odd
+scoreboard players set v3 is_odd 0
+execute store success score v2 is_odd if score v2 is_odd = v3 is_odd
+execute if score v2 is_odd matches 1 run function pack:is_odd/zz_anon_0
+execute if score flag is_odd.return matches 1 run return 0
+scoreboard players operation v4 is_odd = v0 is_odd
+scoreboard players set v5 is_odd 1
+scoreboard players operation v4 is_odd -= v5 is_odd
+data modify storage pack:stack frames prepend value {}
+execute store result storage pack:stack frames[0].v0_0 int 1 run scoreboard players get v0 is_odd
+execute store result storage pack:stack frames[0].v1_0 int 1 run scoreboard players get v1 is_odd
+execute store result storage pack:stack frames[0].v2_0 int 1 run scoreboard players get v2 is_odd
+execute store result storage pack:stack frames[0].v3_0 int 1 run scoreboard players get v3 is_odd
+execute store result storage pack:stack frames[0].v4_0 int 1 run scoreboard players get v4 is_odd
+execute store result storage pack:stack frames[0].v5_0 int 1 run scoreboard players get v5 is_odd
+execute store result storage pack:stack frames[0].v6_0 int 1 run scoreboard players get v6 is_odd
+execute store result storage pack:stack frames[0].return_flag int 1 run scoreboard players get flag is_odd.return
+data modify storage pack:stack frames[0].values set from storage pack:values "is_odd"
+scoreboard players operation v0 is_even = v4 is_odd
+function pack:is_even
+execute store result score v0 is_odd run data get storage pack:stack frames[0].v0_0
+execute store result score v1 is_odd run data get storage pack:stack frames[0].v1_0
+execute store result score v2 is_odd run data get storage pack:stack frames[0].v2_0
+execute store result score v3 is_odd run data get storage pack:stack frames[0].v3_0
+execute store result score v4 is_odd run data get storage pack:stack frames[0].v4_0
+execute store result score v5 is_odd run data get storage pack:stack frames[0].v5_0
+execute store result score v6 is_odd run data get storage pack:stack frames[0].v6_0
+execute store result score flag is_odd.return run data get storage pack:stack frames[0].return_flag
+data modify storage pack:values "is_odd" set from storage pack:stack frames[0].values
+data remove storage pack:stack frames[0]
+scoreboard players operation v6 is_odd = v0 is_even.return
+scoreboard players operation v0 is_odd.return = v6 is_odd
+scoreboard players set flag is_odd.return 1
+return 0
\ No newline at end of file
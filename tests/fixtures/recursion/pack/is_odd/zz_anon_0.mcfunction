@@ -0,0 +1,4 @@
+scoreboard players set v1 is_odd 0
+scoreboard players operation v0 is_odd.return = v1 is_odd
+scoreboard players set flag is_odd.return 1
+return 0
\ No newline at end of file
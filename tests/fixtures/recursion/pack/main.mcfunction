@@ -0,0 +1,9 @@
+scoreboard players set v0 main 5
+scoreboard players operation v0 fact = v0 main
+function pack:fact
+scoreboard players operation v1 main = v0 fact.return
+scoreboard players set v2 main 4
+scoreboard players operation v0 is_even = v2 main
+function pack:is_even
+scoreboard players operation v3 main = v0 is_even.return
+tellraw @a ["",{"text":"fact is "},{"score":{"name":"v1","objective":"main"}},{"text":" and is_even is "},{"score":{"name":"v3","objective":"main"}}]
\ No newline at end of file
@@ -0,0 +1,7 @@
+scoreboard objectives add fact dummy
+scoreboard objectives add fact.return dummy
+scoreboard objectives add is_even dummy
+scoreboard objectives add is_even.return dummy
+scoreboard objectives add is_odd dummy
+scoreboard objectives add is_odd.return dummy
+scoreboard objectives add main dummy
\ No newline at end of file
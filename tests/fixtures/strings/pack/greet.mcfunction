@@ -0,0 +1,5 @@
+data modify storage pack:values "greet".v1_0 set from storage pack:values "greet".v0_0
+data modify storage pack:values "greet.return".v0_0 set from storage pack:values "greet".v1_0
+scoreboard players operation v0 greet.return = v1 greet
+scoreboard players set flag greet.return 1
+return 0
\ No newline at end of file
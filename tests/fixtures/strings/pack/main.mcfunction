@@ -0,0 +1,26 @@
+data modify storage pack:values "main".v0_0 set value "hello"
+data modify storage pack:values "main".v1_0 set from storage pack:values "main".v0_0
+data modify storage pack:values "main".v2_0 set from storage pack:values "main".v1_0
+data modify storage pack:values "greet".v0_0 set from storage pack:values "main".v2_0
+function pack:greet
+data modify storage pack:values "main".v3_0 set from storage pack:values "greet.return".v0_0
+data modify storage pack:values "main".v4_0 set from storage pack:values "main".v3_0
+data modify storage pack:values "main".v5_0 set value "bob"
+scoreboard players set v6 main 3
+data modify storage pack:values "main".v7_0 set from storage pack:values "main".v5_0
+scoreboard players operation v7.1 main = v6 main
+data modify storage pack:values "main".v8_0 set from storage pack:values "main".v7_0
+scoreboard players operation v8.1 main = v7.1 main
+data modify storage pack:values "main".v9_0 set from storage pack:values "main".v1_0
+data modify storage pack:values "main".v10_0 set from storage pack:values "main".v4_0
+tellraw @a ["",{"text":"a is "},{"nbt":"\"main\".v9_0","storage":"pack:values"},{"text":" and b is "},{"nbt":"\"main\".v10_0","storage":"pack:values"}]
+data modify storage pack:values "main".v11_0 set from storage pack:values "main".v8_0
+scoreboard players operation v11.1 main = v8.1 main
+data modify storage pack:values "main".v12_0 set from storage pack:values "main".v11_0
+data modify storage pack:values "main".v4_0 set from storage pack:values "main".v12_0
+
+data modify storage pack:values "main".v14_0 set from storage pack:values "main".v1_0
+data modify storage pack:values "main".v15_0 set from storage pack:values "main".v4_0
+data modify storage pack:values compare set from storage pack:values "main".v14_0
+execute store success score v16 main run data modify storage pack:values compare set from storage pack:values "main".v15_0
+execute if score v16 main matches 1 run function pack:main/zz_anon_0
\ No newline at end of file
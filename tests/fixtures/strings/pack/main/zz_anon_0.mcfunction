@@ -0,0 +1,2 @@
+data modify storage pack:values "main".v13_0 set from storage pack:values "main".v4_0
+tellraw @a ["",{"text":"b is now "},{"nbt":"\"main\".v13_0","storage":"pack:values"}]
\ No newline at end of file
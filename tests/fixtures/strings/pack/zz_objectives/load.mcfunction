@@ -0,0 +1,3 @@
+scoreboard objectives add greet dummy
+scoreboard objectives add greet.return dummy
+scoreboard objectives add main dummy
\ No newline at end of file
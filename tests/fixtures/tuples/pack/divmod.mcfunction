@@ -0,0 +1,12 @@
+scoreboard players operation v3 divmod = v0 divmod
+scoreboard players operation v4 divmod = v1 divmod
+scoreboard players operation v3 divmod /= v4 divmod
+scoreboard players operation v2 divmod = v3 divmod
+scoreboard players operation v5 divmod = v0 divmod
+scoreboard players operation v6 divmod = v1 divmod
+scoreboard players operation v5 divmod %= v6 divmod
+scoreboard players operation v2.1 divmod = v5 divmod
+scoreboard players operation v0 divmod.return = v2 divmod
+scoreboard players operation v0.1 divmod.return = v2.1 divmod
+scoreboard players set flag divmod.return 1
+return 0
\ No newline at end of file
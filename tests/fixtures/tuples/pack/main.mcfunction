@@ -0,0 +1,45 @@
+scoreboard players set v0 main 17
+scoreboard players set v1 main 5
+scoreboard players operation v0 divmod = v0 main
+scoreboard players operation v1 divmod = v1 main
+function pack:divmod
+scoreboard players operation v2 main = v0 divmod.return
+scoreboard players operation v2.1 main = v0.1 divmod.return
+scoreboard players operation v3 main = v2 main
+scoreboard players operation v4 main = v2.1 main
+function pack:named
+data modify storage pack:values "main".v5_0 set from storage pack:values "named.return".v0_0
+scoreboard players operation v5.1 main = v0.1 named.return
+scoreboard players operation v5.2 main = v0.2 named.return
+scoreboard players operation v5.3 main = v0.3 named.return
+data modify storage pack:values "main".v6_0 set from storage pack:values "main".v5_0
+scoreboard players operation v7 main = v5.1 main
+scoreboard players operation v7.1 main = v5.2 main
+scoreboard players set v9 main 3
+scoreboard players operation v8 main = v9 main
+scoreboard players set v10 main 4
+scoreboard players set v12 main 1000000000
+scoreboard players operation v11 main = v10 main
+scoreboard players operation v11 main /= v12 main
+scoreboard players operation v11.1 main = v10 main
+scoreboard players operation v11.1 main %= v12 main
+scoreboard players operation v8.1 main = v11 main
+scoreboard players operation v8.2 main = v11.1 main
+scoreboard players operation v0 swap = v8 main
+scoreboard players operation v0.1 swap = v8.1 main
+scoreboard players operation v0.2 swap = v8.2 main
+function pack:swap
+scoreboard players operation v13 main = v0 swap.return
+scoreboard players operation v13.1 main = v0.1 swap.return
+scoreboard players operation v13.2 main = v0.2 swap.return
+scoreboard players operation v14 main = v13 main
+scoreboard players operation v14.1 main = v13.1 main
+scoreboard players operation v15 main = v13.2 main
+scoreboard players operation v16 main = v3 main
+scoreboard players operation v17 main = v4 main
+data modify storage pack:values "main".v18_0 set from storage pack:values "main".v6_0
+scoreboard players operation v19 main = v7 main
+scoreboard players operation v19.1 main = v7.1 main
+scoreboard players operation v20 main = v19 main
+scoreboard players operation v21 main = v15 main
+tellraw @a ["",{"text":"q is "},{"score":{"name":"v16","objective":"main"}},{"text":" and r is "},{"score":{"name":"v17","objective":"main"}},{"text":" and name is "},{"nbt":"\"main\".v18_0","storage":"pack:values"},{"text":" and x is "},{"score":{"name":"v20","objective":"main"}},{"text":" and i is "},{"score":{"name":"v21","objective":"main"}}]
\ No newline at end of file
@@ -0,0 +1,17 @@
+data modify storage pack:values "named".v1_0 set value "bob"
+data modify storage pack:values "named".v0_0 set from storage pack:values "named".v1_0
+scoreboard players set v2 named 1
+scoreboard players set v3 named 2
+scoreboard players operation v4 named = v2 named
+scoreboard players operation v4.1 named = v3 named
+scoreboard players operation v0.1 named = v4 named
+scoreboard players operation v0.2 named = v4.1 named
+scoreboard players set v5 named 1
+scoreboard players operation v0.3 named = v5 named
+data modify storage pack:values "named.return".v0_0 set from storage pack:values "named".v0_0
+scoreboard players operation v0 named.return = v0 named
+scoreboard players operation v0.1 named.return = v0.1 named
+scoreboard players operation v0.2 named.return = v0.2 named
+scoreboard players operation v0.3 named.return = v0.3 named
+scoreboard players set flag named.return 1
+return 0
\ No newline at end of file
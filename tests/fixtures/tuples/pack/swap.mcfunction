@@ -0,0 +1,17 @@
+scoreboard players operation v1 swap = v0 swap
+scoreboard players operation v1.1 swap = v0.1 swap
+scoreboard players operation v1.2 swap = v0.2 swap
+scoreboard players operation v2 swap = v1 swap
+scoreboard players operation v3 swap = v1.1 swap
+scoreboard players operation v3.1 swap = v1.2 swap
+scoreboard players operation v5 swap = v3 swap
+scoreboard players operation v5.1 swap = v3.1 swap
+scoreboard players operation v4 swap = v5 swap
+scoreboard players operation v4.1 swap = v5.1 swap
+scoreboard players operation v6 swap = v2 swap
+scoreboard players operation v4.2 swap = v6 swap
+scoreboard players operation v0 swap.return = v4 swap
+scoreboard players operation v0.1 swap.return = v4.1 swap
+scoreboard players operation v0.2 swap.return = v4.2 swap
+scoreboard players set flag swap.return 1
+return 0
\ No newline at end of file
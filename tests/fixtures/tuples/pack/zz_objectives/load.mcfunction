@@ -0,0 +1,7 @@
+scoreboard objectives add divmod dummy
+scoreboard objectives add divmod.return dummy
+scoreboard objectives add main dummy
+scoreboard objectives add named dummy
+scoreboard objectives add named.return dummy
+scoreboard objectives add swap dummy
+scoreboard objectives add swap.return dummy
\ No newline at end of file
@@ -0,0 +1,14 @@
+use std::process::Command;
+
+// Compiles every fixture in tests/fixtures and fails if the generated files differ from the expected ones. After an
+// intended change to the generated code, `sculk --golden tests/fixtures --bless` updates the expected files
+#[test]
+fn fixtures_match_expected_files() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sculk"))
+        .args(["--golden", "tests/fixtures", "--color", "never"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run sculk");
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
@@ -0,0 +1,89 @@
+use std::{path::PathBuf, process::Command};
+
+// Runs sculk on a source file with the given flags, from a directory of its own so that a compiled pack doesn't end up
+// in the repository, and returns the code it exited with (None if it was killed) and what it printed
+fn run(name: &str, src: &str, flags: &[&str]) -> (Option<i32>, String) {
+    let dir: PathBuf = std::env::temp_dir().join(format!("sculk_malformed_{}_{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).expect("failed to create the directory to run in");
+    std::fs::write(dir.join("main.sculk"), src).expect("failed to write the source file");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sculk"))
+        .arg("main.sculk")
+        .args(flags)
+        .args(["--color", "never"])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run sculk");
+
+    std::fs::remove_dir_all(&dir).ok();
+
+    (output.status.code(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Compiling, checking and differential testing all have to report malformed source instead of panicking, which
+// exits with 101
+fn assert_no_panic(name: &str, src: &str) {
+    for (mode, flags) in [("compile", &[][..]), ("check", &["--check"][..]), ("differential", &["--differential"][..])] {
+        let (code, stdout) = run(&format!("{}_{}", name, mode), src, flags);
+        assert!(code == Some(0) || code == Some(1), "sculk {} crashed on:\n{}\n{}", mode, src, stdout);
+    }
+}
+
+#[test]
+fn nesting_deeper_than_the_limit_is_an_error() {
+    let depth = 1000;
+
+    for (name, src) in [
+        ("parens", format!("fn main() {{ let x = {}1{}; }}", "(".repeat(depth), ")".repeat(depth))),
+        ("blocks", format!("fn main() {{ {}{} }}", "if true { ".repeat(depth), "}".repeat(depth))),
+    ] {
+        let (code, stdout) = run(name, &src, &["--differential"]);
+
+        assert_eq!(code, Some(1), "{}", stdout);
+        assert!(stdout.contains("could not be parsed"), "{}", stdout);
+
+        assert_no_panic(name, &src);
+    }
+}
+
+#[test]
+fn errors_that_start_after_the_last_token_read_never_panic() {
+    assert_no_panic("missing_semicolon", "fn main() { /say hi }");
+    assert_no_panic("for_parens", "fn main() {\n    for (let i = 0) { }\n}\n");
+    assert_no_panic("dangling_operator", "fn main() {\n    let b = 1 + ];\n}\n");
+}
+
+#[test]
+fn random_tokens_never_panic() {
+    const TOKENS: &[&str] = &[
+        "fn", "let", "struct", "if", "else", "for", "in", "return", "break", "match", "await", "as", "at", "x", "main",
+        "1", "-4", "true", "\"s\"", "@a", "(", ")", "{", "}", "[", "]", "<", ">", ",", ";", ":", ".", "..", "..=", "=",
+        "==", "+", "-", "*", "/", "%", "!", "?", "&&", "||", "=>", "->", "#", "'l", "+=", "++",
+    ];
+
+    // a fixed xorshift seed keeps the inputs the same between runs, so a failure can be reproduced
+    let mut state: u32 = 0x5c01_c0de;
+
+    for i in 0..20 {
+        let src = (0..200)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                TOKENS[state as usize % TOKENS.len()]
+            })
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        assert_no_panic(&format!("random_{}", i), &src);
+    }
+}
+
+#[test]
+fn check_reports_malformed_input() {
+    let (code, stdout) = run("check_missing_semicolon", "fn main() { /say hi }", &["--check"]);
+
+    assert_eq!(code, Some(1), "{}", stdout);
+    assert!(stdout.contains("expected ; after command literal"), "{}", stdout);
+    assert!(stdout.contains("main.sculk: 1 error"), "{}", stdout);
+}